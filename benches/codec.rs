@@ -0,0 +1,74 @@
+#[macro_use]
+extern crate criterion;
+extern crate diesel_pg_hstore;
+
+use std::collections::HashMap;
+
+use criterion::Criterion;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::codec;
+
+fn small() -> Hstore {
+    let mut store = Hstore::new();
+    store.insert("env".into(), "production".into());
+    store.insert("region".into(), "us-east-1".into());
+    store.insert("active".into(), "t".into());
+    store
+}
+
+fn wide() -> Hstore {
+    (0..10_000)
+        .map(|i| (format!("key_{}", i), format!("value_{}", i)))
+        .collect()
+}
+
+fn large_values() -> Hstore {
+    (0..16)
+        .map(|i| (format!("blob_{}", i), "x".repeat(64 * 1024)))
+        .collect()
+}
+
+fn bench_case(c: &mut Criterion, name: &str, store: Hstore) {
+    let mut encoded = Vec::new();
+    codec::encode(&store, &mut encoded).unwrap();
+
+    let nullable: HashMap<String, Option<String>> = store.iter()
+        .map(|(k, v)| (k.clone(), Some(v.clone())))
+        .collect();
+    let encoded_nullable = encoded.clone();
+
+    // A new buffer each time, as for `ToSql`
+    c.bench_function(&format!("encode {}", name), move |b| {
+        b.iter(|| {
+            let mut buf = Vec::new();
+            codec::encode(&store, &mut buf).unwrap();
+            buf
+        })
+    });
+
+    c.bench_function(&format!("decode {}", name), move |b| {
+        b.iter(|| codec::decode(&encoded).unwrap())
+    });
+
+    c.bench_function(&format!("encode nullable {}", name), move |b| {
+        b.iter(|| {
+            let mut buf = Vec::new();
+            codec::encode_nullable(&nullable, &mut buf).unwrap();
+            buf
+        })
+    });
+
+    c.bench_function(&format!("decode nullable {}", name), move |b| {
+        b.iter(|| codec::decode_nullable(&encoded_nullable).unwrap())
+    });
+}
+
+fn codec_benches(c: &mut Criterion) {
+    bench_case(c, "small", small());
+    bench_case(c, "wide", wide());
+    bench_case(c, "large values", large_values());
+}
+
+criterion_group!(benches, codec_benches);
+criterion_main!(benches);
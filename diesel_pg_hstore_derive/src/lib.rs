@@ -0,0 +1,125 @@
+//! Derives `ToHstore` and `FromHstore` from
+//! [diesel_pg_hstore](https://docs.rs/diesel_pg_hstore)'s `mapping` module.
+//!
+//! Use these through diesel_pg_hstore's `derive` feature rather than directly. Each field is
+//! an hstore key, its name unless renamed with `#[hstore(rename = "...")]`. Values are
+//! written with `ToString` and read with `FromStr`, and an `Option` field may be missing.
+
+extern crate proc_macro;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use syn::{Body, DeriveInput, Field, Lit, MetaItem, NestedMetaItem, Ty, VariantData};
+
+#[proc_macro_derive(ToHstore, attributes(hstore))]
+pub fn derive_to_hstore(input: TokenStream) -> TokenStream {
+    let item = syn::parse_derive_input(&input.to_string()).unwrap();
+    expand_to_hstore(&item).parse().unwrap()
+}
+
+#[proc_macro_derive(FromHstore, attributes(hstore))]
+pub fn derive_from_hstore(input: TokenStream) -> TokenStream {
+    let item = syn::parse_derive_input(&input.to_string()).unwrap();
+    expand_from_hstore(&item).parse().unwrap()
+}
+
+fn expand_to_hstore(item: &DeriveInput) -> quote::Tokens {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    let inserts = fields(item, "ToHstore").iter().map(|field| {
+        let ident = &field.ident;
+        let key = key(field);
+        if is_option(&field.ty) {
+            quote! {
+                if let Some(ref value) = self.#ident {
+                    hstore.insert(#key.to_string(), ::std::string::ToString::to_string(value));
+                }
+            }
+        }
+        else {
+            quote! {
+                hstore.insert(#key.to_string(), ::std::string::ToString::to_string(&self.#ident));
+            }
+        }
+    }).collect::<Vec<_>>();
+
+    quote! {
+        impl #impl_generics ::diesel_pg_hstore::mapping::ToHstore for #name #ty_generics #where_clause {
+            #[allow(unused_mut)]
+            fn to_hstore(&self) -> ::diesel_pg_hstore::Hstore {
+                let mut hstore = ::diesel_pg_hstore::Hstore::new();
+                #(#inserts)*
+                hstore
+            }
+        }
+    }
+}
+
+fn expand_from_hstore(item: &DeriveInput) -> quote::Tokens {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    let values = fields(item, "FromHstore").iter().map(|field| {
+        let ident = &field.ident;
+        let key = key(field);
+        if is_option(&field.ty) {
+            quote! { #ident: ::diesel_pg_hstore::mapping::optional(hstore, #key)? }
+        }
+        else {
+            quote! { #ident: ::diesel_pg_hstore::mapping::required(hstore, #key)? }
+        }
+    }).collect::<Vec<_>>();
+
+    quote! {
+        impl #impl_generics ::diesel_pg_hstore::mapping::FromHstore for #name #ty_generics #where_clause {
+            fn from_hstore(hstore: &::diesel_pg_hstore::Hstore) -> ::std::result::Result<Self, ::diesel_pg_hstore::mapping::FromHstoreError> {
+                Ok(#name {
+                    #(#values,)*
+                })
+            }
+        }
+    }
+}
+
+/// The named fields of a struct
+fn fields<'a>(item: &'a DeriveInput, derive: &str) -> &'a [Field] {
+    match item.body {
+        Body::Struct(VariantData::Struct(ref fields)) => fields,
+        Body::Struct(VariantData::Unit) => &[],
+        _ => panic!("#[derive({})] can only be used with structs with named fields", derive),
+    }
+}
+
+/// The hstore key of a field, from `#[hstore(rename = "...")]` or its name
+fn key(field: &Field) -> String {
+    let mut key = None;
+    for attr in &field.attrs {
+        let options = match attr.value {
+            MetaItem::List(ref ident, ref options) if ident == "hstore" => options,
+            _ => continue,
+        };
+        for option in options {
+            match *option {
+                NestedMetaItem::MetaItem(MetaItem::NameValue(ref name, Lit::Str(ref rename, _))) if name == "rename" => {
+                    key = Some(rename.clone());
+                },
+                _ => panic!("unknown hstore option, expected `rename = \"key\"`"),
+            }
+        }
+    }
+    key.unwrap_or_else(|| field.ident.as_ref().expect("named field").to_string())
+}
+
+/// Whether a field is an `Option`, which may be missing from the hstore
+fn is_option(ty: &Ty) -> bool {
+    match *ty {
+        Ty::Path(None, ref path) => match path.segments.last() {
+            Some(segment) => segment.ident == "Option",
+            None => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,46 @@
+//! Write the hstore column of a table to a Parquet file, as a map column.
+//!
+//! ```text
+//! DATABASE_URL=postgres://localhost/shop \
+//!     cargo run --example parquet_export --features "arrow parquet" -- products attributes out.parquet
+//! ```
+//!
+//! The table must have an integer `id` column.
+
+extern crate diesel;
+extern crate diesel_pg_hstore;
+extern crate parquet;
+
+use std::env;
+use std::fs::File;
+
+use diesel::prelude::*;
+use parquet::arrow::ArrowWriter;
+
+use diesel_pg_hstore::arrow_batch::Batches;
+use diesel_pg_hstore::scan::Scan;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 4 {
+        eprintln!("usage: parquet_export <table> <hstore column> <output file>");
+        std::process::exit(2);
+    }
+
+    let url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db = PgConnection::establish(&url).expect("connecting to the database");
+
+    let scan = Scan::new(args[1].as_str()).store(args[2].as_str());
+    let batches = Batches::new(&scan, &db, 10_000);
+    let file = File::create(&args[3]).expect("creating the output file");
+    let mut writer = ArrowWriter::try_new(file, batches.schema(), None).expect("creating the Parquet writer");
+
+    let mut rows = 0;
+    for batch in batches {
+        let batch = batch.expect("reading the table");
+        rows += batch.num_rows();
+        writer.write(&batch).expect("writing a batch");
+    }
+    writer.close().expect("finishing the Parquet file");
+    println!("wrote {} rows to {}", rows, args[3]);
+}
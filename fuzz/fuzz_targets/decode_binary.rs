@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::pg::Pg;
+use diesel::types::FromSql;
+use diesel_pg_hstore::Hstore;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = <Hstore as FromSql<Hstore, Pg>>::from_sql(Some(data));
+});
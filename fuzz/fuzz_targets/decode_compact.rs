@@ -0,0 +1,11 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate diesel_pg_hstore;
+
+use diesel_pg_hstore::Hstore;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(store) = Hstore::from_bytes(data) {
+        assert_eq!(store.to_bytes(), data);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate diesel_pg_hstore;
+
+use diesel_pg_hstore::text;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(store) = text::parse(s) {
+            assert_eq!(text::parse(&text::format(&store)).unwrap(), store);
+        }
+    }
+});
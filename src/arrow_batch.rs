@@ -0,0 +1,199 @@
+//! Converting hstores to Arrow arrays, for analytics pipelines.
+//!
+//! With the `arrow` feature, hstores convert to an Arrow `MapArray` of UTF-8 keys and values,
+//! so they can be handed to Arrow, DataFusion or a Parquet writer as maps rather than as text:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::arrow_batch;
+//!
+//! let rows: Vec<(i64, Hstore)> = products.select((id, attributes)).load(&db)?;
+//! let batch = arrow_batch::record_batch(&rows, "attributes")?;
+//! assert_eq!(batch.schema().field(1).name(), "attributes");
+//! ```
+//!
+//! Each map's entries are sorted by key, so the same hstore always converts to the same array.
+//! A missing hstore converts to a null map. [`Batches`](struct.Batches.html) reads a whole table
+//! a batch at a time with a [`Scan`](../scan/struct.Scan.html).
+//!
+//! With the `parquet` feature as well, the `parquet_export` example writes a table to a Parquet
+//! file:
+//!
+//! ```text
+//! cargo run --example parquet_export --features "arrow parquet" -- products attributes out.parquet
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::iter;
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, Int64Array, MapArray, RecordBatch};
+use arrow_array::builder::{MapBuilder, StringBuilder};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use diesel::pg::PgConnection;
+use diesel::result::Error as QueryError;
+
+use super::Hstore;
+use super::scan::{Rows, Scan};
+
+/// Convert hstores to a `MapArray`, with a null map for each `None`.
+pub fn map_array<'a, I>(stores: I) -> MapArray
+    where I: IntoIterator<Item = Option<&'a Hstore>>
+{
+    let mut builder = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+    for store in stores {
+        match store {
+            Some(store) => {
+                let mut entries: Vec<_> = store.iter().collect();
+                entries.sort();
+                for (key, value) in entries {
+                    builder.keys().append_value(key);
+                    builder.values().append_value(value);
+                }
+                builder.append(true).expect("keys and values to be appended together");
+            }
+            None => builder.append(false).expect("keys and values to be appended together"),
+        }
+    }
+    builder.finish()
+}
+
+/// The Arrow type of the arrays made by [`map_array`](fn.map_array.html)
+pub fn map_data_type() -> DataType {
+    map_array(iter::empty()).data_type().clone()
+}
+
+/// The schema of the batches made by [`record_batch`](fn.record_batch.html): a non-null
+/// `Int64` column `id`, and a map column named `store`.
+pub fn schema(store: &str) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new(store, map_data_type(), true),
+    ]))
+}
+
+/// Convert `(id, Hstore)` rows to a record batch, with the hstores in a map column named
+/// `store`.
+pub fn record_batch(rows: &[(i64, Hstore)], store: &str) -> Result<RecordBatch, ArrowError> {
+    let ids = Int64Array::from_iter_values(rows.iter().map(|&(id, _)| id));
+    let stores = map_array(rows.iter().map(|row| Some(&row.1)));
+    RecordBatch::try_new(schema(store), vec![Arc::new(ids) as ArrayRef, Arc::new(stores)])
+}
+
+/// An error converting a table to record batches.
+#[derive(Debug)]
+pub enum ExportError {
+    /// Reading the table failed.
+    Query(QueryError),
+    /// Building a batch failed.
+    Arrow(ArrowError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportError::Query(ref e) => write!(f, "reading the table failed: {}", e),
+            ExportError::Arrow(ref e) => write!(f, "building a record batch failed: {}", e),
+        }
+    }
+}
+
+impl StdError for ExportError {
+    fn description(&self) -> &str {
+        match *self {
+            ExportError::Query(_) => "reading the table failed",
+            ExportError::Arrow(_) => "building a record batch failed",
+        }
+    }
+}
+
+impl From<QueryError> for ExportError {
+    fn from(e: QueryError) -> ExportError {
+        ExportError::Query(e)
+    }
+}
+
+impl From<ArrowError> for ExportError {
+    fn from(e: ArrowError) -> ExportError {
+        ExportError::Arrow(e)
+    }
+}
+
+/// The rows of a [`Scan`](../scan/struct.Scan.html) as record batches of up to `batch_size`
+/// rows, in id order.
+///
+/// ```rust,ignore
+/// let scan = Scan::new("products").store("attributes");
+/// for batch in Batches::new(&scan, &db, 10_000) {
+///     writer.write(&batch?)?;
+/// }
+/// ```
+///
+/// A failed query is yielded as an error, after which the iterator ends.
+pub struct Batches<'a> {
+    rows: Rows<'a>,
+    schema: SchemaRef,
+    batch_size: usize,
+    done: bool,
+}
+
+impl<'a> Batches<'a> {
+    /// Read `scan` in batches of `batch_size` rows.
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn new(scan: &'a Scan, conn: &'a PgConnection, batch_size: usize) -> Batches<'a> {
+        assert!(batch_size > 0, "batch size must be positive");
+        Batches {
+            rows: scan.rows(conn),
+            schema: schema(scan.store_name()),
+            batch_size: batch_size,
+            done: false,
+        }
+    }
+
+    /// The schema of every batch
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl<'a> Iterator for Batches<'a> {
+    type Item = Result<RecordBatch, ExportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut rows = Vec::with_capacity(self.batch_size);
+        while rows.len() < self.batch_size {
+            match self.rows.next() {
+                Some(Ok(row)) => rows.push(row),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        if rows.is_empty() {
+            return None;
+        }
+
+        let store = self.schema.field(1).name().clone();
+        Some(record_batch(&rows, &store).map_err(ExportError::from))
+    }
+}
+
+impl<'a> fmt::Debug for Batches<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Batches")
+            .field("rows", &self.rows)
+            .field("batch_size", &self.batch_size)
+            .field("done", &self.done)
+            .finish()
+    }
+}
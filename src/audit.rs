@@ -0,0 +1,169 @@
+//! One line summaries of hstore changes, for audit logs and admin pages.
+//!
+//! An [`AuditFormat`](struct.AuditFormat.html) describes the change between two hstores, or the
+//! change an [`HstorePatch`](../patch/struct.HstorePatch.html) makes, with one comma separated
+//! item per key in key order:
+//!
+//! * `+key=value` for a key added, or set by a patch
+//! * `-key` for a key removed
+//! * `key: old→new` for a key whose value changed
+//!
+//! The values of sensitive keys are replaced with a placeholder, so the summary still says which
+//! of them changed without saying how:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::audit::AuditFormat;
+//!
+//! let mut old = Hstore::new();
+//! old.insert("legacy".into(), "t".into());
+//! old.insert("region".into(), "eu".into());
+//! old.insert("api_token".into(), "abc".into());
+//!
+//! let mut new = Hstore::new();
+//! new.insert("env".into(), "prod".into());
+//! new.insert("region".into(), "us".into());
+//! new.insert("api_token".into(), "xyz".into());
+//!
+//! let format = AuditFormat::new().redact("api_token");
+//! assert_eq!(format.diff(&old, &new), "api_token: [redacted]→[redacted], +env=prod, -legacy, region: eu→us");
+//! ```
+//!
+//! Keys and values are quoted as in [`Hstore::pretty`](../struct.Hstore.html#method.pretty)
+//! when they contain spaces, commas or quotes.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+use super::Hstore;
+use super::patch::HstorePatch;
+use super::pretty;
+
+/// How changes are summarized.
+pub struct AuditFormat {
+    redacted: HashSet<String>,
+    redact_if: Option<Box<Fn(&str) -> bool + Send + Sync>>,
+    placeholder: String,
+}
+
+impl AuditFormat {
+    /// Summarize every value as is
+    pub fn new() -> AuditFormat {
+        AuditFormat {
+            redacted: HashSet::new(),
+            redact_if: None,
+            placeholder: "[redacted]".into(),
+        }
+    }
+
+    /// Hide the values of `key`.
+    pub fn redact<K: Into<String>>(mut self, key: K) -> AuditFormat {
+        self.redacted.insert(key.into());
+        self
+    }
+
+    /// Hide the values of every key for which `redact_if` returns true, such as keys containing
+    /// `password`.
+    pub fn redact_if<F>(mut self, redact_if: F) -> AuditFormat
+        where F: Fn(&str) -> bool + Send + Sync + 'static
+    {
+        self.redact_if = Some(Box::new(redact_if));
+        self
+    }
+
+    /// Show hidden values as `placeholder` rather than `[redacted]`.
+    pub fn placeholder<P: Into<String>>(mut self, placeholder: P) -> AuditFormat {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    /// Whether the values of `key` are hidden
+    pub fn is_redacted(&self, key: &str) -> bool {
+        self.redacted.contains(key) || self.redact_if.as_ref().map_or(false, |redact_if| redact_if(key))
+    }
+
+    /// Summarize the change from `old` to `new`, or the empty string if there is none.
+    pub fn diff(&self, old: &Hstore, new: &Hstore) -> String {
+        let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+        let mut items = Vec::new();
+        for key in keys {
+            match (old.0.get(key), new.0.get(key)) {
+                (Some(o), Some(n)) if o != n => items.push(format!(
+                    "{}: {}→{}",
+                    pretty::display(key),
+                    self.value(key, o),
+                    self.value(key, n),
+                )),
+                (Some(_), None) => items.push(format!("-{}", pretty::display(key))),
+                (None, Some(n)) => items.push(self.set(key, n)),
+                _ => {}
+            }
+        }
+        items.join(", ")
+    }
+
+    /// Summarize the change a patch makes, or the empty string if it is empty.
+    ///
+    /// Since a patch doesn't know the values it replaces, every key it sets is listed as added.
+    pub fn patch(&self, patch: &HstorePatch) -> String {
+        let mut items = Vec::new();
+        let mut sets = patch.sets().iter().peekable();
+        let mut deletes = patch.deletes().iter().peekable();
+        loop {
+            let take_set = match (sets.peek(), deletes.peek()) {
+                (Some(&(key, _)), Some(&deleted)) => key < deleted,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            if take_set {
+                let (key, value) = sets.next().unwrap();
+                items.push(self.set(key, value));
+            }
+            else {
+                items.push(format!("-{}", pretty::display(deletes.next().unwrap())));
+            }
+        }
+        items.join(", ")
+    }
+
+    fn set(&self, key: &str, value: &str) -> String {
+        format!("+{}={}", pretty::display(key), self.value(key, value))
+    }
+
+    /// `value`, or the placeholder if the values of `key` are hidden
+    pub(crate) fn shown<'a>(&'a self, key: &str, value: &'a str) -> &'a str {
+        if self.is_redacted(key) {
+            &self.placeholder
+        }
+        else {
+            value
+        }
+    }
+
+    fn value(&self, key: &str, value: &str) -> String {
+        if self.is_redacted(key) {
+            self.placeholder.clone()
+        }
+        else {
+            pretty::display(value)
+        }
+    }
+}
+
+impl Default for AuditFormat {
+    fn default() -> AuditFormat {
+        AuditFormat::new()
+    }
+}
+
+impl fmt::Debug for AuditFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AuditFormat")
+            .field("redacted", &self.redacted)
+            .field("redact_if", &self.redact_if.is_some())
+            .field("placeholder", &self.placeholder)
+            .finish()
+    }
+}
@@ -0,0 +1,305 @@
+//! Changing the hstores of large tables in batches.
+//!
+//! A single `UPDATE` over a large table holds its locks until every row is done. The helpers
+//! here split the work into ranges of ids, committing each batch in its own transaction, and
+//! report progress after each one.
+//!
+//! ```rust,no_run
+//! # extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! # use diesel::prelude::*;
+//! use diesel_pg_hstore::batch;
+//!
+//! # fn main() {
+//! # let db = PgConnection::establish("").unwrap();
+//! let updated = batch::backfill_key(&db, "products", "currency", "USD", 10_000, |progress| {
+//!     println!("{} rows updated, up to id {} of {}",
+//!         progress.updated(), progress.last_id(), progress.max_id());
+//! }).unwrap();
+//! # }
+//! ```
+//!
+//! [`ChunkedPatch`](struct.ChunkedPatch.html) applies an
+//! [`HstorePatch`](../patch/struct.HstorePatch.html) to the rows matching a filter in the same
+//! way, with hooks to retry failed chunks and to stop early.
+//!
+//! Tables must have an integer `id` column. Since each batch is committed as it completes, an
+//! error leaves the batches before it applied.
+
+use std::fmt;
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::types::{BigInt, Nullable};
+
+use super::patch::HstorePatch;
+use super::quote::{quote_ident, quote_literal, quote_name};
+
+/// How far a batched operation has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    updated: usize,
+    last_id: i64,
+    max_id: i64,
+}
+
+impl Progress {
+    /// The number of rows updated so far
+    pub fn updated(&self) -> usize {
+        self.updated
+    }
+
+    /// The last id covered by the batches done so far
+    pub fn last_id(&self) -> i64 {
+        self.last_id
+    }
+
+    /// The largest id in the table when the operation started
+    pub fn max_id(&self) -> i64 {
+        self.max_id
+    }
+}
+
+/// Set `key` to `default` in every row of `table` which doesn't have it, in batches of
+/// `batch_size` ids.
+///
+/// The hstore column is assumed to be named `store`. Use
+/// [`backfill_key_in`](fn.backfill_key_in.html) to name it. Returns the number of rows updated.
+///
+/// Panics if `batch_size` isn't positive.
+pub fn backfill_key<F>(conn: &PgConnection, table: &str, key: &str, default: &str, batch_size: i64, progress: F)
+    -> QueryResult<usize>
+    where F: FnMut(&Progress)
+{
+    backfill_key_in(conn, table, "store", key, default, batch_size, progress)
+}
+
+/// Like [`backfill_key`](fn.backfill_key.html), for the hstore column `store`.
+pub fn backfill_key_in<F>(
+    conn: &PgConnection,
+    table: &str,
+    store: &str,
+    key: &str,
+    default: &str,
+    batch_size: i64,
+    progress: F,
+) -> QueryResult<usize>
+    where F: FnMut(&Progress)
+{
+    let store = quote_ident(store);
+    let update = format!(
+        "UPDATE {table} SET {store} = {store} || hstore({key}, {default}) WHERE NOT ({store} ? {key})",
+        table = quote_name(table),
+        store = store,
+        key = quote_literal(key),
+        default = quote_literal(default),
+    );
+    in_batches(conn, table, &update, batch_size, progress)
+}
+
+/// Run `update`, an `UPDATE` statement over `table` ending in a `WHERE` clause, once per range
+/// of ids.
+pub(crate) fn in_batches<F>(conn: &PgConnection, table: &str, update: &str, batch_size: i64, mut progress: F)
+    -> QueryResult<usize>
+    where F: FnMut(&Progress)
+{
+    assert!(batch_size > 0, "batch size must be positive");
+
+    let bounds = format!("SELECT min(id)::bigint, max(id)::bigint FROM {}", quote_name(table));
+    let (min_id, max_id) = sql::<(Nullable<BigInt>, Nullable<BigInt>)>(&bounds)
+        .get_result::<(Option<i64>, Option<i64>)>(conn)?;
+    let (min_id, max_id) = match (min_id, max_id) {
+        (Some(min_id), Some(max_id)) => (min_id, max_id),
+        _ => return Ok(0),
+    };
+
+    let mut state = Progress {
+        updated: 0,
+        last_id: min_id - 1,
+        max_id: max_id,
+    };
+    let mut start = min_id;
+    while start <= max_id {
+        let end = start.saturating_add(batch_size - 1).min(max_id);
+        let batch = format!("{} AND id BETWEEN {} AND {}", update, start, end);
+        state.updated += conn.transaction(|| conn.execute(&batch))?;
+        state.last_id = end;
+        progress(&state);
+
+        if end == i64::max_value() {
+            break;
+        }
+        start = end + 1;
+    }
+
+    Ok(state.updated)
+}
+
+/// Whether a batched operation should carry on after a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Go on to the next chunk
+    Continue,
+    /// Stop, leaving the remaining rows unchanged
+    Abort,
+}
+
+/// Applies an `HstorePatch` to the rows of a table matching a filter, a chunk at a time.
+///
+/// Chunks are chosen by id, each holding up to `chunk_size` matching rows, and each is updated
+/// and committed in its own transaction.
+///
+/// ```rust,no_run
+/// # extern crate diesel;
+/// # extern crate diesel_pg_hstore;
+/// # use diesel::prelude::*;
+/// use diesel_pg_hstore::batch::{ChunkedPatch, Control};
+/// use diesel_pg_hstore::patch::HstorePatch;
+///
+/// # fn main() {
+/// # let db = PgConnection::establish("").unwrap();
+/// let patch = HstorePatch::new().set("archived", "true").delete("draft");
+///
+/// let progress = ChunkedPatch::new("documents", &patch)
+///     .filter_sql("created_at < now() - interval '1 year'")
+///     .chunk_size(5_000)
+///     .retry_if(|_error, attempt| attempt < 3)
+///     .run(&db, |progress| {
+///         println!("{} rows patched", progress.updated());
+///         Control::Continue
+///     })
+///     .unwrap();
+/// # }
+/// ```
+pub struct ChunkedPatch<'a> {
+    table: String,
+    store: String,
+    patch: &'a HstorePatch,
+    filter: Option<String>,
+    chunk_size: i64,
+    retry_if: Box<Fn(&Error, u32) -> bool + 'a>,
+}
+
+impl<'a> ChunkedPatch<'a> {
+    /// Apply `patch` to the `store` column of every row of `table`, in chunks of 1000 rows.
+    pub fn new<T: Into<String>>(table: T, patch: &'a HstorePatch) -> ChunkedPatch<'a> {
+        ChunkedPatch {
+            table: table.into(),
+            store: "store".into(),
+            patch: patch,
+            filter: None,
+            chunk_size: 1000,
+            retry_if: Box::new(|_, _| false),
+        }
+    }
+
+    /// Patch the hstore column `store` instead.
+    pub fn store<S: Into<String>>(mut self, store: S) -> ChunkedPatch<'a> {
+        self.store = store.into();
+        self
+    }
+
+    /// Only patch rows matching an SQL condition.
+    pub fn filter_sql<F: Into<String>>(mut self, filter: F) -> ChunkedPatch<'a> {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Set the most rows updated in each chunk.
+    ///
+    /// Panics if `chunk_size` isn't positive.
+    pub fn chunk_size(mut self, chunk_size: i64) -> ChunkedPatch<'a> {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Decide whether to retry a chunk which failed, given the error and the number of attempts
+    /// made so far. By default failed chunks aren't retried.
+    pub fn retry_if<F>(mut self, retry_if: F) -> ChunkedPatch<'a>
+        where F: Fn(&Error, u32) -> bool + 'a
+    {
+        self.retry_if = Box::new(retry_if);
+        self
+    }
+
+    /// The statement patching the chunk of rows after `after_id`, returning their ids
+    pub fn chunk_sql(&self, after_id: i64) -> String {
+        let store = quote_ident(&self.store);
+        let table = quote_name(&self.table);
+        let filter = match self.filter {
+            Some(ref filter) => format!(" AND ({})", filter),
+            None => String::new(),
+        };
+
+        format!(
+            "UPDATE {table} SET {store} = {patched} WHERE id IN (\
+             SELECT id FROM {table} WHERE id > {after_id}{filter} ORDER BY id LIMIT {limit}\
+             ) RETURNING id::bigint",
+            table = table,
+            store = store,
+            patched = self.patch.to_sql(&store),
+            after_id = after_id,
+            filter = filter,
+            limit = self.chunk_size,
+        )
+    }
+
+    /// Patch every matching row, calling `after_chunk` with the progress made after each chunk.
+    ///
+    /// Returns the progress made, which is short of the whole table if `after_chunk` aborted.
+    pub fn run<F>(&self, conn: &PgConnection, mut after_chunk: F) -> QueryResult<Progress>
+        where F: FnMut(&Progress) -> Control
+    {
+        let bounds = format!("SELECT min(id)::bigint, max(id)::bigint FROM {}", quote_name(&self.table));
+        let (min_id, max_id) = sql::<(Nullable<BigInt>, Nullable<BigInt>)>(&bounds)
+            .get_result::<(Option<i64>, Option<i64>)>(conn)?;
+
+        let mut state = Progress {
+            updated: 0,
+            last_id: min_id.unwrap_or(0).saturating_sub(1),
+            max_id: max_id.unwrap_or(0),
+        };
+        if min_id.is_none() || self.patch.is_empty() {
+            return Ok(state);
+        }
+
+        loop {
+            let chunk = self.chunk_sql(state.last_id);
+            let mut attempt = 0;
+            let ids = loop {
+                attempt += 1;
+                match conn.transaction(|| sql::<BigInt>(&chunk).load::<i64>(conn)) {
+                    Ok(ids) => break ids,
+                    Err(ref e) if (self.retry_if)(e, attempt) => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+
+            let last_id = match ids.iter().max() {
+                Some(&last_id) => last_id,
+                None => return Ok(state),
+            };
+            state.updated += ids.len();
+            state.last_id = last_id;
+
+            if after_chunk(&state) == Control::Abort {
+                return Ok(state);
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Debug for ChunkedPatch<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChunkedPatch")
+            .field("table", &self.table)
+            .field("store", &self.store)
+            .field("patch", &self.patch)
+            .field("filter", &self.filter)
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
@@ -0,0 +1,116 @@
+//! Convert, validate and compare hstore literals.
+//!
+//! Run `hstore-cli help` for usage. Where a literal or JSON document is expected, `-` or a
+//! missing argument reads it from stdin instead.
+
+extern crate diesel_pg_hstore;
+extern crate serde_json;
+
+use std::env;
+use std::io::{self, Read, Write};
+use std::process;
+
+use serde_json::{Map, Value};
+
+use diesel_pg_hstore::{text, Hstore};
+use diesel_pg_hstore::macros::diff;
+
+const USAGE: &str = "\
+Usage: hstore-cli <command> [args]
+
+Commands:
+    to-json [LITERAL]       Convert an hstore literal to a JSON object
+    from-json [JSON]        Convert a JSON object to an hstore literal
+    validate [LITERAL]      Check that a literal parses, exiting non-zero if not
+    diff LEFT RIGHT         Show the entries that differ between two literals
+    help                    Show this message
+
+LITERAL and JSON are read from stdin when missing or `-`.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = args.first().map(|s| s.as_str()).unwrap_or("help");
+    let rest = if args.is_empty() { &args[..] } else { &args[1..] };
+
+    let result = match command {
+        "to-json" => input(rest).and_then(|s| to_json(&s)),
+        "from-json" => input(rest).and_then(|s| from_json(&s)),
+        "validate" => input(rest).and_then(|s| validate(&s)),
+        "diff" if rest.len() == 2 => show_diff(&rest[0], &rest[1]),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(e) = result {
+        writeln!(io::stderr(), "{}", e).unwrap();
+        process::exit(1);
+    }
+}
+
+fn input(args: &[String]) -> Result<String, String> {
+    if args.len() > 1 {
+        return Err(USAGE.to_string());
+    }
+
+    match args.first() {
+        Some(s) if s != "-" => Ok(s.clone()),
+        _ => {
+            let mut s = String::new();
+            io::stdin().read_to_string(&mut s).map_err(|e| e.to_string())?;
+            Ok(s)
+        },
+    }
+}
+
+fn parse(literal: &str) -> Result<Hstore, String> {
+    text::parse(literal.trim()).map_err(|e| format!("invalid hstore: {}", e))
+}
+
+fn to_json(literal: &str) -> Result<(), String> {
+    let store = parse(literal)?;
+    let object: Map<String, Value> = store.into_iter()
+        .map(|(k, v)| (k, Value::String(v)))
+        .collect();
+    let json = serde_json::to_string_pretty(&Value::Object(object)).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn from_json(json: &str) -> Result<(), String> {
+    let object = match serde_json::from_str(json).map_err(|e| format!("invalid json: {}", e))? {
+        Value::Object(object) => object,
+        _ => return Err("invalid json: expected an object".to_string()),
+    };
+
+    let mut store = Hstore::with_capacity(object.len());
+    for (key, value) in object {
+        match value {
+            Value::Null => {},
+            Value::String(s) => { store.insert(key, s); },
+            Value::Bool(_) | Value::Number(_) => { store.insert(key, value.to_string()); },
+            _ => return Err(format!("invalid json: value of {:?} is not a scalar", key)),
+        }
+    }
+
+    println!("{}", text::format(&store));
+    Ok(())
+}
+
+fn validate(literal: &str) -> Result<(), String> {
+    let store = parse(literal)?;
+    println!("ok: {} entries", store.len());
+    Ok(())
+}
+
+fn show_diff(left: &str, right: &str) -> Result<(), String> {
+    let left = parse(left)?;
+    let right = parse(right)?;
+    match diff(&left, &right) {
+        Some(diff) => print!("{}", diff),
+        None => println!("no differences"),
+    }
+    Ok(())
+}
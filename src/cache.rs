@@ -0,0 +1,258 @@
+//! Caching hstore rows read on every request.
+//!
+//! A [`CachedTable`](struct.CachedTable.html) reads the hstore column of a table by id through a
+//! cache, as [`FrozenHstore`](../frozen/struct.FrozenHstore.html)s which are cheap to hand out,
+//! and keeps the cache up to date when rows are written through it:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::cache::CachedTable;
+//!
+//! let cache = moka::sync::Cache::builder()
+//!     .max_capacity(10_000)
+//!     .time_to_live(Duration::from_secs(60))
+//!     .build();
+//! let settings = CachedTable::new("tenants", cache).store("settings");
+//!
+//! let theme = settings.get(&db, tenant_id)?.and_then(|s| s.get("theme").cloned());
+//! settings.apply(&db, tenant_id, &HstorePatch::new().set("theme", "dark"))?;
+//! ```
+//!
+//! Any cache implementing [`HstoreCache`](trait.HstoreCache.html) can be used. With the `moka`
+//! feature, it is implemented for moka's `sync::Cache`.
+//!
+//! The cache only sees writes made through the `CachedTable`, including its
+//! [`update_returning_diff`](struct.CachedTable.html#method.update_returning_diff) and
+//! [`run_diffing`](struct.CachedTable.html#method.run_diffing), which invalidate the rows they
+//! change. Rows changed any other way are stale until they expire or are
+//! [invalidated](struct.CachedTable.html#method.invalidate), so give the cache a time to live
+//! if other processes write to the table. Tables must have an integer `id` column.
+//!
+//! Reads and writes made through the same `CachedTable` at the same time are kept in order: a
+//! `get` which read a row before a concurrent write doesn't cache what it read, and of two
+//! concurrent writes to a row, neither is cached.
+//!
+//! Inside a transaction, the cache is bypassed: `get` reads the database without caching the
+//! row, as it may see the transaction's own writes, and writes only invalidate the rows they
+//! change, as they may be rolled back. Another connection can still cache a row's old value
+//! before the transaction commits, so [invalidate](struct.CachedTable.html#method.invalidate)
+//! the rows written once it has. Transactions begun with `BEGIN` in SQL, rather than through
+//! Diesel, aren't seen.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use diesel::connection::TransactionManager;
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::types::BigInt;
+
+use super::Hstore;
+use super::frozen::FrozenHstore;
+use super::patch::{self, DiffingUpdate, HstorePatch};
+use super::quote::{quote_ident, quote_name};
+
+/// A cache of hstores by row id.
+pub trait HstoreCache {
+    /// The cached hstore of row `id`
+    fn get(&self, id: i64) -> Option<FrozenHstore>;
+
+    /// Cache `hstore` as the hstore of row `id`.
+    fn insert(&self, id: i64, hstore: FrozenHstore);
+
+    /// Forget row `id`.
+    fn invalidate(&self, id: i64);
+}
+
+/// Reads and writes the hstore column of a table through a cache.
+pub struct CachedTable<C> {
+    table: String,
+    store: String,
+    cache: C,
+    /// The number of writes made through this table to each row, to tell whether a row was
+    /// written while it was being read or written
+    generations: Mutex<HashMap<i64, u64>>,
+}
+
+impl<C: HstoreCache> CachedTable<C> {
+    /// Cache the `store` column of `table` in `cache`.
+    pub fn new<T: Into<String>>(table: T, cache: C) -> CachedTable<C> {
+        CachedTable {
+            table: table.into(),
+            store: "store".into(),
+            cache: cache,
+            generations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache the hstore column `store` instead.
+    pub fn store<S: Into<String>>(mut self, store: S) -> CachedTable<C> {
+        self.store = store.into();
+        self
+    }
+
+    /// The cache
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// The hstore of row `id`, from the cache or else the database, or `None` if there is no
+    /// such row. Rows which don't exist aren't cached.
+    pub fn get(&self, conn: &PgConnection, id: i64) -> QueryResult<Option<FrozenHstore>> {
+        let in_transaction = in_transaction(conn);
+        if !in_transaction {
+            if let Some(hstore) = self.cache.get(id) {
+                return Ok(Some(hstore));
+            }
+        }
+
+        let seen = self.generation(id);
+        let query = format!(
+            "SELECT {} FROM {} WHERE id = ",
+            quote_ident(&self.store),
+            quote_name(&self.table),
+        );
+        let found = sql::<Hstore>(&query)
+            .bind::<BigInt, _>(id)
+            .get_result::<Hstore>(conn)
+            .optional()?;
+        Ok(found.map(|hstore| {
+            let hstore = FrozenHstore::new(hstore);
+            if !in_transaction {
+                self.fill(id, seen, &hstore);
+            }
+            hstore
+        }))
+    }
+
+    /// Replace the hstore of row `id`, and cache it as written to the database. Returns whether
+    /// the row exists.
+    pub fn set(&self, conn: &PgConnection, id: i64, hstore: Hstore) -> QueryResult<bool> {
+        let seen = self.generation(id);
+        let store = quote_ident(&self.store);
+        let query = format!(
+            "UPDATE {} SET {} = ",
+            quote_name(&self.table),
+            store,
+        );
+        let updated = sql::<Hstore>(&query)
+            .bind::<Hstore, _>(&hstore)
+            .sql(" WHERE id = ")
+            .bind::<BigInt, _>(id)
+            .sql(&format!(" RETURNING {}", store))
+            .get_result::<Hstore>(conn)
+            .optional()?;
+        let written = updated.map(FrozenHstore::new);
+        self.wrote(conn, id, seen, written.as_ref());
+        Ok(written.is_some())
+    }
+
+    /// Apply `patch` to row `id` in the database, and cache the result. Returns the new hstore,
+    /// or `None` if there is no such row.
+    pub fn apply(&self, conn: &PgConnection, id: i64, patch: &HstorePatch) -> QueryResult<Option<FrozenHstore>> {
+        let seen = self.generation(id);
+        let store = quote_ident(&self.store);
+        let query = format!(
+            "UPDATE {table} SET {store} = {patched} WHERE id = ",
+            table = quote_name(&self.table),
+            store = store,
+            patched = patch.to_sql(&store),
+        );
+        let updated = sql::<Hstore>(&query)
+            .bind::<BigInt, _>(id)
+            .sql(&format!(" RETURNING {}", store))
+            .get_result::<Hstore>(conn)
+            .optional()?;
+        let written = updated.map(FrozenHstore::new);
+        self.wrote(conn, id, seen, written.as_ref());
+        Ok(written)
+    }
+
+    /// [`patch::update_returning_diff`](../patch/fn.update_returning_diff.html) on this table,
+    /// invalidating the row.
+    pub fn update_returning_diff(&self, conn: &PgConnection, id: i64, patch: &HstorePatch) -> QueryResult<Option<HstorePatch>> {
+        let diff = patch::update_returning_diff(conn, &self.table, &self.store, id, patch);
+        self.invalidate(id);
+        diff
+    }
+
+    /// Run a [`DiffingUpdate`](../patch/struct.DiffingUpdate.html) of this table, invalidating
+    /// every row it changes.
+    pub fn run_diffing(&self, conn: &PgConnection, update: &DiffingUpdate) -> QueryResult<Vec<(i64, HstorePatch)>> {
+        let changed = update.run(conn)?;
+        for &(id, _) in &changed {
+            self.invalidate(id);
+        }
+        Ok(changed)
+    }
+
+    /// Forget row `id`, so it is read from the database next time.
+    pub fn invalidate(&self, id: i64) {
+        let mut generations = self.generations.lock().unwrap();
+        *generations.entry(id).or_insert(0) += 1;
+        self.cache.invalidate(id);
+    }
+
+    /// The number of writes to row `id` so far
+    fn generation(&self, id: i64) -> u64 {
+        self.generations.lock().unwrap().get(&id).cloned().unwrap_or(0)
+    }
+
+    /// Cache `hstore`, read from row `id` after `seen` writes, unless it has been written since
+    fn fill(&self, id: i64, seen: u64, hstore: &FrozenHstore) {
+        let generations = self.generations.lock().unwrap();
+        if generations.get(&id).cloned().unwrap_or(0) == seen {
+            self.cache.insert(id, hstore.clone());
+        }
+    }
+
+    /// Count a write to row `id`, made after `seen` writes. The hstore `written` is cached if
+    /// there is one and it is known to be the latest, and otherwise the row is forgotten.
+    fn wrote(&self, conn: &PgConnection, id: i64, seen: u64, written: Option<&FrozenHstore>) {
+        let mut generations = self.generations.lock().unwrap();
+        let generation = generations.entry(id).or_insert(0);
+        let latest = *generation == seen && !in_transaction(conn);
+        *generation += 1;
+
+        match written {
+            Some(hstore) if latest => self.cache.insert(id, hstore.clone()),
+            _ => self.cache.invalidate(id),
+        }
+    }
+}
+
+fn in_transaction(conn: &PgConnection) -> bool {
+    TransactionManager::<PgConnection>::get_transaction_depth(conn.transaction_manager()) > 0
+}
+
+impl<C> fmt::Debug for CachedTable<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachedTable")
+            .field("table", &self.table)
+            .field("store", &self.store)
+            .finish()
+    }
+}
+
+#[cfg(feature = "moka")]
+mod moka_impls {
+    use moka::sync::Cache;
+
+    use super::HstoreCache;
+    use super::super::frozen::FrozenHstore;
+
+    impl HstoreCache for Cache<i64, FrozenHstore> {
+        fn get(&self, id: i64) -> Option<FrozenHstore> {
+            Cache::get(self, &id)
+        }
+
+        fn insert(&self, id: i64, hstore: FrozenHstore) {
+            Cache::insert(self, id, hstore)
+        }
+
+        fn invalidate(&self, id: i64) {
+            Cache::invalidate(self, &id)
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Partial updates of an hstore column in a single `UPDATE`.
+//!
+//! An [`HstoreChangeset`](struct.HstoreChangeset.html) sets and deletes keys in the database, as
+//! `(store - keys) || hstore(keys, values)`, so the rest of the hstore is never loaded or
+//! written back. Updates of different keys by concurrent transactions don't overwrite each
+//! other, as they would if each wrote the whole hstore it had read:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::changeset::HstoreChangeset;
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     products {
+//!         id -> Integer,
+//!         attributes -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let changes = HstoreChangeset::new()
+//!     .set("color", "red")
+//!     .delete("legacy_sku");
+//! let query = diesel::update(products::table.find(1))
+//!     .set(changes.on(products::attributes));
+//! # }
+//! ```
+//!
+//! [`apply`](../dsl/trait.HstoreOpExtensions.html#method.apply) gives the same as an expression,
+//! to be combined with other columns or other hstore expressions. For instance,
+//! `attributes.merged(new, strategy).apply(changes)` saves `new` with a
+//! [merge strategy](../merge/index.html), then applies `changes`.
+//!
+//! As with the other operators, the result is `NULL` for a `NULL` hstore.
+
+use diesel::dsl::Eq;
+use diesel::expression::{AppearsOnTable, Expression, NonAggregate, SelectableExpression};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::types::{Array, Text};
+
+use super::Hstore;
+use super::dsl::{HstoreOpExtensions, HstoreOrNullableHstore};
+use super::patch::HstorePatch;
+
+/// Keys to set and delete in the database.
+///
+/// This is an [`HstorePatch`](../patch/struct.HstorePatch.html) applied with bound parameters.
+/// When a key is both set and deleted, whichever was asked for last wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HstoreChangeset {
+    patch: HstorePatch,
+}
+
+impl HstoreChangeset {
+    /// Create an empty changeset, which leaves the hstore unchanged
+    pub fn new() -> HstoreChangeset {
+        HstoreChangeset::default()
+    }
+
+    /// Set `key` to `value`.
+    pub fn set<K, V>(mut self, key: K, value: V) -> HstoreChangeset
+        where K: Into<String>,
+              V: Into<String>
+    {
+        self.patch = self.patch.set(key, value);
+        self
+    }
+
+    /// Delete `key`.
+    pub fn delete<K: Into<String>>(mut self, key: K) -> HstoreChangeset {
+        self.patch = self.patch.delete(key);
+        self
+    }
+
+    /// The keys set and deleted
+    pub fn patch(&self) -> &HstorePatch {
+        &self.patch
+    }
+
+    /// Whether the changeset changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.patch.is_empty()
+    }
+
+    /// Apply the changeset to an Hstore in memory, as the database would.
+    pub fn apply_to(&self, hstore: &mut Hstore) {
+        self.patch.apply_to(hstore)
+    }
+
+    /// The assignment of the changed hstore to `column`, `column = column.apply(self)`, for
+    /// `diesel::update(...).set(...)`.
+    pub fn on<C>(self, column: C) -> Eq<C, Applied<C>>
+        where C: Column + ExpressionMethods + Copy,
+              C::SqlType: HstoreOrNullableHstore
+    {
+        column.eq(column.apply(self))
+    }
+}
+
+impl From<HstorePatch> for HstoreChangeset {
+    fn from(patch: HstorePatch) -> HstoreChangeset {
+        HstoreChangeset {
+            patch: patch,
+        }
+    }
+}
+
+/// The return type of [`apply`](../dsl/trait.HstoreOpExtensions.html#method.apply)
+#[derive(Debug, Clone)]
+pub struct Applied<T> {
+    store: T,
+    changeset: HstoreChangeset,
+}
+
+impl<T> Applied<T> {
+    pub(crate) fn new(store: T, changeset: HstoreChangeset) -> Applied<T> {
+        Applied {
+            store: store,
+            changeset: changeset,
+        }
+    }
+}
+
+impl<T> Expression for Applied<T>
+    where T: Expression
+{
+    type SqlType = T::SqlType;
+}
+
+impl<T> QueryId for Applied<T> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T, QS> AppearsOnTable<QS> for Applied<T>
+    where T: AppearsOnTable<QS>
+{
+}
+
+impl<T, QS> SelectableExpression<QS> for Applied<T>
+    where T: SelectableExpression<QS>
+{
+}
+
+impl<T> NonAggregate for Applied<T>
+    where T: NonAggregate
+{
+}
+
+impl<T> QueryFragment<Pg> for Applied<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        let patch = &self.changeset.patch;
+        let deletes = !patch.deletes().is_empty();
+        let sets = !patch.sets().is_empty();
+
+        // (((store - keys) || hstore(keys, values)))
+        if sets {
+            out.push_sql("(");
+        }
+        if deletes {
+            out.push_sql("(");
+        }
+        self.store.walk_ast(out.reborrow())?;
+        if deletes {
+            out.push_sql(" - ");
+            out.push_bind_param::<Array<Text>, _>(&patch.deletes().iter().collect::<Vec<_>>())?;
+            out.push_sql(")");
+        }
+        if sets {
+            out.push_sql(" || hstore(");
+            out.push_bind_param::<Array<Text>, _>(&patch.sets().keys().collect::<Vec<_>>())?;
+            out.push_sql(", ");
+            out.push_bind_param::<Array<Text>, _>(&patch.sets().values().collect::<Vec<_>>())?;
+            out.push_sql("))");
+        }
+        Ok(())
+    }
+}
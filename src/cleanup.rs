@@ -0,0 +1,139 @@
+//! Cleaning up the hstores of a whole table.
+//!
+//! A [`Cleanup`](struct.Cleanup.html) lists cleanups to apply to every row of a table, and
+//! generates a single `UPDATE` performing them, only touching rows which change. Its dry run
+//! counterpart counts those rows without changing anything:
+//!
+//! ```rust
+//! use diesel_pg_hstore::cleanup::Cleanup;
+//!
+//! let cleanup = Cleanup::new("products", "attributes")
+//!     .trim_values()
+//!     .remove_empty_values()
+//!     .remove_keys_matching("^tmp_");
+//!
+//! let update = cleanup.update_sql();
+//! let dry_run = cleanup.dry_run_sql();
+//! // let would_change = cleanup.dry_run(&db)?;
+//! // let changed = cleanup.run(&db)?;
+//! ```
+//!
+//! Cleanups are applied in the order they are listed, so the example above also removes values
+//! which were only whitespace. Patterns are Postgres regular expressions, matched with `~`.
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::types::BigInt;
+
+use super::quote::{quote_ident, quote_literal, quote_name};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    RemoveEmptyValues,
+    TrimValues,
+    RemoveKeysMatching(String),
+}
+
+/// Cleanups to apply to an hstore column across a whole table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cleanup {
+    table: String,
+    store: String,
+    steps: Vec<Step>,
+}
+
+impl Cleanup {
+    /// Clean up the hstore column `store` of `table`.
+    pub fn new<T, S>(table: T, store: S) -> Cleanup
+        where T: Into<String>,
+              S: Into<String>
+    {
+        Cleanup {
+            table: table.into(),
+            store: store.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Remove keys whose value is the empty string.
+    pub fn remove_empty_values(mut self) -> Cleanup {
+        self.steps.push(Step::RemoveEmptyValues);
+        self
+    }
+
+    /// Trim leading and trailing spaces from every value.
+    pub fn trim_values(mut self) -> Cleanup {
+        self.steps.push(Step::TrimValues);
+        self
+    }
+
+    /// Remove keys matching the regular expression `pattern`.
+    pub fn remove_keys_matching<P: Into<String>>(mut self, pattern: P) -> Cleanup {
+        self.steps.push(Step::RemoveKeysMatching(pattern.into()));
+        self
+    }
+
+    /// The cleaned up hstore, as an SQL expression over the column
+    pub fn expression_sql(&self) -> String {
+        let mut expr = quote_ident(&self.store);
+        for step in &self.steps {
+            expr = match *step {
+                Step::RemoveEmptyValues => format!(
+                    "({0} - ARRAY(SELECT key FROM each({0}) WHERE value = ''))",
+                    expr,
+                ),
+                Step::TrimValues => format!(
+                    "coalesce((SELECT hstore(array_agg(key), array_agg(trim(value))) FROM each({0})), ''::hstore)",
+                    expr,
+                ),
+                Step::RemoveKeysMatching(ref pattern) => format!(
+                    "({0} - ARRAY(SELECT k FROM skeys({0}) AS k WHERE k ~ {1}))",
+                    expr,
+                    quote_literal(pattern),
+                ),
+            };
+        }
+        expr
+    }
+
+    fn changed_sql(&self) -> String {
+        format!("{} <> {}", quote_ident(&self.store), self.expression_sql())
+    }
+
+    /// The statement applying the cleanups
+    pub fn update_sql(&self) -> String {
+        format!(
+            "UPDATE {} SET {} = {} WHERE {}",
+            quote_name(&self.table),
+            quote_ident(&self.store),
+            self.expression_sql(),
+            self.changed_sql(),
+        )
+    }
+
+    /// The query counting the rows the cleanups would change
+    pub fn dry_run_sql(&self) -> String {
+        format!(
+            "SELECT count(*) FROM {} WHERE {}",
+            quote_name(&self.table),
+            self.changed_sql(),
+        )
+    }
+
+    /// Apply the cleanups, returning the number of rows changed.
+    pub fn run(&self, conn: &PgConnection) -> QueryResult<usize> {
+        if self.steps.is_empty() {
+            return Ok(0);
+        }
+        conn.execute(&self.update_sql())
+    }
+
+    /// Count the rows the cleanups would change, without changing them.
+    pub fn dry_run(&self, conn: &PgConnection) -> QueryResult<i64> {
+        if self.steps.is_empty() {
+            return Ok(0);
+        }
+        sql::<BigInt>(&self.dry_run_sql()).get_result(conn)
+    }
+}
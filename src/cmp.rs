@@ -0,0 +1,96 @@
+//! Comparing hstores with other maps and with lists of pairs.
+
+use std::collections::{HashMap, HashSet};
+
+use super::Hstore;
+
+fn eq_pairs<K, V>(hstore: &Hstore, pairs: &[(K, V)]) -> bool
+    where K: AsRef<str>,
+          V: AsRef<str>
+{
+    if pairs.len() != hstore.len() {
+        return false;
+    }
+
+    let mut seen = HashSet::with_capacity(pairs.len());
+    pairs.iter().all(|&(ref key, ref value)| {
+        let key = key.as_ref();
+        seen.insert(key) && hstore.0.get(key).map_or(false, |v| v == value.as_ref())
+    })
+}
+
+impl PartialEq<HashMap<String, String>> for Hstore {
+    fn eq(&self, other: &HashMap<String, String>) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<Hstore> for HashMap<String, String> {
+    fn eq(&self, other: &Hstore) -> bool {
+        *self == other.0
+    }
+}
+
+/// Lets test assertions compare an Hstore directly with the entries it should hold:
+///
+/// ```rust
+/// use diesel_pg_hstore::Hstore;
+///
+/// let mut store = Hstore::new();
+/// store.insert("a".into(), "1".into());
+/// store.insert("b".into(), "2".into());
+///
+/// assert_eq!(store, [("a", "1"), ("b", "2")]);
+/// assert_eq!(store, [("b", "2"), ("a", "1")]);
+/// assert_eq!(store, *store.clone());
+/// assert_ne!(store, [("a", "1")]);
+/// ```
+///
+/// A list of pairs, whether a slice, an array or a `Vec`, is equal to an Hstore if it has the
+/// same entries in any order. A list with two pairs for the same key is never equal.
+impl<K, V> PartialEq<[(K, V)]> for Hstore
+    where K: AsRef<str>,
+          V: AsRef<str>
+{
+    fn eq(&self, other: &[(K, V)]) -> bool {
+        eq_pairs(self, other)
+    }
+}
+
+impl<'a, K, V> PartialEq<&'a [(K, V)]> for Hstore
+    where K: AsRef<str>,
+          V: AsRef<str>
+{
+    fn eq(&self, other: &&'a [(K, V)]) -> bool {
+        eq_pairs(self, other)
+    }
+}
+
+impl<K, V> PartialEq<Vec<(K, V)>> for Hstore
+    where K: AsRef<str>,
+          V: AsRef<str>
+{
+    fn eq(&self, other: &Vec<(K, V)>) -> bool {
+        eq_pairs(self, other)
+    }
+}
+
+macro_rules! eq_arrays {
+    ($($n:expr)*) => {
+        $(
+            impl<K, V> PartialEq<[(K, V); $n]> for Hstore
+                where K: AsRef<str>,
+                      V: AsRef<str>
+            {
+                fn eq(&self, other: &[(K, V); $n]) -> bool {
+                    eq_pairs(self, other)
+                }
+            }
+        )*
+    }
+}
+
+eq_arrays! {
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16
+    17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+}
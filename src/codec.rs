@@ -0,0 +1,356 @@
+//! The hstore binary wire format.
+//!
+//! This module is only public when the `bench-internals` feature is enabled, so the codec can be
+//! benchmarked and profiled without a database round trip. It is not covered by semver.
+//!
+//! Encoding sizes the output once from the lengths of the keys and values, rather than growing
+//! it entry by entry. Decoding reads the entries straight from the value, checks each key and
+//! value is UTF-8 once, and sizes the map from the entry count.
+
+use std::cmp;
+use std::str;
+use std::sync::Arc;
+use std::error::Error as StdError;
+use std::collections::HashMap;
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian};
+
+use super::Hstore;
+use super::metrics::{self, CodecObserver};
+use super::error::{DecodeError, DecodeErrorKind};
+use super::sim;
+use super::value_codec::ValueCodec;
+
+/// Append the binary representation of `hstore` to `buf`.
+pub fn encode(hstore: &Hstore, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>> {
+    encode_in_order(hstore, false, None, buf)
+}
+
+/// Append the binary representation of `hstore` to `buf`, passing each value through
+/// `value_codec`.
+pub(crate) fn encode_with(hstore: &Hstore, value_codec: &ValueCodec, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>> {
+    encode_in_order(hstore, false, Some(value_codec), buf)
+}
+
+/// Append the binary representation of `hstore` to `buf`, with the entries in the order
+/// Postgres keeps them, so equal hstores always encode to identical bytes.
+pub fn encode_sorted(hstore: &Hstore, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>> {
+    encode_in_order(hstore, true, None, buf)
+}
+
+fn encode_in_order(hstore: &Hstore, sorted: bool, value_codec: Option<&ValueCodec>, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>> {
+    if sorted {
+        write_hstore(sim::each(hstore).into_iter(), value_codec, buf)
+    }
+    else {
+        write_hstore(hstore.0.iter().map(|(k, v)| (k.as_str(), v.as_str())), value_codec, buf)
+    }
+}
+
+/// Append the binary representation of the entries of any map to `buf`.
+pub(crate) fn encode_map<'a, I>(entries: I, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>>
+    where I: Iterator<Item = (&'a String, &'a String)> + Clone
+{
+    write_hstore(entries.map(|(k, v)| (k.as_str(), v.as_str())), None, buf)
+}
+
+/// Append the entry count and the entries to `buf`
+fn write_hstore<'a, I>(entries: I, value_codec: Option<&ValueCodec>, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>>
+    where I: Iterator<Item = (&'a str, &'a str)> + Clone
+{
+    let observer = metrics::observer();
+
+    buf.reserve(encoded_len(entries.clone().map(|(k, v)| (k, Some(v)))));
+    let start = buf.len();
+    buf.extend_from_slice(&[0; 4]);
+
+    let count = write_entries(entries, value_codec, &observer, buf)?;
+
+    (&mut buf[start..start + 4])
+        .write_i32::<BigEndian>(count)
+        .unwrap();
+
+    if let Some(observer) = observer {
+        observer.encoded(count as usize);
+    }
+
+    Ok(())
+}
+
+/// The size of the encoded entries, exact unless a value codec changes the values
+fn encoded_len<'a, I>(entries: I) -> usize
+    where I: Iterator<Item = (&'a str, Option<&'a str>)>
+{
+    entries.fold(4, |len, (key, value)| len + 8 + key.len() + value.map_or(0, str::len))
+}
+
+/// Append each entry to `buf`, returning the number written
+fn write_entries<'a, I>(entries: I, value_codec: Option<&ValueCodec>, observer: &Option<Arc<CodecObserver>>, buf: &mut Vec<u8>) -> Result<i32, Box<StdError + Send + Sync>>
+    where I: Iterator<Item = (&'a str, &'a str)>
+{
+    let mut count = 0;
+    for (key, value) in entries {
+        count += 1;
+
+        let value = match value_codec {
+            Some(value_codec) => value_codec.encode(key, value)?,
+            None => value.into(),
+        };
+
+        check_size(observer, key, &value);
+
+        write_pascal_string(key, buf)?;
+        write_pascal_string(&value, buf)?;
+    }
+    Ok(count)
+}
+
+/// Decode an Hstore from its binary representation.
+///
+/// Entries having a null value are ignored, unlike [`decode_nullable`](fn.decode_nullable.html).
+/// Errors are [`DecodeError`](../error/struct.DecodeError.html)s.
+pub fn decode(buf: &[u8]) -> Result<Hstore, Box<StdError + Send + Sync>> {
+    decode_map(buf).map(Hstore)
+}
+
+/// Decode an Hstore from its binary representation, passing each value through `value_codec`.
+pub(crate) fn decode_with(buf: &[u8], value_codec: &ValueCodec) -> Result<Hstore, Box<StdError + Send + Sync>> {
+    decode_map_with(buf, Some(value_codec)).map(Hstore)
+}
+
+/// A map an hstore can be decoded into
+pub(crate) trait DecodeMap {
+    /// An empty map with room for `capacity` entries, if the map can reserve room
+    fn with_capacity(capacity: usize) -> Self;
+
+    fn insert_entry(&mut self, key: String, value: String);
+
+    fn entry_count(&self) -> usize;
+}
+
+impl DecodeMap for HashMap<String, String> {
+    fn with_capacity(capacity: usize) -> Self {
+        HashMap::with_capacity(capacity)
+    }
+
+    fn insert_entry(&mut self, key: String, value: String) {
+        self.insert(key, value);
+    }
+
+    fn entry_count(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Decode the entries of an hstore into any map, as for [`decode`](fn.decode.html).
+pub(crate) fn decode_map<M: DecodeMap>(buf: &[u8]) -> Result<M, Box<StdError + Send + Sync>> {
+    decode_map_with(buf, None)
+}
+
+fn decode_map_with<M: DecodeMap>(buf: &[u8], value_codec: Option<&ValueCodec>) -> Result<M, Box<StdError + Send + Sync>> {
+    let observer = metrics::observer();
+    let result = decode_entries::<M>(buf, value_codec, &observer);
+
+    if let Some(observer) = observer {
+        match result {
+            Ok(ref map) => observer.decoded(map.entry_count()),
+            Err(ref e) => observer.decode_failed(e),
+        }
+    }
+
+    Ok(result?)
+}
+
+fn decode_entries<M: DecodeMap>(buf: &[u8], value_codec: Option<&ValueCodec>, observer: &Option<Arc<CodecObserver>>) -> Result<M, DecodeError> {
+    let mut entries = EntryReader::new(buf)?;
+
+    let mut map = M::with_capacity(entries.capacity());
+
+    while let Some((k, v)) = entries.consume()? {
+        let v = match v {
+            Some(v) => v,
+            None => {
+                if let Some(ref observer) = *observer {
+                    observer.null_dropped(k);
+                }
+                continue;
+            },
+        };
+        check_size(observer, k, v);
+        let v = match value_codec {
+            Some(value_codec) => value_codec.decode(k, v)
+                .map_err(|e| entries.error(DecodeErrorKind::ValueCodec(e), entries.offset(), Some(k)))?
+                .into_owned(),
+            None => v.into(),
+        };
+        map.insert_entry(k.into(), v);
+    }
+
+    Ok(map)
+}
+
+/// Append the binary representation of an hstore whose values may be null to `buf`.
+pub fn encode_nullable(entries: &HashMap<String, Option<String>>, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Send + Sync>> {
+    let observer = metrics::observer();
+
+    buf.reserve(encoded_len(entries.iter().map(|(k, v)| (k.as_str(), v.as_ref().map(String::as_str)))));
+    buf.write_i32::<BigEndian>(entries.len() as i32).unwrap();
+    for (key, value) in entries {
+        write_pascal_string(key, buf)?;
+
+        let value = match *value {
+            Some(ref value) => value,
+            None => {
+                buf.write_i32::<BigEndian>(-1).unwrap();
+                continue;
+            }
+        };
+        check_size(&observer, key, value);
+        write_pascal_string(value, buf)?;
+    }
+
+    if let Some(observer) = observer {
+        observer.encoded(entries.len());
+    }
+    Ok(())
+}
+
+/// Decode an hstore from its binary representation, keeping entries having a null value.
+pub fn decode_nullable(buf: &[u8]) -> Result<HashMap<String, Option<String>>, Box<StdError + Send + Sync>> {
+    let observer = metrics::observer();
+    let result = decode_nullable_entries(buf, &observer);
+
+    if let Some(observer) = observer {
+        match result {
+            Ok(ref entries) => observer.decoded(entries.len()),
+            Err(ref e) => observer.decode_failed(e),
+        }
+    }
+
+    Ok(result?)
+}
+
+fn decode_nullable_entries(buf: &[u8], observer: &Option<Arc<CodecObserver>>) -> Result<HashMap<String, Option<String>>, DecodeError> {
+    let mut entries = EntryReader::new(buf)?;
+
+    let mut map = HashMap::with_capacity(entries.capacity());
+
+    while let Some((k, v)) = entries.consume()? {
+        if let Some(v) = v {
+            check_size(observer, k, v);
+        }
+        map.insert(k.into(), v.map(String::from));
+    }
+
+    Ok(map)
+}
+
+/// Report a value over the observer's size limit
+#[inline]
+fn check_size(observer: &Option<Arc<CodecObserver>>, key: &str, value: &str) {
+    if let Some(ref observer) = *observer {
+        if value.len() > observer.value_size_limit() {
+            observer.oversized_value(key, value.len());
+        }
+    }
+}
+
+fn write_pascal_string(s: &str, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Sync + Send>> {
+    let size: i32 = s.len() as i32;
+    buf.write_i32::<BigEndian>(size).unwrap();
+    buf.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// Reads the entries of an encoded hstore in place, borrowing keys and values from it
+struct EntryReader<'a> {
+    remaining: i32,
+    buf: &'a [u8],
+    /// The length of the whole value
+    len: usize,
+    /// The index of the next entry
+    entry: usize,
+    previous_key: Option<&'a str>,
+}
+
+impl<'a> EntryReader<'a> {
+    fn new(buf: &'a [u8]) -> Result<EntryReader<'a>, DecodeError> {
+        let mut entries = EntryReader {
+            remaining: 0,
+            buf: buf,
+            len: buf.len(),
+            entry: 0,
+            previous_key: None,
+        };
+
+        let count = entries.read_i32().map_err(|kind| DecodeError::new(kind, 0))?;
+        if count < 0 {
+            return Err(DecodeError::new(DecodeErrorKind::InvalidCount, 0));
+        }
+        entries.remaining = count;
+        Ok(entries)
+    }
+
+    /// The number of entries to allocate for. Each takes at least 8 bytes, so a corrupt count
+    /// can't allocate more than the value's size.
+    fn capacity(&self) -> usize {
+        cmp::min(self.remaining as usize, self.buf.len() / 8)
+    }
+
+    /// The offset of the unread bytes into the value
+    fn offset(&self) -> usize {
+        self.len - self.buf.len()
+    }
+
+    #[inline]
+    fn read_i32(&mut self) -> Result<i32, DecodeErrorKind> {
+        self.buf.read_i32::<BigEndian>().map_err(|_| DecodeErrorKind::Truncated)
+    }
+
+    /// An error at `offset` in the current entry, whose key is `key` if it has been read
+    fn error(&self, kind: DecodeErrorKind, offset: usize, key: Option<&str>) -> DecodeError {
+        DecodeError::new(kind, offset).in_entry(self.entry, key, self.previous_key)
+    }
+
+    /// The next entry, with `None` for a null value
+    #[inline]
+    fn consume(&mut self) -> Result<Option<(&'a str, Option<&'a str>)>, DecodeError> {
+        if self.remaining == 0 {
+            if !self.buf.is_empty() {
+                return Err(DecodeError::new(DecodeErrorKind::TrailingBytes, self.offset()));
+            }
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+
+        let offset = self.offset();
+        let key_len = self.read_i32().map_err(|kind| self.error(kind, offset, None))?;
+        if key_len < 0 || key_len as usize > self.buf.len() {
+            return Err(self.error(DecodeErrorKind::InvalidKeyLength, offset, None));
+        }
+        let (key, buf) = self.buf.split_at(key_len as usize);
+        let key = str::from_utf8(key)
+            .map_err(|_| self.error(DecodeErrorKind::InvalidUtf8, offset + 4, None))?;
+        self.buf = buf;
+
+        let offset = self.offset();
+        let value_len = self.read_i32().map_err(|kind| self.error(kind, offset, Some(key)))?;
+        let value = if value_len < 0 {
+            None
+        }
+        else if value_len as usize > self.buf.len() {
+            return Err(self.error(DecodeErrorKind::InvalidValueLength, offset, Some(key)));
+        }
+        else {
+            let (value, buf) = self.buf.split_at(value_len as usize);
+            let value = str::from_utf8(value)
+                .map_err(|_| self.error(DecodeErrorKind::InvalidUtf8, offset + 4, Some(key)))?;
+            self.buf = buf;
+            Some(value)
+        };
+
+        self.entry += 1;
+        self.previous_key = Some(key);
+        Ok(Some((key, value)))
+    }
+}
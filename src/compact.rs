@@ -0,0 +1,197 @@
+//! A compact binary format for caching hstores outside the database.
+//!
+//! [`Hstore::to_bytes`](../struct.Hstore.html#method.to_bytes) and
+//! [`from_bytes`](../struct.Hstore.html#method.from_bytes) read and write a layout defined by
+//! this crate, for stashing maps in Redis, memcached or files. It is not the Postgres wire
+//! format; the bytes hold the map exactly as it is in memory.
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("a".into(), "1".into());
+//!
+//! let bytes = store.to_bytes();
+//! assert_eq!(bytes, b"HS\x01\x01\x01a\x011");
+//! assert_eq!(Hstore::from_bytes(&bytes).unwrap(), store);
+//! ```
+//!
+//! The layout is the magic bytes `HS`, a version byte, then the number of entries followed by
+//! each key and value, sorted by key. The count and every length are unsigned LEB128 varints,
+//! and keys and values are UTF-8. The same map always encodes to the same bytes, so they can be
+//! compared or hashed.
+//!
+//! Decoding checks everything, as the bytes may come from anywhere: it fails rather than panics
+//! on truncated or malformed input, and never allocates more than the input's size suggests.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::str;
+
+use super::Hstore;
+
+/// The bytes every encoded hstore starts with
+pub const MAGIC: &[u8] = b"HS";
+
+/// The version of the layout written by [`to_bytes`](../struct.Hstore.html#method.to_bytes)
+pub const VERSION: u8 = 1;
+
+/// An error decoding bytes which aren't an encoded hstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes don't start with [`MAGIC`](constant.MAGIC.html).
+    NotAnHstore,
+    /// The bytes were written by a version of the layout this crate can't read.
+    UnsupportedVersion(u8),
+    /// The bytes end part way through.
+    Truncated,
+    /// A varint is longer than any length this platform can hold.
+    Overflow,
+    /// A key or value isn't UTF-8.
+    InvalidUtf8,
+    /// Keys aren't in strictly increasing order, so a key is repeated or the bytes were not
+    /// written by this crate.
+    Unsorted,
+    /// There are bytes after the last entry.
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::UnsupportedVersion(version) => write!(f, "unsupported hstore bytes version {}", version),
+            _ => f.write_str(self.description()),
+        }
+    }
+}
+
+impl StdError for DecodeError {
+    fn description(&self) -> &str {
+        match *self {
+            DecodeError::NotAnHstore => "bytes are not an encoded hstore",
+            DecodeError::UnsupportedVersion(_) => "unsupported hstore bytes version",
+            DecodeError::Truncated => "hstore bytes are truncated",
+            DecodeError::Overflow => "length in hstore bytes is too large",
+            DecodeError::InvalidUtf8 => "hstore bytes contain invalid UTF-8",
+            DecodeError::Unsorted => "hstore bytes have unsorted or repeated keys",
+            DecodeError::TrailingBytes => "hstore bytes have trailing data",
+        }
+    }
+}
+
+impl Hstore {
+    /// Encode in this crate's compact binary format. See the [`compact`](compact/index.html)
+    /// module.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Decode from this crate's compact binary format. See the [`compact`](compact/index.html)
+    /// module.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Hstore, DecodeError> {
+        decode(bytes)
+    }
+}
+
+/// Encode `hstore` in the compact binary format.
+pub fn encode(hstore: &Hstore) -> Vec<u8> {
+    let mut entries: Vec<_> = hstore.0.iter().collect();
+    entries.sort();
+
+    let size = entries.iter().map(|&(k, v)| k.len() + v.len() + 2).sum::<usize>();
+    let mut buf = Vec::with_capacity(MAGIC.len() + 1 + size + 1);
+    buf.extend_from_slice(MAGIC);
+    buf.push(VERSION);
+    write_varint(entries.len(), &mut buf);
+    for (key, value) in entries {
+        write_varint(key.len(), &mut buf);
+        buf.extend_from_slice(key.as_bytes());
+        write_varint(value.len(), &mut buf);
+        buf.extend_from_slice(value.as_bytes());
+    }
+    buf
+}
+
+/// Decode an hstore from the compact binary format.
+pub fn decode(bytes: &[u8]) -> Result<Hstore, DecodeError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(DecodeError::NotAnHstore);
+    }
+    let mut reader = Reader { bytes: &bytes[MAGIC.len()..] };
+    match reader.byte()? {
+        VERSION => {}
+        version => return Err(DecodeError::UnsupportedVersion(version)),
+    }
+
+    let count = reader.varint()?;
+    // Every entry takes at least two bytes, so a larger count can't be honest
+    if count > reader.bytes.len() / 2 {
+        return Err(DecodeError::Truncated);
+    }
+
+    let mut hstore = Hstore::new();
+    hstore.0.reserve(count);
+    let mut previous: Option<&str> = None;
+    for _ in 0..count {
+        let key = reader.string()?;
+        if previous.map_or(false, |previous| previous >= key) {
+            return Err(DecodeError::Unsorted);
+        }
+        previous = Some(key);
+
+        let value = reader.string()?;
+        hstore.0.insert(key.to_string(), value.to_string());
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(hstore)
+}
+
+fn write_varint(mut n: usize, buf: &mut Vec<u8>) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let (&byte, rest) = self.bytes.split_first().ok_or(DecodeError::Truncated)?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<usize, DecodeError> {
+        let mut n: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as usize;
+            if shift >= usize::max_value().count_ones() || (bits << shift) >> shift != bits {
+                return Err(DecodeError::Overflow);
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.varint()?;
+        if len > self.bytes.len() {
+            return Err(DecodeError::Truncated);
+        }
+        let (s, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        str::from_utf8(s).map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
@@ -0,0 +1,126 @@
+//! Compressing large values with deflate.
+//!
+//! Available with the `compression` feature. [`DeflateCodec`](struct.DeflateCodec.html) is a
+//! [`ValueCodec`](../value_codec/trait.ValueCodec.html) which compresses values at least as long
+//! as its threshold before they are sent to Postgres, and decompresses them when loaded. It
+//! applies to the columns bound and loaded as an
+//! [`HstoreWith`](../value_codec/struct.HstoreWith.html) naming it:
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate lazy_static;
+//! extern crate diesel_pg_hstore;
+//!
+//! use diesel_pg_hstore::compression::DeflateCodec;
+//! use diesel_pg_hstore::value_codec::{ColumnCodec, HstoreWith, ValueCodec};
+//!
+//! lazy_static! {
+//!     static ref DEFLATE: DeflateCodec = DeflateCodec::new(4096);
+//! }
+//!
+//! struct Compressed;
+//!
+//! impl ColumnCodec for Compressed {
+//!     fn value_codec() -> &'static ValueCodec {
+//!         &*DEFLATE
+//!     }
+//! }
+//!
+//! /// Bound and loaded in place of the `documents` column's Hstore.
+//! type Documents = HstoreWith<Compressed>;
+//! # fn main() {}
+//! ```
+//!
+//! Compressed values are stored as `deflate:` followed by the base64 encoded compressed bytes.
+//! A value is only stored compressed if that makes it shorter, unless it already starts with
+//! `deflate:`, in which case it is always compressed so it can't be mistaken for one which was.
+//!
+//! To combine compression with [encryption](../encryption/index.html), use a
+//! [`Chain`](../value_codec/struct.Chain.html) listing the compression codec first; encrypted
+//! values don't compress.
+
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::io::{Read, Write};
+
+use base64;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use super::value_codec::ValueCodec;
+
+const PREFIX: &str = "deflate:";
+
+/// A `ValueCodec` compressing large values with deflate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateCodec {
+    threshold: usize,
+    level: u32,
+}
+
+impl DeflateCodec {
+    /// Create a codec compressing values of at least `threshold` bytes.
+    pub fn new(threshold: usize) -> DeflateCodec {
+        DeflateCodec {
+            threshold: threshold,
+            level: Compression::default().level(),
+        }
+    }
+
+    /// Set the compression level, from 0 (none) to 9 (best).
+    pub fn with_level(mut self, level: u32) -> DeflateCodec {
+        self.level = level;
+        self
+    }
+
+    /// The size in bytes from which values are compressed
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    fn compress(&self, value: &str) -> Result<String, Box<StdError + Send + Sync>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(self.level));
+        encoder.write_all(value.as_bytes())?;
+        let compressed = encoder.finish()?;
+        Ok(format!("{}{}", PREFIX, base64::encode(&compressed)))
+    }
+}
+
+/// Compresses values of at least 1024 bytes.
+impl Default for DeflateCodec {
+    fn default() -> DeflateCodec {
+        DeflateCodec::new(1024)
+    }
+}
+
+impl ValueCodec for DeflateCodec {
+    fn encode<'a>(&self, _key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>> {
+        if value.starts_with(PREFIX) {
+            return Ok(self.compress(value)?.into());
+        }
+        if value.len() < self.threshold {
+            return Ok(value.into());
+        }
+
+        let compressed = self.compress(value)?;
+        if compressed.len() < value.len() {
+            Ok(compressed.into())
+        }
+        else {
+            Ok(value.into())
+        }
+    }
+
+    fn decode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>> {
+        if !value.starts_with(PREFIX) {
+            return Ok(value.into());
+        }
+
+        let compressed = base64::decode(&value[PREFIX.len()..])?;
+        let mut decompressed = String::new();
+        DeflateDecoder::new(&compressed[..]).read_to_string(&mut decompressed)
+            .map_err(|e| format!("failed to decompress the value of {:?}: {}", key, e))?;
+        Ok(decompressed.into())
+    }
+}
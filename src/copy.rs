@@ -0,0 +1,124 @@
+//! Hstore cells in the `COPY` text format.
+//!
+//! `COPY ... TO STDOUT` (and `pg_dump`) write each column's text representation with an extra
+//! layer of backslash escaping on top, and `\N` for null. These functions handle that layer so
+//! rows from a dump can be turned directly into Hstore values, and back again.
+//!
+//! ```rust
+//! use diesel_pg_hstore::copy;
+//!
+//! let line = "1\t\"note\"=>\"line one\\nline two\", \"path\"=>\"C:\\\\\\\\temp\"\t\\N";
+//! let fields: Vec<&str> = line.split('\t').collect();
+//!
+//! let store = copy::parse_cell(fields[1]).unwrap().unwrap();
+//! assert_eq!(store["note"], "line one\nline two");
+//! assert_eq!(store["path"], "C:\\temp");
+//!
+//! assert_eq!(copy::parse_cell(fields[2]).unwrap(), None);
+//! ```
+//!
+//! Splitting a line on the delimiter is safe, as delimiters occurring in data are always escaped.
+
+use super::Hstore;
+use super::text::{self, ParseError};
+
+/// Parse an hstore from a `COPY` text format field, returning `None` for `\N`.
+///
+/// Errors report positions within the unescaped hstore text.
+pub fn parse_cell(field: &str) -> Result<Option<Hstore>, ParseError> {
+    if field == "\\N" {
+        return Ok(None);
+    }
+
+    let literal = unescape(field)?;
+    text::parse(&literal).map(Some)
+}
+
+/// Format an hstore as a `COPY` text format field, suitable for `COPY ... FROM STDIN`.
+///
+/// `None` is written as `\N`. Tabs are escaped, so the default delimiter can be used.
+pub fn format_cell(hstore: Option<&Hstore>) -> String {
+    match hstore {
+        Some(hstore) => escape(&text::format(hstore)),
+        None => "\\N".to_string(),
+    }
+}
+
+/// Remove the `COPY` text format escaping from a field.
+///
+/// Handles the `\b`, `\f`, `\n`, `\r`, `\t` and `\v` escapes, octal (`\123`) and hex (`\x53`)
+/// byte escapes, and a backslash before any other character standing for that character.
+pub fn unescape(field: &str) -> Result<String, ParseError> {
+    let input = field.as_bytes();
+    let mut out = Vec::with_capacity(input.len());
+
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        i += 1;
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+
+        let c = match input.get(i) {
+            Some(&c) => c,
+            None => return Err(ParseError::new(field.len(), "unexpected end of field")),
+        };
+        i += 1;
+
+        let byte = match c {
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            c if c >= b'0' && c <= b'7' => {
+                let mut value = u32::from(c - b'0');
+                let mut digits = 1;
+                while digits < 3 && i < input.len() && input[i] >= b'0' && input[i] <= b'7' {
+                    value = value * 8 + u32::from(input[i] - b'0');
+                    digits += 1;
+                    i += 1;
+                }
+                value as u8
+            },
+            b'x' if i < input.len() && (input[i] as char).is_digit(16) => {
+                let mut value = 0;
+                let mut digits = 0;
+                while digits < 2 && i < input.len() {
+                    match (input[i] as char).to_digit(16) {
+                        Some(d) => value = value * 16 + d,
+                        None => break,
+                    }
+                    digits += 1;
+                    i += 1;
+                }
+                value as u8
+            },
+            c => c,
+        };
+        out.push(byte);
+    }
+
+    String::from_utf8(out).map_err(|e| ParseError::new(e.utf8_error().valid_up_to(), "invalid utf-8 in field"))
+}
+
+/// Apply the `COPY` text format escaping to a value.
+pub fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\u{b}' => out.push_str("\\v"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,167 @@
+//! Using hstore values as integer counters.
+//!
+//! [`Hstore::counters`](../struct.Hstore.html#method.counters) gives a
+//! [`Counters`](struct.Counters.html) view which parses values as `i64`, treating missing keys
+//! as zero:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::counters::Overflow;
+//!
+//! let mut views = Hstore::new();
+//! {
+//!     let mut counters = views.counters();
+//!     counters.incr("home", 3).unwrap();
+//!     counters.decr("home", 1).unwrap();
+//!     assert_eq!(counters.get_count("about").unwrap(), 0);
+//! }
+//! assert_eq!(views["home"], "2");
+//!
+//! let mut counters = views.counters().overflow(Overflow::Error);
+//! assert!(counters.incr("home", i64::max_value()).is_err());
+//! ```
+//!
+//! By default arithmetic saturates at the bounds of `i64`, and a value which isn't an integer is
+//! an error. Both can be changed with [`Overflow`](enum.Overflow.html) and
+//! [`Invalid`](enum.Invalid.html).
+//!
+//! To increment a counter in the database without loading it first, see
+//! [`incr`](../dsl/trait.HstoreOpExtensions.html#method.incr) in the `dsl` module.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use super::Hstore;
+
+/// What to do when arithmetic on a counter overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Clamp the result to `i64::min_value()` or `i64::max_value()`
+    Saturate,
+    /// Leave the counter unchanged and return an error
+    Error,
+}
+
+/// What to do with a value which isn't an integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invalid {
+    /// Leave the value unchanged and return an error
+    Error,
+    /// Treat the value as zero, replacing it on the next change
+    Zero,
+}
+
+/// A counter could not be read or changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CountError {
+    /// The value stored under `key` isn't an integer.
+    Invalid {
+        /// The key of the counter
+        key: String,
+        /// The value stored under it
+        value: String,
+    },
+    /// Changing the counter under `key` overflowed.
+    Overflow {
+        /// The key of the counter
+        key: String,
+    },
+}
+
+impl fmt::Display for CountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CountError::Invalid { ref key, ref value } =>
+                write!(f, "invalid count {:?} for hstore key {:?}", value, key),
+            CountError::Overflow { ref key } =>
+                write!(f, "count for hstore key {:?} overflowed", key),
+        }
+    }
+}
+
+impl StdError for CountError {
+    fn description(&self) -> &str {
+        match *self {
+            CountError::Invalid { .. } => "invalid hstore count",
+            CountError::Overflow { .. } => "hstore count overflowed",
+        }
+    }
+}
+
+/// A view of an Hstore's values as integer counters.
+#[derive(Debug)]
+pub struct Counters<'a> {
+    store: &'a mut Hstore,
+    overflow: Overflow,
+    invalid: Invalid,
+}
+
+impl<'a> Counters<'a> {
+    /// Set what happens when arithmetic overflows. Defaults to `Overflow::Saturate`.
+    pub fn overflow(mut self, overflow: Overflow) -> Counters<'a> {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Set what happens to values which aren't integers. Defaults to `Invalid::Error`.
+    pub fn invalid(mut self, invalid: Invalid) -> Counters<'a> {
+        self.invalid = invalid;
+        self
+    }
+
+    /// The count stored under `key`, or zero if there is none.
+    pub fn get_count(&self, key: &str) -> Result<i64, CountError> {
+        let value = match self.store.get(key) {
+            Some(value) => value,
+            None => return Ok(0),
+        };
+
+        match (value.parse(), self.invalid) {
+            (Ok(count), _) => Ok(count),
+            (Err(_), Invalid::Zero) => Ok(0),
+            (Err(_), Invalid::Error) => Err(CountError::Invalid {
+                key: key.to_string(),
+                value: value.clone(),
+            }),
+        }
+    }
+
+    /// Add `n` to the count stored under `key`, returning the new count.
+    pub fn incr(&mut self, key: &str, n: i64) -> Result<i64, CountError> {
+        let count = self.get_count(key)?;
+        let count = match (count.checked_add(n), self.overflow) {
+            (Some(count), _) => count,
+            (None, Overflow::Saturate) => count.saturating_add(n),
+            (None, Overflow::Error) => return Err(CountError::Overflow { key: key.to_string() }),
+        };
+
+        self.store.insert(key.to_string(), count.to_string());
+        Ok(count)
+    }
+
+    /// Subtract `n` from the count stored under `key`, returning the new count.
+    pub fn decr(&mut self, key: &str, n: i64) -> Result<i64, CountError> {
+        let count = self.get_count(key)?;
+        let count = match (count.checked_sub(n), self.overflow) {
+            (Some(count), _) => count,
+            (None, Overflow::Saturate) => count.saturating_sub(n),
+            (None, Overflow::Error) => return Err(CountError::Overflow { key: key.to_string() }),
+        };
+
+        self.store.insert(key.to_string(), count.to_string());
+        Ok(count)
+    }
+}
+
+impl Hstore {
+    /// View the values as integer counters.
+    ///
+    /// Please see the [counters](counters/index.html) module.
+    pub fn counters(&mut self) -> Counters {
+        Counters {
+            store: self,
+            overflow: Overflow::Saturate,
+            invalid: Invalid::Error,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Exporting and importing hstores as CSV.
+//!
+//! With the `csv` feature, an hstore can be written as a two column CSV file of keys and values,
+//! and read back from one. The first line is the header `key,value`, and the keys are written in
+//! sorted order:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("color".into(), "red".into());
+//! store.insert("note".into(), "says \"hi\", twice".into());
+//!
+//! let csv = store.to_csv().unwrap();
+//! assert_eq!(csv, "key,value\ncolor,red\nnote,\"says \"\"hi\"\", twice\"\n");
+//! assert_eq!(Hstore::from_csv(&csv).unwrap(), store);
+//! ```
+//!
+//! [`write_table`](fn.write_table.html) exports every key of a whole table, a page of rows at a
+//! time, as `id,key,value` lines.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+use csv;
+use diesel::pg::PgConnection;
+use diesel::result::Error as QueryError;
+
+use super::Hstore;
+use super::scan::Scan;
+
+impl Hstore {
+    /// Write as CSV, with the header `key,value`.
+    pub fn to_csv(&self) -> csv::Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        write_hstore(self, &mut writer)?;
+        let bytes = writer.into_inner().map_err(|e| io::Error::from(e.error().kind()))?;
+        Ok(String::from_utf8(bytes).expect("CSV of strings to be UTF-8"))
+    }
+
+    /// Read two column CSV, with a header line. When a key is repeated, the last value wins.
+    pub fn from_csv(s: &str) -> csv::Result<Hstore> {
+        read_hstore(&mut csv::Reader::from_reader(s.as_bytes()))
+    }
+}
+
+/// Write the header `key,value`, then the entries of `hstore` in sorted order.
+pub fn write_hstore<W: io::Write>(hstore: &Hstore, writer: &mut csv::Writer<W>) -> csv::Result<()> {
+    let mut entries: Vec<_> = hstore.iter().collect();
+    entries.sort();
+
+    writer.write_record(&["key", "value"])?;
+    for (key, value) in entries {
+        writer.write_record(&[key.as_str(), value.as_str()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read every record of a two column CSV file as an entry.
+///
+/// Whether the first line is a header is up to how `reader` was built.
+pub fn read_hstore<R: io::Read>(reader: &mut csv::Reader<R>) -> csv::Result<Hstore> {
+    let mut hstore = Hstore::new();
+    for record in reader.deserialize() {
+        let (key, value): (String, String) = record?;
+        hstore.insert(key, value);
+    }
+    Ok(hstore)
+}
+
+/// An error exporting a table.
+#[derive(Debug)]
+pub enum ExportError {
+    /// Reading the table failed.
+    Query(QueryError),
+    /// Writing the CSV failed.
+    Csv(csv::Error),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportError::Query(ref e) => write!(f, "reading the table failed: {}", e),
+            ExportError::Csv(ref e) => write!(f, "writing CSV failed: {}", e),
+        }
+    }
+}
+
+impl StdError for ExportError {
+    fn description(&self) -> &str {
+        match *self {
+            ExportError::Query(_) => "reading the table failed",
+            ExportError::Csv(_) => "writing CSV failed",
+        }
+    }
+}
+
+impl From<QueryError> for ExportError {
+    fn from(e: QueryError) -> ExportError {
+        ExportError::Query(e)
+    }
+}
+
+impl From<csv::Error> for ExportError {
+    fn from(e: csv::Error) -> ExportError {
+        ExportError::Csv(e)
+    }
+}
+
+/// Write every key of the hstore column `store` of `table` as CSV, with the header
+/// `id,key,value`, ordered by id then key. Returns the number of lines written after the header.
+///
+/// Rows are read a page at a time with a [`Scan`](../scan/struct.Scan.html), so the table must
+/// have an integer `id` column.
+pub fn write_table<W: io::Write>(conn: &PgConnection, table: &str, store: &str, out: W) -> Result<usize, ExportError> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(&["id", "key", "value"])?;
+
+    let scan = Scan::new(table).store(store);
+    let mut lines = 0;
+    for row in scan.rows(conn) {
+        let (id, hstore) = row?;
+        let id = id.to_string();
+
+        let mut entries: Vec<_> = hstore.iter().collect();
+        entries.sort();
+        for (key, value) in entries {
+            writer.write_record(&[id.as_str(), key.as_str(), value.as_str()])?;
+            lines += 1;
+        }
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(lines)
+}
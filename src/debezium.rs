@@ -0,0 +1,137 @@
+//! Reading hstores from Debezium change events.
+//!
+//! Debezium emits hstore columns in one of several shapes, depending on its
+//! `hstore.handling.mode` and converter settings:
+//!
+//! * `json` mode, the default, sends a JSON object encoded as a string:
+//!   `"{\"a\":\"1\",\"b\":null}"`.
+//! * `map` mode sends the map itself, which the JSON converter writes as an object:
+//!   `{"a":"1","b":null}`.
+//! * Some setups forward the Postgres text literal unchanged: `"a"=>"1", "b"=>NULL`.
+//!
+//! [`parse`](fn.parse.html) accepts the column's string in any of these, and
+//! [`from_value`](fn.from_value.html) accepts the column's field of an already parsed event:
+//!
+//! ```rust
+//! # extern crate diesel_pg_hstore;
+//! # extern crate serde_json;
+//! use diesel_pg_hstore::debezium;
+//!
+//! # fn main() {
+//! let store = debezium::parse(r#"{"color":"red","size":null}"#).unwrap();
+//! assert_eq!(store, [("color", "red")]);
+//!
+//! let store = debezium::parse(r#""color"=>"red", "size"=>NULL"#).unwrap();
+//! assert_eq!(store, [("color", "red")]);
+//!
+//! let event: serde_json::Value = serde_json::from_str(r#"{"after": {"attrs": "{\"color\":\"red\"}"}}"#).unwrap();
+//! let store = debezium::from_value(&event["after"]["attrs"]).unwrap().unwrap();
+//! assert_eq!(store, [("color", "red")]);
+//! # }
+//! ```
+//!
+//! As when loading from the database, null values are skipped.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use serde_json;
+use serde_json::{Map, Value};
+
+use super::Hstore;
+use super::text::{self, ParseError};
+
+/// An error reading an hstore from a change event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebeziumError {
+    /// The column looked like JSON, but isn't valid JSON.
+    Json(String),
+    /// The column looked like an hstore literal, but isn't a valid one.
+    Text(ParseError),
+    /// The JSON isn't an object or a string.
+    NotAnHstore,
+    /// A value in the JSON object isn't a string or null.
+    NonStringValue {
+        /// The key of the value
+        key: String,
+    },
+}
+
+impl fmt::Display for DebeziumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DebeziumError::Json(ref message) => write!(f, "invalid JSON hstore: {}", message),
+            DebeziumError::Text(ref e) => write!(f, "invalid hstore literal: {}", e),
+            DebeziumError::NotAnHstore => f.write_str("JSON value is not an hstore"),
+            DebeziumError::NonStringValue { ref key } => write!(f, "value of {:?} is not a string", key),
+        }
+    }
+}
+
+impl StdError for DebeziumError {
+    fn description(&self) -> &str {
+        match *self {
+            DebeziumError::Json(_) => "invalid JSON hstore",
+            DebeziumError::Text(_) => "invalid hstore literal",
+            DebeziumError::NotAnHstore => "JSON value is not an hstore",
+            DebeziumError::NonStringValue { .. } => "hstore value is not a string",
+        }
+    }
+}
+
+impl From<ParseError> for DebeziumError {
+    fn from(e: ParseError) -> DebeziumError {
+        DebeziumError::Text(e)
+    }
+}
+
+/// Parse the string Debezium sent for an hstore column: a JSON object, possibly itself encoded
+/// as a JSON string, or an hstore literal.
+///
+/// Unquoted hstore keys may start with `{`, so a string which isn't valid JSON is tried as a
+/// literal before failing.
+pub fn parse(s: &str) -> Result<Hstore, DebeziumError> {
+    let trimmed = s.trim_start();
+    if trimmed.starts_with('{') {
+        match serde_json::from_str::<Map<String, Value>>(s) {
+            Ok(object) => from_object(&object),
+            Err(e) => text::parse(s).map_err(|_| DebeziumError::Json(e.to_string())),
+        }
+    }
+    else if trimmed.starts_with('"') {
+        // A quoted string alone isn't an hstore literal, so one which decodes is JSON, as when an
+        // event is serialized twice
+        match serde_json::from_str::<String>(s) {
+            Ok(inner) => parse(&inner),
+            Err(_) => Ok(text::parse(s)?),
+        }
+    }
+    else {
+        Ok(text::parse(s)?)
+    }
+}
+
+/// Read an hstore column from a parsed change event. A JSON null, such as the column of a
+/// deleted row, is `None`.
+pub fn from_value(value: &Value) -> Result<Option<Hstore>, DebeziumError> {
+    match *value {
+        Value::Null => Ok(None),
+        Value::String(ref s) => parse(s).map(Some),
+        Value::Object(ref object) => from_object(object).map(Some),
+        _ => Err(DebeziumError::NotAnHstore),
+    }
+}
+
+fn from_object(object: &Map<String, Value>) -> Result<Hstore, DebeziumError> {
+    let mut hstore = Hstore::new();
+    for (key, value) in object {
+        match *value {
+            Value::String(ref value) => {
+                hstore.insert(key.clone(), value.clone());
+            }
+            Value::Null => {}
+            _ => return Err(DebeziumError::NonStringValue { key: key.clone() }),
+        }
+    }
+    Ok(hstore)
+}
@@ -0,0 +1,167 @@
+//! Serializing each distinct hstore of a bulk insert once.
+//!
+//! Rows of a batch often share a handful of hstores, such as the metadata of an ingestion run.
+//! An [`EncodeCache`](struct.EncodeCache.html) serializes each distinct hstore the first time
+//! it is seen and hands back an [`EncodedHstore`](struct.EncodedHstore.html) sharing those
+//! bytes, which binds like an Hstore:
+//!
+//! ```rust,ignore
+//! #[derive(Insertable)]
+//! #[table_name = "events"]
+//! struct NewEvent<'a> {
+//!     name: &'a str,
+//!     meta: EncodedHstore,
+//! }
+//!
+//! let mut cache = EncodeCache::new();
+//! let rows = incoming.iter()
+//!     .map(|e| Ok(NewEvent { name: &e.name, meta: cache.encode(&e.meta)? }))
+//!     .collect::<Result<Vec<_>, Box<Error + Send + Sync>>>()?;
+//! diesel::insert_into(events::table).values(&rows).execute(&db)?;
+//! ```
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error as StdError;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use super::Hstore;
+use super::codec;
+
+/// An hstore already serialized for binding, cheap to clone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedHstore(Arc<Vec<u8>>);
+
+impl EncodedHstore {
+    /// Serialize `hstore`
+    pub fn new(hstore: &Hstore) -> Result<EncodedHstore, Box<StdError + Send + Sync>> {
+        let mut buf = Vec::new();
+        codec::encode(hstore, &mut buf)?;
+        Ok(EncodedHstore(Arc::new(buf)))
+    }
+
+    /// Whether two EncodedHstores share the same bytes
+    pub fn ptr_eq(this: &EncodedHstore, other: &EncodedHstore) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+/// With the `zeroize` feature, the bytes are overwritten with zeros when the last clone is
+/// dropped, as an Hstore's are.
+#[cfg(feature = "zeroize")]
+impl Drop for EncodedHstore {
+    fn drop(&mut self) {
+        if let Some(buf) = Arc::get_mut(&mut self.0) {
+            ::zeroize::Zeroize::zeroize(buf);
+        }
+    }
+}
+
+/// Serializes each distinct hstore once. See the [dedup](index.html) module.
+#[derive(Debug, Default)]
+pub struct EncodeCache {
+    /// Each hstore seen and its bytes, by a hash of its sorted entries
+    encoded: HashMap<u64, Vec<(Hstore, EncodedHstore)>>,
+    hits: usize,
+}
+
+impl EncodeCache {
+    /// An empty cache
+    pub fn new() -> EncodeCache {
+        EncodeCache::default()
+    }
+
+    /// The serialized `hstore`, sharing the bytes of any equal hstore encoded before.
+    pub fn encode(&mut self, hstore: &Hstore) -> Result<EncodedHstore, Box<StdError + Send + Sync>> {
+        let candidates = self.encoded.entry(hash(hstore)).or_insert_with(Vec::new);
+        if let Some(&(_, ref encoded)) = candidates.iter().find(|&&(ref seen, _)| seen == hstore) {
+            self.hits += 1;
+            return Ok(encoded.clone());
+        }
+
+        let encoded = EncodedHstore::new(hstore)?;
+        candidates.push((hstore.clone(), encoded.clone()));
+        Ok(encoded)
+    }
+
+    /// The number of distinct hstores serialized
+    pub fn distinct(&self) -> usize {
+        self.encoded.values().map(Vec::len).sum()
+    }
+
+    /// The number of times an hstore was found already serialized
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
+
+fn hash(hstore: &Hstore) -> u64 {
+    let mut entries: Vec<_> = hstore.0.iter().collect();
+    entries.sort();
+
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::io::Write;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::types::*;
+
+    use super::EncodedHstore;
+    use super::super::Hstore;
+
+    impl AsExpression<Hstore> for EncodedHstore {
+        type Expression = Bound<Hstore, EncodedHstore>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a> AsExpression<Hstore> for &'a EncodedHstore {
+        type Expression = Bound<Hstore, &'a EncodedHstore>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl AsExpression<Nullable<Hstore>> for EncodedHstore {
+        type Expression = Bound<Nullable<Hstore>, EncodedHstore>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a> AsExpression<Nullable<Hstore>> for &'a EncodedHstore {
+        type Expression = Bound<Nullable<Hstore>, &'a EncodedHstore>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl ToSql<Hstore, Pg> for EncodedHstore {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            out.write_all(&self.0)?;
+            Ok(IsNull::No)
+        }
+    }
+
+    impl ToSql<Nullable<Hstore>, Pg> for EncodedHstore {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(self, out)
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Finding out where deprecated keys are still in use.
+//!
+//! Register the keys you want to retire with [`deprecate_key`](fn.deprecate_key.html), then pass
+//! the hstores you load to [`check`](fn.check.html). Each hstore holding a deprecated key is
+//! counted once per key in the [`report`](fn.report.html). With the `tracing` feature, a
+//! warning is also emitted for each of them.
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::deprecation;
+//!
+//! deprecation::deprecate_key("colour", Some("color"));
+//!
+//! let mut store = Hstore::new();
+//! store.insert("colour".into(), "red".into());
+//! assert_eq!(deprecation::check(&store), 1);
+//!
+//! let report = deprecation::report();
+//! assert_eq!(report[0].key(), "colour");
+//! assert_eq!(report[0].replacement(), Some("color"));
+//! assert_eq!(report[0].found(), 1);
+//! ```
+//!
+//! Nothing is checked unless you call `check`: decoding and looking up keys never consult the
+//! deprecated keys. The keys and counts are shared by the whole process.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::Hstore;
+
+#[derive(Debug)]
+struct Deprecation {
+    replacement: Option<String>,
+    found: AtomicUsize,
+}
+
+lazy_static! {
+    static ref DEPRECATED: RwLock<HashMap<String, Deprecation>> = RwLock::new(HashMap::new());
+}
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Mark `key` as deprecated, optionally naming the key which replaces it.
+///
+/// Deprecating a key again replaces its hint and resets its count.
+pub fn deprecate_key(key: &str, replacement: Option<&str>) {
+    let deprecation = Deprecation {
+        replacement: replacement.map(|r| r.to_string()),
+        found: AtomicUsize::new(0),
+    };
+    DEPRECATED.write().unwrap().insert(key.to_string(), deprecation);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Forget every deprecated key, along with its count.
+pub fn clear_deprecated_keys() {
+    ENABLED.store(false, Ordering::SeqCst);
+    DEPRECATED.write().unwrap().clear();
+}
+
+/// Count the deprecated keys `hstore` holds, returning how many it holds.
+pub fn check(hstore: &Hstore) -> usize {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return 0;
+    }
+
+    let deprecated = DEPRECATED.read().unwrap();
+    let mut found = 0;
+    if hstore.len() < deprecated.len() {
+        for key in hstore.keys() {
+            if let Some(deprecation) = deprecated.get(key) {
+                record(key, deprecation);
+                found += 1;
+            }
+        }
+    }
+    else {
+        for (key, deprecation) in deprecated.iter() {
+            if hstore.contains_key(key) {
+                record(key, deprecation);
+                found += 1;
+            }
+        }
+    }
+    found
+}
+
+fn record(key: &str, deprecation: &Deprecation) {
+    deprecation.found.fetch_add(1, Ordering::Relaxed);
+    warn(key, deprecation);
+}
+
+/// How often a deprecated key has been found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedKeyUse {
+    key: String,
+    replacement: Option<String>,
+    found: usize,
+}
+
+impl DeprecatedKeyUse {
+    /// The deprecated key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The key replacing it, if any
+    pub fn replacement(&self) -> Option<&str> {
+        self.replacement.as_ref().map(|r| r.as_str())
+    }
+
+    /// The number of checked hstores which held it
+    pub fn found(&self) -> usize {
+        self.found
+    }
+}
+
+/// The use of every deprecated key, sorted by key.
+pub fn report() -> Vec<DeprecatedKeyUse> {
+    let mut report: Vec<DeprecatedKeyUse> = DEPRECATED.read().unwrap().iter()
+        .map(|(key, deprecation)| DeprecatedKeyUse {
+            key: key.clone(),
+            replacement: deprecation.replacement.clone(),
+            found: deprecation.found.load(Ordering::Relaxed),
+        })
+        .collect();
+    report.sort_by(|a, b| a.key.cmp(&b.key));
+    report
+}
+
+#[cfg(feature = "tracing")]
+fn warn(key: &str, deprecation: &Deprecation) {
+    warn!(
+        key = key,
+        replacement = deprecation.replacement.as_ref().map(|r| r.as_str()).unwrap_or(""),
+        "deprecated hstore key found"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn warn(_key: &str, _deprecation: &Deprecation) {}
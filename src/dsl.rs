@@ -0,0 +1,1004 @@
+//! Hstore expressions for use with Diesel's query builder.
+//!
+//! Bring [`HstoreOpExtensions`](trait.HstoreOpExtensions.html) into scope to call these as
+//! methods on any hstore expression, such as a column:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::dsl::HstoreOpExtensions;
+//! use diesel_pg_hstore::list::ListCodec;
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     posts {
+//!         id -> Integer,
+//!         meta -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let codec = ListCodec::default();
+//! let query = diesel::update(posts::table.find(1))
+//!     .set(posts::meta.eq(posts::meta.list_append("tags", "rust", &codec)));
+//! # }
+//! ```
+//!
+//! The methods work the same on nullable columns, `Nullable<Hstore>` in `table!`. Their results
+//! are then nullable too, as each is `NULL` when the hstore is, so `has_key` is a
+//! `Nullable<Bool>` to load as an `Option<bool>`. `get_value` is always nullable, as missing
+//! keys are `NULL`, while `get_value_or` never is.
+//!
+//! This module is available with the `dsl` feature, which is enabled by default.
+//!
+//! The `diagnostics` feature, which needs Rust 1.78 or later, adds a hint to the compiler error
+//! reported when an expression which isn't an hstore is passed where one is required, such as to
+//! [`HstoreChangeset::on`](../changeset/struct.HstoreChangeset.html#method.on) or to your own
+//! helpers bounded by [`HstoreOrNullableHstore`](trait.HstoreOrNullableHstore.html). Calling a
+//! method on one instead reports the unsatisfied `HstoreOrNullableHstore` bound.
+
+use diesel::expression::{AppearsOnTable, AsExpression, Expression, NonAggregate, SelectableExpression};
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::types::{Array, BigInt, Bool, Integer, Nullable, Text};
+
+use super::Hstore;
+use super::changeset::{Applied, HstoreChangeset};
+use super::list::ListCodec;
+use super::localized::localized_key;
+use super::matrix::TextMatrix;
+use super::merge::{HstoreMergeStrategy, Merged};
+use super::window::{SincePrevious, Window};
+
+/// Methods available on every hstore expression.
+///
+/// This can also bound your own helpers taking any hstore expression.
+pub trait HstoreOpExtensions: Expression + Sized {
+    /// The value of `key`, or `NULL` if it is missing, `store -> key`.
+    ///
+    /// The key may be a Rust string, bound as a parameter, or any text expression, such as
+    /// another column:
+    ///
+    /// ```rust,ignore
+    /// posts::table
+    ///     .inner_join(fields::table)
+    ///     .select(posts::meta.get_value(fields::name))
+    /// ```
+    fn get_value<K>(self, key: K) -> GetValue<Self, K::Expression>
+        where K: AsExpression<Text>
+    {
+        GetValue {
+            store: self,
+            key: key.as_expression(),
+        }
+    }
+
+    /// The value of `key`, or `default` if it is missing or `NULL`,
+    /// `coalesce(store -> key, default)`.
+    ///
+    /// Unlike [`get_value`](#method.get_value), this is never `NULL`, so it loads as a `String`:
+    ///
+    /// ```rust,ignore
+    /// let colors: Vec<String> = products
+    ///     .select(attributes.get_value_or("color", "unknown"))
+    ///     .load(&db)?;
+    /// ```
+    fn get_value_or<K, D>(self, key: K, default: D) -> GetValueOr<Self, K::Expression, D::Expression>
+        where K: AsExpression<Text>,
+              D: AsExpression<Text>
+    {
+        GetValueOr {
+            store: self,
+            key: key.as_expression(),
+            default: default.as_expression(),
+        }
+    }
+
+    /// The values of `keys`, in the same order, with `NULL` for those missing,
+    /// `store -> ARRAY[keys]`. This loads as a `Vec<Option<String>>`.
+    fn get_array<K>(self, keys: K) -> GetArray<Self, K::Expression>
+        where K: AsExpression<Array<Text>>
+    {
+        GetArray {
+            store: self,
+            keys: keys.as_expression(),
+        }
+    }
+
+    /// The hstore with the entries of `other` added, replacing those with the same keys,
+    /// `store || other`. `other` may be an `Hstore` value, bound as a parameter, or another
+    /// hstore expression of the same nullability.
+    fn concat<O>(self, other: O) -> Concat<Self, O::Expression>
+        where O: AsExpression<Self::SqlType>
+    {
+        Concat {
+            store: self,
+            other: other.as_expression(),
+        }
+    }
+
+    /// Whether the hstore has `key`, `store ? key`. The key may be any text expression.
+    fn has_key<K>(self, key: K) -> HasKey<Self, K::Expression>
+        where K: AsExpression<Text>
+    {
+        HasKey {
+            store: self,
+            key: key.as_expression(),
+        }
+    }
+
+    /// The hstore without `key`, `store - key`. The key may be any text expression.
+    fn remove_key<K>(self, key: K) -> RemoveKey<Self, K::Expression>
+        where K: AsExpression<Text>
+    {
+        RemoveKey {
+            store: self,
+            key: key.as_expression(),
+        }
+    }
+
+    /// Whether the value of `key` is `value`, `store ? key AND store -> key = value`. This is
+    /// false when the key is missing, and null only when the store is.
+    ///
+    /// This can't use a GIN index on the column, but the equivalent containment,
+    /// `store @> hstore(key, value)`, can. A [`GinIndexes`](../gin/struct.GinIndexes.html)
+    /// rewrites it so for the columns it knows are indexed.
+    fn key_eq<K, V>(self, key: K, value: V) -> KeyEq<Self>
+        where K: Into<String>,
+              V: Into<String>
+    {
+        KeyEq {
+            store: self,
+            key: key.into(),
+            value: value.into(),
+            contains: false,
+        }
+    }
+
+    /// Whether the value of every key of `pairs` is the one in `pairs`, as for
+    /// [`key_eq`](#method.key_eq) on each pair. This is true of every hstore if `pairs` is empty,
+    /// and null for a null store.
+    ///
+    /// Like [`key_eq`](#method.key_eq), this is rewritten as `store @> pairs` by a
+    /// [`GinIndexes`](../gin/struct.GinIndexes.html) for the columns it knows are indexed.
+    fn filter_by_pairs(self, pairs: Hstore) -> FilterByPairs<Self> {
+        FilterByPairs {
+            store: self,
+            pairs: pairs,
+            contains: false,
+        }
+    }
+
+    /// Append an item to the list stored under `key`, creating the list if the key is missing.
+    ///
+    /// Please see the [list](../list/index.html) module for how lists are encoded.
+    fn list_append<K, I>(self, key: K, item: I, codec: &ListCodec) -> ListAppend<Self>
+        where K: Into<String>,
+              I: AsRef<str>
+    {
+        ListAppend {
+            store: self,
+            key: key.into(),
+            item: codec.escape_item(item.as_ref()),
+            delimiter: codec.delimiter().to_string(),
+        }
+    }
+
+    /// Remove every occurrence of an item from the list stored under `key`.
+    ///
+    /// The store is unchanged if the key is missing.
+    fn list_remove<K, I>(self, key: K, item: I, codec: &ListCodec) -> ListRemove<Self>
+        where K: Into<String>,
+              I: AsRef<str>
+    {
+        ListRemove {
+            store: self,
+            key: key.into(),
+            item: codec.escape_item(item.as_ref()),
+            delimiter: codec.delimiter().to_string(),
+        }
+    }
+
+    /// Add `n` to the counter stored under `key`, treating a missing key as zero.
+    ///
+    /// The result saturates at the bounds of a `bigint`. The statement fails if the stored value
+    /// isn't an integer. Please see the [counters](../counters/index.html) module for working
+    /// with counters once loaded.
+    fn incr<K>(self, key: K, n: i64) -> Incr<Self>
+        where K: Into<String>
+    {
+        Incr {
+            store: self,
+            key: key.into(),
+            n: n,
+        }
+    }
+
+    /// The entries added or changed since the previous row in `window`, with their new values.
+    ///
+    /// Please see the [window](../window/index.html) module.
+    fn added_since_previous<P, O>(self, window: Window<P, O>) -> SincePrevious<Self, P, O> {
+        SincePrevious::added(self, window)
+    }
+
+    /// The entries removed or changed since the previous row in `window`, with their old values.
+    ///
+    /// Please see the [window](../window/index.html) module.
+    fn removed_since_previous<P, O>(self, window: Window<P, O>) -> SincePrevious<Self, P, O> {
+        SincePrevious::removed(self, window)
+    }
+
+    /// Both [`added_since_previous`](#method.added_since_previous) and
+    /// [`removed_since_previous`](#method.removed_since_previous), ready to be selected together.
+    fn changes_since_previous<P, O>(self, window: Window<P, O>) -> (SincePrevious<Self, P, O>, SincePrevious<Self, P, O>)
+        where Self: Clone,
+              P: Clone,
+              O: Clone
+    {
+        (self.clone().added_since_previous(window.clone()), self.removed_since_previous(window))
+    }
+
+    /// The result of saving `new` over the hstore, as decided by `strategy`.
+    ///
+    /// Please see the [merge](../merge/index.html) module for the strategies.
+    fn merged<M>(self, new: Hstore, strategy: M) -> Merged<Self, M>
+        where M: HstoreMergeStrategy
+    {
+        Merged::new(self, new, strategy)
+    }
+
+    /// The hstore with the keys of `changeset` set and deleted,
+    /// `(store - keys) || hstore(keys, values)`. Please see the
+    /// [changeset](../changeset/index.html) module.
+    fn apply(self, changeset: HstoreChangeset) -> Applied<Self> {
+        Applied::new(self, changeset)
+    }
+
+    /// The first translation of `key` present, trying each locale in order, or `NULL`.
+    ///
+    /// This is `COALESCE(store -> 'key.locale', ...)`. Please see the
+    /// [localized](../localized/index.html) module for how translations are stored.
+    fn get_localized<I, L>(self, key: &str, locales: I) -> GetLocalized<Self>
+        where I: IntoIterator<Item = L>,
+              L: AsRef<str>
+    {
+        GetLocalized {
+            store: self,
+            keys: locales.into_iter().map(|locale| localized_key(key, locale.as_ref())).collect(),
+        }
+    }
+
+    /// The number of bytes the hstore takes to store, `pg_column_size(store)`.
+    ///
+    /// This is the size on disk, after any compression, so it is what to watch for rows whose
+    /// hstore has grown too large. Please see [`text_length`](#method.text_length) for the size
+    /// of the hstore as text.
+    fn stored_size(self) -> StoredSize<Self> {
+        StoredSize {
+            store: self,
+        }
+    }
+
+    /// The hstore as text, `(store)::text`.
+    ///
+    /// Hstores can't be compared without the btree operator class, so this is how to order, group
+    /// or select distinct rows by whole hstores. The result is an ordinary text expression, so it
+    /// can be compared or passed on like any other:
+    ///
+    /// ```rust,ignore
+    /// let stores = posts::table
+    ///     .select(posts::meta.cast_to_text())
+    ///     .distinct()
+    ///     .order(posts::meta.cast_to_text())
+    ///     .load::<String>(&db)?;
+    /// ```
+    ///
+    /// Postgres writes the entries in an order depending only on their keys, so equal hstores
+    /// always have the same text.
+    fn cast_to_text(self) -> CastToText<Self> {
+        CastToText {
+            store: self,
+        }
+    }
+
+    /// The entries as `{key, value}` rows of a two-dimensional array, `%# store`, which loads as
+    /// a `Vec<(String, Option<String>)>`.
+    ///
+    /// The entries are in the order Postgres keeps them. Please see the
+    /// [matrix](../matrix/index.html) module.
+    fn to_pairs(self) -> ToPairs<Self> {
+        ToPairs {
+            store: self,
+        }
+    }
+
+    /// The number of characters in the hstore as text, `length(store::text)`.
+    fn text_length(self) -> TextLength<Self> {
+        TextLength {
+            store: self,
+        }
+    }
+}
+
+impl<T> HstoreOpExtensions for T
+    where T: Expression,
+          T::SqlType: HstoreOrNullableHstore
+{
+}
+
+/// The SQL types of the expressions [`HstoreOpExtensions`](trait.HstoreOpExtensions.html) is
+/// implemented for, `Hstore` and `Nullable<Hstore>`.
+///
+/// The associated types are the SQL types of the operators' results, which are nullable for a
+/// nullable hstore. With the `diagnostics` feature, an expression of another SQL type is reported
+/// with a hint at the fix.
+#[cfg_attr(feature = "diagnostics", diagnostic::on_unimplemented(
+    message = "`{Self}` is not an hstore SQL type",
+    label = "expected an expression of SQL type `Hstore` or `Nullable<Hstore>`",
+    note = "columns must be declared `-> Hstore` in `table!`, with `use diesel_pg_hstore::table_import::*;`",
+    note = "text holding hstore literals can be cast with `HstoreTextExtensions::cast_to_hstore`"
+))]
+pub trait HstoreOrNullableHstore {
+    /// `Hstore`, or `Nullable<Hstore>`
+    type Hstore;
+    /// `Array<Nullable<Text>>`, or `Nullable<Array<Nullable<Text>>>`
+    type TextArray;
+    /// `Bool`, or `Nullable<Bool>`
+    type Bool;
+    /// `Integer`, or `Nullable<Integer>`
+    type Integer;
+    /// `Text`, or `Nullable<Text>`
+    type Text;
+    /// `TextMatrix`, or `Nullable<TextMatrix>`
+    type TextMatrix;
+}
+
+impl HstoreOrNullableHstore for Hstore {
+    type Hstore = Hstore;
+    type TextArray = Array<Nullable<Text>>;
+    type Bool = Bool;
+    type Integer = Integer;
+    type Text = Text;
+    type TextMatrix = TextMatrix;
+}
+
+impl HstoreOrNullableHstore for Nullable<Hstore> {
+    type Hstore = Nullable<Hstore>;
+    type TextArray = Nullable<Array<Nullable<Text>>>;
+    type Bool = Nullable<Bool>;
+    type Integer = Nullable<Integer>;
+    type Text = Nullable<Text>;
+    type TextMatrix = Nullable<TextMatrix>;
+}
+
+/// Methods available on every text expression.
+pub trait HstoreTextExtensions: Expression<SqlType = Text> + Sized {
+    /// Cast text holding an hstore literal, such as `"a"=>"1"`, to an hstore.
+    ///
+    /// This lets text columns be used with the hstore operators until they are migrated. The
+    /// statement fails if any value isn't a valid literal.
+    fn cast_to_hstore(self) -> CastToHstore<Self> {
+        CastToHstore {
+            text: self,
+        }
+    }
+}
+
+impl<T: Expression<SqlType = Text>> HstoreTextExtensions for T {}
+
+/// Implements the expression traits for a node of type `$sql_type` wrapping an expression `T`,
+/// and for nodes wrapping a second expression `K`. A type `nullable $sql_type` is the associated
+/// type of [`HstoreOrNullableHstore`](trait.HstoreOrNullableHstore.html) for `T`'s SQL type.
+macro_rules! hstore_expression {
+    (@traits $name:ident) => {
+        impl<T> QueryId for $name<T> {
+            type QueryId = ();
+
+            const HAS_STATIC_QUERY_ID: bool = false;
+        }
+
+        impl<T, QS> AppearsOnTable<QS> for $name<T>
+            where T: AppearsOnTable<QS>,
+                  $name<T>: Expression
+        {
+        }
+
+        impl<T, QS> SelectableExpression<QS> for $name<T>
+            where T: SelectableExpression<QS>,
+                  $name<T>: AppearsOnTable<QS>
+        {
+        }
+
+        impl<T> NonAggregate for $name<T>
+            where T: NonAggregate
+        {
+        }
+    };
+    (@traits $name:ident<T, K>) => {
+        impl<T, K> QueryId for $name<T, K> {
+            type QueryId = ();
+
+            const HAS_STATIC_QUERY_ID: bool = false;
+        }
+
+        impl<T, K, QS> AppearsOnTable<QS> for $name<T, K>
+            where T: AppearsOnTable<QS>,
+                  K: AppearsOnTable<QS>,
+                  $name<T, K>: Expression
+        {
+        }
+
+        impl<T, K, QS> SelectableExpression<QS> for $name<T, K>
+            where T: SelectableExpression<QS>,
+                  K: SelectableExpression<QS>,
+                  $name<T, K>: AppearsOnTable<QS>
+        {
+        }
+
+        impl<T, K> NonAggregate for $name<T, K>
+            where T: NonAggregate,
+                  K: NonAggregate
+        {
+        }
+    };
+    (@traits $name:ident<T, K, D>) => {
+        impl<T, K, D> QueryId for $name<T, K, D> {
+            type QueryId = ();
+
+            const HAS_STATIC_QUERY_ID: bool = false;
+        }
+
+        impl<T, K, D, QS> AppearsOnTable<QS> for $name<T, K, D>
+            where T: AppearsOnTable<QS>,
+                  K: AppearsOnTable<QS>,
+                  D: AppearsOnTable<QS>,
+                  $name<T, K, D>: Expression
+        {
+        }
+
+        impl<T, K, D, QS> SelectableExpression<QS> for $name<T, K, D>
+            where T: SelectableExpression<QS>,
+                  K: SelectableExpression<QS>,
+                  D: SelectableExpression<QS>,
+                  $name<T, K, D>: AppearsOnTable<QS>
+        {
+        }
+
+        impl<T, K, D> NonAggregate for $name<T, K, D>
+            where T: NonAggregate,
+                  K: NonAggregate,
+                  D: NonAggregate
+        {
+        }
+    };
+    ($name:ident, nullable $sql_type:ident) => {
+        impl<T> Expression for $name<T>
+            where T: Expression,
+                  T::SqlType: HstoreOrNullableHstore
+        {
+            type SqlType = <T::SqlType as HstoreOrNullableHstore>::$sql_type;
+        }
+
+        hstore_expression!(@traits $name);
+    };
+    ($name:ident<T, K>, nullable $sql_type:ident) => {
+        impl<T, K> Expression for $name<T, K>
+            where T: Expression,
+                  T::SqlType: HstoreOrNullableHstore,
+                  K: Expression
+        {
+            type SqlType = <T::SqlType as HstoreOrNullableHstore>::$sql_type;
+        }
+
+        hstore_expression!(@traits $name<T, K>);
+    };
+    ($name:ident, $sql_type:ty) => {
+        impl<T> Expression for $name<T>
+            where T: Expression
+        {
+            type SqlType = $sql_type;
+        }
+
+        hstore_expression!(@traits $name);
+    };
+    ($name:ident<T, K>, $sql_type:ty) => {
+        impl<T, K> Expression for $name<T, K>
+            where T: Expression,
+                  K: Expression
+        {
+            type SqlType = $sql_type;
+        }
+
+        hstore_expression!(@traits $name<T, K>);
+    };
+    ($name:ident<T, K, D>, $sql_type:ty) => {
+        impl<T, K, D> Expression for $name<T, K, D>
+            where T: Expression,
+                  K: Expression,
+                  D: Expression
+        {
+            type SqlType = $sql_type;
+        }
+
+        hstore_expression!(@traits $name<T, K, D>);
+    };
+}
+
+/// Renders `store <operator> key`, in parentheses
+fn walk_binary<T, K>(store: &T, operator: &str, key: &K, mut out: AstPass<Pg>) -> QueryResult<()>
+    where T: QueryFragment<Pg>,
+          K: QueryFragment<Pg>
+{
+    out.push_sql("(");
+    store.walk_ast(out.reborrow())?;
+    out.push_sql(operator);
+    key.walk_ast(out.reborrow())?;
+    out.push_sql(")");
+    Ok(())
+}
+
+/// The return type of [`get_value`](trait.HstoreOpExtensions.html#method.get_value)
+#[derive(Debug, Clone)]
+pub struct GetValue<T, K> {
+    store: T,
+    key: K,
+}
+
+hstore_expression!(GetValue<T, K>, Nullable<Text>);
+
+impl<T, K> QueryFragment<Pg> for GetValue<T, K>
+    where T: QueryFragment<Pg>,
+          K: QueryFragment<Pg>
+{
+    fn walk_ast(&self, out: AstPass<Pg>) -> QueryResult<()> {
+        walk_binary(&self.store, " -> ", &self.key, out)
+    }
+}
+
+/// The return type of [`get_value_or`](trait.HstoreOpExtensions.html#method.get_value_or)
+#[derive(Debug, Clone)]
+pub struct GetValueOr<T, K, D> {
+    store: T,
+    key: K,
+    default: D,
+}
+
+hstore_expression!(GetValueOr<T, K, D>, Text);
+
+impl<T, K, D> QueryFragment<Pg> for GetValueOr<T, K, D>
+    where T: QueryFragment<Pg>,
+          K: QueryFragment<Pg>,
+          D: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("coalesce(");
+        walk_binary(&self.store, " -> ", &self.key, out.reborrow())?;
+        out.push_sql(", ");
+        self.default.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The return type of [`get_array`](trait.HstoreOpExtensions.html#method.get_array)
+#[derive(Debug, Clone)]
+pub struct GetArray<T, K> {
+    store: T,
+    keys: K,
+}
+
+hstore_expression!(GetArray<T, K>, nullable TextArray);
+
+impl<T, K> QueryFragment<Pg> for GetArray<T, K>
+    where T: QueryFragment<Pg>,
+          K: QueryFragment<Pg>
+{
+    fn walk_ast(&self, out: AstPass<Pg>) -> QueryResult<()> {
+        walk_binary(&self.store, " -> ", &self.keys, out)
+    }
+}
+
+/// The return type of [`concat`](trait.HstoreOpExtensions.html#method.concat)
+#[derive(Debug, Clone)]
+pub struct Concat<T, O> {
+    store: T,
+    other: O,
+}
+
+hstore_expression!(Concat<T, K>, nullable Hstore);
+
+impl<T, O> QueryFragment<Pg> for Concat<T, O>
+    where T: QueryFragment<Pg>,
+          O: QueryFragment<Pg>
+{
+    fn walk_ast(&self, out: AstPass<Pg>) -> QueryResult<()> {
+        walk_binary(&self.store, " || ", &self.other, out)
+    }
+}
+
+/// The return type of [`has_key`](trait.HstoreOpExtensions.html#method.has_key)
+#[derive(Debug, Clone)]
+pub struct HasKey<T, K> {
+    store: T,
+    key: K,
+}
+
+hstore_expression!(HasKey<T, K>, nullable Bool);
+
+impl<T, K> QueryFragment<Pg> for HasKey<T, K>
+    where T: QueryFragment<Pg>,
+          K: QueryFragment<Pg>
+{
+    fn walk_ast(&self, out: AstPass<Pg>) -> QueryResult<()> {
+        walk_binary(&self.store, " ? ", &self.key, out)
+    }
+}
+
+/// The return type of [`key_eq`](trait.HstoreOpExtensions.html#method.key_eq)
+#[derive(Debug, Clone)]
+pub struct KeyEq<T> {
+    store: T,
+    key: String,
+    value: String,
+    pub(crate) contains: bool,
+}
+
+hstore_expression!(KeyEq, nullable Bool);
+
+impl<T> KeyEq<T> {
+    /// Whether this is rendered as containment, `store @> hstore(key, value)`
+    pub fn is_containment(&self) -> bool {
+        self.contains
+    }
+}
+
+impl<T> QueryFragment<Pg> for KeyEq<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(");
+        if self.contains {
+            self.store.walk_ast(out.reborrow())?;
+            out.push_sql(" @> hstore(");
+            out.push_bind_param::<Text, _>(&self.key)?;
+            out.push_sql(", ");
+            out.push_bind_param::<Text, _>(&self.value)?;
+            out.push_sql(")");
+        }
+        else {
+            walk_key_eq(&self.store, &self.key, &self.value, out.reborrow())?;
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// `store ? key AND store -> key = value`, which is false rather than null when the key is
+/// missing, as containment is
+fn walk_key_eq<T>(store: &T, key: &str, value: &str, mut out: AstPass<Pg>) -> QueryResult<()>
+    where T: QueryFragment<Pg>
+{
+    store.walk_ast(out.reborrow())?;
+    out.push_sql(" ? ");
+    out.push_bind_param::<Text, _>(&key)?;
+    out.push_sql(" AND ");
+    store.walk_ast(out.reborrow())?;
+    out.push_sql(" -> ");
+    out.push_bind_param::<Text, _>(&key)?;
+    out.push_sql(" = ");
+    out.push_bind_param::<Text, _>(&value)
+}
+
+/// The return type of [`filter_by_pairs`](trait.HstoreOpExtensions.html#method.filter_by_pairs)
+#[derive(Debug, Clone)]
+pub struct FilterByPairs<T> {
+    store: T,
+    pairs: Hstore,
+    pub(crate) contains: bool,
+}
+
+hstore_expression!(FilterByPairs, nullable Bool);
+
+impl<T> FilterByPairs<T> {
+    /// Whether this is rendered as containment, `store @> pairs`
+    pub fn is_containment(&self) -> bool {
+        self.contains
+    }
+}
+
+impl<T> QueryFragment<Pg> for FilterByPairs<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        // With no pairs, containment is the only form which is still null for a null store
+        if self.contains || self.pairs.is_empty() {
+            out.push_sql("(");
+            self.store.walk_ast(out.reborrow())?;
+            out.push_sql(" @> ");
+            out.push_bind_param::<Hstore, _>(&self.pairs)?;
+            out.push_sql(")");
+            return Ok(());
+        }
+
+        let mut pairs: Vec<_> = self.pairs.iter().collect();
+        pairs.sort();
+        out.push_sql("(");
+        for (i, (key, value)) in pairs.into_iter().enumerate() {
+            if i > 0 {
+                out.push_sql(" AND ");
+            }
+            walk_key_eq(&self.store, key, value, out.reborrow())?;
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The return type of [`remove_key`](trait.HstoreOpExtensions.html#method.remove_key)
+#[derive(Debug, Clone)]
+pub struct RemoveKey<T, K> {
+    store: T,
+    key: K,
+}
+
+hstore_expression!(RemoveKey<T, K>, nullable Hstore);
+
+impl<T, K> QueryFragment<Pg> for RemoveKey<T, K>
+    where T: QueryFragment<Pg>,
+          K: QueryFragment<Pg>
+{
+    fn walk_ast(&self, out: AstPass<Pg>) -> QueryResult<()> {
+        walk_binary(&self.store, " - ", &self.key, out)
+    }
+}
+
+/// The return type of [`cast_to_hstore`](trait.HstoreTextExtensions.html#method.cast_to_hstore)
+#[derive(Debug, Clone)]
+pub struct CastToHstore<T> {
+    text: T,
+}
+
+hstore_expression!(CastToHstore, Hstore);
+
+impl<T> QueryFragment<Pg> for CastToHstore<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(");
+        self.text.walk_ast(out.reborrow())?;
+        out.push_sql(")::hstore");
+        Ok(())
+    }
+}
+
+/// The return type of [`list_append`](trait.HstoreOpExtensions.html#method.list_append)
+#[derive(Debug, Clone)]
+pub struct ListAppend<T> {
+    store: T,
+    key: String,
+    item: String,
+    delimiter: String,
+}
+
+hstore_expression!(ListAppend, nullable Hstore);
+
+impl<T> QueryFragment<Pg> for ListAppend<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        // store || hstore(key, CASE WHEN coalesce(store -> key, '') = '' THEN item
+        //                      ELSE (store -> key) || delimiter || item END)
+        out.push_sql("(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" || hstore(");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(", CASE WHEN coalesce(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" -> ");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(", '') = '' THEN ");
+        out.push_bind_param::<Text, _>(&self.item)?;
+        out.push_sql(" ELSE (");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" -> ");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(") || ");
+        out.push_bind_param::<Text, _>(&self.delimiter)?;
+        out.push_sql(" || ");
+        out.push_bind_param::<Text, _>(&self.item)?;
+        out.push_sql(" END))");
+        Ok(())
+    }
+}
+
+/// The return type of [`list_remove`](trait.HstoreOpExtensions.html#method.list_remove)
+#[derive(Debug, Clone)]
+pub struct ListRemove<T> {
+    store: T,
+    key: String,
+    item: String,
+    delimiter: String,
+}
+
+hstore_expression!(ListRemove, nullable Hstore);
+
+impl<T> QueryFragment<Pg> for ListRemove<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        // CASE WHEN store ? key THEN store || hstore(key, array_to_string(array_remove(
+        //     string_to_array(store -> key, delimiter), item), delimiter)) ELSE store END
+        out.push_sql("(CASE WHEN ");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" ? ");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(" THEN ");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" || hstore(");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(", array_to_string(array_remove(string_to_array(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" -> ");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(", ");
+        out.push_bind_param::<Text, _>(&self.delimiter)?;
+        out.push_sql("), ");
+        out.push_bind_param::<Text, _>(&self.item)?;
+        out.push_sql("), ");
+        out.push_bind_param::<Text, _>(&self.delimiter)?;
+        out.push_sql(")) ELSE ");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" END)");
+        Ok(())
+    }
+}
+
+/// The return type of [`get_localized`](trait.HstoreOpExtensions.html#method.get_localized)
+#[derive(Debug, Clone)]
+pub struct GetLocalized<T> {
+    store: T,
+    keys: Vec<String>,
+}
+
+hstore_expression!(GetLocalized, Nullable<Text>);
+
+impl<T> QueryFragment<Pg> for GetLocalized<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        if self.keys.is_empty() {
+            out.push_sql("NULL::text");
+            return Ok(());
+        }
+
+        out.push_sql("COALESCE(");
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                out.push_sql(", ");
+            }
+            self.store.walk_ast(out.reborrow())?;
+            out.push_sql(" -> ");
+            out.push_bind_param::<Text, _>(key)?;
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The return type of [`cast_to_text`](trait.HstoreOpExtensions.html#method.cast_to_text)
+#[derive(Debug, Clone)]
+pub struct CastToText<T> {
+    store: T,
+}
+
+hstore_expression!(CastToText, nullable Text);
+
+impl<T> QueryFragment<Pg> for CastToText<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")::text");
+        Ok(())
+    }
+}
+
+/// The return type of [`to_pairs`](trait.HstoreOpExtensions.html#method.to_pairs)
+#[derive(Debug, Clone)]
+pub struct ToPairs<T> {
+    store: T,
+}
+
+hstore_expression!(ToPairs, nullable TextMatrix);
+
+impl<T> QueryFragment<Pg> for ToPairs<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(%# ");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The return type of [`stored_size`](trait.HstoreOpExtensions.html#method.stored_size)
+#[derive(Debug, Clone)]
+pub struct StoredSize<T> {
+    store: T,
+}
+
+hstore_expression!(StoredSize, nullable Integer);
+
+impl<T> QueryFragment<Pg> for StoredSize<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("pg_column_size(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The return type of [`text_length`](trait.HstoreOpExtensions.html#method.text_length)
+#[derive(Debug, Clone)]
+pub struct TextLength<T> {
+    store: T,
+}
+
+hstore_expression!(TextLength, nullable Integer);
+
+impl<T> QueryFragment<Pg> for TextLength<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("length((");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")::text)");
+        Ok(())
+    }
+}
+
+/// The return type of [`incr`](trait.HstoreOpExtensions.html#method.incr)
+#[derive(Debug, Clone)]
+pub struct Incr<T> {
+    store: T,
+    key: String,
+    n: i64,
+}
+
+hstore_expression!(Incr, nullable Hstore);
+
+impl<T> QueryFragment<Pg> for Incr<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        // store || hstore(key, greatest(least(coalesce(store -> key, '0')::bigint::numeric + n,
+        //                                      i64::MAX), i64::MIN)::bigint::text)
+        //
+        // The stored value is cast to bigint first, so that anything but an integer is rejected
+        // rather than rounded; numeric is only there to add without overflowing.
+        out.push_sql("(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" || hstore(");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(", greatest(least(coalesce(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(" -> ");
+        out.push_bind_param::<Text, _>(&self.key)?;
+        out.push_sql(", '0')::bigint::numeric + ");
+        out.push_bind_param::<BigInt, _>(&self.n)?;
+        out.push_sql(", ");
+        out.push_sql(&i64::max_value().to_string());
+        out.push_sql("), ");
+        out.push_sql(&i64::min_value().to_string());
+        out.push_sql(")::bigint::text))");
+        Ok(())
+    }
+}
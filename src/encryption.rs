@@ -0,0 +1,121 @@
+//! Encrypting selected values with AES-256-GCM.
+//!
+//! Available with the `encryption` feature. [`AesGcmCodec`](struct.AesGcmCodec.html) is a
+//! [`ValueCodec`](../value_codec/trait.ValueCodec.html) which encrypts the values of the keys you
+//! name before they are sent to Postgres, and decrypts them when loaded. It applies to the
+//! columns bound and loaded as an [`HstoreWith`](../value_codec/struct.HstoreWith.html) naming it:
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate lazy_static;
+//! extern crate diesel_pg_hstore;
+//!
+//! use diesel_pg_hstore::encryption::AesGcmCodec;
+//! use diesel_pg_hstore::value_codec::{ColumnCodec, HstoreWith, ValueCodec};
+//!
+//! # fn secret_key() -> [u8; 32] { [7; 32] }
+//! lazy_static! {
+//!     static ref TOKENS: AesGcmCodec = AesGcmCodec::new(&secret_key(), &["api_token", "refresh_token"]);
+//! }
+//!
+//! struct Tokens;
+//!
+//! impl ColumnCodec for Tokens {
+//!     fn value_codec() -> &'static ValueCodec {
+//!         &*TOKENS
+//!     }
+//! }
+//!
+//! /// Bound and loaded in place of the `credentials` column's Hstore.
+//! type Credentials = HstoreWith<Tokens>;
+//! # fn main() {}
+//! ```
+//!
+//! Encrypted values are stored as `aes256gcm:` followed by the base64 encoded nonce and
+//! ciphertext. The hstore key is used as associated data, so a ciphertext copied to another key
+//! will fail to decrypt. Values of the selected keys which don't have the prefix are assumed to
+//! have been written before encryption was enabled, and are returned as-is.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fmt;
+
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use base64;
+
+use super::value_codec::ValueCodec;
+
+const PREFIX: &str = "aes256gcm:";
+const NONCE_LEN: usize = 12;
+
+/// A `ValueCodec` encrypting the values of selected keys with AES-256-GCM.
+pub struct AesGcmCodec {
+    cipher: Aes256Gcm,
+    keys: HashSet<String>,
+}
+
+impl AesGcmCodec {
+    /// Create a codec encrypting the values of `keys` with the given 256 bit secret.
+    pub fn new<S: AsRef<str>>(secret: &[u8; 32], keys: &[S]) -> AesGcmCodec {
+        AesGcmCodec {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(secret)),
+            keys: keys.iter().map(|k| k.as_ref().to_string()).collect(),
+        }
+    }
+
+    /// Whether the values of `key` are encrypted
+    pub fn encrypts(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+}
+
+impl fmt::Debug for AesGcmCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AesGcmCodec")
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
+
+impl ValueCodec for AesGcmCodec {
+    fn encode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>> {
+        if !self.encrypts(key) {
+            return Ok(value.into());
+        }
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: value.as_bytes(),
+            aad: key.as_bytes(),
+        };
+        let ciphertext = self.cipher.encrypt(&nonce, payload)
+            .map_err(|_| format!("failed to encrypt the value of {:?}", key))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", PREFIX, base64::encode(&sealed)).into())
+    }
+
+    fn decode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>> {
+        if !self.encrypts(key) || !value.starts_with(PREFIX) {
+            return Ok(value.into());
+        }
+
+        let sealed = base64::decode(&value[PREFIX.len()..])?;
+        if sealed.len() < NONCE_LEN {
+            return Err(format!("encrypted value of {:?} is too short", key).into());
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: key.as_bytes(),
+        };
+        let plaintext = self.cipher.decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| format!("failed to decrypt the value of {:?}", key))?;
+
+        Ok(String::from_utf8(plaintext)?.into())
+    }
+}
@@ -0,0 +1,212 @@
+//! The env file format, `KEY=VALUE` per line.
+//!
+//! This is the format read by dotenv libraries, Docker Compose's `env_file` and, for simple
+//! values, `source` in a shell:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! APP_ENV=production
+//! export GREETING="hello \"world\"\n"
+//! RAW='no $expansion here'
+//! ```
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("APP_ENV".into(), "production".into());
+//! store.insert("GREETING".into(), "hello \"world\"\n".into());
+//!
+//! let file = store.to_env_file().unwrap();
+//! assert_eq!(file, "APP_ENV=production\nGREETING=\"hello \\\"world\\\"\\n\"\n");
+//! assert_eq!(Hstore::from_env_file(&file).unwrap(), store);
+//! ```
+//!
+//! Writing sorts the keys and quotes any value which isn't plain, escaping `\`, `"`, `$`, `` ` ``
+//! and control characters, so each entry stays on one line. Reading accepts an `export` prefix,
+//! unquoted values (which end at a ` #` comment), single quoted values taken literally and
+//! double quoted values with the same escapes. Variables are not expanded. When a key appears
+//! more than once, the last value wins.
+//!
+//! Keys must start with a letter or `_`, and may contain letters, digits, `_`, `.` and `-`.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use super::Hstore;
+
+/// An error writing or reading an env file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvFileError {
+    /// A key can't be written as an env file key.
+    InvalidKey {
+        /// The key
+        key: String,
+    },
+    /// A line couldn't be read.
+    Syntax {
+        /// The line number, starting from 1
+        line: usize,
+        /// What is wrong with it
+        message: &'static str,
+    },
+}
+
+impl fmt::Display for EnvFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EnvFileError::InvalidKey { ref key } => write!(f, "invalid env file key {:?}", key),
+            EnvFileError::Syntax { line, message } => write!(f, "{} on line {}", message, line),
+        }
+    }
+}
+
+impl StdError for EnvFileError {
+    fn description(&self) -> &str {
+        match *self {
+            EnvFileError::InvalidKey { .. } => "invalid env file key",
+            EnvFileError::Syntax { message, .. } => message,
+        }
+    }
+}
+
+impl Hstore {
+    /// Write as an env file. See the [`env_file`](env_file/index.html) module.
+    pub fn to_env_file(&self) -> Result<String, EnvFileError> {
+        format(self)
+    }
+
+    /// Read an env file. See the [`env_file`](env_file/index.html) module.
+    pub fn from_env_file(s: &str) -> Result<Hstore, EnvFileError> {
+        parse(s)
+    }
+}
+
+/// Write an hstore as an env file.
+pub fn format(hstore: &Hstore) -> Result<String, EnvFileError> {
+    let mut entries: Vec<_> = hstore.iter().collect();
+    entries.sort();
+
+    let mut out = String::new();
+    for (key, value) in entries {
+        if !is_valid_key(key) {
+            return Err(EnvFileError::InvalidKey { key: key.clone() });
+        }
+        out.push_str(key);
+        out.push('=');
+        write_value(value, &mut out);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Read an env file into an hstore.
+pub fn parse(s: &str) -> Result<Hstore, EnvFileError> {
+    let mut hstore = Hstore::new();
+    for (i, line) in s.lines().enumerate() {
+        let syntax = |message| EnvFileError::Syntax { line: i + 1, message: message };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = match line.strip_prefix("export ") {
+            Some(rest) => rest.trim_start(),
+            None => line,
+        };
+
+        let eq = line.find('=').ok_or_else(|| syntax("expected `=`"))?;
+        let key = line[..eq].trim_end();
+        if !is_valid_key(key) {
+            return Err(syntax("invalid key"));
+        }
+
+        let value = read_value(line[eq + 1..].trim_start()).map_err(syntax)?;
+        hstore.insert(key.to_string(), value);
+    }
+    Ok(hstore)
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+fn is_plain(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| {
+        c.is_ascii_alphanumeric() || "_-.,/:@%+".contains(c)
+    })
+}
+
+fn write_value(value: &str, out: &mut String) {
+    if is_plain(value) {
+        out.push_str(value);
+        return;
+    }
+
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\\' | '"' | '$' | '`' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn read_value(s: &str) -> Result<String, &'static str> {
+    let (value, rest) = if let Some(quoted) = s.strip_prefix('\'') {
+        let end = quoted.find('\'').ok_or("unterminated single quoted value")?;
+        (quoted[..end].to_string(), &quoted[end + 1..])
+    }
+    else if let Some(quoted) = s.strip_prefix('"') {
+        read_double_quoted(quoted)?
+    }
+    else {
+        let end = s.find(" #").unwrap_or(s.len());
+        return Ok(s[..end].trim_end().to_string());
+    };
+
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(value)
+    }
+    else {
+        Err("unexpected text after quoted value")
+    }
+}
+
+/// The value of a double quoted string, given the text after its opening quote, and the text
+/// after its closing quote
+fn read_double_quoted(s: &str) -> Result<(String, &str), &'static str> {
+    let mut value = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Ok((value, chars.as_str())),
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some(c @ '\\') | Some(c @ '"') | Some(c @ '$') | Some(c @ '`') => value.push(c),
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => break,
+            },
+            _ => value.push(c),
+        }
+    }
+    Err("unterminated double quoted value")
+}
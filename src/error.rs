@@ -0,0 +1,144 @@
+//! Errors deserializing hstores received from Postgres.
+//!
+//! When an hstore can't be decoded, Diesel returns
+//! `diesel::result::Error::DeserializationError` wrapping a [`DecodeError`](struct.DecodeError.html),
+//! which says where in the value decoding failed and which keys it had reached:
+//!
+//! ```rust,ignore
+//! use diesel::result::Error;
+//! use diesel_pg_hstore::error::DecodeError;
+//!
+//! match posts::table.load::<Post>(&db) {
+//!     Err(Error::DeserializationError(ref e)) => {
+//!         if let Some(e) = e.downcast_ref::<DecodeError>() {
+//!             log::error!("bad hstore near key {:?}: {}", e.key().or(e.previous_key()), e);
+//!         }
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! Diesel 1.0 passes `FromSql` only the bytes of a value, so the column and statement aren't
+//! known here. Name them where the query is made.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+/// What went wrong decoding an hstore.
+#[derive(Debug)]
+pub enum DecodeErrorKind {
+    /// The entry count is negative.
+    InvalidCount,
+    /// The value ends part way through an entry.
+    Truncated,
+    /// A key's length is negative or runs past the end of the value.
+    InvalidKeyLength,
+    /// A value's length runs past the end of the value.
+    InvalidValueLength,
+    /// A key or value isn't UTF-8.
+    InvalidUtf8,
+    /// There are bytes after the last entry.
+    TrailingBytes,
+    /// The column's [value codec](../value_codec/index.html) couldn't decode a value.
+    ValueCodec(Box<StdError + Send + Sync>),
+}
+
+impl DecodeErrorKind {
+    fn message(&self) -> &str {
+        match *self {
+            DecodeErrorKind::InvalidCount => "invalid entry count",
+            DecodeErrorKind::Truncated => "value is truncated",
+            DecodeErrorKind::InvalidKeyLength => "invalid key length",
+            DecodeErrorKind::InvalidValueLength => "invalid value length",
+            DecodeErrorKind::InvalidUtf8 => "invalid UTF-8",
+            DecodeErrorKind::TrailingBytes => "invalid buffer size",
+            DecodeErrorKind::ValueCodec(_) => "value codec failed",
+        }
+    }
+}
+
+/// An error decoding an hstore, with where it happened.
+#[derive(Debug)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    offset: usize,
+    entry: Option<usize>,
+    key: Option<String>,
+    previous_key: Option<String>,
+}
+
+impl DecodeError {
+    pub(crate) fn new(kind: DecodeErrorKind, offset: usize) -> DecodeError {
+        DecodeError {
+            kind: kind,
+            offset: offset,
+            entry: None,
+            key: None,
+            previous_key: None,
+        }
+    }
+
+    pub(crate) fn in_entry(mut self, entry: usize, key: Option<&str>, previous_key: Option<&str>) -> DecodeError {
+        self.entry = Some(entry);
+        self.key = key.map(String::from);
+        self.previous_key = previous_key.map(String::from);
+        self
+    }
+
+    /// What went wrong
+    pub fn kind(&self) -> &DecodeErrorKind {
+        &self.kind
+    }
+
+    /// The byte offset into the value where decoding failed
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The index of the entry being decoded, if decoding got as far as the entries
+    pub fn entry(&self) -> Option<usize> {
+        self.entry
+    }
+
+    /// The key of the entry being decoded, if it was read
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_ref().map(String::as_str)
+    }
+
+    /// The key of the last entry decoded before the failure, if any
+    pub fn previous_key(&self) -> Option<&str> {
+        self.previous_key.as_ref().map(String::as_str)
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error deserializing hstore: {} at byte {}", self.kind.message(), self.offset)?;
+        if let Some(entry) = self.entry {
+            write!(f, ", in entry {}", entry)?;
+        }
+        if let Some(ref key) = self.key {
+            write!(f, " (key {:?})", key)?;
+        }
+        else if let Some(ref key) = self.previous_key {
+            write!(f, " (after key {:?})", key)?;
+        }
+        if let DecodeErrorKind::ValueCodec(ref e) = self.kind {
+            write!(f, ": {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for DecodeError {
+    fn description(&self) -> &str {
+        self.kind.message()
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match self.kind {
+            DecodeErrorKind::ValueCodec(ref e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
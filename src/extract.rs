@@ -0,0 +1,272 @@
+//! Web framework extractors reading hstores from requests.
+//!
+//! With the `axum` or `actix` feature, handlers can take an
+//! [`HstoreForm`](struct.HstoreForm.html), read from an `application/x-www-form-urlencoded`
+//! body, or an [`HstoreQuery`](struct.HstoreQuery.html), read from the query string, and store
+//! it as is:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::extract::HstoreForm;
+//! use diesel_pg_hstore::urlencoded::FormLimits;
+//!
+//! async fn update_metadata(Path(id): Path<i32>, HstoreForm(metadata): HstoreForm) -> StatusCode {
+//!     diesel::update(products.find(id)).set(attributes.eq(metadata)).execute(&db)?;
+//!     StatusCode::NO_CONTENT
+//! }
+//!
+//! // axum
+//! let app = Router::new()
+//!     .route("/products/:id/metadata", post(update_metadata))
+//!     .layer(Extension(FormLimits::new().allow_keys(vec!["color", "size"])));
+//!
+//! // actix
+//! let app = App::new()
+//!     .app_data(FormLimits::new().allow_keys(vec!["color", "size"]))
+//!     .route("/products/{id}/metadata", web::post().to(update_metadata));
+//! ```
+//!
+//! Forms are read within the [`FormLimits`](../urlencoded/struct.FormLimits.html) found in the
+//! request's extensions (axum) or app data (actix), or the default limits if there are none. A
+//! request which breaks them is rejected with `413 Payload Too Large` if it's too long, and
+//! `400 Bad Request` otherwise; a form body of another content type is rejected with
+//! `415 Unsupported Media Type`.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::Deref;
+
+use super::Hstore;
+use super::urlencoded::{FormError, FormLimits};
+
+/// An hstore read from a urlencoded request body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HstoreForm(pub Hstore);
+
+/// An hstore read from a request's query string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HstoreQuery(pub Hstore);
+
+impl HstoreForm {
+    /// The hstore
+    pub fn into_inner(self) -> Hstore {
+        self.0
+    }
+}
+
+impl HstoreQuery {
+    /// The hstore
+    pub fn into_inner(self) -> Hstore {
+        self.0
+    }
+}
+
+impl Deref for HstoreForm {
+    type Target = Hstore;
+
+    fn deref(&self) -> &Hstore {
+        &self.0
+    }
+}
+
+impl Deref for HstoreQuery {
+    type Target = Hstore;
+
+    fn deref(&self) -> &Hstore {
+        &self.0
+    }
+}
+
+/// Why a request was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// The body isn't `application/x-www-form-urlencoded`.
+    UnsupportedContentType,
+    /// Reading the body failed.
+    Body(String),
+    /// The form breaks the limits or isn't urlencoded.
+    Form(FormError),
+}
+
+impl Rejection {
+    /// The status code of the response
+    pub fn status(&self) -> u16 {
+        match *self {
+            Rejection::UnsupportedContentType => 415,
+            Rejection::Form(FormError::TooLarge) => 413,
+            _ => 400,
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rejection::UnsupportedContentType => f.write_str("expected an application/x-www-form-urlencoded body"),
+            Rejection::Body(ref message) => write!(f, "reading the body failed: {}", message),
+            Rejection::Form(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl StdError for Rejection {
+    fn description(&self) -> &str {
+        match *self {
+            Rejection::UnsupportedContentType => "unsupported content type",
+            Rejection::Body(_) => "reading the body failed",
+            Rejection::Form(ref e) => e.description(),
+        }
+    }
+}
+
+impl From<FormError> for Rejection {
+    fn from(e: FormError) -> Rejection {
+        Rejection::Form(e)
+    }
+}
+
+/// Whether a `Content-Type` header value is a urlencoded form, ignoring any parameters
+fn is_form(content_type: Option<&[u8]>) -> bool {
+    content_type
+        .and_then(|value| value.split(|&b| b == b';').next())
+        .map_or(false, |mime| mime.trim_ascii().eq_ignore_ascii_case(b"application/x-www-form-urlencoded"))
+}
+
+/// Whether a `Content-Length` header value is over `limit`
+fn too_long(content_length: Option<&[u8]>, limit: usize) -> bool {
+    content_length
+        .and_then(|value| ::std::str::from_utf8(value).ok())
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .map_or(false, |length| length > limit)
+}
+
+/// Read a form body within `limits`
+fn parse_body(body: &[u8], limits: &FormLimits) -> Result<Hstore, Rejection> {
+    if body.len() > limits.byte_limit() {
+        return Err(Rejection::Form(FormError::TooLarge));
+    }
+    let body = ::std::str::from_utf8(body).map_err(|_| Rejection::Form(FormError::Invalid("body is not UTF-8".into())))?;
+    Ok(limits.parse(body)?)
+}
+
+#[cfg(feature = "axum")]
+mod axum_impls {
+    use std::future::{ready, Future};
+    use std::pin::Pin;
+
+    use axum_core::extract::{FromRequest, FromRequestParts, Request};
+    use axum_core::response::{IntoResponse, Response};
+    use bytes::Bytes;
+    use futures_util::FutureExt;
+    use http::StatusCode;
+    use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use http::request::Parts;
+
+    use super::{is_form, parse_body, too_long, HstoreForm, HstoreQuery, Rejection};
+    use super::super::urlencoded::{FormError, FormLimits};
+
+    type Extraction<'a, T> = Pin<Box<Future<Output = Result<T, Rejection>> + Send + 'a>>;
+
+    impl<S> FromRequest<S> for HstoreForm
+        where S: Send + Sync
+    {
+        type Rejection = Rejection;
+
+        fn from_request<'life0, 'async_trait>(req: Request, state: &'life0 S) -> Extraction<'async_trait, HstoreForm>
+            where 'life0: 'async_trait,
+                  Self: 'async_trait
+        {
+            let limits = req.extensions().get::<FormLimits>().cloned().unwrap_or_default();
+            let headers = req.headers();
+            if !is_form(headers.get(CONTENT_TYPE).map(|value| value.as_bytes())) {
+                return Box::pin(ready(Err(Rejection::UnsupportedContentType)));
+            }
+            if too_long(headers.get(CONTENT_LENGTH).map(|value| value.as_bytes()), limits.byte_limit()) {
+                return Box::pin(ready(Err(Rejection::Form(FormError::TooLarge))));
+            }
+
+            Box::pin(Bytes::from_request(req, state).map(move |body| {
+                let body = body.map_err(|e| Rejection::Body(e.body_text()))?;
+                parse_body(&body, &limits).map(HstoreForm)
+            }))
+        }
+    }
+
+    impl<S> FromRequestParts<S> for HstoreQuery
+        where S: Send + Sync
+    {
+        type Rejection = Rejection;
+
+        fn from_request_parts<'life0, 'life1, 'async_trait>(parts: &'life0 mut Parts, _state: &'life1 S) -> Extraction<'async_trait, HstoreQuery>
+            where 'life0: 'async_trait,
+                  'life1: 'async_trait,
+                  Self: 'async_trait
+        {
+            let limits = parts.extensions.get::<FormLimits>().cloned().unwrap_or_default();
+            let query = parts.uri.query().unwrap_or("");
+            Box::pin(ready(limits.parse(query).map(HstoreQuery).map_err(Rejection::Form)))
+        }
+    }
+
+    impl IntoResponse for Rejection {
+        fn into_response(self) -> Response {
+            let status = StatusCode::from_u16(self.status()).expect("rejection statuses to be valid");
+            (status, self.to_string()).into_response()
+        }
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_impls {
+    use std::future::{ready, Ready};
+
+    use actix_web::{FromRequest, HttpRequest, ResponseError};
+    use actix_web::dev::Payload;
+    use actix_web::http::StatusCode;
+    use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use actix_web::web::Bytes;
+    use futures_util::FutureExt;
+    use futures_util::future::LocalBoxFuture;
+
+    use super::{is_form, parse_body, too_long, HstoreForm, HstoreQuery, Rejection};
+    use super::super::urlencoded::{FormError, FormLimits};
+
+    fn limits(req: &HttpRequest) -> FormLimits {
+        req.app_data::<FormLimits>().cloned().unwrap_or_default()
+    }
+
+    impl FromRequest for HstoreForm {
+        type Error = Rejection;
+        type Future = LocalBoxFuture<'static, Result<HstoreForm, Rejection>>;
+
+        fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+            let limits = limits(req);
+            let headers = req.headers();
+            if !is_form(headers.get(CONTENT_TYPE).map(|value| value.as_bytes())) {
+                return Box::pin(ready(Err(Rejection::UnsupportedContentType)));
+            }
+            if too_long(headers.get(CONTENT_LENGTH).map(|value| value.as_bytes()), limits.byte_limit()) {
+                return Box::pin(ready(Err(Rejection::Form(FormError::TooLarge))));
+            }
+
+            Box::pin(Bytes::from_request(req, payload).map(move |body| {
+                let body = body.map_err(|e| Rejection::Body(e.to_string()))?;
+                parse_body(&body, &limits).map(HstoreForm)
+            }))
+        }
+    }
+
+    impl FromRequest for HstoreQuery {
+        type Error = Rejection;
+        type Future = Ready<Result<HstoreQuery, Rejection>>;
+
+        fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            ready(limits(req).parse(req.query_string()).map(HstoreQuery).map_err(Rejection::Form))
+        }
+    }
+
+    impl ResponseError for Rejection {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::from_u16(self.status()).expect("rejection statuses to be valid")
+        }
+    }
+}
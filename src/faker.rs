@@ -0,0 +1,116 @@
+//! Sample data generation using [fake](https://docs.rs/fake).
+//!
+//! Available when the `fake` feature is enabled. `Hstore` implements `Dummy<Faker>`, giving a
+//! handful of short ascii entries, and `Dummy<HstoreFaker>` for control over the shape of the
+//! generated data.
+//!
+//! ```rust
+//! # extern crate fake;
+//! # extern crate diesel_pg_hstore;
+//! use fake::{Fake, Faker};
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::faker::HstoreFaker;
+//!
+//! # fn main() {
+//! let any: Hstore = Faker.fake();
+//!
+//! let wide: Hstore = HstoreFaker {
+//!     count: 100..101,
+//!     unicode: true,
+//!     ..HstoreFaker::default()
+//! }.fake();
+//! assert_eq!(wide.len(), 100);
+//! # }
+//! ```
+
+use std::ops::Range;
+
+use fake::{Dummy, Faker, Rng};
+
+use super::Hstore;
+
+/// Describes the entries of a generated Hstore.
+///
+/// All ranges are half-open, as with `Rng::gen_range`. Lengths are measured in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HstoreFaker {
+    /// How many entries to generate.
+    pub count: Range<usize>,
+    /// Length of each key.
+    pub key_len: Range<usize>,
+    /// Length of each value.
+    pub value_len: Range<usize>,
+    /// Generate arbitrary unicode rather than ascii alphanumerics.
+    pub unicode: bool,
+}
+
+impl Default for HstoreFaker {
+    fn default() -> HstoreFaker {
+        HstoreFaker {
+            count: 1..10,
+            key_len: 1..16,
+            value_len: 0..32,
+            unicode: false,
+        }
+    }
+}
+
+impl Dummy<Faker> for Hstore {
+    fn dummy_with_rng<R: Rng + ?Sized>(_: &Faker, rng: &mut R) -> Hstore {
+        Hstore::dummy_with_rng(&HstoreFaker::default(), rng)
+    }
+}
+
+impl Dummy<HstoreFaker> for Hstore {
+    fn dummy_with_rng<R: Rng + ?Sized>(config: &HstoreFaker, rng: &mut R) -> Hstore {
+        let count = sample(&config.count, rng);
+        let mut hstore = Hstore::with_capacity(count);
+
+        // Short or ascii-only keys can run out of unique values, so give up rather than spin.
+        let mut attempts = count.saturating_mul(10);
+        while hstore.len() < count && attempts > 0 {
+            attempts -= 1;
+
+            let key = string(config, &config.key_len, rng);
+            if hstore.contains_key(&key) {
+                continue;
+            }
+            let value = string(config, &config.value_len, rng);
+            hstore.insert(key, value);
+        }
+
+        hstore
+    }
+}
+
+fn sample<R: Rng + ?Sized>(range: &Range<usize>, rng: &mut R) -> usize {
+    if range.start >= range.end {
+        range.start
+    }
+    else {
+        rng.gen_range(range.start..range.end)
+    }
+}
+
+fn string<R: Rng + ?Sized>(config: &HstoreFaker, len: &Range<usize>, rng: &mut R) -> String {
+    const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    let len = sample(len, rng);
+    let mut s = String::with_capacity(len);
+    for _ in 0..len {
+        let c = if config.unicode {
+            // Postgres text can't hold NUL
+            loop {
+                let c: char = rng.gen();
+                if c != '\0' {
+                    break c;
+                }
+            }
+        }
+        else {
+            ALPHANUMERIC[rng.gen_range(0..ALPHANUMERIC.len())] as char
+        };
+        s.push(c);
+    }
+    s
+}
@@ -0,0 +1,270 @@
+//! Converting hstores to and from flat YAML and TOML documents.
+//!
+//! With the `yaml` or `toml` feature, an hstore can be written as a document of string values,
+//! and read back from one. Other scalar values, such as numbers and booleans, are read as their
+//! text, and YAML nulls are skipped, as null hstore values are.
+//!
+//! Documents may also nest one level of tables, which [`Nesting::OneLevel`](enum.Nesting.html)
+//! maps to keys joined with `.`:
+//!
+//! ```rust
+//! # #[cfg(feature = "toml")] {
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::flat::Nesting;
+//!
+//! let store = Hstore::from_toml(r#"
+//!     name = "api"
+//!     replicas = 3
+//!
+//!     [database]
+//!     host = "db.internal"
+//! "#, Nesting::OneLevel).unwrap();
+//! assert_eq!(store["replicas"], "3");
+//! assert_eq!(store["database.host"], "db.internal");
+//!
+//! let toml = store.to_toml(Nesting::OneLevel).unwrap();
+//! assert_eq!(Hstore::from_toml(&toml, Nesting::OneLevel).unwrap(), store);
+//! # }
+//! ```
+//!
+//! When writing with `OneLevel`, a key is split at its first `.`, so `database.pool.size`
+//! becomes the key `"pool.size"` of the table `database`.
+
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+
+use super::Hstore;
+
+/// How nested tables map to hstore keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nesting {
+    /// Every value is at the top level. Nested tables are an error.
+    Flat,
+    /// Tables may nest one level deep, their keys prefixed with the table's name and `.`.
+    OneLevel,
+}
+
+/// An error converting between an hstore and a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlatError {
+    /// The document couldn't be parsed or written.
+    Format(String),
+    /// The document isn't a table or mapping at the top level.
+    NotATable,
+    /// A value is a table nested deeper than allowed.
+    Nested {
+        /// The key of the table
+        key: String,
+    },
+    /// A value is a list, which has no hstore equivalent.
+    List {
+        /// The key of the list
+        key: String,
+    },
+    /// Writing with `OneLevel`, a key is both a value and the name of a table.
+    Conflict {
+        /// The key
+        key: String,
+    },
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlatError::Format(ref message) => f.write_str(message),
+            FlatError::NotATable => f.write_str("document is not a table"),
+            FlatError::Nested { ref key } => write!(f, "table {:?} is nested too deeply", key),
+            FlatError::List { ref key } => write!(f, "value of {:?} is a list", key),
+            FlatError::Conflict { ref key } => write!(f, "key {:?} is both a value and a table", key),
+        }
+    }
+}
+
+impl StdError for FlatError {
+    fn description(&self) -> &str {
+        match *self {
+            FlatError::Format(_) => "invalid document",
+            FlatError::NotATable => "document is not a table",
+            FlatError::Nested { .. } => "table nested too deeply",
+            FlatError::List { .. } => "value is a list",
+            FlatError::Conflict { .. } => "key is both a value and a table",
+        }
+    }
+}
+
+/// A top level entry of a document being written
+enum Entry<'a> {
+    Value(&'a str),
+    Table(BTreeMap<&'a str, &'a str>),
+}
+
+/// The entries of `hstore` as a document's top level, sorted by key
+fn group(hstore: &Hstore, nesting: Nesting) -> Result<BTreeMap<&str, Entry>, FlatError> {
+    let mut top = BTreeMap::new();
+    for (key, value) in hstore.iter() {
+        let split = match nesting {
+            Nesting::OneLevel => key.find('.').map(|dot| (&key[..dot], &key[dot + 1..])),
+            Nesting::Flat => None,
+        };
+
+        match split {
+            None => {
+                if top.insert(key.as_str(), Entry::Value(value)).is_some() {
+                    return Err(FlatError::Conflict { key: key.clone() });
+                }
+            }
+            Some((table, inner)) => {
+                let entry = top.entry(table).or_insert_with(|| Entry::Table(BTreeMap::new()));
+                match *entry {
+                    Entry::Table(ref mut entries) => {
+                        entries.insert(inner, value.as_str());
+                    }
+                    Entry::Value(_) => return Err(FlatError::Conflict { key: table.to_string() }),
+                }
+            }
+        }
+    }
+    Ok(top)
+}
+
+/// The hstore key of `key` within the table `table`, if any
+fn join(table: Option<&str>, key: &str) -> String {
+    match table {
+        Some(table) => format!("{}.{}", table, key),
+        None => key.to_string(),
+    }
+}
+
+#[cfg(feature = "toml")]
+mod toml_impls {
+    use toml;
+    use toml::Value;
+    use toml::value::Table;
+
+    use super::{group, join, Entry, FlatError, Nesting};
+    use super::super::Hstore;
+
+    impl Hstore {
+        /// Read a TOML document. See the [`flat`](flat/index.html) module.
+        pub fn from_toml(s: &str, nesting: Nesting) -> Result<Hstore, FlatError> {
+            let table: Table = s.parse().map_err(|e: toml::de::Error| FlatError::Format(e.to_string()))?;
+            let mut hstore = Hstore::new();
+            read_table(&table, None, nesting, &mut hstore)?;
+            Ok(hstore)
+        }
+
+        /// Write as a TOML document. See the [`flat`](flat/index.html) module.
+        pub fn to_toml(&self, nesting: Nesting) -> Result<String, FlatError> {
+            let mut table = Table::new();
+            for (key, entry) in group(self, nesting)? {
+                let value = match entry {
+                    Entry::Value(value) => Value::String(value.to_string()),
+                    Entry::Table(entries) => Value::Table(entries.into_iter()
+                        .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+                        .collect()),
+                };
+                table.insert(key.to_string(), value);
+            }
+            toml::to_string(&table).map_err(|e| FlatError::Format(e.to_string()))
+        }
+    }
+
+    fn read_table(table: &Table, prefix: Option<&str>, nesting: Nesting, hstore: &mut Hstore) -> Result<(), FlatError> {
+        for (key, value) in table {
+            let full_key = join(prefix, key);
+            let text = match *value {
+                Value::String(ref s) => s.clone(),
+                Value::Integer(i) => i.to_string(),
+                Value::Float(f) => f.to_string(),
+                Value::Boolean(b) => b.to_string(),
+                Value::Datetime(ref d) => d.to_string(),
+                Value::Array(_) => return Err(FlatError::List { key: full_key }),
+                Value::Table(ref inner) => {
+                    if prefix.is_some() || nesting == Nesting::Flat {
+                        return Err(FlatError::Nested { key: full_key });
+                    }
+                    read_table(inner, Some(key.as_str()), nesting, hstore)?;
+                    continue;
+                }
+            };
+            hstore.insert(full_key, text);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "yaml")]
+mod yaml_impls {
+    use serde_yaml;
+    use serde_yaml::{Mapping, Value};
+
+    use super::{group, join, Entry, FlatError, Nesting};
+    use super::super::Hstore;
+
+    impl Hstore {
+        /// Read a YAML document. See the [`flat`](flat/index.html) module.
+        pub fn from_yaml(s: &str, nesting: Nesting) -> Result<Hstore, FlatError> {
+            let document: Value = serde_yaml::from_str(s).map_err(|e| FlatError::Format(e.to_string()))?;
+            let mut hstore = Hstore::new();
+            match document {
+                Value::Mapping(ref mapping) => read_mapping(mapping, None, nesting, &mut hstore)?,
+                Value::Null => {}
+                _ => return Err(FlatError::NotATable),
+            }
+            Ok(hstore)
+        }
+
+        /// Write as a YAML document. See the [`flat`](flat/index.html) module.
+        pub fn to_yaml(&self, nesting: Nesting) -> Result<String, FlatError> {
+            let mut mapping = Mapping::new();
+            for (key, entry) in group(self, nesting)? {
+                let value = match entry {
+                    Entry::Value(value) => Value::String(value.to_string()),
+                    Entry::Table(entries) => Value::Mapping(entries.into_iter()
+                        .map(|(k, v)| (Value::String(k.to_string()), Value::String(v.to_string())))
+                        .collect()),
+                };
+                mapping.insert(Value::String(key.to_string()), value);
+            }
+            serde_yaml::to_string(&Value::Mapping(mapping)).map_err(|e| FlatError::Format(e.to_string()))
+        }
+    }
+
+    /// A scalar's text, or `None` for anything else
+    fn scalar(value: &Value) -> Option<String> {
+        match *value {
+            Value::String(ref s) => Some(s.clone()),
+            Value::Number(ref n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    fn read_mapping(mapping: &Mapping, prefix: Option<&str>, nesting: Nesting, hstore: &mut Hstore) -> Result<(), FlatError> {
+        for (key, value) in mapping {
+            let key = scalar(key).ok_or(FlatError::NotATable)?;
+            let full_key = join(prefix, &key);
+            match *value {
+                Value::Null => {}
+                Value::Sequence(_) => return Err(FlatError::List { key: full_key }),
+                Value::Mapping(ref inner) => {
+                    if prefix.is_some() || nesting == Nesting::Flat {
+                        return Err(FlatError::Nested { key: full_key });
+                    }
+                    read_mapping(inner, Some(key.as_str()), nesting, hstore)?;
+                }
+                Value::Tagged(ref tagged) => match scalar(&tagged.value) {
+                    Some(text) => {
+                        hstore.insert(full_key, text);
+                    }
+                    None => return Err(FlatError::Nested { key: full_key }),
+                },
+                _ => {
+                    hstore.insert(full_key, scalar(value).expect("remaining values to be scalars"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
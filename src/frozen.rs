@@ -0,0 +1,147 @@
+//! Read-only hstores which are cheap to clone.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::Hstore;
+
+/// A read-only Hstore which can be cloned in constant time.
+///
+/// Clones share the same map, so a `FrozenHstore` loaded once can be handed to many threads or
+/// requests without copying it. It can be used anywhere `Hstore` can, including as a field of a
+/// `Queryable` struct. Reading goes through `Deref`; to change it, use
+/// [`make_mut`](#method.make_mut) or [`thaw`](#method.thaw), which only copy the map when it is
+/// shared.
+///
+/// ```rust
+/// use diesel_pg_hstore::{FrozenHstore, Hstore};
+///
+/// let mut settings = Hstore::new();
+/// settings.insert("theme".into(), "dark".into());
+///
+/// let frozen = settings.freeze();
+/// let shared = frozen.clone();
+/// assert!(FrozenHstore::ptr_eq(&frozen, &shared));
+///
+/// let mut settings = shared.thaw();
+/// settings.insert("theme".into(), "light".into());
+/// assert_eq!(frozen["theme"], "dark");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrozenHstore(pub(crate) Arc<Hstore>);
+
+/// A [`FrozenHstore`](struct.FrozenHstore.html), named for how it is used as a cache.
+///
+/// Clones share one map until [`make_mut`](struct.FrozenHstore.html#method.make_mut) is first
+/// called on one of them, which copies the map for that clone alone. A settings map read on
+/// every request and rarely changed can be kept this way:
+///
+/// ```rust
+/// use diesel_pg_hstore::{CowHstore, Hstore};
+///
+/// let cached = CowHstore::new(Hstore::new());
+///
+/// let mut for_request = cached.clone();
+/// assert!(CowHstore::ptr_eq(&cached, &for_request));
+///
+/// for_request.make_mut().insert("theme".into(), "light".into());
+/// assert!(!CowHstore::ptr_eq(&cached, &for_request));
+/// assert!(cached.is_empty());
+/// ```
+pub type CowHstore = FrozenHstore;
+
+impl FrozenHstore {
+    /// Freeze an Hstore
+    pub fn new(hstore: Hstore) -> FrozenHstore {
+        FrozenHstore(Arc::new(hstore))
+    }
+
+    /// Get a mutable reference to the Hstore, first copying it if any clones share it.
+    pub fn make_mut(&mut self) -> &mut Hstore {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Convert back into a mutable Hstore, copying it only if any clones share it.
+    pub fn thaw(self) -> Hstore {
+        match Arc::try_unwrap(self.0) {
+            Ok(hstore) => hstore,
+            Err(shared) => (*shared).clone(),
+        }
+    }
+
+    /// Whether two FrozenHstores share the same map
+    pub fn ptr_eq(this: &FrozenHstore, other: &FrozenHstore) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl Deref for FrozenHstore {
+    type Target = Hstore;
+
+    fn deref(&self) -> &Hstore {
+        &self.0
+    }
+}
+
+impl From<Hstore> for FrozenHstore {
+    fn from(hstore: Hstore) -> FrozenHstore {
+        FrozenHstore::new(hstore)
+    }
+}
+
+impl Hstore {
+    /// Convert into a [`FrozenHstore`](struct.FrozenHstore.html), which is cheap to clone.
+    pub fn freeze(self) -> FrozenHstore {
+        FrozenHstore::new(self)
+    }
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::io::Write;
+    use diesel::Queryable;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::row::Row;
+    use diesel::types::*;
+
+    use super::FrozenHstore;
+    use super::super::Hstore;
+
+    impl Queryable<Hstore, Pg> for FrozenHstore {
+        type Row = Self;
+
+        fn build(row: Self::Row) -> Self {
+            row
+        }
+    }
+
+    impl<'a> AsExpression<Hstore> for &'a FrozenHstore {
+        type Expression = Bound<Hstore, &'a FrozenHstore>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl FromSql<Hstore, Pg> for FrozenHstore {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            Ok(FrozenHstore::new(Hstore::from_sql(bytes)?))
+        }
+    }
+
+    impl FromSqlRow<Hstore, Pg> for FrozenHstore {
+        fn build_from_row<R: Row<Pg>>(row: &mut R) -> Result<Self, Box<StdError + Send + Sync>> {
+            FrozenHstore::from_sql(row.take())
+        }
+    }
+
+    impl ToSql<Hstore, Pg> for FrozenHstore {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(&**self, out)
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! The hstore extension's SQL functions, for use with Diesel's query builder.
+//!
+//! Each function takes an hstore expression, such as a column, or an `Hstore` value, which is
+//! bound as a parameter.
+//!
+//! The set-returning functions [`each`](fn.each.html), [`skeys`](fn.skeys.html) and
+//! [`svals`](fn.svals.html) make a row of each entry when selected, repeating the row's other
+//! columns. This turns hstore contents into rows, to count or group in Rust:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::functions::{each, skeys};
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     products {
+//!         id -> Integer,
+//!         attributes -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! // Loads as `Vec<(i32, (String, Option<String>))>`
+//! let entries = products::table.select((products::id, each(products::attributes)));
+//! // Loads as `Vec<String>`
+//! let keys = products::table.select(skeys(products::attributes)).distinct();
+//! # }
+//! ```
+//!
+//! Diesel 1.0 has no lateral joins, so these can't be used in the `FROM` clause through the
+//! query builder; that needs `diesel::sql_query`.
+//!
+//! Rows of a composite type, such as a table's row type, convert to and from hstores keyed by
+//! field name. [`populate_record`](fn.populate_record.html) and
+//! [`update_record`](fn.update_record.html) set the fields named in an hstore, and
+//! [`hstore_from_record`](fn.hstore_from_record.html) makes an hstore of a row. These are generic
+//! over the row's SQL type, so they can be used with any row-typed expression.
+//!
+//! With the `json` feature, hstores can be converted to `json` or `jsonb`, to be returned as
+//! JSON or compared with `jsonb` columns without leaving the database:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! # #[cfg(feature = "json")]
+//! # mod example {
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::functions::hstore_to_jsonb;
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     products {
+//!         id -> Integer,
+//!         attributes -> Hstore,
+//!         published -> Jsonb,
+//!     }
+//! }
+//!
+//! # pub fn query() {
+//! let unpublished = products::table
+//!     .select(products::id)
+//!     .filter(hstore_to_jsonb(products::attributes).ne(products::published));
+//! # }
+//! # }
+//! # fn main() {}
+//! ```
+//!
+//! This module is available with the `dsl` feature, which is enabled by default.
+
+use std::marker::PhantomData;
+
+use diesel::expression::{AppearsOnTable, AsExpression, Expression, NonAggregate, SelectableExpression};
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::types::{Nullable, Text};
+#[cfg(feature = "json")]
+use diesel::types::{Json, Jsonb};
+
+use super::Hstore;
+use super::matrix::TextMatrix;
+
+/// Defines a function `$function` of one hstore, returning a node `$name` of type `$sql_type`
+/// which renders as `$sql_function(store)`.
+macro_rules! hstore_function {
+    ($(#[$attr:meta])* fn $function:ident; $(#[$node_attr:meta])* struct $name:ident: $sql_type:ty = $sql_function:expr;) => {
+        $(#[$attr])*
+        pub fn $function<T>(store: T) -> $name<T::Expression>
+            where T: AsExpression<Hstore>
+        {
+            $name {
+                store: store.as_expression(),
+            }
+        }
+
+        $(#[$node_attr])*
+        #[derive(Debug, Clone)]
+        pub struct $name<T> {
+            store: T,
+        }
+
+        impl<T> Expression for $name<T>
+            where T: Expression
+        {
+            type SqlType = $sql_type;
+        }
+
+        impl<T> QueryId for $name<T> {
+            type QueryId = ();
+
+            const HAS_STATIC_QUERY_ID: bool = false;
+        }
+
+        impl<T, QS> AppearsOnTable<QS> for $name<T>
+            where T: AppearsOnTable<QS>
+        {
+        }
+
+        impl<T, QS> SelectableExpression<QS> for $name<T>
+            where T: SelectableExpression<QS>
+        {
+        }
+
+        impl<T> NonAggregate for $name<T>
+            where T: NonAggregate
+        {
+        }
+
+        impl<T> QueryFragment<Pg> for $name<T>
+            where T: QueryFragment<Pg>
+        {
+            fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+                out.push_sql(concat!($sql_function, "("));
+                self.store.walk_ast(out.reborrow())?;
+                out.push_sql(")");
+                Ok(())
+            }
+        }
+    };
+}
+
+hstore_function! {
+    /// The keys of the hstore as a set, `skeys(store)`, in the order Postgres keeps them.
+    fn skeys;
+    /// The return type of [`skeys`](fn.skeys.html)
+    struct Skeys: Text = "skeys";
+}
+
+hstore_function! {
+    /// The values of the hstore as a set, `svals(store)`, in the same order as
+    /// [`skeys`](fn.skeys.html). Values may be `NULL`.
+    fn svals;
+    /// The return type of [`svals`](fn.svals.html)
+    struct Svals: Nullable<Text> = "svals";
+}
+
+hstore_function! {
+    /// The entries as `{key, value}` rows of a two-dimensional array, `hstore_to_matrix(store)`,
+    /// which loads as a `Vec<(String, Option<String>)>`. This is the same as
+    /// [`to_pairs`](../dsl/trait.HstoreOpExtensions.html#method.to_pairs).
+    fn hstore_to_matrix;
+    /// The return type of [`hstore_to_matrix`](fn.hstore_to_matrix.html)
+    struct HstoreToMatrix: TextMatrix = "hstore_to_matrix";
+}
+
+/// The entries of the hstore as a set of `(key, value)` rows, `each(store)`, in the same order
+/// as [`skeys`](fn.skeys.html). Values may be `NULL`.
+///
+/// This selects the two columns of `(each(store)).*`. Please see
+/// [`Each::key`](struct.Each.html#method.key) and [`Each::value`](struct.Each.html#method.value)
+/// for selecting them separately.
+pub fn each<T>(store: T) -> Each<T::Expression>
+    where T: AsExpression<Hstore>
+{
+    Each {
+        store: store.as_expression(),
+    }
+}
+
+/// The return type of [`each`](fn.each.html)
+#[derive(Debug, Clone)]
+pub struct Each<T> {
+    store: T,
+}
+
+impl<T> Each<T> {
+    /// The keys, `(each(store)).key`
+    pub fn key(self) -> EachColumn<T, Text> {
+        EachColumn::new(self.store, "key")
+    }
+
+    /// The values, `(each(store)).value`
+    pub fn value(self) -> EachColumn<T, Nullable<Text>> {
+        EachColumn::new(self.store, "value")
+    }
+}
+
+impl<T> Expression for Each<T>
+    where T: Expression
+{
+    type SqlType = (Text, Nullable<Text>);
+}
+
+impl<T> QueryId for Each<T> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T, QS> AppearsOnTable<QS> for Each<T>
+    where T: AppearsOnTable<QS>
+{
+}
+
+impl<T, QS> SelectableExpression<QS> for Each<T>
+    where T: SelectableExpression<QS>
+{
+}
+
+impl<T> NonAggregate for Each<T>
+    where T: NonAggregate
+{
+}
+
+impl<T> QueryFragment<Pg> for Each<T>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(each(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")).*");
+        Ok(())
+    }
+}
+
+/// The return type of [`Each::key`](struct.Each.html#method.key) and
+/// [`Each::value`](struct.Each.html#method.value)
+#[derive(Debug, Clone)]
+pub struct EachColumn<T, ST> {
+    store: T,
+    column: &'static str,
+    _sql_type: PhantomData<ST>,
+}
+
+impl<T, ST> EachColumn<T, ST> {
+    fn new(store: T, column: &'static str) -> EachColumn<T, ST> {
+        EachColumn {
+            store: store,
+            column: column,
+            _sql_type: PhantomData,
+        }
+    }
+}
+
+impl<T, ST> Expression for EachColumn<T, ST>
+    where T: Expression
+{
+    type SqlType = ST;
+}
+
+impl<T, ST> QueryId for EachColumn<T, ST> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T, ST, QS> AppearsOnTable<QS> for EachColumn<T, ST>
+    where T: AppearsOnTable<QS>
+{
+}
+
+impl<T, ST, QS> SelectableExpression<QS> for EachColumn<T, ST>
+    where T: SelectableExpression<QS>
+{
+}
+
+impl<T, ST> NonAggregate for EachColumn<T, ST>
+    where T: NonAggregate
+{
+}
+
+impl<T, ST> QueryFragment<Pg> for EachColumn<T, ST>
+    where T: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(each(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")).");
+        out.push_sql(self.column);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json")]
+hstore_function! {
+    /// The hstore as a `json` object, `hstore_to_json(store)`. Every value is a JSON string, or
+    /// `null`.
+    fn hstore_to_json;
+    /// The return type of [`hstore_to_json`](fn.hstore_to_json.html)
+    struct HstoreToJson: Json = "hstore_to_json";
+}
+
+#[cfg(feature = "json")]
+hstore_function! {
+    /// The hstore as a `jsonb` object, `hstore_to_jsonb(store)`. Every value is a JSON string, or
+    /// `null`.
+    fn hstore_to_jsonb;
+    /// The return type of [`hstore_to_jsonb`](fn.hstore_to_jsonb.html)
+    struct HstoreToJsonb: Jsonb = "hstore_to_jsonb";
+}
+
+#[cfg(feature = "json")]
+hstore_function! {
+    /// The hstore as a `json` object, `hstore_to_json_loose(store)`, with values which look like
+    /// numbers or booleans written as JSON numbers or booleans rather than strings.
+    fn hstore_to_json_loose;
+    /// The return type of [`hstore_to_json_loose`](fn.hstore_to_json_loose.html)
+    struct HstoreToJsonLoose: Json = "hstore_to_json_loose";
+}
+
+/// The row `record` with the fields named in `store` set to their values there,
+/// `populate_record(record, store)`. Fields not in `store` keep their values, and a `NULL` in
+/// `store` sets the field to `NULL`.
+///
+/// The record may be any expression of a composite type. The statement fails if a value can't
+/// be converted to the type of its field.
+pub fn populate_record<R, S>(record: R, store: S) -> PopulateRecord<R, S::Expression>
+    where R: Expression,
+          S: AsExpression<Hstore>
+{
+    PopulateRecord {
+        record: record,
+        store: store.as_expression(),
+        operator: false,
+    }
+}
+
+/// The same as [`populate_record`](fn.populate_record.html), written with the operator,
+/// `record #= store`.
+pub fn update_record<R, S>(record: R, store: S) -> PopulateRecord<R, S::Expression>
+    where R: Expression,
+          S: AsExpression<Hstore>
+{
+    PopulateRecord {
+        record: record,
+        store: store.as_expression(),
+        operator: true,
+    }
+}
+
+/// The return type of [`populate_record`](fn.populate_record.html) and
+/// [`update_record`](fn.update_record.html)
+#[derive(Debug, Clone)]
+pub struct PopulateRecord<R, S> {
+    record: R,
+    store: S,
+    operator: bool,
+}
+
+impl<R, S> Expression for PopulateRecord<R, S>
+    where R: Expression,
+          S: Expression
+{
+    type SqlType = R::SqlType;
+}
+
+impl<R, S> QueryId for PopulateRecord<R, S> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<R, S, QS> AppearsOnTable<QS> for PopulateRecord<R, S>
+    where R: AppearsOnTable<QS>,
+          S: AppearsOnTable<QS>
+{
+}
+
+impl<R, S, QS> SelectableExpression<QS> for PopulateRecord<R, S>
+    where R: SelectableExpression<QS>,
+          S: SelectableExpression<QS>
+{
+}
+
+impl<R, S> NonAggregate for PopulateRecord<R, S>
+    where R: NonAggregate,
+          S: NonAggregate
+{
+}
+
+impl<R, S> QueryFragment<Pg> for PopulateRecord<R, S>
+    where R: QueryFragment<Pg>,
+          S: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql(if self.operator { "(" } else { "populate_record(" });
+        self.record.walk_ast(out.reborrow())?;
+        out.push_sql(if self.operator { " #= " } else { ", " });
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The fields of the row `record` as an hstore keyed by field name, `hstore(record)`. `NULL`
+/// fields are `NULL` values, which an [`HstoreNullable`](../null_values/struct.HstoreNullable.html)
+/// keeps when loaded.
+pub fn hstore_from_record<R>(record: R) -> HstoreFromRecord<R>
+    where R: Expression
+{
+    HstoreFromRecord {
+        record: record,
+    }
+}
+
+/// The return type of [`hstore_from_record`](fn.hstore_from_record.html)
+#[derive(Debug, Clone)]
+pub struct HstoreFromRecord<R> {
+    record: R,
+}
+
+impl<R> Expression for HstoreFromRecord<R>
+    where R: Expression
+{
+    type SqlType = Hstore;
+}
+
+impl<R> QueryId for HstoreFromRecord<R> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<R, QS> AppearsOnTable<QS> for HstoreFromRecord<R>
+    where R: AppearsOnTable<QS>
+{
+}
+
+impl<R, QS> SelectableExpression<QS> for HstoreFromRecord<R>
+    where R: SelectableExpression<QS>
+{
+}
+
+impl<R> NonAggregate for HstoreFromRecord<R>
+    where R: NonAggregate
+{
+}
+
+impl<R> QueryFragment<Pg> for HstoreFromRecord<R>
+    where R: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("hstore(");
+        self.record.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Rewriting predicates to use GIN indexes.
+//!
+//! A GIN index on an hstore column answers containment, `store @> hstore(...)`, but not
+//! comparisons of single values such as `store -> 'env' = 'prod'`, which scan the whole table.
+//! Register the indexed columns in a [`GinIndexes`](struct.GinIndexes.html), and it rewrites
+//! [`key_eq`](../dsl/trait.HstoreOpExtensions.html#method.key_eq) and
+//! [`filter_by_pairs`](../dsl/trait.HstoreOpExtensions.html#method.filter_by_pairs) on those
+//! columns into containment:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel::pg::Pg;
+//! use diesel_pg_hstore::dsl::HstoreOpExtensions;
+//! use diesel_pg_hstore::gin::GinIndexes;
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     posts {
+//!         id -> Integer,
+//!         meta -> Hstore,
+//!         draft -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! // CREATE INDEX posts_meta ON posts USING gin (meta);
+//! let indexes = GinIndexes::new().index(posts::meta);
+//!
+//! let query = posts::table.filter(indexes.rewrite(posts::meta.key_eq("env", "prod")));
+//! let sql = diesel::debug_query::<Pg, _>(&query).to_string();
+//! assert!(sql.contains(r#""posts"."meta" @> hstore($1, $2)"#));
+//!
+//! let query = posts::table.filter(indexes.rewrite(posts::draft.key_eq("env", "prod")));
+//! let sql = diesel::debug_query::<Pg, _>(&query).to_string();
+//! assert!(sql.contains(r#""posts"."draft" ? $1 AND "posts"."draft" -> $2 = $3"#));
+//! # }
+//! ```
+//!
+//! The two forms select the same rows, and are null for the same rows, so they can also be
+//! negated. Columns are told apart by type, so an index must be registered for each `table!`
+//! column it covers.
+
+use std::any::TypeId;
+use std::collections::HashSet;
+
+use diesel::Column;
+
+use super::dsl::{FilterByPairs, KeyEq};
+
+/// The hstore columns with a GIN index.
+#[derive(Debug, Clone, Default)]
+pub struct GinIndexes {
+    columns: HashSet<TypeId>,
+}
+
+impl GinIndexes {
+    /// No indexed columns
+    pub fn new() -> GinIndexes {
+        GinIndexes::default()
+    }
+
+    /// Register `column` as indexed.
+    pub fn index<C: Column + 'static>(mut self, _column: C) -> GinIndexes {
+        self.columns.insert(TypeId::of::<C>());
+        self
+    }
+
+    /// Whether `column` is registered as indexed
+    pub fn is_indexed<C: 'static>(&self, _column: &C) -> bool {
+        self.columns.contains(&TypeId::of::<C>())
+    }
+
+    /// `predicate`, in containment form if its column is indexed.
+    pub fn rewrite<P: Rewrite>(&self, predicate: P) -> P {
+        predicate.rewrite(self)
+    }
+}
+
+/// A predicate which a [`GinIndexes`](struct.GinIndexes.html) can rewrite.
+pub trait Rewrite {
+    /// This predicate, in containment form if its column is in `indexes`.
+    fn rewrite(self, indexes: &GinIndexes) -> Self;
+}
+
+impl<T: 'static> Rewrite for KeyEq<T> {
+    fn rewrite(mut self, indexes: &GinIndexes) -> KeyEq<T> {
+        self.contains = indexes.columns.contains(&TypeId::of::<T>());
+        self
+    }
+}
+
+impl<T: 'static> Rewrite for FilterByPairs<T> {
+    fn rewrite(mut self, indexes: &GinIndexes) -> FilterByPairs<T> {
+        self.contains = indexes.columns.contains(&TypeId::of::<T>());
+        self
+    }
+}
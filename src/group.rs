@@ -0,0 +1,69 @@
+//! Folding rows of keys and values into hstores.
+//!
+//! Exports often read entries as rows, from `each(store)` or a join against an
+//! entity-attribute-value table, and need them back as one hstore per entity.
+//! [`group_rows`](fn.group_rows.html) does that fold over any iterator of
+//! `(group, key, value)` rows:
+//!
+//! ```rust
+//! use diesel_pg_hstore::group::group_rows;
+//!
+//! let rows = vec![
+//!     (1, "color".to_string(), Some("red".to_string())),
+//!     (1, "size".to_string(), None),
+//!     (2, "color".to_string(), Some("blue".to_string())),
+//! ];
+//!
+//! let groups: Vec<_> = group_rows(rows).collect();
+//! assert_eq!(groups[0].0, 1);
+//! assert_eq!(groups[0].1, [("color", "red")]);
+//! assert_eq!(groups[1].1, [("color", "blue")]);
+//! ```
+//!
+//! Rows are grouped while they are consecutive, as with `GROUP BY` over sorted input, so they
+//! should be ordered by the group column, for example with `ORDER BY entity_id`. Only one group
+//! is held in memory at a time. Rows with a `None` value are skipped, as null hstore values are,
+//! but a group of only such rows still yields an empty hstore. When a key is repeated within a
+//! group, the last value wins.
+
+use std::iter::Peekable;
+
+use super::Hstore;
+
+/// Group consecutive `(group, key, value)` rows into `(group, hstore)` items.
+pub fn group_rows<I, G>(rows: I) -> GroupRows<I::IntoIter>
+    where I: IntoIterator<Item = (G, String, Option<String>)>,
+          G: PartialEq
+{
+    GroupRows {
+        rows: rows.into_iter().peekable(),
+    }
+}
+
+/// The iterator returned by [`group_rows`](fn.group_rows.html)
+pub struct GroupRows<I: Iterator> {
+    rows: Peekable<I>,
+}
+
+impl<I, G> Iterator for GroupRows<I>
+    where I: Iterator<Item = (G, String, Option<String>)>,
+          G: PartialEq
+{
+    type Item = (G, Hstore);
+
+    fn next(&mut self) -> Option<(G, Hstore)> {
+        let (group, key, value) = self.rows.next()?;
+
+        let mut hstore = Hstore::new();
+        if let Some(value) = value {
+            hstore.0.insert(key, value);
+        }
+        while self.rows.peek().map_or(false, |&(ref next, _, _)| *next == group) {
+            let (_, key, value) = self.rows.next().expect("a peeked row");
+            if let Some(value) = value {
+                hstore.0.insert(key, value);
+            }
+        }
+        Some((group, hstore))
+    }
+}
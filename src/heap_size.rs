@@ -0,0 +1,68 @@
+//! Measuring the memory held by hstores.
+//!
+//! With the `heap-size` feature, [`Hstore::heap_size`](../struct.Hstore.html#method.heap_size)
+//! reports the bytes an hstore has allocated on the heap, so caches holding many maps can keep
+//! to a memory budget:
+//!
+//! ```rust
+//! use std::mem;
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! assert_eq!(store.heap_size(), 0);
+//!
+//! store.insert("color".into(), "red".into());
+//! let total = mem::size_of::<Hstore>() + store.heap_size();
+//! assert!(total > mem::size_of::<Hstore>() + "color".len() + "red".len());
+//! ```
+//!
+//! The size counts the capacity of the table and of every key and value, not just their length,
+//! as that is what is allocated. The table's share is computed from the layout of the standard
+//! library's `HashMap`, which isn't documented, so it is an estimate, though a close one; the
+//! allocator's own overhead isn't included.
+//!
+//! A [`FrozenHstore`](../frozen/struct.FrozenHstore.html) shares its map, so its size is the
+//! size of the map it shares, which callers should count once however many handles they hold.
+
+use std::mem;
+
+use super::Hstore;
+use super::frozen::FrozenHstore;
+
+/// The width of the groups of control bytes the table is probed in
+const GROUP_WIDTH: usize = 16;
+
+/// The number of buckets a table with `capacity` has
+fn buckets(capacity: usize) -> usize {
+    match capacity {
+        0 => 0,
+        1..=3 => 4,
+        4..=7 => 8,
+        _ => (capacity.saturating_mul(8) / 7).next_power_of_two(),
+    }
+}
+
+/// The bytes allocated by a table of `(String, String)` entries with `capacity`
+fn table_size(capacity: usize) -> usize {
+    match buckets(capacity) {
+        0 => 0,
+        buckets => buckets * mem::size_of::<(String, String)>() + buckets + GROUP_WIDTH,
+    }
+}
+
+impl Hstore {
+    /// The bytes allocated on the heap by the map and its keys and values. See the
+    /// [`heap_size`](heap_size/index.html) module.
+    pub fn heap_size(&self) -> usize {
+        let strings: usize = self.0.iter().map(|(key, value)| key.capacity() + value.capacity()).sum();
+        table_size(self.0.capacity()) + strings
+    }
+}
+
+impl FrozenHstore {
+    /// The bytes allocated on the heap by the shared map, including the map itself and its
+    /// reference counts.
+    pub fn heap_size(&self) -> usize {
+        2 * mem::size_of::<usize>() + mem::size_of::<Hstore>() + self.0.heap_size()
+    }
+}
@@ -0,0 +1,282 @@
+//! Importing rows with hstore columns, reporting the rows which fail.
+//!
+//! A [`BulkImport`](struct.BulkImport.html) inserts rows in chunks, each in its own savepoint.
+//! Rows whose hstores can't be parsed or are rejected by a validation hook are left out before
+//! inserting, and when the database rejects a chunk, it is split in halves and retried until the
+//! failing rows are found. The rest of the load goes in, and the failures are reported:
+//!
+//! ```rust,no_run
+//! # extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! # use diesel::prelude::*;
+//! use diesel_pg_hstore::import::BulkImport;
+//!
+//! # fn main() {
+//! # let db = PgConnection::establish("").unwrap();
+//! let rows = vec![
+//!     vec![Some("ABC-1".to_string()), Some(r#""color"=>"red""#.to_string())],
+//!     vec![Some("ABC-2".to_string()), Some(r#""color"=>"#.to_string())],
+//! ];
+//! let report = BulkImport::new("products")
+//!     .column("sku")
+//!     .hstore_column("attributes")
+//!     .validate(|_column, store| {
+//!         if store.contains_key("color") { Ok(()) } else { Err("color is required".into()) }
+//!     })
+//!     .run(&db, &rows)
+//!     .unwrap();
+//! for failure in report.failures() {
+//!     println!("{}", failure);
+//! }
+//! # }
+//! ```
+//!
+//! Values are given as text, or `None` for `NULL`, and are converted by the database to the type
+//! of their column. The whole import runs in one transaction, which commits the rows that
+//! succeeded, or if already in a transaction, in a savepoint of it. Errors other than the
+//! database rejecting a statement, such as a lost connection, abort the import.
+
+use std::fmt;
+
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+use super::Hstore;
+use super::quote::{quote_ident, quote_literal, quote_name};
+use super::text::{self, ParseError};
+
+/// Why a row wasn't imported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureKind {
+    /// The row has the wrong number of values.
+    Columns {
+        /// The number of columns imported
+        expected: usize,
+        /// The number of values in the row
+        found: usize,
+    },
+    /// An hstore value isn't a valid hstore literal.
+    Decode(ParseError),
+    /// An hstore value was rejected by the validation hook.
+    Invalid(String),
+    /// The database rejected the row, with this message.
+    Database(String),
+}
+
+/// A row which wasn't imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowFailure {
+    /// The index of the row in the rows imported
+    pub row: usize,
+    /// The column of the value which failed, if known
+    pub column: Option<String>,
+    /// Why the row failed
+    pub kind: FailureKind,
+}
+
+impl fmt::Display for RowFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "row {}", self.row)?;
+        if let Some(ref column) = self.column {
+            write!(f, ", column {}", column)?;
+        }
+        match self.kind {
+            FailureKind::Columns { expected, found } => write!(f, ": expected {} values, found {}", expected, found),
+            FailureKind::Decode(ref e) => write!(f, ": invalid hstore: {}", e),
+            FailureKind::Invalid(ref message) => write!(f, ": invalid: {}", message),
+            FailureKind::Database(ref message) => write!(f, ": rejected: {}", message),
+        }
+    }
+}
+
+/// The outcome of an import.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    imported: usize,
+    failures: Vec<RowFailure>,
+}
+
+impl ImportReport {
+    /// The number of rows inserted
+    pub fn imported(&self) -> usize {
+        self.imported
+    }
+
+    /// The rows which weren't, in order
+    pub fn failures(&self) -> &[RowFailure] {
+        &self.failures
+    }
+
+    /// Whether every row was inserted
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A column being imported
+#[derive(Debug, Clone)]
+struct ImportColumn {
+    name: String,
+    hstore: bool,
+}
+
+/// A hook checking an hstore before it is inserted
+type Validate<'a> = Box<Fn(&str, &Hstore) -> Result<(), String> + 'a>;
+
+/// An insert of many rows into a table, tolerating rows which fail.
+pub struct BulkImport<'a> {
+    table: String,
+    columns: Vec<ImportColumn>,
+    chunk_size: usize,
+    validate: Option<Validate<'a>>,
+}
+
+impl<'a> BulkImport<'a> {
+    /// Import into `table`, in chunks of 1000 rows.
+    pub fn new<T: Into<String>>(table: T) -> BulkImport<'a> {
+        BulkImport {
+            table: table.into(),
+            columns: Vec::new(),
+            chunk_size: 1000,
+            validate: None,
+        }
+    }
+
+    /// Import the next value of each row into `column`.
+    pub fn column<C: Into<String>>(mut self, column: C) -> BulkImport<'a> {
+        self.columns.push(ImportColumn {
+            name: column.into(),
+            hstore: false,
+        });
+        self
+    }
+
+    /// Import the next value of each row into the hstore column `column`, parsing it as an
+    /// hstore literal first.
+    pub fn hstore_column<C: Into<String>>(mut self, column: C) -> BulkImport<'a> {
+        self.columns.push(ImportColumn {
+            name: column.into(),
+            hstore: true,
+        });
+        self
+    }
+
+    /// Set the most rows inserted by each statement.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunk_size(mut self, chunk_size: usize) -> BulkImport<'a> {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Check each hstore before it is inserted, given its column. Rows with an hstore for which
+    /// `validate` returns an error are left out.
+    pub fn validate<F>(mut self, validate: F) -> BulkImport<'a>
+        where F: Fn(&str, &Hstore) -> Result<(), String> + 'a
+    {
+        self.validate = Some(Box::new(validate));
+        self
+    }
+
+    /// Insert `rows`, each holding a value for every column in order.
+    pub fn run(&self, conn: &PgConnection, rows: &[Vec<Option<String>>]) -> QueryResult<ImportReport> {
+        let mut report = ImportReport::default();
+        conn.transaction::<_, Error, _>(|| {
+            for (start, chunk) in rows.chunks(self.chunk_size).enumerate() {
+                let start = start * self.chunk_size;
+                let mut values = Vec::with_capacity(chunk.len());
+                for (i, row) in chunk.iter().enumerate() {
+                    match self.row_values(row) {
+                        Ok(row_values) => values.push((start + i, row_values)),
+                        Err((column, kind)) => report.failures.push(RowFailure {
+                            row: start + i,
+                            column: column,
+                            kind: kind,
+                        }),
+                    }
+                }
+                self.insert(conn, &values, &mut report)?;
+            }
+            Ok(())
+        })?;
+        report.failures.sort_by_key(|failure| failure.row);
+        Ok(report)
+    }
+
+    /// The row as a parenthesized list of SQL literals, or the column which failed and why
+    fn row_values(&self, row: &[Option<String>]) -> Result<String, (Option<String>, FailureKind)> {
+        if row.len() != self.columns.len() {
+            let kind = FailureKind::Columns {
+                expected: self.columns.len(),
+                found: row.len(),
+            };
+            return Err((None, kind));
+        }
+
+        let mut literals = Vec::with_capacity(row.len());
+        for (column, value) in self.columns.iter().zip(row) {
+            let literal = match *value {
+                None => "NULL".to_string(),
+                Some(ref value) if column.hstore => {
+                    let hstore = text::parse(value).map_err(|e| (Some(column.name.clone()), FailureKind::Decode(e)))?;
+                    if let Some(ref validate) = self.validate {
+                        validate(&column.name, &hstore).map_err(|message| (Some(column.name.clone()), FailureKind::Invalid(message)))?;
+                    }
+                    text::sql_literal(&hstore)
+                }
+                Some(ref value) => quote_literal(value),
+            };
+            literals.push(literal);
+        }
+        Ok(format!("({})", literals.join(", ")))
+    }
+
+    /// Insert `rows` in a savepoint, splitting them to find the failing rows if it fails
+    fn insert(&self, conn: &PgConnection, rows: &[(usize, String)], report: &mut ImportReport) -> QueryResult<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let columns: Vec<String> = self.columns.iter().map(|column| quote_ident(&column.name)).collect();
+        let values: Vec<&str> = rows.iter().map(|row| row.1.as_str()).collect();
+        let statement = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            quote_name(&self.table),
+            columns.join(", "),
+            values.join(", "),
+        );
+
+        match conn.transaction(|| conn.execute(&statement)) {
+            Ok(inserted) => {
+                report.imported += inserted;
+                Ok(())
+            }
+            Err(Error::DatabaseError(_, ref info)) if rows.len() == 1 => {
+                report.failures.push(RowFailure {
+                    row: rows[0].0,
+                    column: info.column_name().map(String::from),
+                    kind: FailureKind::Database(info.message().to_string()),
+                });
+                Ok(())
+            }
+            Err(Error::DatabaseError(..)) => {
+                let (first, second) = rows.split_at(rows.len() / 2);
+                self.insert(conn, first, report)?;
+                self.insert(conn, second, report)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for BulkImport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BulkImport")
+            .field("table", &self.table)
+            .field("columns", &self.columns)
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
@@ -0,0 +1,263 @@
+//! How often each key occurs in an hstore column, from the planner's statistics.
+//!
+//! Postgres can gather statistics on the keys of an hstore column, as the elements of the
+//! expression `akeys(store)`, once an extended statistics object is created for it. These are
+//! what the planner uses to estimate `store ? key`, and reading them is much cheaper than
+//! counting the keys of every row:
+//!
+//! ```rust,no_run
+//! # extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! # use diesel::prelude::*;
+//! use diesel_pg_hstore::key_stats::KeyStatistics;
+//!
+//! # fn main() {
+//! # let db = PgConnection::establish("").unwrap();
+//! let stats = KeyStatistics::new("products")
+//!     .store("attributes")
+//!     .create_statistics()
+//!     .analyze()
+//!     .read(&db)
+//!     .unwrap();
+//! for &(ref key, frequency) in stats.keys() {
+//!     println!("{}: in {:.1}% of rows", key, frequency * 100.0);
+//! }
+//! println!("`attributes ? 'color'` selects {:.1}%", stats.selectivity("color") * 100.0);
+//! # }
+//! ```
+//!
+//! Without [`create_statistics`](struct.KeyStatistics.html#method.create_statistics) and
+//! [`analyze`](struct.KeyStatistics.html#method.analyze), reading fails if the statistics
+//! object is missing or the table hasn't been analyzed since, and the error says which
+//! statement to run. Statistics on expressions need Postgres 14 or later.
+//!
+//! Only the most common keys are listed, up to the column's statistics target (100 by
+//! default), and the frequencies are estimated from a sample of the table.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::types::{Array, Float4, Nullable, Text};
+
+use super::quote::{quote_ident, quote_name};
+
+/// The selectivity Postgres assumes for containment when it knows nothing better
+const DEFAULT_CONTAIN_SEL: f64 = 0.005;
+
+/// An error reading key statistics.
+#[derive(Debug)]
+pub enum StatsError {
+    /// Querying the database failed.
+    Query(Error),
+    /// There is no statistics object on the keys of the column. Running `create` makes one.
+    NoStatistics {
+        /// The statement creating the statistics object
+        create: String,
+    },
+    /// The table hasn't been analyzed since the statistics object was made. Running `analyze`
+    /// gathers the statistics.
+    NotAnalyzed {
+        /// The statement analyzing the table
+        analyze: String,
+    },
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StatsError::Query(ref e) => write!(f, "reading key statistics failed: {}", e),
+            StatsError::NoStatistics { ref create } => write!(f, "no key statistics; run `{}`", create),
+            StatsError::NotAnalyzed { ref analyze } => write!(f, "key statistics not gathered yet; run `{}`", analyze),
+        }
+    }
+}
+
+impl StdError for StatsError {
+    fn description(&self) -> &str {
+        match *self {
+            StatsError::Query(_) => "reading key statistics failed",
+            StatsError::NoStatistics { .. } => "no key statistics",
+            StatsError::NotAnalyzed { .. } => "key statistics not gathered yet",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            StatsError::Query(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for StatsError {
+    fn from(e: Error) -> StatsError {
+        StatsError::Query(e)
+    }
+}
+
+/// Reads the key statistics of an hstore column.
+#[derive(Debug, Clone)]
+pub struct KeyStatistics {
+    table: String,
+    store: String,
+    name: Option<String>,
+    create: bool,
+    analyze: bool,
+}
+
+impl KeyStatistics {
+    /// Read the statistics of the `store` column of `table`.
+    pub fn new<T: Into<String>>(table: T) -> KeyStatistics {
+        KeyStatistics {
+            table: table.into(),
+            store: "store".into(),
+            name: None,
+            create: false,
+            analyze: false,
+        }
+    }
+
+    /// Read the statistics of the hstore column `store` instead.
+    pub fn store<S: Into<String>>(mut self, store: S) -> KeyStatistics {
+        self.store = store.into();
+        self
+    }
+
+    /// Name the statistics object `name`, rather than `<table>_<store>_keys`.
+    pub fn statistics_name<N: Into<String>>(mut self, name: N) -> KeyStatistics {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Create the statistics object if it is missing.
+    pub fn create_statistics(mut self) -> KeyStatistics {
+        self.create = true;
+        self
+    }
+
+    /// Analyze the table before reading, so the statistics are up to date.
+    pub fn analyze(mut self) -> KeyStatistics {
+        self.analyze = true;
+        self
+    }
+
+    /// The name of the statistics object
+    pub fn name(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => {
+                let table = self.table.rsplit('.').next().unwrap_or(&self.table);
+                format!("{}_{}_keys", table, self.store)
+            }
+        }
+    }
+
+    /// The statement creating the statistics object, if it is missing
+    pub fn create_sql(&self) -> String {
+        format!(
+            "CREATE STATISTICS IF NOT EXISTS {} ON (akeys({})) FROM {}",
+            quote_ident(&self.name()),
+            quote_ident(&self.store),
+            quote_name(&self.table),
+        )
+    }
+
+    /// The statement gathering the statistics
+    pub fn analyze_sql(&self) -> String {
+        format!("ANALYZE {}", quote_name(&self.table))
+    }
+
+    /// Read the statistics, first creating the statistics object and analyzing the table if
+    /// asked.
+    pub fn read(&self, conn: &PgConnection) -> Result<KeyStats, StatsError> {
+        if self.create {
+            conn.execute(&self.create_sql())?;
+        }
+        if self.analyze {
+            conn.execute(&self.analyze_sql())?;
+        }
+
+        let exists = sql::<Text>("SELECT stxname::text FROM pg_statistic_ext WHERE stxname = ")
+            .bind::<Text, _>(self.name())
+            .get_result::<String>(conn)
+            .optional()?;
+        if exists.is_none() {
+            return Err(StatsError::NoStatistics {
+                create: self.create_sql(),
+            });
+        }
+
+        let stats = sql::<(Float4, Nullable<Array<Text>>, Nullable<Array<Float4>>)>(
+            "SELECT null_frac, most_common_elems::text::text[], most_common_elem_freqs \
+             FROM pg_stats_ext_exprs WHERE NOT inherited AND statistics_name = ")
+            .bind::<Text, _>(self.name())
+            .get_result::<(f32, Option<Vec<String>>, Option<Vec<f32>>)>(conn)
+            .optional()?;
+        match stats {
+            Some((null_frac, keys, frequencies)) => Ok(KeyStats::new(null_frac, keys.unwrap_or_default(), frequencies.unwrap_or_default())),
+            None => Err(StatsError::NotAnalyzed {
+                analyze: self.analyze_sql(),
+            }),
+        }
+    }
+}
+
+/// The key statistics of an hstore column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyStats {
+    null_frac: f64,
+    keys: Vec<(String, f64)>,
+    min_frequency: Option<f64>,
+}
+
+impl KeyStats {
+    /// Statistics from `pg_stats`, whose `frequencies` end with the least and greatest
+    /// frequencies and the frequency of null keys
+    fn new(null_frac: f32, keys: Vec<String>, frequencies: Vec<f32>) -> KeyStats {
+        let null_frac = f64::from(null_frac);
+        let min_frequency = frequencies.get(keys.len()).map(|&f| f64::from(f) * (1.0 - null_frac));
+        let mut keys: Vec<(String, f64)> = keys.into_iter()
+            .zip(frequencies)
+            .map(|(key, frequency)| (key, f64::from(frequency) * (1.0 - null_frac)))
+            .collect();
+        keys.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(::std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        KeyStats {
+            null_frac: null_frac,
+            keys: keys,
+            min_frequency: min_frequency,
+        }
+    }
+
+    /// The most common keys with the fraction of rows having each, most common first
+    pub fn keys(&self) -> &[(String, f64)] {
+        &self.keys
+    }
+
+    /// The fraction of rows having `key`, if it is among the most common keys
+    pub fn frequency(&self, key: &str) -> Option<f64> {
+        self.keys.iter().find(|entry| entry.0 == key).map(|&(_, frequency)| frequency)
+    }
+
+    /// The fraction of rows whose hstore is `NULL`
+    pub fn null_frac(&self) -> f64 {
+        self.null_frac
+    }
+
+    /// The fraction of rows the planner expects `store ? key` to select.
+    ///
+    /// Keys which aren't among the most common are assumed to be in half as many rows as the
+    /// least common one listed, as Postgres does.
+    pub fn selectivity(&self, key: &str) -> f64 {
+        match self.frequency(key) {
+            Some(frequency) => frequency,
+            None => match self.min_frequency {
+                Some(min_frequency) => (min_frequency / 2.0).min(DEFAULT_CONTAIN_SEL),
+                None => DEFAULT_CONTAIN_SEL,
+            },
+        }
+    }
+}
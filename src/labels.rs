@@ -0,0 +1,154 @@
+//! Converting hstores to metric label sets.
+//!
+//! With the `labels` feature, an hstore's entries can be emitted as Prometheus or
+//! OpenTelemetry labels. Keys are sanitized into valid label names, and
+//! [`LabelRules`](struct.LabelRules.html) keep the set bounded, as every distinct label set is a
+//! new time series:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::labels::LabelRules;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("region".into(), "eu-west".into());
+//! store.insert("app.version".into(), "1.2".into());
+//! store.insert("user_id".into(), "8271".into());
+//!
+//! let rules = LabelRules::new().drop_key("user_id").max_labels(8);
+//! let set = store.to_labels(&rules);
+//! assert_eq!(set.labels, [
+//!     ("app_version".to_string(), "1.2".to_string()),
+//!     ("region".to_string(), "eu-west".to_string()),
+//! ]);
+//! assert_eq!(set.dropped, ["user_id"]);
+//! ```
+//!
+//! A name is made of ASCII letters, digits and `_`, and doesn't start with a digit; other
+//! characters become `_`. Names starting `__` are reserved by Prometheus, and their entries are
+//! dropped, as are entries with empty values, which Prometheus treats as no label. When several
+//! keys sanitize to the same name, the first key in sorted order is kept.
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::Hstore;
+
+/// The label name for an hstore key, or `None` if the key can't be one.
+pub fn sanitize_name(key: &str) -> Option<String> {
+    let mut name = String::with_capacity(key.len() + 1);
+    if key.starts_with(|c: char| c.is_ascii_digit()) {
+        name.push('_');
+    }
+    name.extend(key.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }));
+
+    if name.is_empty() || name.starts_with("__") {
+        None
+    }
+    else {
+        Some(name)
+    }
+}
+
+/// How an hstore converts to a label set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelRules {
+    drop: HashSet<String>,
+    max_labels: Option<usize>,
+    max_value_len: Option<usize>,
+}
+
+impl LabelRules {
+    /// Convert every entry, with no limits.
+    pub fn new() -> LabelRules {
+        LabelRules::default()
+    }
+
+    /// Never convert the entry `key`.
+    pub fn drop_key<K: Into<String>>(mut self, key: K) -> LabelRules {
+        self.drop.insert(key.into());
+        self
+    }
+
+    /// Never convert any of `keys`.
+    pub fn drop_keys<I>(mut self, keys: I) -> LabelRules
+        where I: IntoIterator,
+              I::Item: Into<String>
+    {
+        self.drop.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keep at most `max_labels` labels, the first by name.
+    pub fn max_labels(mut self, max_labels: usize) -> LabelRules {
+        self.max_labels = Some(max_labels);
+        self
+    }
+
+    /// Truncate values to at most `max_value_len` bytes, at a character boundary.
+    pub fn max_value_len(mut self, max_value_len: usize) -> LabelRules {
+        self.max_value_len = Some(max_value_len);
+        self
+    }
+
+    /// Convert `hstore` to a label set.
+    pub fn to_labels(&self, hstore: &Hstore) -> LabelSet {
+        let mut entries: Vec<_> = hstore.iter().collect();
+        entries.sort();
+
+        let mut labels = BTreeMap::new();
+        let mut dropped = Vec::new();
+        for (key, value) in entries {
+            let value = self.truncate(value);
+            match sanitize_name(key).filter(|name| !labels.contains_key(name)) {
+                Some(name) if !self.drop.contains(key) && !value.is_empty() => {
+                    labels.insert(name, (key, value));
+                }
+                _ => dropped.push(key.clone()),
+            }
+        }
+
+        let max_labels = self.max_labels.unwrap_or(labels.len());
+        let mut set = LabelSet {
+            labels: Vec::with_capacity(labels.len().min(max_labels)),
+            dropped: dropped,
+        };
+        for (i, (name, (key, value))) in labels.into_iter().enumerate() {
+            if i < max_labels {
+                set.labels.push((name, value));
+            }
+            else {
+                set.dropped.push(key.clone());
+            }
+        }
+        set.dropped.sort();
+        set
+    }
+
+    fn truncate(&self, value: &str) -> String {
+        match self.max_value_len {
+            Some(max) if value.len() > max => {
+                let mut end = max;
+                while !value.is_char_boundary(end) {
+                    end -= 1;
+                }
+                value[..end].to_string()
+            }
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// The labels converted from an hstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelSet {
+    /// The labels, as name and value, sorted by name
+    pub labels: Vec<(String, String)>,
+    /// The keys of the entries which weren't converted, sorted
+    pub dropped: Vec<String>,
+}
+
+impl Hstore {
+    /// Convert to a label set. See the [`labels`](labels/index.html) module.
+    pub fn to_labels(&self, rules: &LabelRules) -> LabelSet {
+        rules.to_labels(self)
+    }
+}
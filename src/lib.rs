@@ -11,6 +11,10 @@
 //! diesel_pg_hstore = "*"
 //! ```
 //!
+//! The [dsl](dsl/index.html) operators, the SQL [functions](functions/index.html) and the serde
+//! support each have a feature of the same name, `dsl`, `functions` and `serde`, all on by
+//! default. Turn off the default features if you only need the `Hstore` type.
+//!
 //! Bring the crate into your project. (For example, from your `lib.rs` file)
 //! ```rust,ignore
 //! extern diesel_pg_hstore;
@@ -94,20 +98,278 @@
 //! ### Nullable hstore values
 //!
 //! Postgres hstore entries having a null value are simply ignored.
+//!
+//! ### Optional hstore fields
+//!
+//! An `Option<Hstore>` field of an `Insertable` struct behaves like any other optional field:
+//! `None` inserts the column's `DEFAULT`, which is `NULL` for a nullable column without one.
+//! This works for both `Hstore` and `Nullable<Hstore>` columns.
+//!
+//! A `Nullable<Hstore>` column loads as an `Option<Hstore>`, and the
+//! [dsl](dsl/index.html) operators can be used on it, with nullable results.
+//!
+//! ### Raw SQL
+//!
+//! Hstores can be bound to `diesel::sql_query` like any built in type, as `Hstore`,
+//! `Nullable<Hstore>` or `Array<Hstore>`, and read back through `QueryableByName` structs.
+//! Arrays which may hold `NULL`, such as `array_agg(store)` over a `LEFT JOIN`, are
+//! `Array<Nullable<Hstore>>` and map to `Vec<Option<Hstore>>`:
+//!
+//! ```rust,no_run
+//! # extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! # use diesel::prelude::*;
+//! use diesel::types::{Array, Integer, Text};
+//! use diesel_pg_hstore::Hstore;
+//!
+//! # fn main() {
+//! # let db = PgConnection::establish("").unwrap();
+//! let settings = Hstore::new();
+//! diesel::sql_query("UPDATE user_profile SET settings = settings || $1 WHERE id = $2")
+//!     .bind::<Hstore, _>(&settings)
+//!     .bind::<Integer, _>(1)
+//!     .execute(&db)
+//!     .unwrap();
+//! diesel::sql_query("UPDATE user_profile SET settings = settings - $1")
+//!     .bind::<Array<Text>, _>(vec!["obsolete"])
+//!     .execute(&db)
+//!     .unwrap();
+//! # }
+//! ```
+//!
+//! ### JSON
+//!
+//! With the `serde` feature, `Hstore` and `HstoreNullable` implement `Serialize` and
+//! `Deserialize` as maps from strings to strings, a JSON object in `serde_json`, with the
+//! entries sorted by key. When deserializing, a `null` value drops the entry from an `Hstore`,
+//! as reading one from Postgres does, while an `HstoreNullable` keeps it. Numbers and booleans
+//! are kept as their text.
+//!
+//! With the `json` feature, the [functions](functions/index.html) module has
+//! `hstore_to_json`, `hstore_to_jsonb` and `hstore_to_json_loose`, for converting in SQL.
+//!
+//! ### Entry order
+//!
+//! `Hstore` is backed by a `HashMap`, whose order changes from run to run. The
+//! [ordered](ordered/index.html) module has `BTreeHstore`, sorted by key, and with the `indexmap`
+//! feature `IndexHstore`, in insertion order. They are read and bound like an `Hstore`, and
+//! serialize their entries in their own order.
+//!
+//! ### Typed structs
+//!
+//! The [mapping](mapping/index.html) module converts structs to and from hstores, a key for each
+//! field, through the `ToHstore` and `FromHstore` traits. With the `derive` feature they can be
+//! derived, `#[derive(ToHstore, FromHstore)]`.
+//!
+//! ### Composite types
+//!
+//! Hstores can be bound by value as well as by reference, so they can be members of tuples and
+//! row expressions built by your queries. Composite rows *returned* by Postgres, such as a
+//! function returning a record with an hstore member, can't be loaded whole, as Diesel 1.0, which
+//! this crate currently targets, has no `Record` type to decode them. Select the hstore member
+//! instead, as `(row).store`, which loads like any other hstore.
+//!
+//! Rows can be converted to and from hstores in SQL with the
+//! [functions](functions/index.html) `populate_record` and `hstore_from_record`, which work with
+//! expressions of any composite type.
 
 extern crate diesel;
 extern crate byteorder;
-extern crate fallible_iterator;
-
+#[macro_use]
+extern crate lazy_static;
+#[cfg(feature = "testing")]
+extern crate dotenv;
+#[cfg(feature = "fake")]
+extern crate fake;
+#[cfg(feature = "encryption")]
+extern crate aes_gcm;
+#[cfg(any(feature = "encryption", feature = "compression"))]
+extern crate base64;
+#[cfg(feature = "compression")]
+extern crate flate2;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
+#[cfg(feature = "valuable")]
+extern crate valuable;
+#[cfg(feature = "validator")]
+extern crate validator;
+#[cfg(feature = "config")]
+extern crate config;
+#[cfg(feature = "serde_urlencoded")]
+extern crate serde_urlencoded;
+#[cfg(feature = "axum")]
+extern crate axum_core;
+#[cfg(feature = "axum")]
+extern crate http;
+#[cfg(feature = "axum")]
+extern crate bytes;
+#[cfg(feature = "actix")]
+extern crate actix_web;
+#[cfg(any(feature = "axum", feature = "actix"))]
+extern crate futures_util;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "csv")]
+extern crate csv;
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_schema;
+#[cfg(feature = "polars")]
+extern crate polars_core;
+#[cfg(feature = "toml")]
+extern crate toml;
+#[cfg(feature = "yaml")]
+extern crate serde_yaml;
+#[cfg(feature = "testcontainers")]
+extern crate testcontainers_modules;
+#[cfg(feature = "moka")]
+extern crate moka;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "indexmap")]
+extern crate indexmap;
+#[cfg(feature = "derive")]
+#[allow(unused_imports)]
+#[macro_use]
+extern crate diesel_pg_hstore_derive;
+
+use std::borrow::Borrow;
 use std::ops::{Index, Deref, DerefMut};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::collections::hash_map::*;
-use std::iter::FromIterator;
+use std::fmt;
+use std::iter::{FromIterator, Sum};
+use std::mem;
+
+#[macro_use]
+#[doc(hidden)]
+pub mod macros;
+pub mod prelude;
+pub mod table_import;
+pub mod sim;
+pub mod text;
+pub mod copy;
+pub mod metrics;
+pub mod typed;
+pub mod list;
+pub mod counters;
+pub mod frozen;
+pub mod null_values;
+pub mod schema;
+pub mod normalize;
+pub mod deprecation;
+pub mod view;
+pub mod pivot;
+pub mod cleanup;
+pub mod batch;
+pub mod import;
+pub mod key_stats;
+pub mod patch;
+pub mod cache;
+pub mod tenant;
+pub mod scan;
+pub mod pretty;
+pub mod audit;
+pub mod logging;
+pub mod env_file;
+pub mod localized;
+pub mod ttl;
+pub mod transform;
+pub mod compact;
+pub mod snapshot;
+pub mod error;
+pub mod group;
+pub mod sorted;
+pub mod dedup;
+pub mod ordered;
+pub mod mapping;
+mod quote;
+mod cmp;
+pub mod value_codec;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "validator")]
+pub mod validation;
+#[cfg(feature = "config")]
+pub mod settings;
+#[cfg(feature = "serde_urlencoded")]
+pub mod urlencoded;
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub mod extract;
+#[cfg(feature = "heap-size")]
+pub mod heap_size;
+#[cfg(feature = "debezium")]
+pub mod debezium;
+#[cfg(feature = "labels")]
+pub mod labels;
+#[cfg(feature = "csv")]
+pub mod csv_file;
+#[cfg(feature = "arrow")]
+pub mod arrow_batch;
+#[cfg(feature = "polars")]
+pub mod polars_frame;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+pub mod flat;
+#[cfg(feature = "dsl")]
+pub mod dsl;
+#[cfg(feature = "dsl")]
+pub mod window;
+#[cfg(feature = "dsl")]
+pub mod predicates;
+#[cfg(feature = "dsl")]
+pub mod merge;
+#[cfg(feature = "dsl")]
+pub mod gin;
+#[cfg(feature = "functions")]
+pub mod functions;
+#[cfg(feature = "dsl")]
+pub mod matrix;
+#[cfg(feature = "dsl")]
+pub mod changeset;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "fake")]
+pub mod faker;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "bench-internals")]
+pub mod codec;
+#[cfg(not(feature = "bench-internals"))]
+mod codec;
+
+pub use typed::HstoreOf;
+pub use frozen::{CowHstore, FrozenHstore};
+pub use null_values::HstoreNullable;
+pub use ordered::BTreeHstore;
+#[cfg(feature = "indexmap")]
+pub use ordered::IndexHstore;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub use diesel_pg_hstore_derive::*;
 
 /// The Hstore wrapper type.
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct Hstore(HashMap<String, String>);
 
+/// Entries are written sorted by key, so the output is the same for equal hstores. Please see
+/// the [snapshot](snapshot/index.html) module.
+impl fmt::Debug for Hstore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sorted: BTreeMap<&String, &String> = self.0.iter().collect();
+        f.debug_tuple("Hstore").field(&sorted).finish()
+    }
+}
+
 /// You can deref the Hstore into it's backing HashMap
 ///
 /// ```rust
@@ -142,6 +404,42 @@ impl DerefMut for Hstore {
     }
 }
 
+/// An Hstore can also be passed to generic code expecting its backing HashMap
+///
+/// ```rust
+/// use diesel_pg_hstore::Hstore;
+/// use std::borrow::Borrow;
+/// use std::collections::HashMap;
+///
+/// fn count<M: AsRef<HashMap<String, String>>>(map: M) -> usize {
+///     map.as_ref().len()
+/// }
+///
+/// let mut settings = Hstore::new();
+/// settings.insert("Hello".into(), "World".into());
+/// assert_eq!(count(&settings), 1);
+///
+/// let hashmap: &HashMap<String, String> = settings.borrow();
+/// assert_eq!(hashmap["Hello"], "World");
+/// ```
+impl AsRef<HashMap<String, String>> for Hstore {
+    fn as_ref(&self) -> &HashMap<String, String> {
+        &self.0
+    }
+}
+
+impl AsMut<HashMap<String, String>> for Hstore {
+    fn as_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.0
+    }
+}
+
+impl Borrow<HashMap<String, String>> for Hstore {
+    fn borrow(&self) -> &HashMap<String, String> {
+        &self.0
+    }
+}
+
 impl Hstore {
     /// Create a new Hstore object
     pub fn new() -> Hstore {
@@ -270,8 +568,8 @@ impl IntoIterator for Hstore {
     type Item = (String, String);
     type IntoIter = IntoIter<String, String>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::replace(&mut self.0, HashMap::new()).into_iter()
     }
 }
 
@@ -318,22 +616,80 @@ impl Extend<(String, String)> for Hstore {
     }
 }
 
+/// Merges every Hstore into one, as `||` does. When a key appears in more than one, the value
+/// from the last wins.
+///
+/// ```rust
+/// # use diesel_pg_hstore::Hstore;
+/// let mut first = Hstore::new();
+/// first.insert("a".into(), "1".into());
+/// first.insert("b".into(), "1".into());
+/// let mut second = Hstore::new();
+/// second.insert("b".into(), "2".into());
+///
+/// let merged: Hstore = vec![first, second].into_iter().collect();
+/// assert_eq!(merged, [("a", "1"), ("b", "2")]);
+/// ```
+impl FromIterator<Hstore> for Hstore {
+    fn from_iter<T>(iter: T) -> Hstore
+        where T: IntoIterator<Item = Hstore>
+    {
+        let mut merged = Hstore::new();
+        for hstore in iter {
+            merged.extend(hstore);
+        }
+        merged
+    }
+}
+
+/// The same as collecting, merging every Hstore into one with the last value for each key.
+impl Sum for Hstore {
+    fn sum<I>(iter: I) -> Hstore
+        where I: Iterator<Item = Hstore>
+    {
+        iter.collect()
+    }
+}
+
+/// With the `zeroize` feature, every key and value is overwritten with zeros when an Hstore is
+/// dropped, or when `zeroize` is called explicitly. This leaves the Hstore empty.
+///
+/// Only the map's own strings are covered. Strings moved out of it, for example by `drain`,
+/// `remove` or `into_iter`, are the caller's responsibility.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Hstore {
+    fn zeroize(&mut self) {
+        for (mut key, mut value) in self.0.drain() {
+            key.zeroize();
+            value.zeroize();
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Hstore {}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Hstore {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self)
+    }
+}
+
 mod impls {
-    use std::str;
     use std::error::Error as StdError;
     use std::io::Write;
-    use std::collections::HashMap;
-    use fallible_iterator::FallibleIterator;
-    use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian};
     use diesel::types::impls::option::UnexpectedNullError;
     use diesel::Queryable;
     use diesel::expression::AsExpression;
     use diesel::expression::bound::Bound;
     use diesel::pg::Pg;
+    use diesel::query_builder::QueryId;
     use diesel::row::Row;
     use diesel::types::*;
 
     use super::Hstore;
+    use super::codec;
 
     impl HasSqlType<Hstore> for Pg {
         fn metadata(lookup: &Self::MetadataLookup) -> Self::TypeMetadata {
@@ -343,6 +699,13 @@ mod impls {
 
     impl NotNull for Hstore {}
     impl SingleValue for Hstore {}
+
+    impl QueryId for Hstore {
+        type QueryId = Hstore;
+
+        const HAS_STATIC_QUERY_ID: bool = true;
+    }
+
     impl Queryable<Hstore, Pg> for Hstore {
         type Row = Self;
 
@@ -351,6 +714,14 @@ mod impls {
         }
     }
 
+    impl AsExpression<Hstore> for Hstore {
+        type Expression = Bound<Hstore, Hstore>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
     impl<'a> AsExpression<Hstore> for &'a Hstore {
         type Expression = Bound<Hstore, &'a Hstore>;
 
@@ -359,32 +730,30 @@ mod impls {
         }
     }
 
-    impl FromSql<Hstore, Pg> for Hstore {
-        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
-            let mut buf = match bytes {
-                Some(bytes) => bytes,
-                None => return Err(Box::new(UnexpectedNullError {
-                    msg: "Unexpected null for non-null column".to_string(),
-                })),
-            };
-            let count = buf.read_i32::<BigEndian>()?;
+    impl AsExpression<Nullable<Hstore>> for Hstore {
+        type Expression = Bound<Nullable<Hstore>, Hstore>;
 
-            if count < 0 {
-                return Err("Invalid entry count for hstore".into());
-            }
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
 
-            let mut entries = HstoreIterator {
-                remaining: count,
-                buf: buf,
-            };
+    impl<'a> AsExpression<Nullable<Hstore>> for &'a Hstore {
+        type Expression = Bound<Nullable<Hstore>, &'a Hstore>;
 
-            let mut map = HashMap::new();
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
 
-            while let Some((k, v)) = entries.next()? {
-                map.insert(k.into(), v.into());
+    impl FromSql<Hstore, Pg> for Hstore {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            match bytes {
+                Some(bytes) => codec::decode(bytes),
+                None => Err(Box::new(UnexpectedNullError {
+                    msg: "Unexpected null for non-null column".to_string(),
+                })),
             }
-
-            Ok(Hstore(map))
         }
     }
 
@@ -399,92 +768,19 @@ mod impls {
             where W: Write
         {
             let mut buf: Vec<u8> = Vec::new();
-            buf.extend_from_slice(&[0; 4]);
-
-            let mut count = 0;
-            for (key, value) in &self.0 {
-                count += 1;
-
-                write_pascal_string(&key, &mut buf)?;
-                write_pascal_string(&value, &mut buf)?;
-            }
-
-            let count = count as i32;
-            (&mut buf[0..4])
-                .write_i32::<BigEndian>(count)
-                .unwrap();
+            codec::encode(self, &mut buf)?;
 
             out.write_all(&buf)?;
+            #[cfg(feature = "zeroize")]
+            ::zeroize::Zeroize::zeroize(&mut buf);
             Ok(IsNull::No)
         }
     }
-
-    fn write_pascal_string(s: &str, buf: &mut Vec<u8>) -> Result<(), Box<StdError + Sync + Send>> {
-        let size: i32 = s.len() as i32;
-        buf.write_i32::<BigEndian>(size).unwrap();
-        buf.extend_from_slice(s.as_bytes());
-        Ok(())
-    }
-
-    struct HstoreIterator<'a> {
-        remaining: i32,
-        buf: &'a [u8],
-    }
-
-    impl<'a> HstoreIterator<'a> {
-        fn consume(&mut self) -> Result<Option<(&'a str, Option<&'a str>)>, Box<StdError + Sync + Send>> {
-            if self.remaining == 0 {
-                if !self.buf.is_empty() {
-                    return Err("invalid buffer size".into());
-                }
-                return Ok(None);
-            }
-
-            self.remaining -= 1;
-
-            let key_len = self.buf.read_i32::<BigEndian>()?;
-            if key_len < 0 {
-                return Err("invalid key length".into());
-            }
-            let (key, buf) = self.buf.split_at(key_len as usize);
-            let key = str::from_utf8(key)?;
-            self.buf = buf;
-
-            let value_len = self.buf.read_i32::<BigEndian>()?;
-            let value = if value_len < 0 {
-                None
-            }
-            else {
-                let (value, buf) = self.buf.split_at(value_len as usize);
-                let value = str::from_utf8(value)?;
-                self.buf = buf;
-                Some(value)
-            };
-
-            Ok(Some((key, value)))
-        }
-    }
-
-    impl<'a> FallibleIterator for HstoreIterator<'a> {
-        type Item = (&'a str, &'a str);
-        type Error = Box<StdError + Sync + Send>;
-
-        #[inline]
-        fn next(&mut self) -> Result<Option<Self::Item>, Self::Error> {
-            while let Some(res) = self.consume()? {
-                match res {
-                    (key, Some(val)) => return Ok(Some((key, val))),
-                    _ => continue,
-                }
-            }
-
-            Ok(None)
-        }
-
-        #[inline]
-        fn size_hint(&self) -> (usize, Option<usize>) {
-            let len = self.remaining as usize;
-            (len, Some(len))
+    impl ToSql<Nullable<Hstore>, Pg> for Hstore {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(self, out)
         }
     }
 }
@@ -0,0 +1,152 @@
+//! Storing lists of strings in hstore values.
+//!
+//! A [`ListCodec`](struct.ListCodec.html) joins items with a delimiter, escaping any delimiter
+//! or escape characters within them so every list round trips:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::list::ListCodec;
+//!
+//! let codec = ListCodec::default();
+//! let mut store = Hstore::new();
+//!
+//! store.set_list("tags", &["red", "green, mostly"], &codec);
+//! assert_eq!(store["tags"], "red,green^1 mostly");
+//! assert_eq!(store.get_list("tags", &codec).unwrap(), vec!["red", "green, mostly"]);
+//! ```
+//!
+//! Within an item, the escape character is written as the escape followed by `0`, and the
+//! delimiter as the escape followed by `1`. An encoded list therefore never contains the
+//! delimiter except between items, which is what lets the `list_append` and `list_remove`
+//! expressions in the [`dsl`](../dsl/index.html) module edit lists in the database using
+//! Postgres' own array functions.
+//!
+//! An empty list is stored as the empty string, so a list holding a single empty item can't be
+//! told apart from an empty one.
+
+use super::Hstore;
+
+/// Joins and splits lists of strings stored in a single hstore value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListCodec {
+    delimiter: char,
+    escape: char,
+}
+
+impl ListCodec {
+    /// Create a codec with the given delimiter and escape characters.
+    ///
+    /// Panics if the two are the same, or either is `0` or `1`.
+    pub fn new(delimiter: char, escape: char) -> ListCodec {
+        assert!(delimiter != escape, "list delimiter and escape must differ");
+        for c in &[delimiter, escape] {
+            assert!(*c != '0' && *c != '1', "list delimiter and escape can't be '0' or '1'");
+        }
+
+        ListCodec {
+            delimiter: delimiter,
+            escape: escape,
+        }
+    }
+
+    /// The character separating items
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    /// The character introducing an escape within an item
+    pub fn escape(&self) -> char {
+        self.escape
+    }
+
+    /// Escape a single item
+    pub fn escape_item(&self, item: &str) -> String {
+        let mut out = String::with_capacity(item.len());
+        for c in item.chars() {
+            if c == self.escape {
+                out.push(self.escape);
+                out.push('0');
+            }
+            else if c == self.delimiter {
+                out.push(self.escape);
+                out.push('1');
+            }
+            else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    /// Reverse `escape_item`.
+    ///
+    /// An escape followed by anything other than `0` or `1` is kept as-is.
+    pub fn unescape_item(&self, item: &str) -> String {
+        let mut out = String::with_capacity(item.len());
+        let mut chars = item.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == self.escape {
+                match chars.peek() {
+                    Some(&'0') => {
+                        chars.next();
+                        out.push(self.escape);
+                        continue;
+                    },
+                    Some(&'1') => {
+                        chars.next();
+                        out.push(self.delimiter);
+                        continue;
+                    },
+                    _ => {},
+                }
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Join a list of items into a single value
+    pub fn encode<I, S>(&self, items: I) -> String
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        let mut out = String::new();
+        for (i, item) in items.into_iter().enumerate() {
+            if i > 0 {
+                out.push(self.delimiter);
+            }
+            out.push_str(&self.escape_item(item.as_ref()));
+        }
+        out
+    }
+
+    /// Split a value into its items
+    pub fn decode(&self, value: &str) -> Vec<String> {
+        if value.is_empty() {
+            return Vec::new();
+        }
+        value.split(self.delimiter).map(|item| self.unescape_item(item)).collect()
+    }
+}
+
+/// Uses `,` as the delimiter and `^` as the escape.
+impl Default for ListCodec {
+    fn default() -> ListCodec {
+        ListCodec::new(',', '^')
+    }
+}
+
+impl Hstore {
+    /// Decode the list stored under `key`, if there is one.
+    pub fn get_list(&self, key: &str, codec: &ListCodec) -> Option<Vec<String>> {
+        self.get(key).map(|value| codec.decode(value))
+    }
+
+    /// Encode and store a list under `key`, returning the previously stored value.
+    pub fn set_list<I, S>(&mut self, key: &str, items: I, codec: &ListCodec) -> Option<String>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.insert(key.to_string(), codec.encode(items))
+    }
+}
@@ -0,0 +1,38 @@
+//! Translations stored as locale suffixed keys.
+//!
+//! Each translation of a value is stored under the key followed by `.` and a locale, as in
+//! `title.fr-CA`. [`Hstore::get_localized`](../struct.Hstore.html#method.get_localized) looks
+//! a value up through a chain of locales, returning the first translation present:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("title.fr".into(), "Bonjour".into());
+//! store.insert("title.en".into(), "Hello".into());
+//!
+//! assert_eq!(store.get_localized("title", &["fr-CA", "fr", "en"]).unwrap(), "Bonjour");
+//! assert_eq!(store.get_localized("title", &["de"]), None);
+//! ```
+//!
+//! With the `dsl` feature, the same lookup can be made in a query with
+//! [`HstoreOpExtensions::get_localized`](../dsl/trait.HstoreOpExtensions.html#method.get_localized).
+
+use super::Hstore;
+
+/// The key holding the `locale` translation of `key`
+pub fn localized_key(key: &str, locale: &str) -> String {
+    format!("{}.{}", key, locale)
+}
+
+impl Hstore {
+    /// The first translation of `key` present, trying each locale in order.
+    pub fn get_localized<I, L>(&self, key: &str, locales: I) -> Option<&String>
+        where I: IntoIterator<Item = L>,
+              L: AsRef<str>
+    {
+        locales.into_iter()
+            .filter_map(|locale| self.get(&localized_key(key, locale.as_ref())))
+            .next()
+    }
+}
@@ -0,0 +1,118 @@
+//! Logging hstores as structured fields.
+//!
+//! A [`LogValue`](struct.LogValue.html) presents an hstore's entries to a logger as a map, sorted
+//! by key, with the values of sensitive keys hidden as an
+//! [`AuditFormat`](../audit/struct.AuditFormat.html) would hide them:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::audit::AuditFormat;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("region".into(), "eu".into());
+//! store.insert("api_token".into(), "abc".into());
+//!
+//! let format = AuditFormat::new().redact("api_token");
+//! let value = store.log_value_redacted(&format);
+//! assert_eq!(format!("{:?}", value), r#"{"api_token": "[redacted]", "region": "eu"}"#);
+//! ```
+//!
+//! With the `tracing` feature, a `LogValue` is recorded with `?`, as in
+//! `info!(attributes = ?store.log_value_redacted(&format))`. With the `valuable` feature, both
+//! `Hstore` and `LogValue` implement `valuable::Valuable`, so subscribers built with
+//! `tracing_unstable` can record them as maps with `tracing::field::valuable`, rather than as
+//! text.
+
+use std::fmt;
+
+use super::Hstore;
+use super::audit::AuditFormat;
+
+/// An hstore as logged. See the [`logging`](index.html) module.
+#[derive(Clone, Copy)]
+pub struct LogValue<'a> {
+    hstore: &'a Hstore,
+    format: Option<&'a AuditFormat>,
+}
+
+impl<'a> LogValue<'a> {
+    /// The entries as logged, sorted by key
+    pub fn entries(&self) -> Vec<(&'a str, &'a str)> {
+        let mut entries: Vec<_> = self.hstore.iter()
+            .map(|(key, value)| match self.format {
+                Some(format) => (key.as_str(), format.shown(key, value)),
+                None => (key.as_str(), value.as_str()),
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+impl<'a> fmt::Debug for LogValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.entries()).finish()
+    }
+}
+
+impl Hstore {
+    /// Log every value as is.
+    pub fn log_value(&self) -> LogValue {
+        LogValue {
+            hstore: self,
+            format: None,
+        }
+    }
+
+    /// Log with the values `format` redacts hidden.
+    pub fn log_value_redacted<'a>(&'a self, format: &'a AuditFormat) -> LogValue<'a> {
+        LogValue {
+            hstore: self,
+            format: Some(format),
+        }
+    }
+}
+
+#[cfg(feature = "valuable")]
+mod valuable_impls {
+    use valuable::{Mappable, Valuable, Value, Visit};
+
+    use super::LogValue;
+    use super::super::Hstore;
+
+    impl<'a> Valuable for LogValue<'a> {
+        fn as_value(&self) -> Value {
+            Value::Mappable(self)
+        }
+
+        fn visit(&self, visit: &mut Visit) {
+            for (key, value) in self.entries() {
+                visit.visit_entry(Value::String(key), Value::String(value));
+            }
+        }
+    }
+
+    impl<'a> Mappable for LogValue<'a> {
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.hstore.len(), Some(self.hstore.len()))
+        }
+    }
+
+    /// Visits every entry as is, sorted by key. Use
+    /// [`log_value_redacted`](../struct.Hstore.html#method.log_value_redacted) to hide values.
+    impl Valuable for Hstore {
+        fn as_value(&self) -> Value {
+            Value::Mappable(self)
+        }
+
+        fn visit(&self, visit: &mut Visit) {
+            self.log_value().visit(visit)
+        }
+    }
+
+    impl Mappable for Hstore {
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.len(), Some(self.len()))
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Support code for the exported macros. Not public API.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::Hstore;
+
+/// Asserts that two hstores are equal.
+///
+/// On failure, rather than printing both maps, the panic message lists each differing key in
+/// sorted order:
+///
+/// * `-` entries only present on the left
+/// * `+` entries only present on the right
+/// * `~` entries whose value differs
+///
+/// A custom message may be given, as with `assert_eq!`.
+///
+/// ```rust,should_panic
+/// #[macro_use] extern crate diesel_pg_hstore;
+/// use diesel_pg_hstore::Hstore;
+///
+/// # fn main() {
+/// let mut left = Hstore::new();
+/// left.insert("env".into(), "prod".into());
+/// left.insert("legacy".into(), "t".into());
+///
+/// let mut right = Hstore::new();
+/// right.insert("env".into(), "staging".into());
+///
+/// // assertion failed: `(left == right)`
+/// // ~ "env": "prod" != "staging"
+/// // - "legacy" => "t"
+/// assert_hstore_eq!(left, right);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_hstore_eq {
+    ($left:expr, $right:expr) => ({
+        match (&$left, &$right) {
+            (left, right) => {
+                if let Some(diff) = $crate::macros::diff(left, right) {
+                    panic!("assertion failed: `(left == right)`\n{}", diff)
+                }
+            }
+        }
+    });
+    ($left:expr, $right:expr, $($arg:tt)+) => ({
+        match (&$left, &$right) {
+            (left, right) => {
+                if let Some(diff) = $crate::macros::diff(left, right) {
+                    panic!("assertion failed: `(left == right)`: {}\n{}", format_args!($($arg)+), diff)
+                }
+            }
+        }
+    });
+}
+
+/// Describe the differences between two hstores, one key per line, or `None` if they're equal.
+pub fn diff(left: &Hstore, right: &Hstore) -> Option<String> {
+    if left == right {
+        return None;
+    }
+
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+
+    let mut out = String::new();
+    for key in keys {
+        match (left.get(key), right.get(key)) {
+            (Some(l), Some(r)) if l != r => writeln!(out, "~ {:?}: {:?} != {:?}", key, l, r),
+            (Some(l), None) => writeln!(out, "- {:?} => {:?}", key, l),
+            (None, Some(r)) => writeln!(out, "+ {:?} => {:?}", key, r),
+            _ => Ok(()),
+        }.unwrap();
+    }
+
+    Some(out)
+}
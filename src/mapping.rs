@@ -0,0 +1,279 @@
+//! Converting structs to and from hstores.
+//!
+//! A struct implementing [`ToHstore`](trait.ToHstore.html) and
+//! [`FromHstore`](trait.FromHstore.html) is written as an hstore with a key for each field, its
+//! value formatted with `ToString`, and read back with each value parsed with `FromStr`. A field
+//! of type `Option<T>` may be missing, and a `None` is written as a missing key.
+//!
+//! With the `derive` feature, both traits can be derived. `#[hstore(rename = "...")]` sets the
+//! key of a field, which is otherwise its name:
+//!
+//! ```rust,ignore
+//! #[macro_use]
+//! extern crate diesel_pg_hstore;
+//!
+//! #[derive(ToHstore, FromHstore)]
+//! struct Settings {
+//!     retries: u32,
+//!     #[hstore(rename = "ui.theme")]
+//!     theme: Option<String>,
+//! }
+//! ```
+//!
+//! They can also be implemented by hand, with [`required`](fn.required.html) and
+//! [`optional`](fn.optional.html) parsing the values:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::mapping::{self, FromHstore, FromHstoreError, ToHstore};
+//!
+//! struct Settings {
+//!     retries: u32,
+//!     theme: Option<String>,
+//! }
+//!
+//! impl ToHstore for Settings {
+//!     fn to_hstore(&self) -> Hstore {
+//!         let mut hstore = Hstore::new();
+//!         hstore.insert("retries".into(), self.retries.to_string());
+//!         if let Some(ref theme) = self.theme {
+//!             hstore.insert("theme".into(), theme.to_string());
+//!         }
+//!         hstore
+//!     }
+//! }
+//!
+//! impl FromHstore for Settings {
+//!     fn from_hstore(hstore: &Hstore) -> Result<Settings, FromHstoreError> {
+//!         Ok(Settings {
+//!             retries: mapping::required(hstore, "retries")?,
+//!             theme: mapping::optional(hstore, "theme")?,
+//!         })
+//!     }
+//! }
+//!
+//! let hstore: Hstore = "retries=>3".parse().unwrap();
+//! let settings = Settings::from_hstore(&hstore).unwrap();
+//! assert_eq!(settings.retries, 3);
+//! assert_eq!(settings.theme, None);
+//! assert_eq!(settings.to_hstore(), hstore);
+//!
+//! let error = Settings::from_hstore(&"retries=>many".parse().unwrap()).err().unwrap();
+//! assert_eq!(error.key(), "retries");
+//! ```
+//!
+//! To load and save such a struct as an hstore column, wrap it in a
+//! [`Mapped`](struct.Mapped.html), which can be used anywhere an `Hstore` can. To save it
+//! without losing the keys of other fields, combine `to_hstore` with a
+//! [merge strategy](../merge/index.html), as in
+//! `settings_column.merged(settings.to_hstore(), ConcatMerge)`.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use super::Hstore;
+use super::typed::ValueError;
+
+/// A type which can be written as an hstore.
+pub trait ToHstore {
+    /// The hstore of the fields
+    fn to_hstore(&self) -> Hstore;
+}
+
+/// A type which can be read from an hstore.
+pub trait FromHstore: Sized {
+    /// Parse the fields from an hstore. Keys without a field are ignored.
+    fn from_hstore(hstore: &Hstore) -> Result<Self, FromHstoreError>;
+}
+
+/// The value of `key` parsed as `T`, failing if it is missing
+pub fn required<T>(hstore: &Hstore, key: &str) -> Result<T, FromHstoreError>
+    where T: FromStr,
+          T::Err: fmt::Display
+{
+    match optional(hstore, key)? {
+        Some(value) => Ok(value),
+        None => Err(FromHstoreError::MissingKey(key.to_string())),
+    }
+}
+
+/// The value of `key` parsed as `T`, or `None` if it is missing
+pub fn optional<T>(hstore: &Hstore, key: &str) -> Result<Option<T>, FromHstoreError>
+    where T: FromStr,
+          T::Err: fmt::Display
+{
+    match hstore.get(key) {
+        Some(value) => value.parse()
+            .map(Some)
+            .map_err(|e: T::Err| FromHstoreError::InvalidValue(ValueError::new(key, value, e.to_string()))),
+        None => Ok(None),
+    }
+}
+
+/// A struct could not be read from an hstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromHstoreError {
+    /// The key of a required field is missing.
+    MissingKey(String),
+    /// A value could not be parsed as the type of its field.
+    InvalidValue(ValueError),
+}
+
+impl FromHstoreError {
+    /// The key of the field which could not be read
+    pub fn key(&self) -> &str {
+        match *self {
+            FromHstoreError::MissingKey(ref key) => key,
+            FromHstoreError::InvalidValue(ref e) => e.key(),
+        }
+    }
+}
+
+impl fmt::Display for FromHstoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FromHstoreError::MissingKey(ref key) => write!(f, "missing hstore key {:?}", key),
+            FromHstoreError::InvalidValue(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl StdError for FromHstoreError {
+    fn description(&self) -> &str {
+        match *self {
+            FromHstoreError::MissingKey(_) => "missing hstore key",
+            FromHstoreError::InvalidValue(_) => "invalid hstore value",
+        }
+    }
+}
+
+/// A struct loaded from and saved as an hstore column, through
+/// [`FromHstore`](trait.FromHstore.html) and [`ToHstore`](trait.ToHstore.html).
+///
+/// ```rust
+/// # #[macro_use] extern crate diesel;
+/// # extern crate diesel_pg_hstore;
+/// # use diesel_pg_hstore::Hstore;
+/// # use diesel_pg_hstore::mapping::{FromHstore, FromHstoreError, ToHstore};
+/// use diesel_pg_hstore::mapping::Mapped;
+///
+/// table! {
+///     use diesel::types::*;
+///     use diesel_pg_hstore::Hstore;
+///
+///     accounts {
+///         id -> Integer,
+///         settings -> Hstore,
+///     }
+/// }
+///
+/// # #[derive(Debug)]
+/// # struct Settings;
+/// # impl ToHstore for Settings { fn to_hstore(&self) -> Hstore { Hstore::new() } }
+/// # impl FromHstore for Settings {
+/// #     fn from_hstore(_: &Hstore) -> Result<Settings, FromHstoreError> { Ok(Settings) }
+/// # }
+/// #[derive(Queryable)]
+/// struct Account {
+///     id: i32,
+///     settings: Mapped<Settings>,
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mapped<T>(pub T);
+
+impl<T> Mapped<T> {
+    /// The struct
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Mapped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Mapped<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Mapped<T> {
+    fn from(value: T) -> Mapped<T> {
+        Mapped(value)
+    }
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::fmt;
+    use std::io::Write;
+    use diesel::Queryable;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::row::Row;
+    use diesel::types::*;
+
+    use super::{FromHstore, Mapped, ToHstore};
+    use super::super::Hstore;
+
+    impl<T: FromHstore> Queryable<Hstore, Pg> for Mapped<T> {
+        type Row = Self;
+
+        fn build(row: Self::Row) -> Self {
+            row
+        }
+    }
+
+    impl<T> AsExpression<Hstore> for Mapped<T>
+        where T: ToHstore + fmt::Debug
+    {
+        type Expression = Bound<Hstore, Mapped<T>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a, T> AsExpression<Hstore> for &'a Mapped<T>
+        where T: ToHstore + fmt::Debug
+    {
+        type Expression = Bound<Hstore, &'a Mapped<T>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<T: FromHstore> FromSql<Hstore, Pg> for Mapped<T> {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            let hstore = Hstore::from_sql(bytes)?;
+            Ok(Mapped(T::from_hstore(&hstore)?))
+        }
+    }
+
+    impl<T: FromHstore> FromSqlRow<Hstore, Pg> for Mapped<T> {
+        fn build_from_row<R: Row<Pg>>(row: &mut R) -> Result<Self, Box<StdError + Send + Sync>> {
+            Mapped::from_sql(row.take())
+        }
+    }
+
+    impl<T> ToSql<Hstore, Pg> for Mapped<T>
+        where T: ToHstore + fmt::Debug
+    {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(&self.0.to_hstore(), out)
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Hstores as two-dimensional arrays of keys and values.
+//!
+//! `%# store` and `hstore_to_matrix(store)` return a `text[][]` with a `{key, value}` row for
+//! each entry. Diesel's `Array` type is one-dimensional, so these have their own SQL type,
+//! [`TextMatrix`](struct.TextMatrix.html), which loads as a `Vec<(String, Option<String>)>` of
+//! the entries in the order Postgres keeps them:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::dsl::HstoreOpExtensions;
+//!
+//! let pairs: Vec<(String, Option<String>)> = products
+//!     .select(attributes.to_pairs())
+//!     .first(&db)?;
+//! ```
+//!
+//! This module is available with the `dsl` feature, which is enabled by default.
+
+use std::error::Error as StdError;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use diesel::pg::{Pg, PgMetadataLookup, PgTypeMetadata};
+use diesel::query_builder::QueryId;
+use diesel::types::{HasSqlType, NotNull, SingleValue};
+
+/// The SQL type of a two-dimensional text array of `{key, value}` rows, as returned by
+/// [`to_pairs`](../dsl/trait.HstoreOpExtensions.html#method.to_pairs) and
+/// [`hstore_to_matrix`](../functions/fn.hstore_to_matrix.html).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextMatrix;
+
+/// The oid of `text[]`, which has any number of dimensions
+const TEXT_ARRAY_OID: u32 = 1009;
+
+impl HasSqlType<TextMatrix> for Pg {
+    fn metadata(_: &PgMetadataLookup) -> PgTypeMetadata {
+        PgTypeMetadata {
+            oid: TEXT_ARRAY_OID,
+            array_oid: 0,
+        }
+    }
+}
+
+impl NotNull for TextMatrix {}
+impl SingleValue for TextMatrix {}
+
+impl QueryId for TextMatrix {
+    type QueryId = TextMatrix;
+
+    const HAS_STATIC_QUERY_ID: bool = true;
+}
+
+/// The entries as loaded, keys with their values
+type Pairs = Vec<(String, Option<String>)>;
+
+/// Decode the `{key, value}` rows of a `text[][]` in the binary array format
+fn decode(mut bytes: &[u8]) -> Result<Pairs, Box<StdError + Send + Sync>> {
+    let dimensions = bytes.read_i32::<BigEndian>()?;
+    let _has_nulls = bytes.read_i32::<BigEndian>()?;
+    let _element_oid = bytes.read_u32::<BigEndian>()?;
+    if dimensions == 0 {
+        return Ok(Vec::new());
+    }
+    if dimensions != 2 {
+        return Err(format!("expected a two-dimensional array, found {} dimensions", dimensions).into());
+    }
+
+    let rows = bytes.read_i32::<BigEndian>()?;
+    let _lower_bound = bytes.read_i32::<BigEndian>()?;
+    let columns = bytes.read_i32::<BigEndian>()?;
+    let _lower_bound = bytes.read_i32::<BigEndian>()?;
+    if rows < 0 || columns != 2 {
+        return Err(format!("expected rows of a key and a value, found {} columns", columns).into());
+    }
+
+    let mut pairs = Vec::with_capacity(rows as usize);
+    for _ in 0..rows {
+        let key = match read_element(&mut bytes)? {
+            Some(key) => key,
+            None => return Err("unexpected null key".into()),
+        };
+        let value = read_element(&mut bytes)?;
+        pairs.push((key, value));
+    }
+    if !bytes.is_empty() {
+        return Err("unexpected bytes after the last element".into());
+    }
+    Ok(pairs)
+}
+
+fn read_element(bytes: &mut &[u8]) -> Result<Option<String>, Box<StdError + Send + Sync>> {
+    let len = bytes.read_i32::<BigEndian>()?;
+    if len < 0 {
+        return Ok(None);
+    }
+    if len as usize > bytes.len() {
+        return Err("element length exceeds the array".into());
+    }
+    let (element, rest) = bytes.split_at(len as usize);
+    *bytes = rest;
+    Ok(Some(String::from_utf8(element.to_vec())?))
+}
+
+/// `Queryable` follows from this, through Diesel's implementations for every `Vec` with `FromSql`
+mod impls {
+    use std::error::Error as StdError;
+    use diesel::types::impls::option::UnexpectedNullError;
+    use diesel::pg::Pg;
+    use diesel::types::*;
+
+    use super::{decode, Pairs, TextMatrix};
+
+    impl FromSql<TextMatrix, Pg> for Pairs {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            match bytes {
+                Some(bytes) => decode(bytes),
+                None => Err(Box::new(UnexpectedNullError {
+                    msg: "Unexpected null for non-null column".to_string(),
+                })),
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! How a saved hstore is combined with the one in the database.
+//!
+//! An [`HstoreMergeStrategy`](trait.HstoreMergeStrategy.html) decides what becomes of the stored
+//! hstore when a model is saved with a new one: whether it is replaced, merged, or merged with
+//! some keys removed. Choosing a strategy once, rather than building an expression at every call
+//! site, keeps saves consistent:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::dsl::HstoreOpExtensions;
+//! use diesel_pg_hstore::merge::MergeDeleteMissing;
+//!
+//! // The form manages `color` and `size`; other keys belong to other parts of the app.
+//! let strategy = MergeDeleteMissing::new(vec!["color", "size"]);
+//! diesel::update(products.find(id))
+//!     .set(attributes.eq(attributes.merged(form_values, &strategy)))
+//!     .execute(&db)?;
+//! ```
+//!
+//! The strategies provided are [`Replace`](struct.Replace.html),
+//! [`ConcatMerge`](struct.ConcatMerge.html) and
+//! [`MergeDeleteMissing`](struct.MergeDeleteMissing.html). Any other can be written by
+//! implementing the trait, which renders the merged value as SQL and applies the same merge in
+//! memory.
+
+use diesel::expression::{AppearsOnTable, Expression, NonAggregate, SelectableExpression};
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::types::{Array, Text};
+
+use super::Hstore;
+
+/// A way of saving an hstore over the stored one.
+pub trait HstoreMergeStrategy {
+    /// Render the result of saving `new` over `store`, binding `new` as a parameter.
+    fn walk_merge(&self, store: &QueryFragment<Pg>, new: &Hstore, out: AstPass<Pg>) -> QueryResult<()>;
+
+    /// The result of saving `new` over `old`, as the database would make it.
+    fn merge(&self, old: &Hstore, new: &Hstore) -> Hstore;
+}
+
+impl<M: HstoreMergeStrategy + ?Sized> HstoreMergeStrategy for &M {
+    fn walk_merge(&self, store: &QueryFragment<Pg>, new: &Hstore, out: AstPass<Pg>) -> QueryResult<()> {
+        (**self).walk_merge(store, new, out)
+    }
+
+    fn merge(&self, old: &Hstore, new: &Hstore) -> Hstore {
+        (**self).merge(old, new)
+    }
+}
+
+impl<M: HstoreMergeStrategy + ?Sized> HstoreMergeStrategy for Box<M> {
+    fn walk_merge(&self, store: &QueryFragment<Pg>, new: &Hstore, out: AstPass<Pg>) -> QueryResult<()> {
+        (**self).walk_merge(store, new, out)
+    }
+
+    fn merge(&self, old: &Hstore, new: &Hstore) -> Hstore {
+        (**self).merge(old, new)
+    }
+}
+
+/// Replace the stored hstore, `new`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Replace;
+
+impl HstoreMergeStrategy for Replace {
+    fn walk_merge(&self, _store: &QueryFragment<Pg>, new: &Hstore, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_bind_param::<Hstore, _>(new)
+    }
+
+    fn merge(&self, _old: &Hstore, new: &Hstore) -> Hstore {
+        new.clone()
+    }
+}
+
+/// Set every key of the new hstore, keeping the other stored keys, `store || new`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConcatMerge;
+
+impl HstoreMergeStrategy for ConcatMerge {
+    fn walk_merge(&self, store: &QueryFragment<Pg>, new: &Hstore, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(");
+        store.walk_ast(out.reborrow())?;
+        out.push_sql(" || ");
+        out.push_bind_param::<Hstore, _>(new)?;
+        out.push_sql(")");
+        Ok(())
+    }
+
+    fn merge(&self, old: &Hstore, new: &Hstore) -> Hstore {
+        let mut merged = old.clone();
+        merged.extend(new.iter().map(|(key, value)| (key.clone(), value.clone())));
+        merged
+    }
+}
+
+/// Set every key of the new hstore, and delete the managed keys it doesn't have, keeping the
+/// other stored keys, `(store - managed) || new`.
+///
+/// This suits a form which edits some of the keys: a field left empty removes its key, and keys
+/// the form doesn't know about are left alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeDeleteMissing {
+    managed: Vec<String>,
+}
+
+impl MergeDeleteMissing {
+    /// Manage `keys`.
+    pub fn new<I>(keys: I) -> MergeDeleteMissing
+        where I: IntoIterator,
+              I::Item: Into<String>
+    {
+        MergeDeleteMissing {
+            managed: keys.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The managed keys
+    pub fn managed(&self) -> &[String] {
+        &self.managed
+    }
+}
+
+impl HstoreMergeStrategy for MergeDeleteMissing {
+    fn walk_merge(&self, store: &QueryFragment<Pg>, new: &Hstore, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("((");
+        store.walk_ast(out.reborrow())?;
+        out.push_sql(" - ");
+        out.push_bind_param::<Array<Text>, _>(&self.managed)?;
+        out.push_sql(") || ");
+        out.push_bind_param::<Hstore, _>(new)?;
+        out.push_sql(")");
+        Ok(())
+    }
+
+    fn merge(&self, old: &Hstore, new: &Hstore) -> Hstore {
+        let mut merged: Hstore = old.iter()
+            .filter(|&(key, _)| !self.managed.contains(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        merged.extend(new.iter().map(|(key, value)| (key.clone(), value.clone())));
+        merged
+    }
+}
+
+/// The return type of [`merged`](../dsl/trait.HstoreOpExtensions.html#method.merged)
+#[derive(Debug, Clone)]
+pub struct Merged<T, M> {
+    store: T,
+    new: Hstore,
+    strategy: M,
+}
+
+impl<T, M> Merged<T, M> {
+    pub(crate) fn new(store: T, new: Hstore, strategy: M) -> Merged<T, M> {
+        Merged {
+            store: store,
+            new: new,
+            strategy: strategy,
+        }
+    }
+}
+
+impl<T, M> Expression for Merged<T, M>
+    where T: Expression
+{
+    type SqlType = T::SqlType;
+}
+
+impl<T, M> QueryId for Merged<T, M> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T, M, QS> AppearsOnTable<QS> for Merged<T, M>
+    where T: AppearsOnTable<QS>
+{
+}
+
+impl<T, M, QS> SelectableExpression<QS> for Merged<T, M>
+    where T: SelectableExpression<QS>
+{
+}
+
+impl<T, M> NonAggregate for Merged<T, M>
+    where T: NonAggregate
+{
+}
+
+impl<T, M> QueryFragment<Pg> for Merged<T, M>
+    where T: QueryFragment<Pg>,
+          M: HstoreMergeStrategy
+{
+    fn walk_ast(&self, out: AstPass<Pg>) -> QueryResult<()> {
+        self.strategy.walk_merge(&self.store, &self.new, out)
+    }
+}
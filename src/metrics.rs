@@ -0,0 +1,175 @@
+//! Hooks for observing what the codec does with the data passing through it.
+//!
+//! Register a [`CodecObserver`](trait.CodecObserver.html) with
+//! [`set_observer`](fn.set_observer.html) to be told about every hstore serialized or
+//! deserialized, entries with null values that were dropped while decoding, and values larger
+//! than a limit you choose. [`CodecStats`](struct.CodecStats.html) is an observer which simply
+//! counts these events, ready to be exported to your metrics system of choice.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use diesel_pg_hstore::metrics::{self, CodecStats};
+//!
+//! let stats = Arc::new(CodecStats::with_size_limit(64 * 1024));
+//! metrics::set_observer(stats.clone());
+//!
+//! // ... later, in a reporting loop
+//! let failures = stats.decode_failures();
+//! ```
+//!
+//! The observer is process-wide: it sees the hstores of every connection and thread, and
+//! registering another replaces it for all of them. The codec looks it up once per hstore, and
+//! until one is registered that is a single atomic load, without taking a lock.
+
+use std::error::Error as StdError;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Receives events from the hstore codec.
+///
+/// Every method has an empty default implementation, so only the events of interest need to be
+/// implemented. Methods are called from whichever thread is using the codec.
+pub trait CodecObserver: Send + Sync {
+    /// An hstore with `entries` entries was deserialized.
+    fn decoded(&self, entries: usize) {
+        let _ = entries;
+    }
+
+    /// Deserializing an hstore failed.
+    fn decode_failed(&self, error: &(StdError + Send + Sync)) {
+        let _ = error;
+    }
+
+    /// An entry having a null value was dropped while deserializing.
+    fn null_dropped(&self, key: &str) {
+        let _ = key;
+    }
+
+    /// An hstore with `entries` entries was serialized.
+    fn encoded(&self, entries: usize) {
+        let _ = entries;
+    }
+
+    /// A value of `len` bytes, more than `value_size_limit`, was serialized or deserialized.
+    fn oversized_value(&self, key: &str, len: usize) {
+        let _ = (key, len);
+    }
+
+    /// The size in bytes above which a value is reported to `oversized_value`.
+    fn value_size_limit(&self) -> usize {
+        usize::max_value()
+    }
+}
+
+lazy_static! {
+    static ref OBSERVER: RwLock<Option<Arc<CodecObserver>>> = RwLock::new(None);
+}
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Register the observer for all subsequent codec operations in the process, replacing any
+/// previous one.
+pub fn set_observer(observer: Arc<CodecObserver>) {
+    *OBSERVER.write().unwrap() = Some(observer);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Remove the registered observer.
+pub fn clear_observer() {
+    ENABLED.store(false, Ordering::SeqCst);
+    *OBSERVER.write().unwrap() = None;
+}
+
+/// The registered observer, looked up once by each codec call
+pub(crate) fn observer() -> Option<Arc<CodecObserver>> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    OBSERVER.read().unwrap().clone()
+}
+
+/// A `CodecObserver` which counts each kind of event.
+#[derive(Debug)]
+pub struct CodecStats {
+    size_limit: usize,
+    decoded: AtomicUsize,
+    decode_failures: AtomicUsize,
+    nulls_dropped: AtomicUsize,
+    encoded: AtomicUsize,
+    oversized_values: AtomicUsize,
+}
+
+impl CodecStats {
+    /// Create a new CodecStats which doesn't count oversized values
+    pub fn new() -> CodecStats {
+        CodecStats::with_size_limit(usize::max_value())
+    }
+
+    /// Create a new CodecStats counting values larger than `size_limit` bytes as oversized
+    pub fn with_size_limit(size_limit: usize) -> CodecStats {
+        CodecStats {
+            size_limit: size_limit,
+            decoded: AtomicUsize::new(0),
+            decode_failures: AtomicUsize::new(0),
+            nulls_dropped: AtomicUsize::new(0),
+            encoded: AtomicUsize::new(0),
+            oversized_values: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of hstores successfully deserialized
+    pub fn decoded(&self) -> usize {
+        self.decoded.load(Ordering::Relaxed)
+    }
+
+    /// The number of hstores which failed to deserialize
+    pub fn decode_failures(&self) -> usize {
+        self.decode_failures.load(Ordering::Relaxed)
+    }
+
+    /// The number of entries dropped for having a null value
+    pub fn nulls_dropped(&self) -> usize {
+        self.nulls_dropped.load(Ordering::Relaxed)
+    }
+
+    /// The number of hstores serialized
+    pub fn encoded(&self) -> usize {
+        self.encoded.load(Ordering::Relaxed)
+    }
+
+    /// The number of values seen which were over the size limit
+    pub fn oversized_values(&self) -> usize {
+        self.oversized_values.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for CodecStats {
+    fn default() -> Self {
+        CodecStats::new()
+    }
+}
+
+impl CodecObserver for CodecStats {
+    fn decoded(&self, _: usize) {
+        self.decoded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn decode_failed(&self, _: &(StdError + Send + Sync)) {
+        self.decode_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn null_dropped(&self, _: &str) {
+        self.nulls_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn encoded(&self, _: usize) {
+        self.encoded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn oversized_value(&self, _: &str, _: usize) {
+        self.oversized_values.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn value_size_limit(&self) -> usize {
+        self.size_limit
+    }
+}
@@ -0,0 +1,156 @@
+//! Normalizing the spelling of keys.
+//!
+//! A [`KeyNormalizer`](struct.KeyNormalizer.html) describes how keys should be spelled: with
+//! surrounding whitespace trimmed, lowercased, and (with the `unicode-normalization` feature)
+//! in Unicode normalization form C. Nothing is normalized unless you ask for it, on a given
+//! hstore, in one of three ways:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::normalize::KeyNormalizer;
+//!
+//! let normalizer = KeyNormalizer::new().trim(true).lowercase(true);
+//!
+//! // As entries are inserted
+//! let mut store = Hstore::new();
+//! store.insert_normalized(" Color ", "red", &normalizer);
+//! assert_eq!(store["color"], "red");
+//!
+//! // Over an existing hstore
+//! store.insert("SIZE".into(), "L".into());
+//! store.normalize_keys(&normalizer);
+//! assert_eq!(store["size"], "L");
+//!
+//! // On a copy, to bind without changing the original
+//! store.insert(" Weight".into(), "2kg".into());
+//! let normalized = normalizer.normalized(&store);
+//! assert_eq!(normalized["weight"], "2kg");
+//! assert_eq!(store[" Weight"], "2kg");
+//! ```
+//!
+//! When several keys normalize to the same key, only one value is kept. A key which is already
+//! normalized wins; otherwise the value of the key which sorts first is kept.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::UnicodeNormalization;
+
+use super::Hstore;
+
+/// How keys should be spelled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyNormalizer {
+    trim: bool,
+    lowercase: bool,
+    nfc: bool,
+}
+
+impl KeyNormalizer {
+    /// Create a normalizer which leaves keys unchanged
+    pub fn new() -> KeyNormalizer {
+        KeyNormalizer::default()
+    }
+
+    /// Remove leading and trailing whitespace.
+    pub fn trim(mut self, trim: bool) -> KeyNormalizer {
+        self.trim = trim;
+        self
+    }
+
+    /// Convert keys to lowercase, following Unicode's rules.
+    pub fn lowercase(mut self, lowercase: bool) -> KeyNormalizer {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Convert keys to Unicode normalization form C.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfc(mut self, nfc: bool) -> KeyNormalizer {
+        self.nfc = nfc;
+        self
+    }
+
+    /// Normalize a single key
+    pub fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(key);
+
+        if self.trim {
+            key = match key {
+                Cow::Borrowed(key) => Cow::Borrowed(key.trim()),
+                Cow::Owned(key) => Cow::Owned(key.trim().to_string()),
+            };
+        }
+        if self.nfc {
+            key = Cow::Owned(nfc(&key));
+        }
+        if self.lowercase && key.chars().any(|c| c.is_uppercase()) {
+            key = Cow::Owned(key.to_lowercase());
+        }
+
+        key
+    }
+
+    /// Whether `key` is already normalized
+    pub fn is_normalized(&self, key: &str) -> bool {
+        self.normalize(key) == key
+    }
+
+    /// A copy of `hstore` with its keys normalized
+    pub fn normalized(&self, hstore: &Hstore) -> Hstore {
+        let mut hstore = hstore.clone();
+        hstore.normalize_keys(self);
+        hstore
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+fn nfc(key: &str) -> String {
+    key.nfc().collect()
+}
+
+#[cfg(not(feature = "unicode-normalization"))]
+fn nfc(key: &str) -> String {
+    key.to_string()
+}
+
+impl Hstore {
+    /// Insert a key-value pair, normalizing the key first.
+    pub fn insert_normalized<K, V>(&mut self, key: K, value: V, normalizer: &KeyNormalizer) -> Option<String>
+        where K: AsRef<str>,
+              V: Into<String>
+    {
+        let key = normalizer.normalize(key.as_ref()).into_owned();
+        self.insert(key, value.into())
+    }
+
+    /// Normalize every key.
+    ///
+    /// Please see the [normalize](normalize/index.html) module for which value is kept when
+    /// several keys normalize to the same key.
+    pub fn normalize_keys(&mut self, normalizer: &KeyNormalizer) {
+        if self.keys().all(|key| normalizer.is_normalized(key)) {
+            return;
+        }
+
+        let mut renamed: Vec<(String, String)> = Vec::new();
+        let mut kept = HashMap::with_capacity(self.len());
+        for (key, value) in self.drain() {
+            if normalizer.is_normalized(&key) {
+                kept.insert(key, value);
+            }
+            else {
+                renamed.push((key, value));
+            }
+        }
+
+        renamed.sort();
+        for (key, value) in renamed {
+            let key = normalizer.normalize(&key).into_owned();
+            kept.entry(key).or_insert(value);
+        }
+
+        self.extend(kept);
+    }
+}
@@ -0,0 +1,230 @@
+//! Hstores whose values may be `NULL`.
+//!
+//! An hstore entry may have a `NULL` value, as in `'discontinued=>NULL'`. An
+//! [`Hstore`](../struct.Hstore.html) drops those entries when it is read, so a key set to `NULL`
+//! can't be told from a missing one. An [`HstoreNullable`](struct.HstoreNullable.html) keeps
+//! them, with `Option<String>` values, and can be read and written wherever an `Hstore` can:
+//!
+//! ```rust
+//! use diesel_pg_hstore::{Hstore, HstoreNullable};
+//!
+//! let mut store = HstoreNullable::new();
+//! store.insert("color".into(), Some("red".into()));
+//! store.insert("discontinued".into(), None);
+//!
+//! assert_eq!(store.null_keys(), vec!["discontinued"]);
+//! let hstore: Hstore = store.into_hstore();
+//! assert_eq!(hstore, [("color", "red")]);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use super::Hstore;
+
+/// An Hstore keeping entries with `NULL` values.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct HstoreNullable(HashMap<String, Option<String>>);
+
+/// Entries are written sorted by key, like [`Hstore`](../struct.Hstore.html)'s.
+impl fmt::Debug for HstoreNullable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sorted: BTreeMap<&String, &Option<String>> = self.0.iter().collect();
+        f.debug_tuple("HstoreNullable").field(&sorted).finish()
+    }
+}
+
+impl HstoreNullable {
+    /// An empty HstoreNullable
+    pub fn new() -> HstoreNullable {
+        HstoreNullable(HashMap::new())
+    }
+
+    /// Wrap an existing hashmap
+    pub fn from_hashmap(hm: HashMap<String, Option<String>>) -> HstoreNullable {
+        HstoreNullable(hm)
+    }
+
+    /// The backing hashmap
+    pub fn into_inner(mut self) -> HashMap<String, Option<String>> {
+        mem::replace(&mut self.0, HashMap::new())
+    }
+
+    /// The keys whose value is `NULL`, sorted
+    pub fn null_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.0.iter()
+            .filter(|&(_, value)| value.is_none())
+            .map(|(key, _)| key.as_str())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Convert to an Hstore, dropping the entries whose value is `NULL`, as reading an `Hstore`
+    /// does.
+    pub fn into_hstore(self) -> Hstore {
+        self.into_iter().filter_map(|(key, value)| value.map(|value| (key, value))).collect()
+    }
+}
+
+impl Deref for HstoreNullable {
+    type Target = HashMap<String, Option<String>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for HstoreNullable {
+    fn deref_mut(&mut self) -> &mut HashMap<String, Option<String>> {
+        &mut self.0
+    }
+}
+
+impl From<Hstore> for HstoreNullable {
+    fn from(hstore: Hstore) -> HstoreNullable {
+        hstore.into_iter().map(|(key, value)| (key, Some(value))).collect()
+    }
+}
+
+impl FromIterator<(String, Option<String>)> for HstoreNullable {
+    fn from_iter<I: IntoIterator<Item = (String, Option<String>)>>(iter: I) -> HstoreNullable {
+        HstoreNullable(HashMap::from_iter(iter))
+    }
+}
+
+impl Extend<(String, Option<String>)> for HstoreNullable {
+    fn extend<I: IntoIterator<Item = (String, Option<String>)>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for HstoreNullable {
+    type Item = (String, Option<String>);
+    type IntoIter = ::std::collections::hash_map::IntoIter<String, Option<String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_inner().into_iter()
+    }
+}
+
+/// Zeroizes every key and value, as for [`Hstore`](../struct.Hstore.html).
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for HstoreNullable {
+    fn zeroize(&mut self) {
+        for (mut key, value) in self.0.drain() {
+            key.zeroize();
+            if let Some(mut value) = value {
+                value.zeroize();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for HstoreNullable {}
+
+#[cfg(feature = "zeroize")]
+impl Drop for HstoreNullable {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self)
+    }
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::io::Write;
+    use diesel::types::impls::option::UnexpectedNullError;
+    use diesel::Queryable;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::row::Row;
+    use diesel::types::*;
+
+    use super::HstoreNullable;
+    use super::super::Hstore;
+    use super::super::codec;
+
+    impl Queryable<Hstore, Pg> for HstoreNullable {
+        type Row = Self;
+
+        fn build(row: Self::Row) -> Self {
+            row
+        }
+    }
+
+    impl AsExpression<Hstore> for HstoreNullable {
+        type Expression = Bound<Hstore, HstoreNullable>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a> AsExpression<Hstore> for &'a HstoreNullable {
+        type Expression = Bound<Hstore, &'a HstoreNullable>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl AsExpression<Nullable<Hstore>> for HstoreNullable {
+        type Expression = Bound<Nullable<Hstore>, HstoreNullable>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a> AsExpression<Nullable<Hstore>> for &'a HstoreNullable {
+        type Expression = Bound<Nullable<Hstore>, &'a HstoreNullable>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl FromSql<Hstore, Pg> for HstoreNullable {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            match bytes {
+                Some(bytes) => codec::decode_nullable(bytes).map(HstoreNullable),
+                None => Err(Box::new(UnexpectedNullError {
+                    msg: "Unexpected null for non-null column".to_string(),
+                })),
+            }
+        }
+    }
+
+    impl FromSqlRow<Hstore, Pg> for HstoreNullable {
+        fn build_from_row<T: Row<Pg>>(row: &mut T) -> Result<Self, Box<StdError + Send + Sync>> {
+            HstoreNullable::from_sql(row.take())
+        }
+    }
+
+    impl ToSql<Hstore, Pg> for HstoreNullable {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            let mut buf: Vec<u8> = Vec::new();
+            codec::encode_nullable(self, &mut buf)?;
+
+            out.write_all(&buf)?;
+            #[cfg(feature = "zeroize")]
+            ::zeroize::Zeroize::zeroize(&mut buf);
+            Ok(IsNull::No)
+        }
+    }
+
+    impl ToSql<Nullable<Hstore>, Pg> for HstoreNullable {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(self, out)
+        }
+    }
+}
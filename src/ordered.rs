@@ -0,0 +1,250 @@
+//! Hstores with a deterministic entry order.
+//!
+//! [`Hstore`](../struct.Hstore.html) is backed by a `HashMap`, so iterating over one, or
+//! serializing it, visits the entries in a different order each run. Two other types can be used
+//! wherever an `Hstore` can, reading and binding columns of SQL type `Hstore` and with all the
+//! [operators](../dsl/index.html), without converting at each boundary:
+//!
+//! * [`BTreeHstore`](struct.BTreeHstore.html), backed by a `BTreeMap`, keeps its entries sorted
+//!   by key.
+//! * [`IndexHstore`](struct.IndexHstore.html), backed by an `IndexMap`, keeps them in the order
+//!   they were inserted. Loaded from the database, that is the order Postgres keeps them in, by
+//!   key length and then by key. It needs the `indexmap` feature.
+//!
+//! ```rust
+//! use diesel_pg_hstore::ordered::BTreeHstore;
+//!
+//! let mut store = BTreeHstore::new();
+//! store.insert("size".into(), "10".into());
+//! store.insert("color".into(), "red".into());
+//!
+//! let keys: Vec<&str> = store.keys().map(|key| key.as_str()).collect();
+//! assert_eq!(keys, vec!["color", "size"]);
+//! ```
+
+use std::collections::BTreeMap;
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+
+use super::Hstore;
+use super::codec::DecodeMap;
+
+/// The types and impls shared by the ordered hstores, which differ only in their map
+macro_rules! ordered_hstore {
+    ($(#[$attr:meta])* $name:ident($map:ident) in $impls:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        pub struct $name($map<String, String>);
+
+        impl $name {
+            /// An empty hstore
+            pub fn new() -> $name {
+                $name($map::new())
+            }
+
+            /// The backing map
+            pub fn into_inner(self) -> $map<String, String> {
+                self.0
+            }
+        }
+
+        impl ::std::ops::Deref for $name {
+            type Target = $map<String, String>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl ::std::ops::DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut $map<String, String> {
+                &mut self.0
+            }
+        }
+
+        impl From<$map<String, String>> for $name {
+            fn from(map: $map<String, String>) -> $name {
+                $name(map)
+            }
+        }
+
+        impl From<Hstore> for $name {
+            fn from(hstore: Hstore) -> $name {
+                hstore.into_iter().collect()
+            }
+        }
+
+        impl From<$name> for Hstore {
+            fn from(store: $name) -> Hstore {
+                store.into_iter().collect()
+            }
+        }
+
+        impl ::std::iter::FromIterator<(String, String)> for $name {
+            fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> $name {
+                $name($map::from_iter(iter))
+            }
+        }
+
+        impl Extend<(String, String)> for $name {
+            fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+                self.0.extend(iter)
+            }
+        }
+
+        impl IntoIterator for $name {
+            type Item = (String, String);
+            type IntoIter = <$map<String, String> as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.into_iter()
+            }
+        }
+
+        impl<'a> IntoIterator for &'a $name {
+            type Item = (&'a String, &'a String);
+            type IntoIter = <&'a $map<String, String> as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.iter()
+            }
+        }
+
+        mod $impls {
+            use std::error::Error as StdError;
+            use std::io::Write;
+            use diesel::types::impls::option::UnexpectedNullError;
+            use diesel::Queryable;
+            use diesel::expression::AsExpression;
+            use diesel::expression::bound::Bound;
+            use diesel::pg::Pg;
+            use diesel::row::Row;
+            use diesel::types::*;
+
+            use super::$name;
+            use super::super::Hstore;
+            use super::super::codec;
+
+            impl Queryable<Hstore, Pg> for $name {
+                type Row = Self;
+
+                fn build(row: Self::Row) -> Self {
+                    row
+                }
+            }
+
+            impl AsExpression<Hstore> for $name {
+                type Expression = Bound<Hstore, $name>;
+
+                fn as_expression(self) -> Self::Expression {
+                    Bound::new(self)
+                }
+            }
+
+            impl<'a> AsExpression<Hstore> for &'a $name {
+                type Expression = Bound<Hstore, &'a $name>;
+
+                fn as_expression(self) -> Self::Expression {
+                    Bound::new(self)
+                }
+            }
+
+            impl AsExpression<Nullable<Hstore>> for $name {
+                type Expression = Bound<Nullable<Hstore>, $name>;
+
+                fn as_expression(self) -> Self::Expression {
+                    Bound::new(self)
+                }
+            }
+
+            impl<'a> AsExpression<Nullable<Hstore>> for &'a $name {
+                type Expression = Bound<Nullable<Hstore>, &'a $name>;
+
+                fn as_expression(self) -> Self::Expression {
+                    Bound::new(self)
+                }
+            }
+
+            impl FromSql<Hstore, Pg> for $name {
+                fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+                    match bytes {
+                        Some(bytes) => codec::decode_map(bytes).map($name),
+                        None => Err(Box::new(UnexpectedNullError {
+                            msg: "Unexpected null for non-null column".to_string(),
+                        })),
+                    }
+                }
+            }
+
+            impl FromSqlRow<Hstore, Pg> for $name {
+                fn build_from_row<T: Row<Pg>>(row: &mut T) -> Result<Self, Box<StdError + Send + Sync>> {
+                    $name::from_sql(row.take())
+                }
+            }
+
+            impl ToSql<Hstore, Pg> for $name {
+                fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+                    where W: Write
+                {
+                    let mut buf: Vec<u8> = Vec::new();
+                    codec::encode_map(self.iter(), &mut buf)?;
+
+                    out.write_all(&buf)?;
+                    Ok(IsNull::No)
+                }
+            }
+
+            impl ToSql<Nullable<Hstore>, Pg> for $name {
+                fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+                    where W: Write
+                {
+                    ToSql::<Hstore, Pg>::to_sql(self, out)
+                }
+            }
+        }
+    };
+}
+
+ordered_hstore! {
+    /// An Hstore keeping its entries sorted by key.
+    BTreeHstore(BTreeMap) in btree_impls
+}
+
+#[cfg(feature = "indexmap")]
+ordered_hstore! {
+    /// An Hstore keeping its entries in insertion order.
+    ///
+    /// Converted from an [`Hstore`](../struct.Hstore.html), the entries are in the `HashMap`'s
+    /// order.
+    IndexHstore(IndexMap) in index_impls
+}
+
+impl DecodeMap for BTreeMap<String, String> {
+    /// A `BTreeMap` allocates as it grows
+    fn with_capacity(_: usize) -> Self {
+        BTreeMap::new()
+    }
+
+    fn insert_entry(&mut self, key: String, value: String) {
+        self.insert(key, value);
+    }
+
+    fn entry_count(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl DecodeMap for IndexMap<String, String> {
+    fn with_capacity(capacity: usize) -> Self {
+        IndexMap::with_capacity(capacity)
+    }
+
+    fn insert_entry(&mut self, key: String, value: String) {
+        self.insert(key, value);
+    }
+
+    fn entry_count(&self) -> usize {
+        self.len()
+    }
+}
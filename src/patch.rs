@@ -0,0 +1,266 @@
+//! Describing changes to an hstore.
+//!
+//! An [`HstorePatch`](struct.HstorePatch.html) records keys to set and keys to delete. It can be
+//! applied to an Hstore in memory, or rendered as an SQL expression so the database applies it
+//! to each row without the hstores being loaded:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::patch::HstorePatch;
+//!
+//! let patch = HstorePatch::new()
+//!     .set("currency", "USD")
+//!     .delete("legacy_price");
+//!
+//! let mut store = Hstore::new();
+//! store.insert("legacy_price".into(), "10".into());
+//! patch.apply_to(&mut store);
+//! assert_eq!(store["currency"], "USD");
+//! assert!(!store.contains_key("legacy_price"));
+//! ```
+//!
+//! When a key is both set and deleted, whichever was asked for last wins.
+//!
+//! [`DiffingUpdate`](struct.DiffingUpdate.html) applies a patch, or any other new value, in the
+//! database and returns what actually changed in each row, as a patch from the row's previous
+//! hstore to its new one.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::types::BigInt;
+
+use super::Hstore;
+use super::quote::{quote_ident, quote_name, text_array};
+
+/// Keys to set and keys to delete.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HstorePatch {
+    set: BTreeMap<String, String>,
+    delete: BTreeSet<String>,
+}
+
+impl HstorePatch {
+    /// Create an empty patch
+    pub fn new() -> HstorePatch {
+        HstorePatch::default()
+    }
+
+    /// Set `key` to `value`.
+    pub fn set<K, V>(mut self, key: K, value: V) -> HstorePatch
+        where K: Into<String>,
+              V: Into<String>
+    {
+        let key = key.into();
+        self.delete.remove(&key);
+        self.set.insert(key, value.into());
+        self
+    }
+
+    /// Delete `key`.
+    pub fn delete<K: Into<String>>(mut self, key: K) -> HstorePatch {
+        let key = key.into();
+        self.set.remove(&key);
+        self.delete.insert(key);
+        self
+    }
+
+    /// The patch turning `old` into `new`, setting the keys added or changed and deleting those
+    /// removed.
+    ///
+    /// ```rust
+    /// # use diesel_pg_hstore::Hstore;
+    /// # use diesel_pg_hstore::patch::HstorePatch;
+    /// let mut old = Hstore::new();
+    /// old.insert("a".into(), "1".into());
+    /// old.insert("b".into(), "2".into());
+    /// let mut new = old.clone();
+    /// new.insert("a".into(), "10".into());
+    /// new.remove("b");
+    ///
+    /// assert_eq!(HstorePatch::diff(&old, &new), HstorePatch::new().set("a", "10").delete("b"));
+    /// ```
+    pub fn diff(old: &Hstore, new: &Hstore) -> HstorePatch {
+        let mut patch = HstorePatch::new();
+        for (key, value) in new.iter() {
+            if old.get(key) != Some(value) {
+                patch.set.insert(key.clone(), value.clone());
+            }
+        }
+        for key in old.keys() {
+            if !new.contains_key(key) {
+                patch.delete.insert(key.clone());
+            }
+        }
+        patch
+    }
+
+    /// Whether the patch changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.delete.is_empty()
+    }
+
+    /// The keys set, and their values
+    pub fn sets(&self) -> &BTreeMap<String, String> {
+        &self.set
+    }
+
+    /// The keys deleted
+    pub fn deletes(&self) -> &BTreeSet<String> {
+        &self.delete
+    }
+
+    /// Apply the patch to an Hstore.
+    pub fn apply_to(&self, hstore: &mut Hstore) {
+        for key in &self.delete {
+            hstore.remove(key);
+        }
+        for (key, value) in &self.set {
+            hstore.insert(key.clone(), value.clone());
+        }
+    }
+
+    /// The patched hstore, as an SQL expression applying the patch to `store`.
+    pub fn to_sql(&self, store: &str) -> String {
+        let mut expr = store.to_string();
+        if !self.delete.is_empty() {
+            expr = format!("({} - {})", expr, text_array(&self.delete));
+        }
+        if !self.set.is_empty() {
+            expr = format!(
+                "({} || hstore({}, {}))",
+                expr,
+                text_array(self.set.keys()),
+                text_array(self.set.values()),
+            );
+        }
+        expr
+    }
+}
+
+/// The new value of a [`DiffingUpdate`](struct.DiffingUpdate.html)
+#[derive(Debug, Clone)]
+enum NewValue {
+    Patch(HstorePatch),
+    Sql(String),
+}
+
+/// An `UPDATE` returning what it changed in each row.
+///
+/// The previous hstores are read, and the rows locked, in the same statement as the update, so
+/// the patches returned are exactly what the database changed even when other transactions are
+/// writing to the same rows.
+///
+/// ```rust,no_run
+/// # extern crate diesel;
+/// # extern crate diesel_pg_hstore;
+/// # use diesel::prelude::*;
+/// use diesel_pg_hstore::patch::{DiffingUpdate, HstorePatch};
+///
+/// # fn main() {
+/// # let db = PgConnection::establish("").unwrap();
+/// let patch = HstorePatch::new().set("status", "archived").delete("draft");
+/// let changed = DiffingUpdate::new("posts", patch)
+///     .filter_sql("author_id = 42")
+///     .run(&db)
+///     .unwrap();
+/// for (id, diff) in changed {
+///     println!("post {}: set {:?}, deleted {:?}", id, diff.sets(), diff.deletes());
+/// }
+/// # }
+/// ```
+///
+/// Tables must have an integer `id` column.
+#[derive(Debug, Clone)]
+pub struct DiffingUpdate {
+    table: String,
+    store: String,
+    value: NewValue,
+    filter: Option<String>,
+}
+
+impl DiffingUpdate {
+    /// Apply `patch` to the `store` column of every row of `table`.
+    pub fn new<T: Into<String>>(table: T, patch: HstorePatch) -> DiffingUpdate {
+        DiffingUpdate {
+            table: table.into(),
+            store: "store".into(),
+            value: NewValue::Patch(patch),
+            filter: None,
+        }
+    }
+
+    /// Set the hstore to an SQL expression instead, such as `hstore(other_column)`. The
+    /// expression may refer to the row's columns.
+    pub fn new_sql<T, E>(table: T, value: E) -> DiffingUpdate
+        where T: Into<String>,
+              E: Into<String>
+    {
+        DiffingUpdate {
+            table: table.into(),
+            store: "store".into(),
+            value: NewValue::Sql(value.into()),
+            filter: None,
+        }
+    }
+
+    /// Update the hstore column `store` instead.
+    pub fn store<S: Into<String>>(mut self, store: S) -> DiffingUpdate {
+        self.store = store.into();
+        self
+    }
+
+    /// Only update rows matching an SQL condition.
+    pub fn filter_sql<F: Into<String>>(mut self, filter: F) -> DiffingUpdate {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// The statement, returning the id, previous hstore and new hstore of each row updated
+    pub fn sql(&self) -> String {
+        let store = quote_ident(&self.store);
+        let table = quote_name(&self.table);
+        let value = match self.value {
+            NewValue::Patch(ref patch) => patch.to_sql(&store),
+            NewValue::Sql(ref value) => value.clone(),
+        };
+        let filter = match self.filter {
+            Some(ref filter) => format!(" WHERE {}", filter),
+            None => String::new(),
+        };
+
+        format!(
+            "UPDATE {table} SET {store} = {value} FROM (\
+             SELECT id AS previous_id, {store} AS previous_store FROM {table}{filter} FOR UPDATE\
+             ) AS previous WHERE {table}.id = previous.previous_id \
+             RETURNING {table}.id::bigint, previous.previous_store, {table}.{store}",
+            table = table,
+            store = store,
+            value = value,
+            filter = filter,
+        )
+    }
+
+    /// Run the update, returning the id of each row updated and the patch from its previous
+    /// hstore to its new one, ordered by id. Rows whose hstore didn't change have empty patches.
+    pub fn run(&self, conn: &PgConnection) -> QueryResult<Vec<(i64, HstorePatch)>> {
+        let mut rows = sql::<(BigInt, Hstore, Hstore)>(&self.sql())
+            .load::<(i64, Hstore, Hstore)>(conn)?;
+        rows.sort_by_key(|&(id, _, _)| id);
+        Ok(rows.into_iter()
+            .map(|(id, previous, new)| (id, HstorePatch::diff(&previous, &new)))
+            .collect())
+    }
+}
+
+/// Apply `patch` to the `store` column of the row of `table` with the id `id`, returning what
+/// changed, or `None` if there is no such row. See [`DiffingUpdate`](struct.DiffingUpdate.html).
+pub fn update_returning_diff(conn: &PgConnection, table: &str, store: &str, id: i64, patch: &HstorePatch) -> QueryResult<Option<HstorePatch>> {
+    let rows = DiffingUpdate::new(table, patch.clone())
+        .store(store)
+        .filter_sql(format!("id = {}", id))
+        .run(conn)?;
+    Ok(rows.into_iter().next().map(|(_, diff)| diff))
+}
@@ -0,0 +1,126 @@
+//! Pivoting hstore keys into columns.
+//!
+//! A [`Pivot`](struct.Pivot.html) builds a single query which expands every row's hstore with
+//! `each`, then uses conditional aggregation to turn the requested keys into columns, one row
+//! per group. The query is loaded like any other, into tuples or `Queryable` structs, given the
+//! SQL types of its columns:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel::types::{Integer, Nullable, Text};
+//! use diesel_pg_hstore::pivot::Pivot;
+//!
+//! #[derive(Queryable)]
+//! struct ProductColors {
+//!     id: i32,
+//!     color: Option<String>,
+//!     trim: Option<String>,
+//! }
+//!
+//! # fn main() {
+//! let pivot = Pivot::new("products", "attributes")
+//!     .group_by("id")
+//!     .key("color")
+//!     .key_as("trim_color", "trim");
+//!
+//! let query = pivot.query::<(Integer, Nullable<Text>, Nullable<Text>)>();
+//! // let colors: Vec<ProductColors> = query.load(&db)?;
+//! # }
+//! ```
+//!
+//! Each key column is text, or `NULL` when no row in the group has the key. When several rows
+//! in a group have the key, the greatest value is used. Names may be schema qualified, as in
+//! `reporting.products`.
+
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+
+use super::quote::{quote_ident, quote_literal, quote_name};
+
+/// A query turning hstore keys into columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pivot {
+    table: String,
+    store: String,
+    group_by: Vec<String>,
+    keys: Vec<(String, String)>,
+}
+
+impl Pivot {
+    /// Pivot the hstore column `store` of `table`.
+    pub fn new<T, S>(table: T, store: S) -> Pivot
+        where T: Into<String>,
+              S: Into<String>
+    {
+        Pivot {
+            table: table.into(),
+            store: store.into(),
+            group_by: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Group rows by a column of the table, which comes before the key columns.
+    ///
+    /// Groups are returned in the order of these columns. Without any, the whole table is a
+    /// single group.
+    pub fn group_by<C: Into<String>>(mut self, column: C) -> Pivot {
+        self.group_by.push(column.into());
+        self
+    }
+
+    /// Add a column of the same name for `key`.
+    pub fn key<K: Into<String>>(self, key: K) -> Pivot {
+        let key = key.into();
+        let column = key.clone();
+        self.key_as(key, column)
+    }
+
+    /// Add the column `column` for `key`.
+    pub fn key_as<K, C>(mut self, key: K, column: C) -> Pivot
+        where K: Into<String>,
+              C: Into<String>
+    {
+        self.keys.push((key.into(), column.into()));
+        self
+    }
+
+    /// The query as SQL
+    pub fn to_sql(&self) -> String {
+        let mut select = Vec::with_capacity(self.group_by.len() + self.keys.len());
+        for column in &self.group_by {
+            select.push(format!("t.{}", quote_ident(column)));
+        }
+        for &(ref key, ref column) in &self.keys {
+            select.push(format!(
+                "max(e.value) FILTER (WHERE e.key = {}) AS {}",
+                quote_literal(key),
+                quote_ident(column),
+            ));
+        }
+
+        let mut query = format!(
+            "SELECT {} FROM {} AS t LEFT JOIN LATERAL each(t.{}) AS e ON true",
+            select.join(", "),
+            quote_name(&self.table),
+            quote_ident(&self.store),
+        );
+        if !self.group_by.is_empty() {
+            let columns: Vec<String> = self.group_by.iter()
+                .map(|column| format!("t.{}", quote_ident(column)))
+                .collect();
+            query.push_str(&format!(" GROUP BY {0} ORDER BY {0}", columns.join(", ")));
+        }
+        query
+    }
+
+    /// The query, ready to be loaded as rows of the SQL type `ST`.
+    ///
+    /// `ST` must have one type for each group by column, followed by `Nullable<Text>` for each
+    /// key.
+    pub fn query<ST>(&self) -> SqlLiteral<ST> {
+        sql(&self.to_sql())
+    }
+}
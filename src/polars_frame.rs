@@ -0,0 +1,71 @@
+//! Converting hstores to Polars data frames, for analytics in process.
+//!
+//! With the `polars` feature, `(id, Hstore)` rows, as read by a [`Scan`](../scan/struct.Scan.html)
+//! or loaded with a query, convert to a `DataFrame` in one of two shapes.
+//!
+//! [`exploded`](fn.exploded.html) makes a row for every key, with the columns `id`, `key` and
+//! `value`, ordered by row and then key:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::polars_frame;
+//!
+//! let rows: Vec<(i64, Hstore)> = products.select((id, attributes)).load(&db)?;
+//! let frame = polars_frame::exploded(&rows)?;
+//! let colors = frame.lazy().filter(col("key").eq(lit("color"))).collect()?;
+//! ```
+//!
+//! [`struct_column`](fn.struct_column.html) keeps a row per hstore, with the columns `id` and a
+//! struct column with a field for every key found in any row. A field is null in rows without
+//! that key. Other columns of a query result can be added to either frame with
+//! `DataFrame::with_column`.
+
+use std::collections::BTreeSet;
+
+use polars_core::prelude::{Column, DataFrame, IntoColumn, IntoSeries, NamedFrom, PolarsResult, Series, StructChunked};
+
+use super::Hstore;
+
+/// Convert rows to a frame with a row for every key, with the columns `id`, `key` and `value`.
+pub fn exploded(rows: &[(i64, Hstore)]) -> PolarsResult<DataFrame> {
+    let size = rows.iter().map(|row| row.1.len()).sum();
+    let mut ids = Vec::with_capacity(size);
+    let mut keys = Vec::with_capacity(size);
+    let mut values = Vec::with_capacity(size);
+    for &(id, ref store) in rows {
+        let mut entries: Vec<_> = store.iter().collect();
+        entries.sort();
+        for (key, value) in entries {
+            ids.push(id);
+            keys.push(key.as_str());
+            values.push(value.as_str());
+        }
+    }
+
+    DataFrame::new(vec![
+        Column::new("id".into(), ids),
+        Column::new("key".into(), keys),
+        Column::new("value".into(), values),
+    ])
+}
+
+/// Convert rows to a frame with a row for every hstore, with the columns `id` and a struct
+/// column named `store`.
+///
+/// The struct has a field for every key, in sorted order, which is null where a row doesn't
+/// have the key.
+pub fn struct_column(rows: &[(i64, Hstore)], store: &str) -> PolarsResult<DataFrame> {
+    let ids: Vec<i64> = rows.iter().map(|row| row.0).collect();
+    let keys: BTreeSet<&str> = rows.iter().flat_map(|row| row.1.keys()).map(String::as_str).collect();
+    let fields: Vec<Series> = keys.into_iter()
+        .map(|key| {
+            let values: Vec<Option<&str>> = rows.iter().map(|row| row.1.get(key).map(String::as_str)).collect();
+            Series::new(key.into(), values)
+        })
+        .collect();
+    let stores = StructChunked::from_series(store.into(), rows.len(), fields.iter())?;
+
+    DataFrame::new(vec![
+        Column::new("id".into(), ids),
+        stores.into_series().into_column(),
+    ])
+}
@@ -0,0 +1,218 @@
+//! Named hstore filters, defined once and reused.
+//!
+//! Hot request paths often filter on the same few conditions, such as "production rows which
+//! are active". A [`FilterRegistry`](struct.FilterRegistry.html) holds
+//! [`FilterTemplate`](struct.FilterTemplate.html)s by name, built once at startup, and applies
+//! them to any hstore column as a boxed predicate, without rebuilding the conditions:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::predicates::{FilterRegistry, FilterTemplate};
+//!
+//! lazy_static! {
+//!     static ref FILTERS: FilterRegistry = FilterRegistry::new()
+//!         .define("active_prod", FilterTemplate::new().contains("env", "prod").contains("active", "t"))
+//!         .define("tagged", FilterTemplate::new().has_any_keys(vec!["tag", "label"]));
+//! }
+//!
+//! let active = FILTERS.boxed::<posts::table, _>("active_prod", posts::meta).unwrap();
+//! let rows = posts::table.filter(active).load::<Post>(&db)?;
+//! ```
+//!
+//! A template's conditions are all required, and render as `store @> $1 AND store ? $2 ...`
+//! with the template's hstore and keys bound as parameters. Templates are shared behind an
+//! `Arc`, so applying one only allocates the box.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use diesel::expression::{AppearsOnTable, BoxableExpression, Expression, NonAggregate, SelectableExpression};
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::types::{Array, Bool, Text};
+
+use super::Hstore;
+
+/// A condition of a [`FilterTemplate`](struct.FilterTemplate.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    /// `store ? key`
+    HasKey(String),
+    /// `store ?& keys`
+    HasAllKeys(Vec<String>),
+    /// `store ?| keys`
+    HasAnyKeys(Vec<String>),
+}
+
+/// A set of conditions on an hstore, every one of which must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterTemplate {
+    contains: Hstore,
+    conditions: Vec<Condition>,
+}
+
+impl FilterTemplate {
+    /// A template matching every hstore
+    pub fn new() -> FilterTemplate {
+        FilterTemplate::default()
+    }
+
+    /// Require `key` to have `value`. Every pair required is checked at once, with `@>`.
+    pub fn contains<K, V>(mut self, key: K, value: V) -> FilterTemplate
+        where K: Into<String>,
+              V: Into<String>
+    {
+        self.contains.insert(key.into(), value.into());
+        self
+    }
+
+    /// Require every entry of `entries`, with `@>`.
+    pub fn contains_all(mut self, entries: Hstore) -> FilterTemplate {
+        self.contains.extend(entries);
+        self
+    }
+
+    /// Require `key`, with `?`.
+    pub fn has_key<K: Into<String>>(mut self, key: K) -> FilterTemplate {
+        self.conditions.push(Condition::HasKey(key.into()));
+        self
+    }
+
+    /// Require every one of `keys`, with `?&`.
+    pub fn has_all_keys<I>(mut self, keys: I) -> FilterTemplate
+        where I: IntoIterator,
+              I::Item: Into<String>
+    {
+        self.conditions.push(Condition::HasAllKeys(keys.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Require at least one of `keys`, with `?|`.
+    pub fn has_any_keys<I>(mut self, keys: I) -> FilterTemplate
+        where I: IntoIterator,
+              I::Item: Into<String>
+    {
+        self.conditions.push(Condition::HasAnyKeys(keys.into_iter().map(Into::into).collect()));
+        self
+    }
+}
+
+/// Filter templates by name.
+#[derive(Debug, Clone, Default)]
+pub struct FilterRegistry {
+    templates: HashMap<String, Arc<FilterTemplate>>,
+}
+
+impl FilterRegistry {
+    /// A registry without any templates
+    pub fn new() -> FilterRegistry {
+        FilterRegistry::default()
+    }
+
+    /// Define `name` as `template`, replacing any template already defined with that name.
+    pub fn define<N: Into<String>>(mut self, name: N, template: FilterTemplate) -> FilterRegistry {
+        self.templates.insert(name.into(), Arc::new(template));
+        self
+    }
+
+    /// The template named `name`
+    pub fn get(&self, name: &str) -> Option<&Arc<FilterTemplate>> {
+        self.templates.get(name)
+    }
+
+    /// Apply the template named `name` to the hstore expression `store`.
+    pub fn filter<C>(&self, name: &str, store: C) -> Option<TemplateFilter<C>>
+        where C: Expression<SqlType = Hstore>
+    {
+        self.get(name).map(|template| TemplateFilter {
+            store: store,
+            template: template.clone(),
+        })
+    }
+
+    /// Apply the template named `name` to the hstore expression `store`, boxed so that filters
+    /// on different columns have the same type.
+    pub fn boxed<QS, C>(&self, name: &str, store: C) -> Option<Box<BoxableExpression<QS, Pg, SqlType = Bool>>>
+        where C: Expression<SqlType = Hstore> + SelectableExpression<QS> + NonAggregate + QueryFragment<Pg> + 'static
+    {
+        self.filter(name, store).map(|filter| Box::new(filter) as Box<BoxableExpression<QS, Pg, SqlType = Bool>>)
+    }
+}
+
+/// A [`FilterTemplate`](struct.FilterTemplate.html) applied to an hstore expression
+#[derive(Debug, Clone)]
+pub struct TemplateFilter<C> {
+    store: C,
+    template: Arc<FilterTemplate>,
+}
+
+impl<C> Expression for TemplateFilter<C>
+    where C: Expression
+{
+    type SqlType = Bool;
+}
+
+impl<C> QueryId for TemplateFilter<C> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<C, QS> AppearsOnTable<QS> for TemplateFilter<C>
+    where C: AppearsOnTable<QS>
+{
+}
+
+impl<C, QS> SelectableExpression<QS> for TemplateFilter<C>
+    where C: SelectableExpression<QS>
+{
+}
+
+impl<C> NonAggregate for TemplateFilter<C>
+    where C: NonAggregate
+{
+}
+
+impl<C> QueryFragment<Pg> for TemplateFilter<C>
+    where C: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        let template = &*self.template;
+        if template.contains.is_empty() && template.conditions.is_empty() {
+            out.push_sql("TRUE");
+            return Ok(());
+        }
+
+        out.push_sql("(");
+        let mut first = true;
+        if !template.contains.is_empty() {
+            self.store.walk_ast(out.reborrow())?;
+            out.push_sql(" @> ");
+            out.push_bind_param::<Hstore, _>(&template.contains)?;
+            first = false;
+        }
+        for condition in &template.conditions {
+            if !first {
+                out.push_sql(" AND ");
+            }
+            first = false;
+            self.store.walk_ast(out.reborrow())?;
+            match *condition {
+                Condition::HasKey(ref key) => {
+                    out.push_sql(" ? ");
+                    out.push_bind_param::<Text, _>(key)?;
+                }
+                Condition::HasAllKeys(ref keys) => {
+                    out.push_sql(" ?& ");
+                    out.push_bind_param::<Array<Text>, _>(keys)?;
+                }
+                Condition::HasAnyKeys(ref keys) => {
+                    out.push_sql(" ?| ");
+                    out.push_bind_param::<Array<Text>, _>(keys)?;
+                }
+            }
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
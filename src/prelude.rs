@@ -0,0 +1,38 @@
+//! Everything needed to use hstores with Diesel, in one import.
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::prelude::*;
+//!
+//! table! {
+//!     use diesel_pg_hstore::table_import::*;
+//!
+//!     posts {
+//!         id -> Integer,
+//!         meta -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut meta = Hstore::new();
+//! meta.insert("draft".into(), "true".into());
+//!
+//! let query = diesel::update(posts::table.find(1))
+//!     .set(posts::meta.eq(posts::meta.incr("edits", 1)));
+//! let keys = posts::table.select(skeys(posts::meta)).distinct();
+//! # }
+//! ```
+//!
+//! The extension traits and [`window`](../window/index.html) are included with the `dsl`
+//! feature, and the [SQL functions](../functions/index.html) with the `functions` feature, both
+//! of which are enabled by default.
+
+pub use super::{CowHstore, FrozenHstore, Hstore};
+#[cfg(feature = "dsl")]
+pub use super::dsl::{HstoreOpExtensions, HstoreTextExtensions};
+#[cfg(feature = "dsl")]
+pub use super::window;
+#[cfg(feature = "functions")]
+pub use super::functions::*;
@@ -0,0 +1,172 @@
+//! Formatting hstores for people to read.
+//!
+//! [`Hstore::pretty`](../struct.Hstore.html#method.pretty) writes the entries sorted by key, on
+//! one line or one per line, for logs and command line tools. Unlike the
+//! [text format](../text/index.html) the result isn't meant to be parsed back: keys and values
+//! are only quoted when they would otherwise be ambiguous, and long output can be shortened.
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::pretty::PrettyOptions;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("region".into(), "eu-west-1".into());
+//! store.insert("env".into(), "prod".into());
+//! store.insert("note".into(), "migrated from the old cluster".into());
+//!
+//! assert_eq!(store.pretty(&PrettyOptions::new()),
+//!     r#"env => prod, note => "migrated from the old cluster", region => eu-west-1"#);
+//!
+//! let options = PrettyOptions::new()
+//!     .multi_line(true)
+//!     .align(true)
+//!     .max_value_len(10);
+//! assert_eq!(store.pretty(&options), "\
+//! env    => prod
+//! note   => \"migrated …\"
+//! region => eu-west-1");
+//! ```
+
+use super::Hstore;
+use super::text;
+
+const ELLIPSIS: char = '…';
+
+/// How [`Hstore::pretty`](../struct.Hstore.html#method.pretty) lays out an hstore.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrettyOptions {
+    multi_line: bool,
+    align: bool,
+    max_width: Option<usize>,
+    max_value_len: Option<usize>,
+}
+
+impl PrettyOptions {
+    /// Everything on one line, unaligned and untruncated
+    pub fn new() -> PrettyOptions {
+        PrettyOptions::default()
+    }
+
+    /// Write each entry on its own line.
+    pub fn multi_line(mut self, multi_line: bool) -> PrettyOptions {
+        self.multi_line = multi_line;
+        self
+    }
+
+    /// Pad keys so the values of a multi-line layout line up.
+    pub fn align(mut self, align: bool) -> PrettyOptions {
+        self.align = align;
+        self
+    }
+
+    /// Limit each line to `width` characters.
+    ///
+    /// On a single line, entries which don't fit are left out and counted, as in
+    /// `a => 1, … (3 more)`. Over multiple lines, each line which is too long is cut short.
+    pub fn max_width(mut self, width: usize) -> PrettyOptions {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Cut values longer than `len` characters short.
+    pub fn max_value_len(mut self, len: usize) -> PrettyOptions {
+        self.max_value_len = Some(len);
+        self
+    }
+}
+
+impl Hstore {
+    /// Format for people to read. See the [`pretty`](pretty/index.html) module.
+    pub fn pretty(&self, options: &PrettyOptions) -> String {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort();
+        let entries: Vec<(String, String)> = entries.into_iter()
+            .map(|(key, value)| {
+                let value = match options.max_value_len {
+                    Some(len) => truncate(value, len),
+                    None => value.clone(),
+                };
+                (display(key), display(&value))
+            })
+            .collect();
+
+        if options.multi_line {
+            multi_line(&entries, options)
+        }
+        else {
+            single_line(&entries, options)
+        }
+    }
+}
+
+fn multi_line(entries: &[(String, String)], options: &PrettyOptions) -> String {
+    let key_width = if options.align {
+        entries.iter().map(|&(ref key, _)| key.chars().count()).max().unwrap_or(0)
+    }
+    else {
+        0
+    };
+
+    let lines: Vec<String> = entries.iter()
+        .map(|&(ref key, ref value)| {
+            let padding = key_width.saturating_sub(key.chars().count());
+            let line = format!("{}{} => {}", key, " ".repeat(padding), value);
+            match options.max_width {
+                Some(width) => truncate(&line, width),
+                None => line,
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn single_line(entries: &[(String, String)], options: &PrettyOptions) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for (i, &(ref key, ref value)) in entries.iter().enumerate() {
+        let separator = if i > 0 { ", " } else { "" };
+        let entry = format!("{}{} => {}", separator, key, value);
+        let entry_width = entry.chars().count();
+
+        if let Some(max_width) = options.max_width {
+            let after = entries.len() - i - 1;
+            let reserve = if after > 0 { more(", ", after).chars().count() } else { 0 };
+            if width + entry_width + reserve > max_width {
+                out.push_str(&more(separator, after + 1));
+                break;
+            }
+        }
+
+        out.push_str(&entry);
+        width += entry_width;
+    }
+    out
+}
+
+/// The placeholder for `count` entries left out
+fn more(separator: &str, count: usize) -> String {
+    format!("{}{} ({} more)", separator, ELLIPSIS, count)
+}
+
+/// `s` unquoted if that can't be confused with the surrounding layout
+pub(crate) fn display(s: &str) -> String {
+    let ambiguous = s.is_empty() ||
+        s.contains("=>") ||
+        s.chars().any(|c| c.is_whitespace() || c == ',' || c == '"' || c == '\\');
+    if ambiguous {
+        text::quote(s)
+    }
+    else {
+        s.to_string()
+    }
+}
+
+/// `s` cut to at most `len` characters, ending in an ellipsis if it was cut
+fn truncate(s: &str, len: usize) -> String {
+    if s.chars().count() <= len {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(len.saturating_sub(1)).collect();
+    out.push(ELLIPSIS);
+    out
+}
@@ -0,0 +1,35 @@
+//! Quoting names and values for SQL generated as text.
+
+/// Quote a possibly schema qualified name, such as `reporting.products`.
+pub(crate) fn quote_name(name: &str) -> String {
+    name.split('.').map(quote_ident).collect::<Vec<_>>().join(".")
+}
+
+/// Quote a single identifier.
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Quote a string literal.
+///
+/// As Postgres' own `quote_literal` does, a literal containing a backslash is written as an
+/// escape string, `E'...'`, with the backslashes doubled. It then means the same whether or not
+/// `standard_conforming_strings` is on.
+pub(crate) fn quote_literal(literal: &str) -> String {
+    let quoted = literal.replace('\'', "''");
+    if quoted.contains('\\') {
+        format!("E'{}'", quoted.replace('\\', "\\\\"))
+    }
+    else {
+        format!("'{}'", quoted)
+    }
+}
+
+/// Quote a list of strings as a `text[]` array.
+pub(crate) fn text_array<I, S>(items: I) -> String
+    where I: IntoIterator<Item = S>,
+          S: AsRef<str>
+{
+    let items: Vec<String> = items.into_iter().map(|item| quote_literal(item.as_ref())).collect();
+    format!("ARRAY[{}]::text[]", items.join(", "))
+}
@@ -0,0 +1,162 @@
+//! Reading the hstores of large tables a page at a time.
+//!
+//! Loading a whole table holds every row in memory at once. A [`Scan`](struct.Scan.html) walks
+//! a table in id order instead, fetching `fetch_size` rows per query and yielding them one at
+//! a time:
+//!
+//! ```rust,no_run
+//! # extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! # use diesel::prelude::*;
+//! use diesel_pg_hstore::scan::Scan;
+//!
+//! # fn main() {
+//! # let db = PgConnection::establish("").unwrap();
+//! for row in Scan::new("products").store("attributes").fetch_size(500).rows(&db) {
+//!     let (id, attributes) = row.unwrap();
+//!     println!("{}: {} keys", id, attributes.len());
+//! }
+//! # }
+//! ```
+//!
+//! Each page is found by id, after the last id of the page before, so no transaction is held
+//! open between pages. Rows inserted or changed during a scan are seen if a later page reaches
+//! them. Tables must have an integer `id` column.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::types::BigInt;
+
+use super::Hstore;
+use super::quote::{quote_ident, quote_name};
+
+/// A lazy read of every row of a table, as `(id, Hstore)` pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scan {
+    table: String,
+    store: String,
+    filter: Option<String>,
+    fetch_size: i64,
+}
+
+impl Scan {
+    /// Read the `store` column of `table`, 1000 rows per query.
+    pub fn new<T: Into<String>>(table: T) -> Scan {
+        Scan {
+            table: table.into(),
+            store: "store".into(),
+            filter: None,
+            fetch_size: 1000,
+        }
+    }
+
+    /// Read the hstore column `store` instead.
+    pub fn store<S: Into<String>>(mut self, store: S) -> Scan {
+        self.store = store.into();
+        self
+    }
+
+    /// Only read rows matching an SQL condition.
+    pub fn filter_sql<F: Into<String>>(mut self, filter: F) -> Scan {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Set the number of rows fetched by each query.
+    ///
+    /// Panics if `fetch_size` isn't positive.
+    pub fn fetch_size(mut self, fetch_size: i64) -> Scan {
+        assert!(fetch_size > 0, "fetch size must be positive");
+        self.fetch_size = fetch_size;
+        self
+    }
+
+    /// The hstore column read
+    pub fn store_name(&self) -> &str {
+        &self.store
+    }
+
+    /// The query fetching the page of rows after `after_id`, or the first page
+    pub fn page_sql(&self, after_id: Option<i64>) -> String {
+        let mut conditions = Vec::new();
+        if let Some(after_id) = after_id {
+            conditions.push(format!("id > {}", after_id));
+        }
+        if let Some(ref filter) = self.filter {
+            conditions.push(format!("({})", filter));
+        }
+        let filter = if conditions.is_empty() {
+            String::new()
+        }
+        else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        format!(
+            "SELECT id::bigint, {} FROM {}{} ORDER BY id LIMIT {}",
+            quote_ident(&self.store),
+            quote_name(&self.table),
+            filter,
+            self.fetch_size,
+        )
+    }
+
+    /// Iterate over the rows.
+    pub fn rows<'a>(&'a self, conn: &'a PgConnection) -> Rows<'a> {
+        Rows {
+            scan: self,
+            conn: conn,
+            page: VecDeque::new(),
+            last_id: None,
+            done: false,
+        }
+    }
+}
+
+/// An iterator over the rows of a [`Scan`](struct.Scan.html).
+///
+/// A failed query is yielded as an error, after which the iterator ends.
+pub struct Rows<'a> {
+    scan: &'a Scan,
+    conn: &'a PgConnection,
+    page: VecDeque<(i64, Hstore)>,
+    last_id: Option<i64>,
+    done: bool,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = QueryResult<(i64, Hstore)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.is_empty() && !self.done {
+            let query = self.scan.page_sql(self.last_id);
+            match sql::<(BigInt, Hstore)>(&query).load::<(i64, Hstore)>(self.conn) {
+                Ok(page) => {
+                    self.done = (page.len() as i64) < self.scan.fetch_size;
+                    self.last_id = page.last().map(|&(id, _)| id).or(self.last_id);
+                    self.page = page.into();
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.page.pop_front().map(Ok)
+    }
+}
+
+impl<'a> fmt::Debug for Rows<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Rows")
+            .field("scan", &self.scan)
+            .field("buffered", &self.page.len())
+            .field("last_id", &self.last_id)
+            .field("done", &self.done)
+            .finish()
+    }
+}
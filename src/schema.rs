@@ -0,0 +1,532 @@
+//! Lightweight validation of the keys and values of an hstore.
+//!
+//! An [`HstoreSchema`](struct.HstoreSchema.html) lists the keys an hstore must have, the keys it
+//! may have, and checks for individual values. Validating reports every problem found, not just
+//! the first:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::schema::{HstoreSchema, Violation};
+//!
+//! let schema = HstoreSchema::new()
+//!     .required("name")
+//!     .parses::<u32>("age")
+//!     .deny_unknown_keys();
+//!
+//! let mut person = Hstore::new();
+//! person.insert("age".into(), "old".into());
+//! person.insert("shoe_size".into(), "9".into());
+//!
+//! let violations = schema.validate(&person).unwrap_err();
+//! assert_eq!(violations.len(), 3);
+//! assert_eq!(violations[0], Violation::Missing { key: "name".into() });
+//! ```
+//!
+//! To check a column every time it is written, use [`Validated`](struct.Validated.html).
+//!
+//! With the `regex` feature, values can also be checked against a regular expression with
+//! [`matches`](struct.HstoreSchema.html#method.matches).
+//!
+//! To find the rows of a table which already violate a schema, without loading them, use a
+//! [`ViolationFinder`](struct.ViolationFinder.html).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::types::{Nullable, Text};
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+use super::Hstore;
+use super::quote::{quote_ident, quote_literal, quote_name, text_array};
+
+type Check = Box<Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// The keys and values an hstore is expected to have.
+#[derive(Default)]
+pub struct HstoreSchema {
+    required: BTreeSet<String>,
+    allowed: BTreeSet<String>,
+    checks: BTreeMap<String, Vec<Check>>,
+    patterns: Vec<(String, String)>,
+    deny_unknown_keys: bool,
+}
+
+impl HstoreSchema {
+    /// Create a schema which accepts every hstore
+    pub fn new() -> HstoreSchema {
+        HstoreSchema::default()
+    }
+
+    /// Require `key` to be present.
+    pub fn required<K: Into<String>>(mut self, key: K) -> HstoreSchema {
+        self.required.insert(key.into());
+        self
+    }
+
+    /// Allow `key` to be present, when unknown keys are denied.
+    pub fn allowed<K: Into<String>>(mut self, key: K) -> HstoreSchema {
+        self.allowed.insert(key.into());
+        self
+    }
+
+    /// Check the value of `key` when it is present, with a function returning a description of
+    /// what is wrong with an invalid value.
+    pub fn value<K, F>(mut self, key: K, check: F) -> HstoreSchema
+        where K: Into<String>,
+              F: Fn(&str) -> Result<(), String> + Send + Sync + 'static
+    {
+        self.checks.entry(key.into()).or_insert_with(Vec::new).push(Box::new(check));
+        self
+    }
+
+    /// Require the value of `key`, when present, to parse as `T`.
+    pub fn parses<T>(self, key: &str) -> HstoreSchema
+        where T: FromStr,
+              T::Err: fmt::Display
+    {
+        self.value(key, |value| value.parse::<T>().map(|_| ()).map_err(|e| e.to_string()))
+    }
+
+    /// Require the value of `key`, when present, to match `pattern`.
+    ///
+    /// The pattern isn't anchored, so use `^` and `$` to match the whole value.
+    #[cfg(feature = "regex")]
+    pub fn matches(mut self, key: &str, pattern: Regex) -> HstoreSchema {
+        self.patterns.push((key.to_string(), pattern.as_str().to_string()));
+        self.value(key, move |value| {
+            if pattern.is_match(value) {
+                Ok(())
+            }
+            else {
+                Err(format!("does not match {}", pattern))
+            }
+        })
+    }
+
+    /// Report keys which are not required, allowed, or checked by a value rule.
+    pub fn deny_unknown_keys(mut self) -> HstoreSchema {
+        self.deny_unknown_keys = true;
+        self
+    }
+
+    /// Check an hstore against the schema, returning every violation found.
+    ///
+    /// Violations are ordered by kind, then by key.
+    pub fn validate(&self, hstore: &Hstore) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+
+        for key in &self.required {
+            if !hstore.contains_key(key) {
+                violations.push(Violation::Missing { key: key.clone() });
+            }
+        }
+
+        if self.deny_unknown_keys {
+            let mut unknown: Vec<&String> = hstore.keys()
+                .filter(|k| !self.required.contains(*k) && !self.allowed.contains(*k) && !self.checks.contains_key(*k))
+                .collect();
+            unknown.sort();
+            for key in unknown {
+                violations.push(Violation::Unknown { key: key.clone() });
+            }
+        }
+
+        for (key, checks) in &self.checks {
+            if let Some(value) = hstore.get(key) {
+                for check in checks {
+                    if let Err(message) = check(value) {
+                        violations.push(Violation::InvalidValue {
+                            key: key.clone(),
+                            value: value.clone(),
+                            message: message,
+                        });
+                    }
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(violations)
+        }
+    }
+}
+
+impl fmt::Debug for HstoreSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HstoreSchema")
+            .field("required", &self.required)
+            .field("allowed", &self.allowed)
+            .field("checked", &self.checks.keys().collect::<Vec<_>>())
+            .field("deny_unknown_keys", &self.deny_unknown_keys)
+            .finish()
+    }
+}
+
+/// A way in which an hstore does not match a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A required key is missing.
+    Missing {
+        /// The missing key
+        key: String,
+    },
+    /// A key is present which the schema doesn't know, and unknown keys are denied.
+    Unknown {
+        /// The unknown key
+        key: String,
+    },
+    /// A value failed one of the checks for its key.
+    InvalidValue {
+        /// The key of the value
+        key: String,
+        /// The invalid value
+        value: String,
+        /// What is wrong with it
+        message: String,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Missing { ref key } => write!(f, "missing required key {:?}", key),
+            Violation::Unknown { ref key } => write!(f, "unknown key {:?}", key),
+            Violation::InvalidValue { ref key, ref value, ref message } =>
+                write!(f, "invalid value {:?} for key {:?}: {}", value, key, message),
+        }
+    }
+}
+
+/// A violation found in a row of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowViolation {
+    row: String,
+    violation: Violation,
+}
+
+impl RowViolation {
+    /// The identifying column of the offending row, as text
+    pub fn row(&self) -> &str {
+        &self.row
+    }
+
+    /// What is wrong with it
+    pub fn violation(&self) -> &Violation {
+        &self.violation
+    }
+}
+
+/// Finds the rows of a table whose hstore violates a schema, in the database.
+///
+/// Required keys, unknown keys and patterns given to
+/// [`matches`](struct.HstoreSchema.html#method.matches) are checked. Other value checks run in
+/// Rust, so they are skipped. Patterns are passed to Postgres' `~` operator, so they must mean
+/// the same in both regular expression dialects.
+///
+/// ```rust
+/// use diesel_pg_hstore::schema::{HstoreSchema, ViolationFinder};
+///
+/// let schema = HstoreSchema::new().required("name");
+/// let finder = ViolationFinder::new(&schema, "people", "attributes", "id");
+/// // let violations = finder.find(&db)?;
+/// ```
+#[derive(Debug)]
+pub struct ViolationFinder<'a> {
+    schema: &'a HstoreSchema,
+    table: String,
+    store: String,
+    id: String,
+}
+
+impl<'a> ViolationFinder<'a> {
+    /// Check the hstore column `store` of `table`, identifying rows by the column `id`.
+    pub fn new<T, S, I>(schema: &'a HstoreSchema, table: T, store: S, id: I) -> ViolationFinder<'a>
+        where T: Into<String>,
+              S: Into<String>,
+              I: Into<String>
+    {
+        ViolationFinder {
+            schema: schema,
+            table: table.into(),
+            store: store.into(),
+            id: id.into(),
+        }
+    }
+
+    /// The query as SQL.
+    ///
+    /// Each result row has the offending row's id as text, the kind of violation (`missing`,
+    /// `unknown` or `invalid`), the key, and for invalid values the value and message.
+    pub fn to_sql(&self) -> String {
+        let table = quote_name(&self.table);
+        let store = format!("t.{}", quote_ident(&self.store));
+        let id = format!("t.{}::text", quote_ident(&self.id));
+        let mut checks = Vec::new();
+
+        if !self.schema.required.is_empty() {
+            checks.push(format!(
+                "SELECT {id}, 'missing', k, NULL, NULL FROM {table} AS t, unnest({required}) AS k \
+                 WHERE NOT ({store} ?& {required}) AND NOT ({store} ? k)",
+                id = id,
+                table = table,
+                store = store,
+                required = text_array(&self.schema.required),
+            ));
+        }
+
+        if self.schema.deny_unknown_keys {
+            let known: BTreeSet<&String> = self.schema.required.iter()
+                .chain(&self.schema.allowed)
+                .chain(self.schema.checks.keys())
+                .collect();
+            checks.push(format!(
+                "SELECT {id}, 'unknown', k, NULL, NULL FROM {table} AS t, skeys({store}) AS k \
+                 WHERE k <> ALL ({known})",
+                id = id,
+                table = table,
+                store = store,
+                known = text_array(known),
+            ));
+        }
+
+        for &(ref key, ref pattern) in &self.schema.patterns {
+            checks.push(format!(
+                "SELECT {id}, 'invalid', {key}, {store} -> {key}, {message} FROM {table} AS t \
+                 WHERE NOT (({store} -> {key}) ~ {pattern})",
+                id = id,
+                table = table,
+                store = store,
+                key = quote_literal(key),
+                pattern = quote_literal(pattern),
+                message = quote_literal(&format!("does not match {}", pattern)),
+            ));
+        }
+
+        if checks.is_empty() {
+            return "SELECT NULL::text, NULL::text, NULL::text, NULL::text, NULL::text WHERE false".into();
+        }
+        format!("{} ORDER BY 1, 2, 3", checks.join(" UNION ALL "))
+    }
+
+    /// Run the query, returning every violation found ordered by the text of the row id, then
+    /// kind and key.
+    pub fn find(&self, conn: &PgConnection) -> QueryResult<Vec<RowViolation>> {
+        let rows = sql::<(Text, Text, Text, Nullable<Text>, Nullable<Text>)>(&self.to_sql())
+            .load::<(String, String, String, Option<String>, Option<String>)>(conn)?;
+
+        Ok(rows.into_iter().map(|(row, kind, key, value, message)| {
+            let violation = match kind.as_str() {
+                "missing" => Violation::Missing { key: key },
+                "unknown" => Violation::Unknown { key: key },
+                _ => Violation::InvalidValue {
+                    key: key,
+                    value: value.unwrap_or_default(),
+                    message: message.unwrap_or_default(),
+                },
+            };
+            RowViolation {
+                row: row,
+                violation: violation,
+            }
+        }).collect())
+    }
+}
+
+/// An hstore did not match its schema when it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError(Vec<Violation>);
+
+impl SchemaError {
+    /// Every violation found
+    pub fn violations(&self) -> &[Violation] {
+        &self.0
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "hstore does not match its schema: ")?;
+        for (i, violation) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for SchemaError {
+    fn description(&self) -> &str {
+        "hstore does not match its schema"
+    }
+}
+
+/// Names the schema a [`Validated`](struct.Validated.html) column is checked against.
+pub trait Schema {
+    /// The schema to check against
+    fn schema() -> &'static HstoreSchema;
+}
+
+/// An Hstore which is checked against the schema `S` every time it is written.
+///
+/// Writing an hstore which doesn't match fails with a [`SchemaError`](struct.SchemaError.html)
+/// before anything is sent to the database. Hstores are not checked when they are loaded, so
+/// rows written before the schema existed can still be read.
+///
+/// ```rust
+/// # #[macro_use] extern crate lazy_static;
+/// # extern crate diesel_pg_hstore;
+/// use diesel_pg_hstore::schema::{HstoreSchema, Schema, Validated};
+///
+/// struct Address;
+///
+/// impl Schema for Address {
+///     fn schema() -> &'static HstoreSchema {
+///         lazy_static! {
+///             static ref SCHEMA: HstoreSchema = HstoreSchema::new()
+///                 .required("street")
+///                 .required("city");
+///         }
+///         &SCHEMA
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut address = Validated::<Address>::new();
+/// address.insert("street".into(), "1 Main St".into());
+/// assert!(address.validate().is_err());
+/// # }
+/// ```
+pub struct Validated<S> {
+    hstore: Hstore,
+    schema: PhantomData<fn() -> S>,
+}
+
+impl<S: Schema> Validated<S> {
+    /// Create a new, empty Validated hstore
+    pub fn new() -> Validated<S> {
+        Validated::from_hstore(Hstore::new())
+    }
+
+    /// Wrap an existing Hstore
+    pub fn from_hstore(hstore: Hstore) -> Validated<S> {
+        Validated {
+            hstore: hstore,
+            schema: PhantomData,
+        }
+    }
+
+    /// Consume the Validated hstore, returning the Hstore
+    pub fn into_inner(self) -> Hstore {
+        self.hstore
+    }
+
+    /// Check the hstore against the schema now
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        S::schema().validate(&self.hstore)
+    }
+}
+
+impl<S: Schema> Default for Validated<S> {
+    fn default() -> Validated<S> {
+        Validated::new()
+    }
+}
+
+impl<S> Clone for Validated<S> {
+    fn clone(&self) -> Validated<S> {
+        Validated {
+            hstore: self.hstore.clone(),
+            schema: PhantomData,
+        }
+    }
+}
+
+impl<S> fmt::Debug for Validated<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Validated").field(&self.hstore).finish()
+    }
+}
+
+impl<S> PartialEq for Validated<S> {
+    fn eq(&self, other: &Validated<S>) -> bool {
+        self.hstore == other.hstore
+    }
+}
+
+impl<S> Deref for Validated<S> {
+    type Target = Hstore;
+
+    fn deref(&self) -> &Hstore {
+        &self.hstore
+    }
+}
+
+impl<S> DerefMut for Validated<S> {
+    fn deref_mut(&mut self) -> &mut Hstore {
+        &mut self.hstore
+    }
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::io::Write;
+    use diesel::Queryable;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::row::Row;
+    use diesel::types::*;
+
+    use super::{Schema, SchemaError, Validated};
+    use super::super::Hstore;
+
+    impl<S: Schema> Queryable<Hstore, Pg> for Validated<S> {
+        type Row = Self;
+
+        fn build(row: Self::Row) -> Self {
+            row
+        }
+    }
+
+    impl<'a, S: Schema> AsExpression<Hstore> for &'a Validated<S> {
+        type Expression = Bound<Hstore, &'a Validated<S>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<S: Schema> FromSql<Hstore, Pg> for Validated<S> {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            Ok(Validated::from_hstore(Hstore::from_sql(bytes)?))
+        }
+    }
+
+    impl<S: Schema> FromSqlRow<Hstore, Pg> for Validated<S> {
+        fn build_from_row<R: Row<Pg>>(row: &mut R) -> Result<Self, Box<StdError + Send + Sync>> {
+            Validated::from_sql(row.take())
+        }
+    }
+
+    impl<S: Schema> ToSql<Hstore, Pg> for Validated<S> {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            self.validate().map_err(SchemaError)?;
+            ToSql::<Hstore, Pg>::to_sql(&self.hstore, out)
+        }
+    }
+}
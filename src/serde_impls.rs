@@ -0,0 +1,153 @@
+//! `Serialize` and `Deserialize` for the hstore types, with the `serde` feature.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use super::Hstore;
+use super::null_values::HstoreNullable;
+use super::ordered::BTreeHstore;
+#[cfg(feature = "indexmap")]
+use super::ordered::IndexHstore;
+
+impl Serialize for Hstore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<&String, &String> = self.0.iter().collect();
+        serializer.collect_map(sorted)
+    }
+}
+
+impl Serialize for HstoreNullable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<&String, &Option<String>> = self.iter().collect();
+        serializer.collect_map(sorted)
+    }
+}
+
+/// The entries in their own order, sorted for a `BTreeHstore`
+impl Serialize for BTreeHstore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// The entries in insertion order
+#[cfg(feature = "indexmap")]
+impl Serialize for IndexHstore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hstore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Hstore, D::Error> {
+        deserialize_non_null(deserializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HstoreNullable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HstoreNullable, D::Error> {
+        let entries = deserializer.deserialize_map(EntriesVisitor)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for BTreeHstore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BTreeHstore, D::Error> {
+        deserialize_non_null(deserializer)
+    }
+}
+
+/// The entries are kept in the order they are read
+#[cfg(feature = "indexmap")]
+impl<'de> Deserialize<'de> for IndexHstore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IndexHstore, D::Error> {
+        deserialize_non_null(deserializer)
+    }
+}
+
+/// The entries with a value, dropping those that are `null` as reading from Postgres does
+fn deserialize_non_null<'de, D, M>(deserializer: D) -> Result<M, D::Error>
+    where D: Deserializer<'de>,
+          M: FromIterator<(String, String)>
+{
+    let entries = deserializer.deserialize_map(EntriesVisitor)?;
+    Ok(entries.into_iter().filter_map(|(key, value)| value.map(|value| (key, value))).collect())
+}
+
+/// Reads the entries in order, as the map in the input may itself be ordered
+struct EntriesVisitor;
+
+impl<'de> Visitor<'de> for EntriesVisitor {
+    type Value = Vec<(String, Option<String>)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of strings")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        while let Some((key, value)) = map.next_entry::<String, ValueText>()? {
+            entries.push((key, value.0));
+        }
+        Ok(entries)
+    }
+}
+
+/// A value as text: a string, a number or boolean written out, or `None` for `null`
+struct ValueText(Option<String>);
+
+impl<'de> Deserialize<'de> for ValueText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ValueText, D::Error> {
+        deserializer.deserialize_any(ValueTextVisitor)
+    }
+}
+
+struct ValueTextVisitor;
+
+impl<'de> Visitor<'de> for ValueTextVisitor {
+    type Value = ValueText;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, number, boolean or null")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<ValueText, E> {
+        Ok(ValueText(Some(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<ValueText, E> {
+        Ok(ValueText(Some(v)))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<ValueText, E> {
+        Ok(ValueText(Some(v.to_string())))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<ValueText, E> {
+        Ok(ValueText(Some(v.to_string())))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<ValueText, E> {
+        Ok(ValueText(Some(v.to_string())))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<ValueText, E> {
+        Ok(ValueText(Some(v.to_string())))
+    }
+
+    fn visit_unit<E>(self) -> Result<ValueText, E> {
+        Ok(ValueText(None))
+    }
+
+    fn visit_none<E>(self) -> Result<ValueText, E> {
+        Ok(ValueText(None))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<ValueText, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
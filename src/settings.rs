@@ -0,0 +1,106 @@
+//! Layering hstore settings into the `config` crate.
+//!
+//! With the `config` feature, an Hstore is a `config::Source`, so settings stored in a table
+//! (per tenant, say) can be merged over the usual files and environment variables. Keys
+//! containing `.` are nested, just as they are in other sources:
+//!
+//! ```rust,ignore
+//! extern crate config;
+//! extern crate diesel_pg_hstore;
+//!
+//! use config::{Config, File};
+//! use diesel_pg_hstore::settings::HstoreSource;
+//!
+//! let tenant = HstoreSource::load(&db, "tenant_settings", "settings", tenant_id)?;
+//! let settings = Config::builder()
+//!     .add_source(File::with_name("config/default"))
+//!     .add_source(tenant)
+//!     .build()?;
+//!
+//! // From the tenant row's `database.pool_size=>20`, if it has one
+//! let pool_size: u32 = settings.get("database.pool_size")?;
+//! ```
+//!
+//! Every value is a string. `config` converts them when they are read, as it does for
+//! environment variables.
+
+use config::{ConfigError, Map, Source, Value};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::dsl::sql;
+
+use super::Hstore;
+use super::quote::{quote_ident, quote_name};
+
+impl Source for Hstore {
+    fn clone_into_box(&self) -> Box<Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(collect(self, None))
+    }
+}
+
+/// An Hstore as a `config::Source`, naming where it came from.
+///
+/// The origin is reported by `config` in errors about the values it supplied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HstoreSource {
+    hstore: Hstore,
+    origin: String,
+}
+
+impl HstoreSource {
+    /// A source supplying the entries of `hstore`
+    pub fn new<O: Into<String>>(hstore: Hstore, origin: O) -> HstoreSource {
+        HstoreSource {
+            hstore: hstore,
+            origin: origin.into(),
+        }
+    }
+
+    /// Load the `store` column of the row of `table` with the id `id`.
+    ///
+    /// If there is no such row the source is empty, so it adds nothing to the configuration.
+    pub fn load(conn: &PgConnection, table: &str, store: &str, id: i64) -> QueryResult<HstoreSource> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE id = {}",
+            quote_ident(store),
+            quote_name(table),
+            id,
+        );
+        let hstore = sql::<Hstore>(&query)
+            .get_result(conn)
+            .optional()?
+            .unwrap_or_default();
+
+        Ok(HstoreSource::new(hstore, format!("{}.{} (id {})", table, store, id)))
+    }
+
+    /// The settings
+    pub fn hstore(&self) -> &Hstore {
+        &self.hstore
+    }
+
+    /// Where they came from
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+}
+
+impl Source for HstoreSource {
+    fn clone_into_box(&self) -> Box<Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(collect(&self.hstore, Some(&self.origin)))
+    }
+}
+
+fn collect(hstore: &Hstore, origin: Option<&String>) -> Map<String, Value> {
+    hstore.iter()
+        .map(|(key, value)| (key.clone(), Value::new(origin, value.clone())))
+        .collect()
+}
@@ -0,0 +1,167 @@
+//! Evaluate hstore operators and functions in Rust.
+//!
+//! Each function here mirrors a Postgres hstore operator or function, with the same semantics,
+//! so rules built on top of them can be unit tested without a database.
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::sim;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("env".into(), "prod".into());
+//! store.insert("region".into(), "eu".into());
+//!
+//! let mut wanted = Hstore::new();
+//! wanted.insert("env".into(), "prod".into());
+//!
+//! // store @> 'env=>prod'
+//! assert!(sim::contains(&store, &wanted));
+//! // store ?& ARRAY['env', 'tier']
+//! assert!(!sim::exists_all(&store, &["env", "tier"]));
+//! // akeys(store)
+//! assert_eq!(sim::akeys(&store), vec!["env", "region"]);
+//! ```
+//!
+//! Postgres keeps hstore entries ordered by key length and then by the bytes of the key. The
+//! functions returning arrays or sets (`akeys`, `avals`, `each`, ...) use that same order rather
+//! than the `HashMap` iteration order.
+//!
+//! Since the Hstore type does not hold null values, `defined` is equivalent to `exist` and
+//! `fetch` of a missing key is `None`, just as it is null in Postgres.
+
+use std::cmp::Ordering;
+
+use super::Hstore;
+
+/// The order Postgres keeps hstore keys in: shorter keys first, then by their bytes
+pub(crate) fn pg_cmp(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.as_bytes().cmp(b.as_bytes()))
+}
+
+fn sorted(h: &Hstore) -> Vec<(&String, &String)> {
+    let mut entries: Vec<_> = h.iter().collect();
+    entries.sort_by(|a, b| pg_cmp(a.0, b.0));
+    entries
+}
+
+/// `hstore -> text`
+pub fn fetch<'a>(h: &'a Hstore, key: &str) -> Option<&'a str> {
+    h.get(key).map(|v| v.as_str())
+}
+
+/// `hstore -> text[]`
+pub fn fetch_many<'a>(h: &'a Hstore, keys: &[&str]) -> Vec<Option<&'a str>> {
+    keys.iter().map(|k| fetch(h, k)).collect()
+}
+
+/// `hstore || hstore`
+///
+/// Entries in `b` replace those in `a` having the same key.
+pub fn concat(a: &Hstore, b: &Hstore) -> Hstore {
+    let mut result = a.clone();
+    result.extend(b.iter().map(|(k, v)| (k.clone(), v.clone())));
+    result
+}
+
+/// `hstore ? text`
+pub fn exist(h: &Hstore, key: &str) -> bool {
+    h.contains_key(key)
+}
+
+/// `defined(hstore, text)`
+pub fn defined(h: &Hstore, key: &str) -> bool {
+    exist(h, key)
+}
+
+/// `hstore ?| text[]`
+///
+/// False for an empty array.
+pub fn exists_any(h: &Hstore, keys: &[&str]) -> bool {
+    keys.iter().any(|k| exist(h, k))
+}
+
+/// `hstore ?& text[]`
+///
+/// True for an empty array.
+pub fn exists_all(h: &Hstore, keys: &[&str]) -> bool {
+    keys.iter().all(|k| exist(h, k))
+}
+
+/// `hstore @> hstore`
+pub fn contains(a: &Hstore, b: &Hstore) -> bool {
+    b.iter().all(|(k, v)| a.get(k) == Some(v))
+}
+
+/// `hstore <@ hstore`
+pub fn contained(a: &Hstore, b: &Hstore) -> bool {
+    contains(b, a)
+}
+
+/// `hstore - text`
+pub fn delete(h: &Hstore, key: &str) -> Hstore {
+    let mut result = h.clone();
+    result.remove(key);
+    result
+}
+
+/// `hstore - text[]`
+pub fn delete_keys(h: &Hstore, keys: &[&str]) -> Hstore {
+    let mut result = h.clone();
+    for key in keys {
+        result.remove(key);
+    }
+    result
+}
+
+/// `hstore - hstore`
+///
+/// Only removes entries whose key *and* value match.
+pub fn delete_pairs(a: &Hstore, b: &Hstore) -> Hstore {
+    a.iter()
+        .filter(|&(k, v)| b.get(k) != Some(v))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+/// `slice(hstore, text[])`
+pub fn slice(h: &Hstore, keys: &[&str]) -> Hstore {
+    keys.iter()
+        .filter_map(|&k| h.get(k).map(|v| (k.to_string(), v.clone())))
+        .collect()
+}
+
+/// `hstore(text[], text[])`
+///
+/// Returns `None` where Postgres raises an error because the arrays differ in length. When a
+/// key is repeated the first occurrence is kept.
+pub fn from_arrays(keys: &[&str], values: &[&str]) -> Option<Hstore> {
+    if keys.len() != values.len() {
+        return None;
+    }
+
+    let mut result = Hstore::with_capacity(keys.len());
+    for (k, v) in keys.iter().zip(values) {
+        result.entry(k.to_string()).or_insert_with(|| v.to_string());
+    }
+    Some(result)
+}
+
+/// `akeys(hstore)` and `skeys(hstore)`
+pub fn akeys(h: &Hstore) -> Vec<&str> {
+    sorted(h).into_iter().map(|(k, _)| k.as_str()).collect()
+}
+
+/// `avals(hstore)` and `svals(hstore)`
+pub fn avals(h: &Hstore) -> Vec<&str> {
+    sorted(h).into_iter().map(|(_, v)| v.as_str()).collect()
+}
+
+/// `each(hstore)` and `%# hstore`
+pub fn each(h: &Hstore) -> Vec<(&str, &str)> {
+    sorted(h).into_iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+}
+
+/// `%% hstore`
+pub fn to_flat_array(h: &Hstore) -> Vec<&str> {
+    sorted(h).into_iter().flat_map(|(k, v)| vec![k.as_str(), v.as_str()]).collect()
+}
@@ -0,0 +1,51 @@
+//! Stable output for snapshot tests.
+//!
+//! An Hstore's `Debug` output lists its entries sorted by key, so snapshots of query results
+//! taken with `insta::assert_debug_snapshot!`, or anything else built on `Debug`, don't change
+//! from run to run with the order of the map:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("b".into(), "2".into());
+//! store.insert("a".into(), "1".into());
+//!
+//! let row = (1, store);
+//! assert_eq!(format!("{:?}", row), r#"(1, Hstore({"a": "1", "b": "2"}))"#);
+//! ```
+//!
+//! For snapshots of the hstores alone, [`text`](fn.text.html) gives the text format with every
+//! key and value quoted, in the same sorted order.
+
+use super::Hstore;
+use super::text::write_quoted;
+
+/// Format an hstore as text, sorted by key, with every key and value quoted.
+///
+/// Unlike [`text::format`](../text/fn.format.html), the order doesn't follow Postgres, which
+/// sorts by length first, so the output reads alphabetically.
+///
+/// ```rust
+/// use diesel_pg_hstore::{snapshot, Hstore};
+///
+/// let mut store = Hstore::new();
+/// store.insert("color".into(), "red".into());
+/// store.insert("b".into(), "say \"hi\"".into());
+/// assert_eq!(snapshot::text(&store), r#""b"=>"say \"hi\"", "color"=>"red""#);
+/// ```
+pub fn text(hstore: &Hstore) -> String {
+    let mut entries: Vec<_> = hstore.0.iter().collect();
+    entries.sort();
+
+    let mut out = String::new();
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_quoted(key, &mut out);
+        out.push_str("=>");
+        write_quoted(value, &mut out);
+    }
+    out
+}
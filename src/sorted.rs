@@ -0,0 +1,86 @@
+//! Binding hstores with their entries in a fixed order.
+//!
+//! An Hstore is bound with its entries in the order of its `HashMap`, which differs between
+//! equal maps. Binding [`Hstore::sorted`](../struct.Hstore.html#method.sorted) instead writes
+//! them in the order Postgres itself keeps them, by key length and then by the bytes of the key,
+//! so equal maps are always sent as identical bytes:
+//!
+//! ```rust,ignore
+//! diesel::insert_into(events::table)
+//!     .values(events::meta.eq(meta.sorted()))
+//!     .execute(&db)?;
+//! ```
+//!
+//! The bytes are the same as Postgres' own binary output for the stored hstore.
+
+use super::Hstore;
+
+/// An Hstore to be bound with its entries sorted. See the [sorted](index.html) module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sorted<'a>(&'a Hstore);
+
+impl<'a> Sorted<'a> {
+    /// The Hstore
+    pub fn hstore(&self) -> &'a Hstore {
+        self.0
+    }
+}
+
+impl Hstore {
+    /// Bind with the entries sorted, so equal hstores are sent as identical bytes. See the
+    /// [sorted](sorted/index.html) module.
+    pub fn sorted(&self) -> Sorted {
+        Sorted(self)
+    }
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::io::Write;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::types::*;
+
+    use super::Sorted;
+    use super::super::Hstore;
+    use super::super::codec;
+
+    impl<'a> AsExpression<Hstore> for Sorted<'a> {
+        type Expression = Bound<Hstore, Sorted<'a>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a> AsExpression<Nullable<Hstore>> for Sorted<'a> {
+        type Expression = Bound<Nullable<Hstore>, Sorted<'a>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a> ToSql<Hstore, Pg> for Sorted<'a> {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            let mut buf: Vec<u8> = Vec::new();
+            codec::encode_sorted(self.0, &mut buf)?;
+
+            out.write_all(&buf)?;
+            #[cfg(feature = "zeroize")]
+            ::zeroize::Zeroize::zeroize(&mut buf);
+            Ok(IsNull::No)
+        }
+    }
+
+    impl<'a> ToSql<Nullable<Hstore>, Pg> for Sorted<'a> {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(self, out)
+        }
+    }
+}
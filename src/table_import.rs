@@ -0,0 +1,22 @@
+//! The imports for a `table!` block with hstore columns.
+//!
+//! A `table!` block naming its own imports no longer imports Diesel's types, so both are needed.
+//! This module holds exactly those:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! table! {
+//!     use diesel_pg_hstore::table_import::*;
+//!
+//!     settings {
+//!         id -> Integer,
+//!         name -> Nullable<Text>,
+//!         store -> Hstore,
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+
+pub use diesel::types::*;
+pub use super::Hstore;
@@ -0,0 +1,140 @@
+//! Sharing one hstore column between tenants.
+//!
+//! When several tenants keep their keys in the same column, a [`Tenant`](struct.Tenant.html)
+//! confines each to its own namespace. Every key it writes, deletes or queries is prefixed with
+//! the tenant's name and `:`, and reading keeps only the tenant's own keys, with the prefix
+//! stripped:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::patch::HstorePatch;
+//! use diesel_pg_hstore::tenant::Tenant;
+//!
+//! let acme = Tenant::new("acme");
+//! let globex = Tenant::new("globex");
+//!
+//! let mut store = Hstore::new();
+//! acme.patch(&HstorePatch::new().set("plan", "gold")).apply_to(&mut store);
+//! globex.patch(&HstorePatch::new().set("plan", "free").delete("trial")).apply_to(&mut store);
+//! assert_eq!(store, [("acme:plan", "gold"), ("globex:plan", "free")]);
+//!
+//! assert_eq!(acme.unscope(&store), [("plan", "gold")]);
+//! ```
+//!
+//! With the `dsl` feature, [`get_value`](struct.Tenant.html#method.get_value),
+//! [`has_key`](struct.Tenant.html#method.has_key) and
+//! [`remove_key`](struct.Tenant.html#method.remove_key) build the operators of the
+//! [`dsl`](../dsl/index.html) module with the tenant's keys, and
+//! [`HstorePatch::to_sql`](../patch/struct.HstorePatch.html#method.to_sql) applies a scoped patch
+//! in the database.
+//!
+//! Keys which belong to no tenant are left alone by every tenant.
+
+use super::Hstore;
+use super::patch::HstorePatch;
+
+/// The separator between a tenant's name and its keys
+pub const SEPARATOR: char = ':';
+
+/// A namespace of keys within a shared hstore.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tenant {
+    prefix: String,
+}
+
+impl Tenant {
+    /// The tenant named `name`, whose keys are prefixed with `name:`.
+    ///
+    /// Panics if `name` is empty or contains the [`SEPARATOR`](constant.SEPARATOR.html), as
+    /// then one tenant's keys could be another's.
+    pub fn new<N: Into<String>>(name: N) -> Tenant {
+        let mut prefix = name.into();
+        assert!(!prefix.is_empty(), "tenant name must not be empty");
+        assert!(!prefix.contains(SEPARATOR), "tenant name must not contain {:?}", SEPARATOR);
+        prefix.push(SEPARATOR);
+        Tenant {
+            prefix: prefix,
+        }
+    }
+
+    /// The tenant's name
+    pub fn name(&self) -> &str {
+        &self.prefix[..self.prefix.len() - SEPARATOR.len_utf8()]
+    }
+
+    /// The prefix of the tenant's keys
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The stored key for the tenant's `key`
+    pub fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// The tenant's key for the stored `key`, if the tenant owns it
+    pub fn strip<'a>(&self, key: &'a str) -> Option<&'a str> {
+        key.strip_prefix(self.prefix.as_str())
+    }
+
+    /// The tenant's entries, as stored.
+    pub fn scope(&self, hstore: &Hstore) -> Hstore {
+        hstore.iter().map(|(key, value)| (self.key(key), value.clone())).collect()
+    }
+
+    /// The tenant's entries in a stored hstore, with the prefix stripped. Other keys are left
+    /// out.
+    pub fn unscope(&self, hstore: &Hstore) -> Hstore {
+        hstore.iter()
+            .filter_map(|(key, value)| self.strip(key).map(|key| (key.to_string(), value.clone())))
+            .collect()
+    }
+
+    /// The patch making the tenant's changes to a stored hstore.
+    pub fn patch(&self, patch: &HstorePatch) -> HstorePatch {
+        let deleted = patch.deletes().iter().fold(HstorePatch::new(), |scoped, key| scoped.delete(self.key(key)));
+        patch.sets().iter().fold(deleted, |scoped, (key, value)| scoped.set(self.key(key), value.clone()))
+    }
+}
+
+#[cfg(feature = "dsl")]
+mod dsl_impls {
+    use diesel::expression::{AsExpression, Expression};
+    use diesel::types::Text;
+
+    use super::Tenant;
+    use super::super::Hstore;
+    use super::super::dsl::{GetValue, HasKey, HstoreOpExtensions, RemoveKey};
+
+    /// A tenant's key, bound as a parameter
+    pub type TenantKey = <String as AsExpression<Text>>::Expression;
+
+    impl Tenant {
+        /// The value of the tenant's `key` in `store`, as
+        /// [`get_value`](../dsl/trait.HstoreOpExtensions.html#method.get_value).
+        pub fn get_value<S>(&self, store: S, key: &str) -> GetValue<S, TenantKey>
+            where S: Expression<SqlType = Hstore>
+        {
+            store.get_value(self.key(key))
+        }
+
+        /// Whether `store` has the tenant's `key`, as
+        /// [`has_key`](../dsl/trait.HstoreOpExtensions.html#method.has_key).
+        pub fn has_key<S>(&self, store: S, key: &str) -> HasKey<S, TenantKey>
+            where S: Expression<SqlType = Hstore>
+        {
+            store.has_key(self.key(key))
+        }
+
+        /// `store` without the tenant's `key`, as
+        /// [`remove_key`](../dsl/trait.HstoreOpExtensions.html#method.remove_key).
+        pub fn remove_key<S>(&self, store: S, key: &str) -> RemoveKey<S, TenantKey>
+            where S: Expression<SqlType = Hstore>
+        {
+            store.remove_key(self.key(key))
+        }
+    }
+}
+
+#[cfg(feature = "dsl")]
+pub use self::dsl_impls::TenantKey;
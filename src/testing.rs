@@ -0,0 +1,172 @@
+//! Helpers for writing hstore integration tests.
+//!
+//! These are the same fixtures this crate uses for its own test suite. They are available when
+//! the `testing` feature is enabled:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! diesel_pg_hstore = { version = "*", features = ["testing"] }
+//! ```
+//!
+//! Every connection handed out by [`connection`](fn.connection.html) is inside a test transaction,
+//! so nothing written during a test is ever committed. Tables created with
+//! [`create_table`](fn.create_table.html) are temporary and disappear with the connection.
+//!
+//! ```rust,no_run
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::testing;
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     settings {
+//!         id -> Integer,
+//!         store -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let db = testing::connection();
+//! testing::create_table(&db, "settings").unwrap();
+//!
+//! let rows: Vec<(i32, Hstore)> = settings::table.load(&db).unwrap();
+//! assert!(rows.is_empty());
+//! # }
+//! ```
+//!
+//! With the `testcontainers` feature, [`EphemeralPostgres`](struct.EphemeralPostgres.html)
+//! starts a disposable Postgres in Docker instead, so no `DATABASE_URL` is needed:
+//!
+//! ```rust,ignore
+//! use diesel_pg_hstore::testing::EphemeralPostgres;
+//!
+//! let postgres = EphemeralPostgres::start().unwrap();
+//! let db = postgres.connection();
+//! testing::create_table(&db, "settings").unwrap();
+//! ```
+
+use std::env;
+
+use diesel::Connection;
+use diesel::connection::SimpleConnection;
+use diesel::pg::PgConnection;
+use diesel::result::QueryResult;
+use dotenv;
+#[cfg(feature = "testcontainers")]
+use testcontainers_modules::postgres::Postgres;
+#[cfg(feature = "testcontainers")]
+use testcontainers_modules::testcontainers::{Container, TestcontainersError};
+#[cfg(feature = "testcontainers")]
+use testcontainers_modules::testcontainers::runners::SyncRunner;
+
+/// Establish a connection to `DATABASE_URL` (a `.env` file is honored) and begin a test
+/// transaction on it.
+///
+/// The hstore extension is created before the test transaction begins, so it persists in the
+/// database even though everything else done through the connection is rolled back.
+///
+/// Panics if `DATABASE_URL` is not set or a connection can't be established.
+pub fn connection() -> PgConnection {
+    prepare(establish())
+}
+
+/// Establish a connection to `DATABASE_URL` as [`connection`](fn.connection.html) does, without
+/// beginning a test transaction, for testing code which behaves differently in a transaction.
+///
+/// Everything written through the connection is committed, so it should only write to
+/// temporary tables, such as those made by [`create_table`](fn.create_table.html).
+pub fn autocommit_connection() -> PgConnection {
+    let db = establish();
+    create_extension(&db).expect("To create the hstore extension");
+    db
+}
+
+fn establish() -> PgConnection {
+    dotenv::dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL to be defined (may use .env)");
+    PgConnection::establish(&database_url).expect("To connect to DATABASE_URL")
+}
+
+fn prepare(db: PgConnection) -> PgConnection {
+    create_extension(&db).expect("To create the hstore extension");
+    begin_test_transaction(&db).expect("To begin a test transaction");
+    db
+}
+
+/// A disposable Postgres server, running in a Docker container until this is dropped.
+///
+/// This is available with the `testcontainers` feature, and needs a Docker daemon. Each server
+/// starts empty, so it can be shared by the tests in a file or started per test for complete
+/// isolation. Connections must not outlive it.
+#[cfg(feature = "testcontainers")]
+pub struct EphemeralPostgres {
+    container: Container<Postgres>,
+    url: String,
+}
+
+#[cfg(feature = "testcontainers")]
+impl EphemeralPostgres {
+    /// Start a server, waiting until it accepts connections.
+    pub fn start() -> Result<EphemeralPostgres, TestcontainersError> {
+        let container = Postgres::default().start()?;
+        let url = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host()?,
+            container.get_host_port_ipv4(5432)?,
+        );
+        Ok(EphemeralPostgres {
+            container: container,
+            url: url,
+        })
+    }
+
+    /// The URL to connect to the server with
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Connect to the server, as [`connection`](fn.connection.html) connects to `DATABASE_URL`.
+    ///
+    /// The hstore extension is created and a test transaction begun, so nothing written through
+    /// the connection is committed. Panics if a connection can't be established.
+    pub fn connection(&self) -> PgConnection {
+        let db = PgConnection::establish(&self.url).expect("To connect to the ephemeral Postgres");
+        prepare(db)
+    }
+
+    /// The container the server is running in
+    pub fn container(&self) -> &Container<Postgres> {
+        &self.container
+    }
+}
+
+/// Begin a transaction which will never be committed.
+///
+/// Please see [Connection.begin_test_transaction](https://docs.rs/diesel/1.0.0/diesel/connection/trait.Connection.html#method.begin_test_transaction)
+pub fn begin_test_transaction(db: &PgConnection) -> QueryResult<()> {
+    db.begin_test_transaction()
+}
+
+/// Enable the hstore extension on the connected database if it isn't already.
+pub fn create_extension(db: &PgConnection) -> QueryResult<()> {
+    db.batch_execute("CREATE EXTENSION IF NOT EXISTS hstore;")
+}
+
+/// Create a temporary table with the given name, having the columns `id SERIAL PRIMARY KEY` and
+/// `store hstore NOT NULL`.
+///
+/// Any temporary table of the same name is dropped first. The name is used as-is, so it must
+/// be a valid identifier.
+pub fn create_table(db: &PgConnection, name: &str) -> QueryResult<()> {
+    db.batch_execute(&format!(r#"
+        DROP TABLE IF EXISTS pg_temp.{name};
+        CREATE TEMPORARY TABLE {name} (
+            id SERIAL PRIMARY KEY,
+            store hstore NOT NULL
+        );
+    "#, name = name))
+}
@@ -0,0 +1,530 @@
+//! The hstore text format.
+//!
+//! This is the representation used by `psql`, `COPY` and hstore literals in SQL:
+//!
+//! ```text
+//! "a"=>"1", "b"=>"2", c=>NULL
+//! ```
+//!
+//! Keys and values may be double quoted, with `\` escaping the next character. Unquoted keys end
+//! at whitespace or `=>`, unquoted values at whitespace or `,`. An unquoted `NULL` value (in any
+//! case) is null, and as with the binary format those entries are ignored.
+//!
+//! ```rust
+//! use diesel_pg_hstore::text;
+//!
+//! let store = text::parse(r#"a=>1, "b c"=>"say \"hi\"", d=>NULL"#).unwrap();
+//! assert_eq!(store["b c"], r#"say "hi""#);
+//! assert!(!store.contains_key("d"));
+//!
+//! assert_eq!(text::format(&store), r#""a"=>"1", "b c"=>"say \"hi\"""#);
+//! ```
+//!
+//! [`parse_reader`](fn.parse_reader.html) and [`write`](fn.write.html) do the same incrementally,
+//! for literals too large to comfortably hold as a string as well as a map.
+//!
+//! `Hstore` implements `FromStr` and `Display` with [`parse`](fn.parse.html) and
+//! [`format`](fn.format.html), so it can be read from fixtures with `str::parse` and printed with
+//! `{}`. [`HstoreNullable`](../null_values/struct.HstoreNullable.html) does the same with
+//! [`parse_nullable`](fn.parse_nullable.html) and [`format_nullable`](fn.format_nullable.html),
+//! keeping `NULL` values:
+//!
+//! ```rust
+//! use diesel_pg_hstore::{Hstore, HstoreNullable};
+//!
+//! let store: HstoreNullable = "b=>NULL, a=>1".parse().unwrap();
+//! assert_eq!(store.to_string(), r#""a"=>"1", "b"=>NULL"#);
+//!
+//! let store: Hstore = "b=>NULL, a=>1".parse().unwrap();
+//! assert_eq!(store.to_string(), r#""a"=>"1""#);
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::iter::Peekable;
+use std::str::{self, FromStr};
+
+use super::Hstore;
+use super::null_values::HstoreNullable;
+use super::quote::quote_literal;
+use super::sim;
+
+/// An error encountered while parsing the hstore text format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    position: usize,
+    message: &'static str,
+}
+
+impl ParseError {
+    pub(crate) fn new(position: usize, message: &'static str) -> ParseError {
+        ParseError {
+            position: position,
+            message: message,
+        }
+    }
+
+    /// The byte offset into the input at which the error was found.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl StdError for ParseError {
+    fn description(&self) -> &str {
+        self.message
+    }
+}
+
+/// An error encountered while reading the hstore text format from a reader.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading failed, or the input wasn't UTF-8.
+    Io(io::Error),
+    /// The input isn't a valid hstore.
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref e) => write!(f, "{}", e),
+            ReadError::Parse(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl StdError for ReadError {
+    fn description(&self) -> &str {
+        match *self {
+            ReadError::Io(_) => "reading hstore text failed",
+            ReadError::Parse(ref e) => e.description(),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(e: ParseError) -> ReadError {
+        ReadError::Parse(e)
+    }
+}
+
+/// Parse an hstore from its text representation.
+pub fn parse(s: &str) -> Result<Hstore, ParseError> {
+    parse_chars(s.char_indices())
+}
+
+/// Parse an hstore from its text representation, read incrementally from `reader`.
+///
+/// Only the entry being parsed is held in memory besides the hstore itself, so large literals,
+/// such as those in dumps, can be read without first being loaded into a string. Error positions
+/// are byte offsets into everything read.
+///
+/// ```rust
+/// use diesel_pg_hstore::text;
+///
+/// let store = text::parse_reader(&b"a=>1, b=>2"[..]).unwrap();
+/// assert_eq!(store["b"], "2");
+/// ```
+pub fn parse_reader<R: BufRead>(reader: R) -> Result<Hstore, ReadError> {
+    let mut chars = ReaderChars {
+        reader: reader,
+        offset: 0,
+        error: None,
+    };
+    let parsed = parse_chars(chars.by_ref());
+    match chars.error {
+        Some(e) => Err(ReadError::Io(e)),
+        None => Ok(parsed?),
+    }
+}
+
+fn parse_chars<I: Iterator<Item = (usize, char)>>(chars: I) -> Result<Hstore, ParseError> {
+    // The entries with `NULL` values are dropped only once parsed, as a key set to `NULL` still
+    // hides any duplicates of it
+    Ok(parse_nullable_chars(chars)?.into_hstore())
+}
+
+/// Parse an hstore from its text representation, keeping entries whose value is `NULL`.
+///
+/// ```rust
+/// use diesel_pg_hstore::text;
+///
+/// let store = text::parse_nullable(r#"a=>1, b=>NULL, c=>"NULL""#).unwrap();
+/// assert_eq!(store["b"], None);
+/// assert_eq!(store["c"], Some("NULL".to_string()));
+/// ```
+pub fn parse_nullable(s: &str) -> Result<HstoreNullable, ParseError> {
+    parse_nullable_chars(s.char_indices())
+}
+
+fn parse_nullable_chars<I: Iterator<Item = (usize, char)>>(chars: I) -> Result<HstoreNullable, ParseError> {
+    let mut parser = Parser {
+        chars: chars.peekable(),
+        end: 0,
+    };
+    let mut hstore = HstoreNullable::new();
+
+    parser.skip_whitespace();
+    while parser.peek().is_some() {
+        let key = match parser.token(Word::Key)? {
+            Token::Quoted(key) | Token::Unquoted(key) => key,
+        };
+
+        parser.skip_whitespace();
+        parser.expect('=', "expected \"=>\"")?;
+        parser.expect('>', "expected \"=>\"")?;
+        parser.skip_whitespace();
+
+        let value = match parser.token(Word::Value)? {
+            Token::Unquoted(ref value) if value.eq_ignore_ascii_case("null") => None,
+            Token::Quoted(value) | Token::Unquoted(value) => Some(value),
+        };
+        // Postgres keeps the first of any duplicated keys
+        hstore.entry(key).or_insert(value);
+
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => break,
+            Some(',') => {
+                parser.next();
+                parser.skip_whitespace();
+            },
+            Some(_) => return Err(parser.error("expected \",\"")),
+        }
+    }
+
+    Ok(hstore)
+}
+
+/// Format an hstore as text, with every key and value quoted.
+///
+/// Entries are written in the same order Postgres uses, so the result matches `store::text`.
+pub fn format(hstore: &Hstore) -> String {
+    let mut out = String::new();
+    for (i, (key, value)) in sim::each(hstore).into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_quoted(key, &mut out);
+        out.push_str("=>");
+        write_quoted(value, &mut out);
+    }
+    out
+}
+
+/// Write an hstore as text to `out`, as [`format`](fn.format.html) does, without building the
+/// whole string first.
+pub fn write<W: Write>(hstore: &Hstore, mut out: W) -> io::Result<()> {
+    let mut quoted = String::new();
+    for (i, (key, value)) in sim::each(hstore).into_iter().enumerate() {
+        quoted.clear();
+        if i > 0 {
+            quoted.push_str(", ");
+        }
+        write_quoted(key, &mut quoted);
+        quoted.push_str("=>");
+        write_quoted(value, &mut quoted);
+        out.write_all(quoted.as_bytes())?;
+    }
+    out.flush()
+}
+
+/// Format an hstore whose values may be `NULL` as text, with every key and value quoted except
+/// `NULL`s.
+///
+/// Entries are written in the same order Postgres uses, so the result matches `store::text`.
+pub fn format_nullable(hstore: &HstoreNullable) -> String {
+    let mut entries: Vec<(&String, &Option<String>)> = hstore.iter().collect();
+    entries.sort_by(|a, b| sim::pg_cmp(a.0, b.0));
+
+    let mut out = String::new();
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_quoted(key, &mut out);
+        out.push_str("=>");
+        match *value {
+            Some(ref value) => write_quoted(value, &mut out),
+            None => out.push_str("NULL"),
+        }
+    }
+    out
+}
+
+/// Quote a key or value as it appears in the hstore text format, escaping `"` and `\`.
+///
+/// ```rust
+/// use diesel_pg_hstore::text;
+///
+/// assert_eq!(text::quote(r#"say "hi""#), r#""say \"hi\"""#);
+/// ```
+pub fn quote(s: &str) -> String {
+    let mut out = String::new();
+    write_quoted(s, &mut out);
+    out
+}
+
+/// Format an hstore as an SQL literal, `'...'::hstore`, for embedding in SQL text.
+///
+/// Single quotes are doubled. Since hstore's text format escapes with backslashes, most literals
+/// are written as escape strings, `E'...'`, so they don't depend on `standard_conforming_strings`.
+/// Prefer binding hstores as parameters wherever the SQL allows it.
+pub fn sql_literal(hstore: &Hstore) -> String {
+    format!("{}::hstore", quote_literal(&format(hstore)))
+}
+
+/// Append `s` to `out` as a double quoted string, escaping `"` and `\`.
+pub fn write_quoted(s: &str, out: &mut String) {
+    out.reserve(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+impl Hstore {
+    /// Format as an SQL literal, `'...'::hstore`. See [`text::sql_literal`](fn.sql_literal.html).
+    ///
+    /// ```rust
+    /// use diesel_pg_hstore::Hstore;
+    ///
+    /// let mut store = Hstore::new();
+    /// store.insert("it's".into(), "a \"quote\"".into());
+    /// assert_eq!(store.to_sql_literal(), r#"E'"it''s"=>"a \\"quote\\""'::hstore"#);
+    /// ```
+    pub fn to_sql_literal(&self) -> String {
+        sql_literal(self)
+    }
+
+    /// Parse the text format, read incrementally. See [`text::parse_reader`](text/fn.parse_reader.html).
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Hstore, ReadError> {
+        parse_reader(reader)
+    }
+
+    /// Write the text format. See [`text::write`](text/fn.write.html).
+    pub fn to_writer<W: Write>(&self, out: W) -> io::Result<()> {
+        write(self, out)
+    }
+}
+
+/// Parses the text format. See [`text::parse`](text/fn.parse.html).
+impl FromStr for Hstore {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Hstore, ParseError> {
+        parse(s)
+    }
+}
+
+/// Writes the text format. See [`text::format`](text/fn.format.html).
+impl fmt::Display for Hstore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format(self))
+    }
+}
+
+/// Parses the text format, keeping `NULL` values. See
+/// [`text::parse_nullable`](../text/fn.parse_nullable.html).
+impl FromStr for HstoreNullable {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<HstoreNullable, ParseError> {
+        parse_nullable(s)
+    }
+}
+
+/// Writes the text format, with `NULL` values. See
+/// [`text::format_nullable`](../text/fn.format_nullable.html).
+impl fmt::Display for HstoreNullable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format_nullable(self))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Word {
+    Key,
+    Value,
+}
+
+enum Token {
+    Quoted(String),
+    Unquoted(String),
+}
+
+/// The characters of a reader and their byte offsets, ending early at the first error
+struct ReaderChars<R> {
+    reader: R,
+    offset: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> ReaderChars<R> {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = match self.reader.fill_buf()?.first() {
+            Some(&byte) => byte,
+            None => return Ok(None),
+        };
+        self.reader.consume(1);
+        Ok(Some(byte))
+    }
+
+    fn next_char(&mut self) -> io::Result<Option<char>> {
+        let first = match self.next_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let width = match first {
+            0x00..=0x7f => return Ok(Some(first as char)),
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+
+        let mut bytes = [first, 0, 0, 0];
+        for byte in &mut bytes[1..width] {
+            *byte = self.next_byte()?.unwrap_or(0);
+        }
+        match str::from_utf8(&bytes[..width]) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderChars<R> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.next_char() {
+            Ok(Some(c)) => {
+                let offset = self.offset;
+                self.offset += c.len_utf8();
+                Some((offset, c))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+}
+
+struct Parser<I: Iterator<Item = (usize, char)>> {
+    chars: Peekable<I>,
+    /// The offset just past the last character consumed
+    end: usize,
+}
+
+impl<I: Iterator<Item = (usize, char)>> Parser<I> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let next = self.chars.next();
+        if let Some((i, c)) = next {
+            self.end = i + c.len_utf8();
+        }
+        next
+    }
+
+    fn position(&mut self) -> usize {
+        match self.chars.peek() {
+            Some(&(i, _)) => i,
+            None => self.end,
+        }
+    }
+
+    fn error(&mut self, message: &'static str) -> ParseError {
+        ParseError::new(self.position(), message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char, message: &'static str) -> Result<(), ParseError> {
+        if self.peek() == Some(expected) {
+            self.next();
+            Ok(())
+        }
+        else {
+            Err(self.error(message))
+        }
+    }
+
+    fn token(&mut self, word: Word) -> Result<Token, ParseError> {
+        let mut s = String::new();
+
+        if self.peek() == Some('"') {
+            self.next();
+            loop {
+                match self.next() {
+                    Some((_, '"')) => return Ok(Token::Quoted(s)),
+                    Some((_, '\\')) => match self.next() {
+                        Some((_, c)) => s.push(c),
+                        None => return Err(self.error("unexpected end of string")),
+                    },
+                    Some((_, c)) => s.push(c),
+                    None => return Err(self.error("unexpected end of string")),
+                }
+            }
+        }
+
+        while let Some(c) = self.peek() {
+            let end = c.is_whitespace() || match word {
+                Word::Key => c == '=',
+                Word::Value => c == ',',
+            };
+            if end {
+                break;
+            }
+
+            self.next();
+            if c == '\\' {
+                match self.next() {
+                    Some((_, c)) => s.push(c),
+                    None => return Err(self.error("unexpected end of string")),
+                }
+            }
+            else {
+                s.push(c);
+            }
+        }
+
+        if s.is_empty() {
+            let message = match word {
+                Word::Key => "expected a key",
+                Word::Value => "expected a value",
+            };
+            return Err(self.error(message));
+        }
+        Ok(Token::Unquoted(s))
+    }
+}
@@ -0,0 +1,137 @@
+//! Consuming transforms of a whole Hstore.
+//!
+//! These take the Hstore by value and move its keys and values into the result, so nothing is
+//! cloned:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("name".into(), "widget".into());
+//! store.insert("_etag".into(), "abc123".into());
+//!
+//! let (internal, public) = store.partition(|key, _| key.starts_with('_'));
+//! assert_eq!(public, [("name", "widget")]);
+//! assert_eq!(internal, [("_etag", "abc123")]);
+//! ```
+//!
+//! When [`map_keys`](../struct.Hstore.html#method.map_keys) maps two keys to the same key, a
+//! [`Collision`](enum.Collision.html) policy decides what happens:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::transform::Collision;
+//!
+//! let mut store = Hstore::new();
+//! store.insert("Color".into(), "red".into());
+//! store.insert("color".into(), "blue".into());
+//!
+//! let lower = store.clone().map_keys(|key| key.to_lowercase(), Collision::KeepFirst).unwrap();
+//! assert_eq!(lower, [("color", "red")]);
+//!
+//! let err = store.map_keys(|key| key.to_lowercase(), Collision::Error).unwrap_err();
+//! assert_eq!(err.key, "color");
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use super::Hstore;
+
+/// What [`map_keys`](../struct.Hstore.html#method.map_keys) does when several keys map to the
+/// same key.
+///
+/// Keys are mapped in sorted order of the original keys, so "first" and "last" refer to that
+/// order and the result doesn't depend on the order of the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    /// Fail with a [`KeyCollision`](struct.KeyCollision.html).
+    Error,
+    /// Keep the value of the original key which sorts first.
+    KeepFirst,
+    /// Keep the value of the original key which sorts last.
+    KeepLast,
+}
+
+/// Several keys were mapped to the same key, under [`Collision::Error`](enum.Collision.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCollision {
+    /// The key they were mapped to
+    pub key: String,
+}
+
+impl fmt::Display for KeyCollision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "several keys map to {:?}", self.key)
+    }
+}
+
+impl StdError for KeyCollision {
+    fn description(&self) -> &str {
+        "several keys map to the same key"
+    }
+}
+
+impl Hstore {
+    /// Split the entries in one pass, into those for which `predicate` returns `true` and those
+    /// for which it returns `false`.
+    pub fn partition<F>(self, mut predicate: F) -> (Hstore, Hstore)
+        where F: FnMut(&str, &str) -> bool
+    {
+        let mut matching = Hstore::new();
+        let mut rest = Hstore::new();
+        for (key, value) in self {
+            if predicate(&key, &value) {
+                matching.0.insert(key, value);
+            }
+            else {
+                rest.0.insert(key, value);
+            }
+        }
+        (matching, rest)
+    }
+
+    /// Replace every key with `f(key)`, resolving keys which map to the same key with
+    /// `on_collision`.
+    pub fn map_keys<F>(self, mut f: F, on_collision: Collision) -> Result<Hstore, KeyCollision>
+        where F: FnMut(String) -> String
+    {
+        let mut entries: Vec<_> = self.into_iter().collect();
+        entries.sort();
+
+        let mut mapped = Hstore::new();
+        for (key, value) in entries {
+            let key = f(key);
+            if mapped.0.contains_key(&key) {
+                match on_collision {
+                    Collision::Error => return Err(KeyCollision { key: key }),
+                    Collision::KeepFirst => continue,
+                    Collision::KeepLast => {}
+                }
+            }
+            mapped.0.insert(key, value);
+        }
+        Ok(mapped)
+    }
+
+    /// Replace every value with `f(key, value)`.
+    ///
+    /// ```rust
+    /// # use diesel_pg_hstore::Hstore;
+    /// let mut store = Hstore::new();
+    /// store.insert("greeting".into(), "hello {name}".into());
+    ///
+    /// let store = store.map_values(|_, value| value.replace("{name}", "world"));
+    /// assert_eq!(store["greeting"], "hello world");
+    /// ```
+    pub fn map_values<F>(self, mut f: F) -> Hstore
+        where F: FnMut(&str, String) -> String
+    {
+        let mut mapped = Hstore::new();
+        for (key, value) in self {
+            let value = f(&key, value);
+            mapped.0.insert(key, value);
+        }
+        mapped
+    }
+}
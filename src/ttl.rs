@@ -0,0 +1,199 @@
+//! Keys which expire.
+//!
+//! A key `k` expires when the hstore also holds `k.__ttl`, whose value is the time it expires,
+//! in whole seconds since the Unix epoch. Both are ordinary entries, so they are stored and
+//! loaded like any other, and expiry is only honored by the accessors here:
+//!
+//! ```rust
+//! use std::time::{Duration, UNIX_EPOCH};
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let mut store = Hstore::new();
+//! let noon = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+//! store.insert_expiring("session".into(), "abc".into(), noon);
+//! assert_eq!(store["session.__ttl"], "1500000000");
+//!
+//! let before = noon - Duration::from_secs(1);
+//! assert_eq!(store.get_unexpired("session", before).unwrap(), "abc");
+//! assert_eq!(store.get_unexpired("session", noon), None);
+//!
+//! assert_eq!(store.remove_expired(noon), 1);
+//! assert!(store.is_empty());
+//! ```
+//!
+//! A key expires at the second given, and a `__ttl` entry which isn't an integer never expires.
+//! To find or purge expired keys across a whole table, without loading it, use
+//! [`ExpiredKeys`](struct.ExpiredKeys.html).
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::types::Text;
+
+use super::Hstore;
+use super::quote::{quote_ident, quote_literal, quote_name};
+
+/// The suffix of the key holding another key's expiry time
+pub const TTL_SUFFIX: &str = ".__ttl";
+
+/// The key holding the expiry time of `key`
+pub fn ttl_key(key: &str) -> String {
+    format!("{}{}", key, TTL_SUFFIX)
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64) - if e.duration().subsec_nanos() > 0 { 1 } else { 0 },
+    }
+}
+
+fn from_unix_secs(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    }
+    else {
+        UNIX_EPOCH - Duration::from_secs(secs.wrapping_neg() as u64)
+    }
+}
+
+impl Hstore {
+    /// Insert `key`, expiring at `expires_at`.
+    pub fn insert_expiring(&mut self, key: String, value: String, expires_at: SystemTime) -> Option<String> {
+        self.0.insert(ttl_key(&key), unix_secs(expires_at).to_string());
+        self.0.insert(key, value)
+    }
+
+    /// When `key` expires, if it does
+    pub fn expires_at(&self, key: &str) -> Option<SystemTime> {
+        self.0.get(&ttl_key(key))
+            .and_then(|secs| secs.parse().ok())
+            .map(from_unix_secs)
+    }
+
+    /// Whether `key` has expired as of `now`
+    pub fn is_expired(&self, key: &str, now: SystemTime) -> bool {
+        self.expires_at(key).map_or(false, |expires_at| expires_at <= now)
+    }
+
+    /// The value of `key`, unless it has expired as of `now`
+    pub fn get_unexpired(&self, key: &str, now: SystemTime) -> Option<&String> {
+        if self.is_expired(key, now) {
+            None
+        }
+        else {
+            self.get(key)
+        }
+    }
+
+    /// Remove every key which has expired as of `now`, along with its expiry time. Returns the
+    /// number of keys expired.
+    pub fn remove_expired(&mut self, now: SystemTime) -> usize {
+        let expired: Vec<String> = self.0.keys()
+            .filter(|key| key.ends_with(TTL_SUFFIX))
+            .map(|key| key[..key.len() - TTL_SUFFIX.len()].to_string())
+            .filter(|key| self.is_expired(key, now))
+            .collect();
+
+        for key in &expired {
+            self.0.remove(&ttl_key(key));
+            self.0.remove(key);
+        }
+        expired.len()
+    }
+}
+
+/// Finds and purges expired keys across a table, in the database.
+///
+/// ```rust
+/// use diesel_pg_hstore::ttl::ExpiredKeys;
+///
+/// let expired = ExpiredKeys::new("sessions", "data", "id");
+/// // let found: Vec<(String, String)> = expired.find(&db)?;
+/// // let purged_rows = expired.purge(&db)?;
+/// ```
+///
+/// Keys are compared with the database's clock, `now()`, unless [`as_of`](#method.as_of) is
+/// given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiredKeys {
+    table: String,
+    store: String,
+    id: String,
+    as_of: Option<i64>,
+}
+
+impl ExpiredKeys {
+    /// Look in the hstore column `store` of `table`, identifying rows by the column `id`.
+    pub fn new<T, S, I>(table: T, store: S, id: I) -> ExpiredKeys
+        where T: Into<String>,
+              S: Into<String>,
+              I: Into<String>
+    {
+        ExpiredKeys {
+            table: table.into(),
+            store: store.into(),
+            id: id.into(),
+            as_of: None,
+        }
+    }
+
+    /// Treat keys expiring at or before `now` as expired.
+    pub fn as_of(mut self, now: SystemTime) -> ExpiredKeys {
+        self.as_of = Some(unix_secs(now));
+        self
+    }
+
+    /// The condition on `each` rows `e` that they are expired expiry entries
+    fn expired_condition(&self) -> String {
+        let now = match self.as_of {
+            Some(secs) => secs.to_string(),
+            None => "extract(epoch FROM now())".into(),
+        };
+        format!(
+            "right(e.key, {len}) = {suffix} AND e.value ~ '^-?[0-9]+$' AND e.value::numeric <= {now}",
+            len = TTL_SUFFIX.len(),
+            suffix = quote_literal(TTL_SUFFIX),
+            now = now,
+        )
+    }
+
+    /// The query finding expired keys, as the row id as text and the key
+    pub fn find_sql(&self) -> String {
+        format!(
+            "SELECT t.{id}::text, left(e.key, -{len}) FROM {table} AS t, each(t.{store}) AS e \
+             WHERE {expired} ORDER BY 1, 2",
+            id = quote_ident(&self.id),
+            table = quote_name(&self.table),
+            store = quote_ident(&self.store),
+            len = TTL_SUFFIX.len(),
+            expired = self.expired_condition(),
+        )
+    }
+
+    /// The statement removing expired keys and their expiry times from every row
+    pub fn purge_sql(&self) -> String {
+        let store = quote_ident(&self.store);
+        format!(
+            "UPDATE {table} SET {store} = {store} - ARRAY(\
+             SELECT unnest(ARRAY[e.key, left(e.key, -{len})]) FROM each({store}) AS e WHERE {expired}\
+             ) WHERE EXISTS (SELECT 1 FROM each({store}) AS e WHERE {expired})",
+            table = quote_name(&self.table),
+            store = store,
+            len = TTL_SUFFIX.len(),
+            expired = self.expired_condition(),
+        )
+    }
+
+    /// Find every expired key, as the row id as text and the key, ordered by both.
+    pub fn find(&self, conn: &PgConnection) -> QueryResult<Vec<(String, String)>> {
+        sql::<(Text, Text)>(&self.find_sql()).load(conn)
+    }
+
+    /// Remove every expired key in a single statement, returning the number of rows changed.
+    pub fn purge(&self, conn: &PgConnection) -> QueryResult<usize> {
+        conn.execute(&self.purge_sql())
+    }
+}
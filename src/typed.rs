@@ -0,0 +1,213 @@
+//! Hstores whose values are all of one type.
+
+use std::error::Error as StdError;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use super::Hstore;
+
+/// An hstore column whose values are all parsed as `T` when loaded, and formatted from `T`
+/// when saved.
+///
+/// `HstoreOf` can be used anywhere `Hstore` can, for example `HstoreOf<i64>` for a column of
+/// counters. Values are parsed with `FromStr` and written with `Display`. A value which fails
+/// to parse fails the whole load with a [`ValueError`](struct.ValueError.html) naming the key.
+///
+/// ```rust
+/// # #[macro_use] extern crate diesel;
+/// # extern crate diesel_pg_hstore;
+/// use diesel_pg_hstore::HstoreOf;
+///
+/// table! {
+///     use diesel::types::*;
+///     use diesel_pg_hstore::Hstore;
+///
+///     page_stats {
+///         id -> Integer,
+///         views -> Hstore,
+///     }
+/// }
+///
+/// #[derive(Queryable)]
+/// struct PageStats {
+///     id: i32,
+///     views: HstoreOf<i64>,
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HstoreOf<T>(HashMap<String, T>);
+
+impl<T> HstoreOf<T> {
+    /// Create a new, empty HstoreOf
+    pub fn new() -> HstoreOf<T> {
+        HstoreOf(HashMap::new())
+    }
+
+    /// Create a new HstoreOf from an existing hashmap
+    pub fn from_hashmap(hm: HashMap<String, T>) -> HstoreOf<T> {
+        HstoreOf(hm)
+    }
+
+    /// Consume the HstoreOf, returning its backing HashMap
+    pub fn into_inner(self) -> HashMap<String, T> {
+        self.0
+    }
+
+    /// Parse every value of an Hstore as `T`
+    pub fn parse(hstore: Hstore) -> Result<HstoreOf<T>, ValueError>
+        where T: FromStr,
+              T::Err: fmt::Display
+    {
+        let mut map = HashMap::with_capacity(hstore.len());
+        for (key, value) in hstore {
+            let parsed = match value.parse::<T>() {
+                Ok(parsed) => parsed,
+                Err(e) => return Err(ValueError {
+                    key: key,
+                    value: value,
+                    message: e.to_string(),
+                }),
+            };
+            map.insert(key, parsed);
+        }
+        Ok(HstoreOf(map))
+    }
+
+    /// Format every value as a string, giving an Hstore
+    pub fn to_hstore(&self) -> Hstore
+        where T: fmt::Display
+    {
+        self.0.iter().map(|(k, v)| (k.clone(), v.to_string())).collect()
+    }
+}
+
+impl<T> Default for HstoreOf<T> {
+    fn default() -> HstoreOf<T> {
+        HstoreOf::new()
+    }
+}
+
+/// You can deref the HstoreOf into it's backing HashMap
+impl<T> Deref for HstoreOf<T> {
+    type Target = HashMap<String, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// You can mutably deref the HstoreOf into it's backing HashMap
+impl<T> DerefMut for HstoreOf<T> {
+    fn deref_mut(&mut self) -> &mut HashMap<String, T> {
+        &mut self.0
+    }
+}
+
+/// A value in an hstore could not be parsed as the expected type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueError {
+    key: String,
+    value: String,
+    message: String,
+}
+
+impl ValueError {
+    pub(crate) fn new(key: &str, value: &str, message: String) -> ValueError {
+        ValueError {
+            key: key.to_string(),
+            value: value.to_string(),
+            message: message,
+        }
+    }
+
+    /// The key whose value failed to parse
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The value which failed to parse
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid value {:?} for hstore key {:?}: {}", self.value, self.key, self.message)
+    }
+}
+
+impl StdError for ValueError {
+    fn description(&self) -> &str {
+        "invalid hstore value"
+    }
+}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::fmt;
+    use std::io::Write;
+    use std::str::FromStr;
+    use diesel::Queryable;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::row::Row;
+    use diesel::types::*;
+
+    use super::HstoreOf;
+    use super::super::Hstore;
+
+    impl<T> Queryable<Hstore, Pg> for HstoreOf<T>
+        where T: FromStr,
+              T::Err: fmt::Display
+    {
+        type Row = Self;
+
+        fn build(row: Self::Row) -> Self {
+            row
+        }
+    }
+
+    impl<'a, T> AsExpression<Hstore> for &'a HstoreOf<T>
+        where T: fmt::Display + fmt::Debug
+    {
+        type Expression = Bound<Hstore, &'a HstoreOf<T>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<T> FromSql<Hstore, Pg> for HstoreOf<T>
+        where T: FromStr,
+              T::Err: fmt::Display
+    {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            let hstore = Hstore::from_sql(bytes)?;
+            Ok(HstoreOf::parse(hstore)?)
+        }
+    }
+
+    impl<T> FromSqlRow<Hstore, Pg> for HstoreOf<T>
+        where T: FromStr,
+              T::Err: fmt::Display
+    {
+        fn build_from_row<R: Row<Pg>>(row: &mut R) -> Result<Self, Box<StdError + Send + Sync>> {
+            HstoreOf::from_sql(row.take())
+        }
+    }
+
+    impl<T> ToSql<Hstore, Pg> for HstoreOf<T>
+        where T: fmt::Display + fmt::Debug
+    {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(&self.to_hstore(), out)
+        }
+    }
+}
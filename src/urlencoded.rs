@@ -0,0 +1,204 @@
+//! Converting hstores to and from `application/x-www-form-urlencoded` strings.
+//!
+//! With the `serde_urlencoded` feature, form submissions and query strings can be stored as an
+//! hstore, and hstores written back out as query strings:
+//!
+//! ```rust
+//! use diesel_pg_hstore::Hstore;
+//!
+//! let form = Hstore::from_urlencoded("name=Ada+Lovelace&lang=en%2DGB").unwrap();
+//! assert_eq!(form["name"], "Ada Lovelace");
+//! assert_eq!(form["lang"], "en-GB");
+//!
+//! assert_eq!(form.to_urlencoded(), "lang=en-GB&name=Ada+Lovelace");
+//! ```
+//!
+//! Keys are written in sorted order. When a key is repeated in the input, the last value wins.
+//!
+//! Input from clients should be read with [`FormLimits`](struct.FormLimits.html), which bound its
+//! size and which keys it may set:
+//!
+//! ```rust
+//! use diesel_pg_hstore::urlencoded::{FormError, FormLimits};
+//!
+//! let limits = FormLimits::new().allow_keys(vec!["name", "lang"]).max_value_len(64);
+//! assert!(limits.parse("name=Ada&lang=en").is_ok());
+//! assert_eq!(limits.parse("name=Ada&admin=1"), Err(FormError::KeyNotAllowed { key: "admin".into() }));
+//! ```
+
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fmt;
+
+use serde_urlencoded;
+use serde_urlencoded::de::Error;
+
+use super::Hstore;
+
+impl Hstore {
+    /// Write as a urlencoded string, with the keys in sorted order.
+    pub fn to_urlencoded(&self) -> String {
+        let mut pairs: Vec<(&str, &str)> = self.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        pairs.sort();
+        serde_urlencoded::to_string(pairs).expect("string pairs always serialize")
+    }
+
+    /// Read a urlencoded string, such as a form submission or a query string without its `?`.
+    pub fn from_urlencoded(s: &str) -> Result<Hstore, Error> {
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_str(s)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+/// An error reading a form into an hstore with [`FormLimits`](struct.FormLimits.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormError {
+    /// The form is longer than the limit.
+    TooLarge,
+    /// The form isn't urlencoded.
+    Invalid(String),
+    /// The form has more entries than the limit.
+    TooManyKeys,
+    /// A key isn't in the allow-list.
+    KeyNotAllowed {
+        /// The key
+        key: String,
+    },
+    /// A key is longer than the limit.
+    KeyTooLong {
+        /// The key
+        key: String,
+    },
+    /// A value is longer than the limit.
+    ValueTooLong {
+        /// The key of the value
+        key: String,
+    },
+}
+
+impl fmt::Display for FormError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FormError::Invalid(ref message) => write!(f, "invalid form: {}", message),
+            FormError::KeyNotAllowed { ref key } => write!(f, "key {:?} is not allowed", key),
+            FormError::KeyTooLong { ref key } => write!(f, "key {:?} is too long", key),
+            FormError::ValueTooLong { ref key } => write!(f, "value of {:?} is too long", key),
+            _ => f.write_str(self.description()),
+        }
+    }
+}
+
+impl StdError for FormError {
+    fn description(&self) -> &str {
+        match *self {
+            FormError::TooLarge => "form is too large",
+            FormError::Invalid(_) => "invalid form",
+            FormError::TooManyKeys => "form has too many keys",
+            FormError::KeyNotAllowed { .. } => "key is not allowed",
+            FormError::KeyTooLong { .. } => "key is too long",
+            FormError::ValueTooLong { .. } => "value is too long",
+        }
+    }
+}
+
+/// Bounds on forms read into hstores.
+///
+/// By default a form may be 16 KiB long and have 64 entries, of keys up to 128 bytes and
+/// values up to 4 KiB, and any key is allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormLimits {
+    allowed: Option<HashSet<String>>,
+    max_bytes: usize,
+    max_keys: usize,
+    max_key_len: usize,
+    max_value_len: usize,
+}
+
+impl FormLimits {
+    /// The default limits
+    pub fn new() -> FormLimits {
+        FormLimits {
+            allowed: None,
+            max_bytes: 16 * 1024,
+            max_keys: 64,
+            max_key_len: 128,
+            max_value_len: 4 * 1024,
+        }
+    }
+
+    /// Allow `key`. Once any key is allowed, every other key is rejected.
+    pub fn allow_key<K: Into<String>>(mut self, key: K) -> FormLimits {
+        self.allowed.get_or_insert_with(HashSet::new).insert(key.into());
+        self
+    }
+
+    /// Allow every key of `keys`. Once any key is allowed, every other key is rejected.
+    pub fn allow_keys<I>(mut self, keys: I) -> FormLimits
+        where I: IntoIterator,
+              I::Item: Into<String>
+    {
+        self.allowed.get_or_insert_with(HashSet::new).extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    /// Reject forms longer than `max_bytes`, as encoded.
+    pub fn max_bytes(mut self, max_bytes: usize) -> FormLimits {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Reject forms with more than `max_keys` entries, counting repeated keys each time.
+    pub fn max_keys(mut self, max_keys: usize) -> FormLimits {
+        self.max_keys = max_keys;
+        self
+    }
+
+    /// Reject keys longer than `max_key_len` bytes, as decoded.
+    pub fn max_key_len(mut self, max_key_len: usize) -> FormLimits {
+        self.max_key_len = max_key_len;
+        self
+    }
+
+    /// Reject values longer than `max_value_len` bytes, as decoded.
+    pub fn max_value_len(mut self, max_value_len: usize) -> FormLimits {
+        self.max_value_len = max_value_len;
+        self
+    }
+
+    /// The longest form allowed, as encoded
+    pub fn byte_limit(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Read a urlencoded form within the limits. When a key is repeated, the last value wins.
+    pub fn parse(&self, s: &str) -> Result<Hstore, FormError> {
+        if s.len() > self.max_bytes {
+            return Err(FormError::TooLarge);
+        }
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_str(s).map_err(|e| FormError::Invalid(e.to_string()))?;
+        if pairs.len() > self.max_keys {
+            return Err(FormError::TooManyKeys);
+        }
+
+        let mut hstore = Hstore::new();
+        for (key, value) in pairs {
+            if self.allowed.as_ref().map_or(false, |allowed| !allowed.contains(&key)) {
+                return Err(FormError::KeyNotAllowed { key: key });
+            }
+            if key.len() > self.max_key_len {
+                return Err(FormError::KeyTooLong { key: key });
+            }
+            if value.len() > self.max_value_len {
+                return Err(FormError::ValueTooLong { key: key });
+            }
+            hstore.insert(key, value);
+        }
+        Ok(hstore)
+    }
+}
+
+impl Default for FormLimits {
+    fn default() -> FormLimits {
+        FormLimits::new()
+    }
+}
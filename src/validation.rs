@@ -0,0 +1,76 @@
+//! Validating hstore fields with the `validator` crate.
+//!
+//! With the `validator` feature, Hstore fields of structs deriving `Validate` can be checked
+//! like any other field before they are inserted. The size of the map works with `length`,
+//! and [`HstoreSchema`](../schema/struct.HstoreSchema.html) rules with `custom`:
+//!
+//! ```rust,ignore
+//! #[macro_use] extern crate lazy_static;
+//! extern crate validator;
+//! extern crate diesel_pg_hstore;
+//!
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::schema::{HstoreSchema, Schema};
+//! use diesel_pg_hstore::validation::validate_schema;
+//! use validator::Validate;
+//!
+//! struct Labels;
+//!
+//! impl Schema for Labels {
+//!     fn schema() -> &'static HstoreSchema {
+//!         lazy_static! {
+//!             static ref SCHEMA: HstoreSchema = HstoreSchema::new()
+//!                 .required("team")
+//!                 .parses::<u32>("priority");
+//!         }
+//!         &SCHEMA
+//!     }
+//! }
+//!
+//! #[derive(Validate)]
+//! struct NewService {
+//!     #[validate(length(min = 1, max = 20), custom(function = validate_schema::<Labels>))]
+//!     labels: Hstore,
+//! }
+//! ```
+//!
+//! A failed schema check is reported as a single `ValidationError` with the code `hstore_schema`.
+//! Its message lists every violation, and its `violations` parameter holds them one per string.
+
+use std::borrow::Cow;
+
+use validator::{ValidateLength, ValidationError};
+
+use super::Hstore;
+use super::schema::{HstoreSchema, Schema, Violation};
+
+impl ValidateLength<u64> for Hstore {
+    fn length(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+}
+
+/// Check an hstore against the schema `S`, for use with `#[validate(custom(...))]`.
+pub fn validate_schema<S: Schema>(hstore: &Hstore) -> Result<(), ValidationError> {
+    S::schema().check(hstore)
+}
+
+impl HstoreSchema {
+    /// Check an hstore against the schema, reporting a failure as a `ValidationError`.
+    ///
+    /// This is for custom validation functions checking against a schema which isn't a
+    /// [`Schema`](../schema/trait.Schema.html) type.
+    pub fn check(&self, hstore: &Hstore) -> Result<(), ValidationError> {
+        self.validate(hstore).map_err(|violations| validation_error(&violations))
+    }
+}
+
+/// The `ValidationError` reporting `violations`
+pub fn validation_error(violations: &[Violation]) -> ValidationError {
+    let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+
+    let mut error = ValidationError::new("hstore_schema");
+    error.message = Some(Cow::Owned(violations.join("; ")));
+    error.add_param(Cow::Borrowed("violations"), &violations);
+    error
+}
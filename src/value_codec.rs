@@ -0,0 +1,288 @@
+//! Transforming values as they are written to and read from the database.
+//!
+//! A [`ValueCodec`](trait.ValueCodec.html) sees each value as it is serialized or deserialized,
+//! along with its key, and may replace it. This is how selected values are encrypted or
+//! compressed transparently; see the `encryption` and `compression` features for ready made
+//! codecs.
+//!
+//! A codec only applies to the columns you ask for it on. Bind and load such a column as an
+//! [`HstoreWith<C>`](struct.HstoreWith.html) instead of an `Hstore`, where `C` is a type
+//! implementing [`ColumnCodec`](trait.ColumnCodec.html) to name the codec. Plain `Hstore`s are
+//! never transformed.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate lazy_static;
+//! extern crate diesel_pg_hstore;
+//!
+//! use std::borrow::Cow;
+//! use std::error::Error;
+//! use diesel_pg_hstore::Hstore;
+//! use diesel_pg_hstore::value_codec::{ColumnCodec, HstoreWith, ValueCodec};
+//!
+//! /// Stores the "shout" key in upper case, and reads it back in lower case.
+//! struct Shout;
+//!
+//! impl ValueCodec for Shout {
+//!     fn encode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<Error + Send + Sync>> {
+//!         if key == "shout" { Ok(value.to_uppercase().into()) } else { Ok(value.into()) }
+//!     }
+//!
+//!     fn decode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<Error + Send + Sync>> {
+//!         if key == "shout" { Ok(value.to_lowercase().into()) } else { Ok(value.into()) }
+//!     }
+//! }
+//!
+//! lazy_static! {
+//!     static ref SHOUT: Shout = Shout;
+//! }
+//!
+//! /// The codec of the `greetings` column.
+//! struct Greetings;
+//!
+//! impl ColumnCodec for Greetings {
+//!     fn value_codec() -> &'static ValueCodec {
+//!         &*SHOUT
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut store = Hstore::new();
+//! store.insert("shout".into(), "hello".into());
+//!
+//! // Bound in place of `store`, the value is sent as "HELLO".
+//! let greetings: HstoreWith<Greetings> = store.into();
+//! assert_eq!(greetings["shout"], "hello");
+//! # }
+//! ```
+//!
+//! Only the binary format used by `ToSql` and `FromSql` is affected. Values are passed through
+//! untouched by the [`text`](../text/index.html) module, and by SQL expressions evaluated in the
+//! database. The text format, operators and functions all see the encoded values.
+
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use super::Hstore;
+
+/// Transforms values on their way to and from the database.
+pub trait ValueCodec: Send + Sync {
+    /// Transform a value before it is serialized.
+    fn encode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>>;
+
+    /// Reverse `encode` on a value which has just been deserialized.
+    ///
+    /// Values may have been written before the codec was used on the column, or by other
+    /// clients, so implementations should pass through values they don't recognize.
+    fn decode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>>;
+}
+
+/// Applies several codecs in turn.
+///
+/// Values are encoded by each codec in order, and decoded in the reverse order. To compress
+/// values before encrypting them, list the compression codec first.
+pub struct Chain(pub Vec<Arc<ValueCodec>>);
+
+impl ValueCodec for Chain {
+    fn encode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>> {
+        let mut value = Cow::Borrowed(value);
+        for codec in &self.0 {
+            value = Cow::Owned(codec.encode(key, &value)?.into_owned());
+        }
+        Ok(value)
+    }
+
+    fn decode<'a>(&self, key: &str, value: &'a str) -> Result<Cow<'a, str>, Box<StdError + Send + Sync>> {
+        let mut value = Cow::Borrowed(value);
+        for codec in self.0.iter().rev() {
+            value = Cow::Owned(codec.decode(key, &value)?.into_owned());
+        }
+        Ok(value)
+    }
+}
+
+/// Names the value codec of an [`HstoreWith`](struct.HstoreWith.html).
+///
+/// Implemented by a type used only as `HstoreWith`'s parameter, usually returning a codec built
+/// once in a `lazy_static`.
+pub trait ColumnCodec: 'static {
+    /// The codec applied to the values of an `HstoreWith<Self>`
+    fn value_codec() -> &'static ValueCodec;
+}
+
+/// An Hstore whose values pass through the codec named by `C` when it is bound or loaded.
+///
+/// It can be used wherever an `Hstore` can, reading and binding columns of SQL type `Hstore`.
+/// In memory, it derefs to the plain `Hstore`.
+pub struct HstoreWith<C> {
+    hstore: Hstore,
+    codec: PhantomData<fn() -> C>,
+}
+
+impl<C> HstoreWith<C> {
+    /// Apply `C`'s codec to `hstore` when it is bound
+    pub fn new(hstore: Hstore) -> HstoreWith<C> {
+        HstoreWith {
+            hstore: hstore,
+            codec: PhantomData,
+        }
+    }
+
+    /// The plain Hstore
+    pub fn into_inner(self) -> Hstore {
+        self.hstore
+    }
+}
+
+impl<C> Deref for HstoreWith<C> {
+    type Target = Hstore;
+
+    fn deref(&self) -> &Hstore {
+        &self.hstore
+    }
+}
+
+impl<C> DerefMut for HstoreWith<C> {
+    fn deref_mut(&mut self) -> &mut Hstore {
+        &mut self.hstore
+    }
+}
+
+impl<C> From<Hstore> for HstoreWith<C> {
+    fn from(hstore: Hstore) -> HstoreWith<C> {
+        HstoreWith::new(hstore)
+    }
+}
+
+impl<C> From<HstoreWith<C>> for Hstore {
+    fn from(store: HstoreWith<C>) -> Hstore {
+        store.hstore
+    }
+}
+
+// Implemented by hand, as deriving would require the same of `C`.
+
+impl<C> fmt::Debug for HstoreWith<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("HstoreWith").field(&self.hstore).finish()
+    }
+}
+
+impl<C> Clone for HstoreWith<C> {
+    fn clone(&self) -> HstoreWith<C> {
+        HstoreWith::new(self.hstore.clone())
+    }
+}
+
+impl<C> Default for HstoreWith<C> {
+    fn default() -> HstoreWith<C> {
+        HstoreWith::new(Hstore::new())
+    }
+}
+
+impl<C> PartialEq for HstoreWith<C> {
+    fn eq(&self, other: &HstoreWith<C>) -> bool {
+        self.hstore == other.hstore
+    }
+}
+
+impl<C> Eq for HstoreWith<C> {}
+
+mod impls {
+    use std::error::Error as StdError;
+    use std::io::Write;
+    use diesel::types::impls::option::UnexpectedNullError;
+    use diesel::Queryable;
+    use diesel::expression::AsExpression;
+    use diesel::expression::bound::Bound;
+    use diesel::pg::Pg;
+    use diesel::row::Row;
+    use diesel::types::*;
+
+    use super::{ColumnCodec, HstoreWith};
+    use super::super::Hstore;
+    use super::super::codec;
+
+    impl<C: ColumnCodec> Queryable<Hstore, Pg> for HstoreWith<C> {
+        type Row = Self;
+
+        fn build(row: Self::Row) -> Self {
+            row
+        }
+    }
+
+    impl<C: ColumnCodec> AsExpression<Hstore> for HstoreWith<C> {
+        type Expression = Bound<Hstore, HstoreWith<C>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a, C: ColumnCodec> AsExpression<Hstore> for &'a HstoreWith<C> {
+        type Expression = Bound<Hstore, &'a HstoreWith<C>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<C: ColumnCodec> AsExpression<Nullable<Hstore>> for HstoreWith<C> {
+        type Expression = Bound<Nullable<Hstore>, HstoreWith<C>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<'a, C: ColumnCodec> AsExpression<Nullable<Hstore>> for &'a HstoreWith<C> {
+        type Expression = Bound<Nullable<Hstore>, &'a HstoreWith<C>>;
+
+        fn as_expression(self) -> Self::Expression {
+            Bound::new(self)
+        }
+    }
+
+    impl<C: ColumnCodec> FromSql<Hstore, Pg> for HstoreWith<C> {
+        fn from_sql(bytes: Option<&[u8]>) -> Result<Self, Box<StdError + Send + Sync>> {
+            match bytes {
+                Some(bytes) => codec::decode_with(bytes, C::value_codec()).map(HstoreWith::new),
+                None => Err(Box::new(UnexpectedNullError {
+                    msg: "Unexpected null for non-null column".to_string(),
+                })),
+            }
+        }
+    }
+
+    impl<C: ColumnCodec> FromSqlRow<Hstore, Pg> for HstoreWith<C> {
+        fn build_from_row<T: Row<Pg>>(row: &mut T) -> Result<Self, Box<StdError + Send + Sync>> {
+            HstoreWith::from_sql(row.take())
+        }
+    }
+
+    impl<C: ColumnCodec> ToSql<Hstore, Pg> for HstoreWith<C> {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            let mut buf: Vec<u8> = Vec::new();
+            codec::encode_with(&self.hstore, C::value_codec(), &mut buf)?;
+
+            out.write_all(&buf)?;
+            #[cfg(feature = "zeroize")]
+            ::zeroize::Zeroize::zeroize(&mut buf);
+            Ok(IsNull::No)
+        }
+    }
+
+    impl<C: ColumnCodec> ToSql<Nullable<Hstore>, Pg> for HstoreWith<C> {
+        fn to_sql<W>(&self, out: &mut ToSqlOutput<W, Pg>) -> Result<IsNull, Box<StdError + Send + Sync>>
+            where W: Write
+        {
+            ToSql::<Hstore, Pg>::to_sql(self, out)
+        }
+    }
+}
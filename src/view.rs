@@ -0,0 +1,144 @@
+//! Generating views which expose hstore keys as ordinary columns.
+//!
+//! Tools which expect flat tables can't see inside an hstore. A
+//! [`KeyColumnView`](struct.KeyColumnView.html) describes a view with one typed column per
+//! chosen key, and generates the DDL to create, refresh and drop it:
+//!
+//! ```rust
+//! use diesel_pg_hstore::view::KeyColumnView;
+//!
+//! let view = KeyColumnView::new("product_attributes", "products", "attributes")
+//!     .materialized(true)
+//!     .column("id")
+//!     .key("color", "text")
+//!     .key_as("weight_kg", "weight", "numeric");
+//!
+//! assert_eq!(view.create_sql(), "CREATE MATERIALIZED VIEW \"product_attributes\" AS SELECT \
+//!     \"id\", \
+//!     (\"attributes\" -> 'color')::text AS \"color\", \
+//!     (\"attributes\" -> 'weight_kg')::numeric AS \"weight\" \
+//!     FROM \"products\"");
+//! assert_eq!(view.refresh_sql().unwrap(), "REFRESH MATERIALIZED VIEW \"product_attributes\"");
+//! ```
+//!
+//! Names may be schema qualified, as in `reporting.products`. The SQL types are used as given.
+//! Keys missing from a row are `NULL`, and a value which can't be cast fails the query reading
+//! it, or the refresh of a materialized view.
+
+use super::quote::{quote_ident, quote_literal, quote_name};
+
+/// A view exposing hstore keys as typed columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyColumnView {
+    name: String,
+    table: String,
+    store: String,
+    materialized: bool,
+    columns: Vec<String>,
+    keys: Vec<KeyColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyColumn {
+    key: String,
+    column: String,
+    sql_type: String,
+}
+
+impl KeyColumnView {
+    /// Describe a view called `name`, over the hstore column `store` of `table`.
+    pub fn new<N, T, S>(name: N, table: T, store: S) -> KeyColumnView
+        where N: Into<String>,
+              T: Into<String>,
+              S: Into<String>
+    {
+        KeyColumnView {
+            name: name.into(),
+            table: table.into(),
+            store: store.into(),
+            materialized: false,
+            columns: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Create a materialized view rather than a plain one.
+    pub fn materialized(mut self, materialized: bool) -> KeyColumnView {
+        self.materialized = materialized;
+        self
+    }
+
+    /// Include a column of the table as-is, such as its primary key.
+    pub fn column<C: Into<String>>(mut self, column: C) -> KeyColumnView {
+        self.columns.push(column.into());
+        self
+    }
+
+    /// Expose `key` as a column of the same name, cast to `sql_type`.
+    pub fn key<K, T>(self, key: K, sql_type: T) -> KeyColumnView
+        where K: Into<String>,
+              T: Into<String>
+    {
+        let key = key.into();
+        let column = key.clone();
+        self.key_as(key, column, sql_type)
+    }
+
+    /// Expose `key` as the column `column`, cast to `sql_type`.
+    pub fn key_as<K, C, T>(mut self, key: K, column: C, sql_type: T) -> KeyColumnView
+        where K: Into<String>,
+              C: Into<String>,
+              T: Into<String>
+    {
+        self.keys.push(KeyColumn {
+            key: key.into(),
+            column: column.into(),
+            sql_type: sql_type.into(),
+        });
+        self
+    }
+
+    /// The statement creating the view
+    pub fn create_sql(&self) -> String {
+        let mut select = Vec::with_capacity(self.columns.len() + self.keys.len());
+        for column in &self.columns {
+            select.push(quote_ident(column));
+        }
+        for key in &self.keys {
+            select.push(format!(
+                "({} -> {})::{} AS {}",
+                quote_ident(&self.store),
+                quote_literal(&key.key),
+                key.sql_type,
+                quote_ident(&key.column),
+            ));
+        }
+
+        format!(
+            "CREATE {}VIEW {} AS SELECT {} FROM {}",
+            if self.materialized { "MATERIALIZED " } else { "" },
+            quote_name(&self.name),
+            select.join(", "),
+            quote_name(&self.table),
+        )
+    }
+
+    /// The statement refreshing the view, if it is materialized
+    pub fn refresh_sql(&self) -> Option<String> {
+        if self.materialized {
+            Some(format!("REFRESH MATERIALIZED VIEW {}", quote_name(&self.name)))
+        }
+        else {
+            None
+        }
+    }
+
+    /// The statement dropping the view, if it exists
+    pub fn drop_sql(&self) -> String {
+        format!(
+            "DROP {}VIEW IF EXISTS {}",
+            if self.materialized { "MATERIALIZED " } else { "" },
+            quote_name(&self.name),
+        )
+    }
+}
@@ -0,0 +1,185 @@
+//! Comparing each row's hstore with the previous row's, using window functions.
+//!
+//! Snapshot tables record the whole hstore at each point in time. To reconstruct what changed
+//! between snapshots, describe the window rows are compared in, and select
+//! [`changes_since_previous`](../dsl/trait.HstoreOpExtensions.html#method.changes_since_previous)
+//! alongside the columns identifying each row:
+//!
+//! ```rust
+//! # #[macro_use] extern crate diesel;
+//! # extern crate diesel_pg_hstore;
+//! use diesel::prelude::*;
+//! use diesel_pg_hstore::dsl::HstoreOpExtensions;
+//! use diesel_pg_hstore::window;
+//!
+//! table! {
+//!     use diesel::types::*;
+//!     use diesel_pg_hstore::Hstore;
+//!
+//!     snapshots {
+//!         id -> Integer,
+//!         entity_id -> Integer,
+//!         taken_at -> Timestamp,
+//!         meta -> Hstore,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let in_order = window::order_by(snapshots::taken_at).partition_by(snapshots::entity_id);
+//! let query = snapshots::table.select((
+//!     snapshots::id,
+//!     snapshots::meta.changes_since_previous(in_order),
+//! ));
+//! # }
+//! ```
+//!
+//! Each row then has two hstores, loadable as `(i32, (Hstore, Hstore))`. The first holds the
+//! entries which were added or changed, with their new values, and the second those which were
+//! removed or changed, with their old values. The first row of each partition is compared with
+//! an empty hstore.
+//!
+//! This module is available with the `dsl` feature, which is enabled by default.
+
+use diesel::expression::{AppearsOnTable, Expression, NonAggregate, SelectableExpression};
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+
+/// The window rows are compared in: the order of the rows, and optionally how they are
+/// partitioned.
+#[derive(Debug, Clone)]
+pub struct Window<P, O> {
+    partition_by: Option<P>,
+    order_by: O,
+}
+
+/// Compare rows in the order given by `order_by`, such as a column or `column.desc()`.
+pub fn order_by<O>(order_by: O) -> Window<(), O> {
+    Window {
+        partition_by: None,
+        order_by: order_by,
+    }
+}
+
+impl<O> Window<(), O> {
+    /// Only compare rows having the same value of `partition_by`.
+    pub fn partition_by<P>(self, partition_by: P) -> Window<P, O> {
+        Window {
+            partition_by: Some(partition_by),
+            order_by: self.order_by,
+        }
+    }
+}
+
+impl<P, O> Window<P, O>
+    where P: QueryFragment<Pg>,
+          O: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql(" OVER (");
+        if let Some(ref partition_by) = self.partition_by {
+            out.push_sql("PARTITION BY ");
+            partition_by.walk_ast(out.reborrow())?;
+            out.push_sql(" ");
+        }
+        out.push_sql("ORDER BY ");
+        self.order_by.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+/// The entries which differ from the previous row's.
+///
+/// Please see [`added_since_previous`](../dsl/trait.HstoreOpExtensions.html#method.added_since_previous)
+/// and [`removed_since_previous`](../dsl/trait.HstoreOpExtensions.html#method.removed_since_previous).
+#[derive(Debug, Clone)]
+pub struct SincePrevious<S, P, O> {
+    store: S,
+    window: Window<P, O>,
+    removed: bool,
+}
+
+impl<S, P, O> SincePrevious<S, P, O> {
+    pub(crate) fn added(store: S, window: Window<P, O>) -> SincePrevious<S, P, O> {
+        SincePrevious {
+            store: store,
+            window: window,
+            removed: false,
+        }
+    }
+
+    pub(crate) fn removed(store: S, window: Window<P, O>) -> SincePrevious<S, P, O> {
+        SincePrevious {
+            store: store,
+            window: window,
+            removed: true,
+        }
+    }
+}
+
+impl<S, P, O> Expression for SincePrevious<S, P, O>
+    where S: Expression
+{
+    type SqlType = S::SqlType;
+}
+
+impl<S, P, O> QueryId for SincePrevious<S, P, O> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<S, P, O, QS> AppearsOnTable<QS> for SincePrevious<S, P, O>
+    where S: AppearsOnTable<QS>
+{
+}
+
+impl<S, P, O, QS> SelectableExpression<QS> for SincePrevious<S, P, O>
+    where S: SelectableExpression<QS>
+{
+}
+
+impl<S, P, O> NonAggregate for SincePrevious<S, P, O>
+    where S: NonAggregate
+{
+}
+
+impl<S, P, O> QueryFragment<Pg> for SincePrevious<S, P, O>
+    where S: QueryFragment<Pg>,
+          P: QueryFragment<Pg>,
+          O: QueryFragment<Pg>
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        // added:   store - coalesce(lag(store) OVER (...), ''::hstore)
+        // removed: coalesce(lag(store) OVER (...), ''::hstore) - store
+        out.push_sql("(");
+        if self.removed {
+            self.walk_previous(out.reborrow())?;
+            out.push_sql(" - ");
+            self.store.walk_ast(out.reborrow())?;
+        }
+        else {
+            self.store.walk_ast(out.reborrow())?;
+            out.push_sql(" - ");
+            self.walk_previous(out.reborrow())?;
+        }
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+impl<S, P, O> SincePrevious<S, P, O>
+    where S: QueryFragment<Pg>,
+          P: QueryFragment<Pg>,
+          O: QueryFragment<Pg>
+{
+    fn walk_previous(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("coalesce(lag(");
+        self.store.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        self.window.walk_ast(out.reborrow())?;
+        out.push_sql(", ''::hstore)");
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+extern crate arrow_array;
+extern crate arrow_schema;
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use arrow_array::{Array, Int64Array, MapArray};
+use arrow_array::cast::AsArray;
+use arrow_schema::DataType;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::arrow_batch::{self, Batches};
+use diesel_pg_hstore::scan::Scan;
+use diesel_pg_hstore::testing;
+
+/// The entries of the map at `i`, in order
+fn entries(maps: &MapArray, i: usize) -> Vec<(String, String)> {
+    let entries = maps.value(i);
+    let keys = entries.column(0).as_string::<i32>();
+    let values = entries.column(1).as_string::<i32>();
+    (0..entries.len()).map(|j| (keys.value(j).to_string(), values.value(j).to_string())).collect()
+}
+
+#[test]
+fn map_arrays() {
+    let mut store = Hstore::new();
+    store.insert("b".into(), "2".into());
+    store.insert("a".into(), "1".into());
+    let empty = Hstore::new();
+
+    let maps = arrow_batch::map_array(vec![Some(&store), None, Some(&empty)]);
+    assert_eq!(maps.len(), 3);
+    assert_eq!(entries(&maps, 0), [("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())]);
+    assert!(maps.is_null(1));
+    assert!(maps.is_valid(2));
+    assert_eq!(maps.value_length(2), 0);
+
+    match arrow_batch::map_data_type() {
+        DataType::Map(ref entries, false) => assert_eq!(entries.name(), "entries"),
+        ref other => panic!("unexpected type {:?}", other),
+    }
+}
+
+#[test]
+fn batches_of_a_table() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store)
+          SELECT i, hstore(ARRAY['n', 'sq'], ARRAY[i::text, (i * i)::text]) FROM generate_series(1, 25) AS i;
+    "#).unwrap();
+
+    let scan = Scan::new("hstore_table").fetch_size(7);
+    let batches: Vec<_> = Batches::new(&scan, &db, 10).map(|batch| batch.unwrap()).collect();
+    assert_eq!(batches.iter().map(|batch| batch.num_rows()).collect::<Vec<_>>(), [10, 10, 5]);
+
+    let last = &batches[2];
+    assert_eq!(last.schema().field(1).name(), "store");
+    let ids = last.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(ids.value(4), 25);
+    let maps = last.column(1).as_map();
+    assert_eq!(entries(maps, 4), [("n".to_string(), "25".to_string()), ("sq".to_string(), "625".to_string())]);
+
+    let missing = Scan::new("hstore_table").store("missing");
+    let mut batches = Batches::new(&missing, &db, 10);
+    assert!(batches.next().unwrap().is_err());
+    assert!(batches.next().is_none());
+}
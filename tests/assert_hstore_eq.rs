@@ -0,0 +1,47 @@
+#[macro_use]
+extern crate diesel_pg_hstore;
+
+mod common;
+
+
+#[test]
+fn equal() {
+    let a = common::hstore(&[("a", "1"), ("b", "2")]);
+    assert_hstore_eq!(a, common::hstore(&[("b", "2"), ("a", "1")]));
+    assert_hstore_eq!(&a, &a.clone(), "with a {}", "message");
+}
+
+#[test]
+fn compare_with_pairs_and_maps() {
+    let a = common::hstore(&[("a", "1"), ("b", "2")]);
+    assert_eq!(a, [("b", "2"), ("a", "1")]);
+    assert_eq!(a, vec![("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())]);
+    assert_eq!(a, &[("a", "1"), ("b", "2")][..]);
+    assert_ne!(a, [("a", "1"), ("b", "3")]);
+    assert_ne!(a, [("a", "1"), ("a", "1")]);
+    assert_ne!(common::hstore(&[]), [("a", "1")]);
+
+    let map = a.clone().into_iter().collect::<std::collections::HashMap<_, _>>();
+    assert_eq!(a, map);
+    assert_eq!(map, a);
+}
+
+#[test]
+fn diff() {
+    let left = common::hstore(&[("a", "1"), ("b", "2"), ("c", "3")]);
+    let right = common::hstore(&[("b", "2"), ("c", "x"), ("d", "4")]);
+
+    assert_eq!(
+        diesel_pg_hstore::macros::diff(&left, &right).unwrap(),
+        "- \"a\" => \"1\"\n~ \"c\": \"3\" != \"x\"\n+ \"d\" => \"4\"\n"
+    );
+    assert_eq!(diesel_pg_hstore::macros::diff(&left, &left), None);
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: `(left == right)`: row 7\n~ \"c\": \"3\" != \"x\"\n")]
+fn not_equal() {
+    let left = common::hstore(&[("c", "3")]);
+    let right = common::hstore(&[("c", "x")]);
+    assert_hstore_eq!(left, right, "row {}", 7);
+}
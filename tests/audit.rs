@@ -0,0 +1,43 @@
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel_pg_hstore::audit::AuditFormat;
+use diesel_pg_hstore::patch::HstorePatch;
+
+#[test]
+fn diff() {
+    let old = common::hstore(&[("a", "1"), ("b", "2"), ("note", "old text")]);
+    let new = common::hstore(&[("a", "1"), ("c", ""), ("note", "new text")]);
+
+    let format = AuditFormat::new();
+    assert_eq!(format.diff(&old, &new), r#"-b, +c="", note: "old text"→"new text""#);
+    assert_eq!(format.diff(&old, &old), "");
+}
+
+#[test]
+fn redaction() {
+    let old = common::hstore(&[("db_password", "hunter2")]);
+    let new = common::hstore(&[("db_password", "hunter3"), ("secret", "s")]);
+
+    let format = AuditFormat::new()
+        .redact("secret")
+        .redact_if(|key| key.contains("password"))
+        .placeholder("***");
+    assert!(format.is_redacted("db_password"));
+    assert!(!format.is_redacted("env"));
+    assert_eq!(format.diff(&old, &new), "db_password: ***→***, +secret=***");
+}
+
+#[test]
+fn patch() {
+    let patch = HstorePatch::new()
+        .set("env", "prod")
+        .delete("legacy")
+        .delete("b")
+        .set("token", "t");
+
+    let format = AuditFormat::new().redact("token");
+    assert_eq!(format.patch(&patch), "-b, +env=prod, -legacy, +token=[redacted]");
+    assert_eq!(format.patch(&HstorePatch::new()), "");
+}
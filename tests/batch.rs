@@ -0,0 +1,128 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use std::cell::RefCell;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::batch::{self, ChunkedPatch, Control};
+use diesel_pg_hstore::patch::HstorePatch;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn backfill_key() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store)
+          SELECT i, CASE WHEN i % 3 = 0 THEN 'currency=>EUR'::hstore ELSE ''::hstore END
+          FROM generate_series(1, 25) AS i;
+    "#).unwrap();
+
+    let mut reports = Vec::new();
+    let updated = batch::backfill_key(&db, "hstore_table", "currency", "USD", 10, |progress| {
+        reports.push((progress.updated(), progress.last_id(), progress.max_id()));
+    }).unwrap();
+
+    assert_eq!(updated, 17);
+    assert_eq!(reports, vec![(7, 10, 25), (14, 20, 25), (17, 25, 25)]);
+
+    let stores: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .order(hstore_table::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(stores[0]["currency"], "USD");
+    assert_eq!(stores[2]["currency"], "EUR");
+
+    let updated = batch::backfill_key(&db, "hstore_table", "currency", "USD", 10, |_| {}).unwrap();
+    assert_eq!(updated, 0);
+}
+
+#[test]
+fn empty_table() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let updated = batch::backfill_key(&db, "hstore_table", "currency", "USD", 10, |_| {
+        panic!("no batches expected");
+    }).unwrap();
+    assert_eq!(updated, 0);
+}
+
+#[test]
+fn chunked_patch() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store)
+          SELECT i, hstore('n', i::text) || hstore('draft', 'yes')
+          FROM generate_series(1, 25) AS i;
+    "#).unwrap();
+
+    let patch = HstorePatch::new().set("archived", "true").delete("draft");
+    let mut reports = Vec::new();
+    let progress = ChunkedPatch::new("hstore_table", &patch)
+        .filter_sql("id % 2 = 1")
+        .chunk_size(5)
+        .run(&db, |progress| {
+            reports.push((progress.updated(), progress.last_id()));
+            Control::Continue
+        })
+        .unwrap();
+
+    assert_eq!(progress.updated(), 13);
+    assert_eq!(reports, vec![(5, 9), (10, 19), (13, 25)]);
+
+    let stores: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .order(hstore_table::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(stores[0]["archived"], "true");
+    assert!(!stores[0].contains_key("draft"));
+    assert!(!stores[1].contains_key("archived"));
+    assert_eq!(stores[1]["draft"], "yes");
+}
+
+#[test]
+fn chunked_patch_abort_and_retry() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store)
+          SELECT i, ''::hstore FROM generate_series(1, 25) AS i;
+    "#).unwrap();
+
+    let patch = HstorePatch::new().set("seen", "1");
+    let progress = ChunkedPatch::new("hstore_table", &patch)
+        .chunk_size(10)
+        .run(&db, |_| Control::Abort)
+        .unwrap();
+    assert_eq!(progress.updated(), 10);
+    assert_eq!(progress.last_id(), 10);
+
+    let attempts = RefCell::new(Vec::new());
+    let result = ChunkedPatch::new("hstore_table", &patch)
+        .filter_sql("no_such_column")
+        .retry_if(|_, attempt| {
+            attempts.borrow_mut().push(attempt);
+            attempt < 3
+        })
+        .run(&db, |_| Control::Continue);
+    assert!(result.is_err());
+    assert_eq!(*attempts.borrow(), vec![1, 2, 3]);
+}
@@ -0,0 +1,146 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+extern crate moka;
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use moka::sync::Cache;
+
+use diesel_pg_hstore::FrozenHstore;
+use diesel_pg_hstore::cache::{CachedTable, HstoreCache};
+use diesel_pg_hstore::patch::{DiffingUpdate, HstorePatch};
+use diesel_pg_hstore::testing;
+
+fn make_table(db: &PgConnection) {
+    testing::create_table(db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (1, 'theme=>light'),
+          (2, 'theme=>dark');
+    "#).unwrap();
+}
+
+fn cached_table() -> CachedTable<Cache<i64, FrozenHstore>> {
+    CachedTable::new("hstore_table", Cache::new(100))
+}
+
+#[test]
+fn reads_through() {
+    let db = testing::autocommit_connection();
+    make_table(&db);
+    let table = cached_table();
+
+    let first = table.get(&db, 1).unwrap().unwrap();
+    assert_eq!(*first, [("theme", "light")]);
+    assert!(HstoreCache::get(table.cache(), 1).is_some());
+
+    db.batch_execute("UPDATE hstore_table SET store = 'theme=>blue' WHERE id = 1").unwrap();
+    let second = table.get(&db, 1).unwrap().unwrap();
+    assert!(FrozenHstore::ptr_eq(&first, &second));
+
+    table.invalidate(1);
+    assert_eq!(*table.get(&db, 1).unwrap().unwrap(), [("theme", "blue")]);
+
+    assert_eq!(table.get(&db, 99).unwrap(), None);
+    assert!(HstoreCache::get(table.cache(), 99).is_none());
+}
+
+#[test]
+fn writes_through() {
+    let db = testing::autocommit_connection();
+    make_table(&db);
+    let table = cached_table();
+
+    let mut store = table.get(&db, 1).unwrap().unwrap().thaw();
+    store.insert("font".into(), "serif".into());
+    assert!(table.set(&db, 1, store.clone()).unwrap());
+    assert_eq!(*HstoreCache::get(table.cache(), 1).unwrap(), store);
+    assert!(!table.set(&db, 99, store).unwrap());
+
+    let patched = table.apply(&db, 2, &HstorePatch::new().set("font", "mono").delete("theme")).unwrap();
+    assert_eq!(*patched.unwrap(), [("font", "mono")]);
+
+    table.invalidate(1);
+    table.invalidate(2);
+    assert_eq!(*table.get(&db, 1).unwrap().unwrap(), [("font", "serif"), ("theme", "light")]);
+    assert_eq!(*table.get(&db, 2).unwrap().unwrap(), [("font", "mono")]);
+}
+
+#[test]
+fn set_caches_what_was_written() {
+    let db = testing::autocommit_connection();
+    make_table(&db);
+    db.batch_execute(r#"
+        CREATE FUNCTION pg_temp.stamp() RETURNS trigger AS $$
+        BEGIN
+          NEW.store := NEW.store || 'stamped=>yes';
+          RETURN NEW;
+        END
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER stamp BEFORE UPDATE ON hstore_table
+          FOR EACH ROW EXECUTE PROCEDURE pg_temp.stamp();
+    "#).unwrap();
+    let table = cached_table();
+
+    let store = common::hstore(&[("theme", "blue")]);
+    assert!(table.set(&db, 1, store).unwrap());
+    assert_eq!(*HstoreCache::get(table.cache(), 1).unwrap(), [("stamped", "yes"), ("theme", "blue")]);
+}
+
+#[test]
+fn update_helpers_invalidate() {
+    let db = testing::autocommit_connection();
+    make_table(&db);
+    let table = cached_table();
+    table.get(&db, 1).unwrap();
+    table.get(&db, 2).unwrap();
+
+    let diff = table.update_returning_diff(&db, 1, &HstorePatch::new().set("theme", "dark")).unwrap();
+    assert_eq!(diff, Some(HstorePatch::new().set("theme", "dark")));
+    assert!(HstoreCache::get(table.cache(), 1).is_none());
+    assert_eq!(*table.get(&db, 1).unwrap().unwrap(), [("theme", "dark")]);
+
+    let update = DiffingUpdate::new("hstore_table", HstorePatch::new().delete("theme")).filter_sql("id = 2");
+    assert_eq!(table.run_diffing(&db, &update).unwrap().len(), 1);
+    assert!(HstoreCache::get(table.cache(), 1).is_some());
+    assert!(HstoreCache::get(table.cache(), 2).is_none());
+    assert!(table.get(&db, 2).unwrap().unwrap().is_empty());
+}
+
+#[test]
+fn bypassed_in_transactions() {
+    let db = testing::autocommit_connection();
+    make_table(&db);
+    let table = cached_table();
+    table.get(&db, 1).unwrap();
+
+    let rolled_back = db.transaction::<(), diesel::result::Error, _>(|| {
+        assert!(table.set(&db, 1, common::hstore(&[("theme", "blue")]))?);
+        assert!(HstoreCache::get(table.cache(), 1).is_none());
+        assert_eq!(*table.get(&db, 1)?.unwrap(), [("theme", "blue")]);
+        assert!(HstoreCache::get(table.cache(), 1).is_none());
+
+        table.apply(&db, 2, &HstorePatch::new().set("theme", "blue"))?;
+        assert!(HstoreCache::get(table.cache(), 2).is_none());
+        Err(diesel::result::Error::RollbackTransaction)
+    });
+    assert!(rolled_back.is_err());
+
+    assert_eq!(*table.get(&db, 1).unwrap().unwrap(), [("theme", "light")]);
+    assert_eq!(*table.get(&db, 2).unwrap().unwrap(), [("theme", "dark")]);
+    assert!(HstoreCache::get(table.cache(), 1).is_some());
+}
+
+#[test]
+fn test_transactions_are_transactions() {
+    let db = testing::connection();
+    make_table(&db);
+    let table = cached_table();
+
+    assert!(table.get(&db, 1).unwrap().is_some());
+    assert!(table.set(&db, 2, common::hstore(&[("theme", "blue")])).unwrap());
+    assert!(HstoreCache::get(table.cache(), 1).is_none());
+    assert!(HstoreCache::get(table.cache(), 2).is_none());
+}
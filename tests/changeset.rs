@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::changeset::HstoreChangeset;
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::merge::ConcatMerge;
+use diesel_pg_hstore::patch::HstorePatch;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    products {
+        id -> Integer,
+        attributes -> Hstore,
+        extra -> Nullable<Hstore>,
+    }
+}
+
+fn setup(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (id INTEGER PRIMARY KEY, attributes hstore NOT NULL, extra hstore);
+        INSERT INTO products VALUES (1, 'color=>red, size=>10, legacy_sku=>A1', NULL), (2, 'color=>blue', '');
+    "#).unwrap();
+}
+
+fn attributes(db: &PgConnection, id: i32) -> Hstore {
+    products::table.find(id).select(products::attributes).first(db).unwrap()
+}
+
+#[test]
+fn set_and_delete() {
+    let db = testing::connection();
+    setup(&db);
+
+    let changes = HstoreChangeset::new()
+        .set("color", "green")
+        .set("weight", "2kg")
+        .delete("legacy_sku");
+    diesel::update(products::table.find(1))
+        .set(changes.clone().on(products::attributes))
+        .execute(&db)
+        .unwrap();
+
+    let mut expected: Hstore = "color=>red, size=>10, legacy_sku=>A1".parse().unwrap();
+    changes.apply_to(&mut expected);
+    assert_eq!(expected, [("color", "green"), ("size", "10"), ("weight", "2kg")]);
+    assert_eq!(attributes(&db, 1), expected);
+    assert_eq!(attributes(&db, 2), [("color", "blue")]);
+}
+
+#[test]
+fn concurrent_changes_of_different_keys() {
+    let db = testing::connection();
+    setup(&db);
+
+    // Neither update reads the hstore, so the second keeps the first's key
+    diesel::update(products::table.find(2))
+        .set(HstoreChangeset::new().set("a", "1").on(products::attributes))
+        .execute(&db)
+        .unwrap();
+    diesel::update(products::table.find(2))
+        .set(HstoreChangeset::new().set("b", "2").delete("color").on(products::attributes))
+        .execute(&db)
+        .unwrap();
+    assert_eq!(attributes(&db, 2), [("a", "1"), ("b", "2")]);
+}
+
+#[test]
+fn expressions() {
+    let db = testing::connection();
+    setup(&db);
+
+    let patch = HstorePatch::new().delete("size").delete("missing");
+    let applied: Hstore = products::table
+        .find(1)
+        .select(products::attributes.apply(patch.into()))
+        .first(&db)
+        .unwrap();
+    assert_eq!(applied, [("color", "red"), ("legacy_sku", "A1")]);
+
+    let unchanged: Hstore = products::table
+        .find(1)
+        .select(products::attributes.apply(HstoreChangeset::new()))
+        .first(&db)
+        .unwrap();
+    assert_eq!(unchanged, attributes(&db, 1));
+
+    let new: Hstore = "size=>12, color=>black".parse().unwrap();
+    let merged: Hstore = products::table
+        .find(1)
+        .select(products::attributes.merged(new, ConcatMerge).apply(HstoreChangeset::new().delete("legacy_sku")))
+        .first(&db)
+        .unwrap();
+    assert_eq!(merged, [("color", "black"), ("size", "12")]);
+}
+
+#[test]
+fn nullable() {
+    let db = testing::connection();
+    setup(&db);
+
+    diesel::update(products::table)
+        .set(HstoreChangeset::new().set("a", "1").on(products::extra))
+        .execute(&db)
+        .unwrap();
+    let extra: Vec<Option<Hstore>> = products::table
+        .select(products::extra)
+        .order(products::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(extra, vec![None, Some("a=>1".parse().unwrap())]);
+}
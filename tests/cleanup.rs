@@ -0,0 +1,63 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::cleanup::Cleanup;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+fn load(db: &PgConnection) -> Vec<String> {
+    hstore_table::table
+        .select(hstore_table::store)
+        .order(hstore_table::id)
+        .load::<Hstore>(db)
+        .unwrap()
+        .iter()
+        .map(diesel_pg_hstore::text::format)
+        .collect()
+}
+
+#[test]
+fn cleanup() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (1, 'a=>" x ", b=>"", tmp_c=>1'),
+          (2, 'a=>"  ", keep=>y'),
+          (3, 'clean=>already'),
+          (4, '');
+    "#).unwrap();
+
+    let cleanup = Cleanup::new("hstore_table", "store")
+        .trim_values()
+        .remove_empty_values()
+        .remove_keys_matching("^tmp_");
+
+    assert_eq!(cleanup.dry_run(&db).unwrap(), 2);
+    assert_eq!(load(&db)[0], r#""a"=>" x ", "b"=>"", "tmp_c"=>"1""#);
+
+    assert_eq!(cleanup.run(&db).unwrap(), 2);
+    assert_eq!(load(&db), vec![
+        r#""a"=>"x""#.to_string(),
+        r#""keep"=>"y""#.to_string(),
+        r#""clean"=>"already""#.to_string(),
+        "".to_string(),
+    ]);
+    assert_eq!(cleanup.dry_run(&db).unwrap(), 0);
+
+    assert_eq!(Cleanup::new("hstore_table", "store").run(&db).unwrap(), 0);
+}
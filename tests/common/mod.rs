@@ -0,0 +1,8 @@
+//! Helpers shared by the test files.
+
+use diesel_pg_hstore::Hstore;
+
+/// An hstore of `pairs`, for writing fixtures.
+pub fn hstore(pairs: &[(&str, &str)]) -> Hstore {
+    pairs.iter().map(|&(key, value)| (key.to_string(), value.to_string())).collect()
+}
@@ -0,0 +1,50 @@
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::compact::DecodeError;
+
+#[test]
+fn round_trips() {
+    let long = "x".repeat(300);
+    for store in &[
+        Hstore::new(),
+        common::hstore(&[("", "")]),
+        common::hstore(&[("b", "2"), ("a", "1"), ("ünï", "cödé ✓")]),
+        common::hstore(&[("long", &long), (&long, "long")]),
+    ] {
+        let bytes = store.to_bytes();
+        assert_eq!(&Hstore::from_bytes(&bytes).unwrap(), store);
+        assert_eq!(bytes, store.clone().to_bytes(), "encoding is deterministic");
+    }
+}
+
+#[test]
+fn layout() {
+    assert_eq!(Hstore::new().to_bytes(), b"HS\x01\x00");
+
+    let mut bytes = b"HS\x01\x01\x01k\xac\x02".to_vec();
+    bytes.extend(std::iter::repeat(b'v').take(300));
+    assert_eq!(common::hstore(&[("k", &"v".repeat(300))]).to_bytes(), bytes);
+}
+
+#[test]
+fn decode_errors() {
+    for &(bytes, ref expected) in &[
+        (&b""[..], DecodeError::NotAnHstore),
+        (&b"PG\x01\x00"[..], DecodeError::NotAnHstore),
+        (&b"HS"[..], DecodeError::Truncated),
+        (&b"HS\x02\x00"[..], DecodeError::UnsupportedVersion(2)),
+        (&b"HS\x01\x80"[..], DecodeError::Truncated),
+        (&b"HS\x01\x05\x01a\x011"[..], DecodeError::Truncated),
+        (&b"HS\x01\x01\x01a\x051"[..], DecodeError::Truncated),
+        (&b"HS\x01\x01\x01\xff\x011"[..], DecodeError::InvalidUtf8),
+        (&b"HS\x01\x02\x01b\x011\x01a\x012"[..], DecodeError::Unsorted),
+        (&b"HS\x01\x02\x01a\x011\x01a\x012"[..], DecodeError::Unsorted),
+        (&b"HS\x01\x00\x00"[..], DecodeError::TrailingBytes),
+        (&b"HS\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01"[..], DecodeError::Overflow),
+    ] {
+        assert_eq!(&Hstore::from_bytes(bytes).unwrap_err(), expected, "decoding {:?}", bytes);
+    }
+}
@@ -0,0 +1,100 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+#[macro_use]
+extern crate lazy_static;
+
+use std::sync::Arc;
+
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::types::Text;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::compression::DeflateCodec;
+use diesel_pg_hstore::value_codec::{Chain, ColumnCodec, HstoreWith, ValueCodec};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn codec() {
+    let codec = DeflateCodec::new(16);
+    let large = "abc".repeat(100);
+
+    assert_eq!(codec.encode("k", "short").unwrap(), "short");
+
+    let compressed = codec.encode("k", &large).unwrap().into_owned();
+    assert!(compressed.starts_with("deflate:"));
+    assert!(compressed.len() < large.len());
+    assert_eq!(codec.decode("k", &compressed).unwrap(), large);
+
+    // Not worth compressing
+    let random = "q8Zr2LmX0vKpT4nW";
+    assert_eq!(codec.encode("k", random).unwrap(), random);
+
+    // Always compressed when it looks compressed
+    let lookalike = codec.encode("k", "deflate:").unwrap().into_owned();
+    assert_ne!(lookalike, "deflate:");
+    assert_eq!(codec.decode("k", &lookalike).unwrap(), "deflate:");
+
+    assert!(codec.decode("k", "deflate:not base64!").is_err());
+}
+
+#[test]
+fn chain() {
+    let chain = Chain(vec![
+        Arc::new(DeflateCodec::new(16)),
+        Arc::new(DeflateCodec::new(0)),
+    ]);
+    let large = "abc".repeat(100);
+
+    let encoded = chain.encode("k", &large).unwrap().into_owned();
+    assert!(encoded.starts_with("deflate:"));
+    assert_eq!(chain.decode("k", &encoded).unwrap(), large);
+}
+
+lazy_static! {
+    static ref CODEC: DeflateCodec = DeflateCodec::new(64);
+}
+
+struct Compressed;
+
+impl ColumnCodec for Compressed {
+    fn value_codec() -> &'static ValueCodec {
+        &*CODEC
+    }
+}
+
+#[test]
+fn round_trip() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("blob".into(), "hstore ".repeat(1000));
+    store.insert("name".into(), "small".into());
+    let store = HstoreWith::<Compressed>::new(store);
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    let stored: String = hstore_table::table
+        .select(sql::<Text>("store -> 'blob'"))
+        .first(&db)
+        .unwrap();
+    assert!(stored.starts_with("deflate:"));
+    assert!(stored.len() < 7000);
+
+    let loaded: HstoreWith<Compressed> = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded, store);
+}
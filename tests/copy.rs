@@ -0,0 +1,56 @@
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel_pg_hstore::copy;
+
+#[test]
+fn unescape() {
+    assert_eq!(copy::unescape("a\\tb\\nc\\\\d").unwrap(), "a\tb\nc\\d");
+    assert_eq!(copy::unescape("\\101\\x42\\x4\\7").unwrap(), "AB\u{4}\u{7}");
+    assert_eq!(copy::unescape("\\303\\251").unwrap(), "é");
+    assert_eq!(copy::unescape("\\q\\x").unwrap(), "qx");
+    assert!(copy::unescape("trailing\\").is_err());
+    assert!(copy::unescape("\\303").is_err());
+}
+
+#[test]
+fn round_trip() {
+    let store = common::hstore(&[("tab\tkey", "line\none"), ("slash", "C:\\temp"), ("quote", "\"hi\"")]);
+    let cell = copy::format_cell(Some(&store));
+
+    assert!(!cell.contains('\t'));
+    assert!(!cell.contains('\n'));
+    assert_eq!(copy::parse_cell(&cell).unwrap(), Some(store));
+    assert_eq!(copy::parse_cell(&copy::format_cell(None)).unwrap(), None);
+}
+
+// COPY TO STDOUT isn't available through diesel, so this is its output captured with psql:
+//
+//     INSERT INTO copy_table (store) VALUES
+//       (E'"tab\tkey"=>"line\none", "slash"=>"C:\\\\temp", "quote"=>"\\"hi\\"", "return"=>"a\rb"'::hstore),
+//       (NULL),
+//       ('"é"=>NULL, ""=>""'::hstore);
+//     COPY (SELECT store FROM copy_table ORDER BY id) TO STDOUT;
+const COPY_OUTPUT: &str = include_str!("fixtures/copy.txt");
+
+#[test]
+fn matches_postgres() {
+    let fields: Vec<&str> = COPY_OUTPUT.lines().collect();
+    assert_eq!(fields.len(), 3);
+
+    let store = copy::parse_cell(fields[0]).unwrap().unwrap();
+    assert_eq!(store, common::hstore(&[
+        ("tab\tkey", "line\none"),
+        ("slash", "C:\\temp"),
+        ("quote", "\"hi\""),
+        ("return", "a\rb"),
+    ]));
+    assert_eq!(copy::parse_cell(&copy::format_cell(Some(&store))).unwrap(), Some(store));
+
+    assert_eq!(copy::parse_cell(fields[1]).unwrap(), None);
+    assert_eq!(copy::format_cell(None), fields[1]);
+
+    // The entry with a NULL value is dropped, as by text::parse
+    assert_eq!(copy::parse_cell(fields[2]).unwrap(), Some(common::hstore(&[("", "")])));
+}
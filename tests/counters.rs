@@ -0,0 +1,106 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::counters::{CountError, Invalid, Overflow};
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn overflow() {
+    let mut store = Hstore::new();
+    store.insert("max".into(), i64::max_value().to_string());
+    store.insert("min".into(), i64::min_value().to_string());
+
+    {
+        let mut counters = store.counters();
+        assert_eq!(counters.incr("max", 1).unwrap(), i64::max_value());
+        assert_eq!(counters.decr("min", 1).unwrap(), i64::min_value());
+    }
+
+    let mut counters = store.counters().overflow(Overflow::Error);
+    assert_eq!(counters.incr("max", 1), Err(CountError::Overflow { key: "max".into() }));
+    assert_eq!(counters.decr("max", 1).unwrap(), i64::max_value() - 1);
+}
+
+#[test]
+fn invalid() {
+    let mut store = Hstore::new();
+    store.insert("count".into(), "many".into());
+
+    {
+        let mut counters = store.counters();
+        assert_eq!(counters.incr("count", 1), Err(CountError::Invalid {
+            key: "count".into(),
+            value: "many".into(),
+        }));
+    }
+    assert_eq!(store["count"], "many");
+
+    {
+        let mut counters = store.counters().invalid(Invalid::Zero);
+        assert_eq!(counters.get_count("count").unwrap(), 0);
+        assert_eq!(counters.incr("count", 5).unwrap(), 5);
+    }
+    assert_eq!(store["count"], "5");
+}
+
+#[test]
+fn server_side_incr() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("views".into(), "41".into());
+    store.insert("max".into(), (i64::max_value() - 1).to_string());
+
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store
+            .incr("views", 1)
+            .incr("new", -3)
+            .incr("max", 10)))
+        .execute(&db)
+        .unwrap();
+
+    let mut store: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    let counters = store.counters();
+    assert_eq!(counters.get_count("views").unwrap(), 42);
+    assert_eq!(counters.get_count("new").unwrap(), -3);
+    assert_eq!(counters.get_count("max").unwrap(), i64::max_value());
+}
+
+#[test]
+fn server_side_incr_rejects_fractions() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("views".into(), "1.5".into());
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    let incremented = diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.incr("views", 1)))
+        .execute(&db);
+    assert!(incremented.is_err());
+}
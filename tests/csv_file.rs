@@ -0,0 +1,49 @@
+extern crate csv;
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::csv_file;
+use diesel_pg_hstore::testing;
+
+#[test]
+fn round_trip() {
+    let mut store = Hstore::new();
+    store.insert("plain".into(), "value".into());
+    store.insert("comma, quote\"".into(), "line\nbreak".into());
+    store.insert("empty".into(), "".into());
+
+    let csv = store.to_csv().unwrap();
+    assert_eq!(csv, "key,value\n\"comma, quote\"\"\",\"line\nbreak\"\nempty,\nplain,value\n");
+    assert_eq!(Hstore::from_csv(&csv).unwrap(), store);
+}
+
+#[test]
+fn read_without_header() {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(b';')
+        .from_reader("a;1\nb;2\na;3\n".as_bytes());
+    assert_eq!(csv_file::read_hstore(&mut reader).unwrap(), [("a", "3"), ("b", "2")]);
+
+    assert!(Hstore::from_csv("key,value\na,1,extra\n").is_err());
+}
+
+#[test]
+fn write_table() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (2, 'b=>2, a=>"x,y"'),
+          (1, 'c=>3'),
+          (3, '');
+    "#).unwrap();
+
+    let mut out = Vec::new();
+    let lines = csv_file::write_table(&db, "hstore_table", "store", &mut out).unwrap();
+    assert_eq!(lines, 3);
+    assert_eq!(String::from_utf8(out).unwrap(), "id,key,value\n1,c,3\n2,a,\"x,y\"\n2,b,2\n");
+}
@@ -0,0 +1,62 @@
+extern crate diesel_pg_hstore;
+extern crate serde_json;
+
+use serde_json::Value;
+
+use diesel_pg_hstore::debezium::{self, DebeziumError};
+
+#[test]
+fn every_handling_mode() {
+    let expected = [("a", "1"), ("quote \"", "back\\slash"), ("empty", "")];
+
+    let json = r#"{"a":"1","quote \"":"back\\slash","empty":"","gone":null}"#;
+    assert_eq!(debezium::parse(json).unwrap(), expected);
+
+    let encoded = serde_json::to_string(json).unwrap();
+    assert_eq!(debezium::parse(&encoded).unwrap(), expected);
+
+    let literal = r#""a"=>"1", "quote \""=>"back\\slash", empty=>"", gone=>NULL"#;
+    assert_eq!(debezium::parse(literal).unwrap(), expected);
+
+    assert_eq!(debezium::parse(r#""{"=>"}""#).unwrap(), [("{", "}")]);
+    assert_eq!(debezium::parse("{a}=>1").unwrap(), [("{a}", "1")]);
+    assert_eq!(debezium::parse("").unwrap(), [] as [(&str, &str); 0]);
+    assert_eq!(debezium::parse("{}").unwrap(), [] as [(&str, &str); 0]);
+}
+
+#[test]
+fn event_fields() {
+    let event: Value = serde_json::from_str(r#"{
+        "before": null,
+        "after": {
+            "json_mode": "{\"a\":\"1\"}",
+            "map_mode": {"a": "1", "b": null},
+            "literal": "a=>1",
+            "missing": null,
+            "number": 1
+        }
+    }"#).unwrap();
+    let after = &event["after"];
+
+    assert_eq!(debezium::from_value(&after["json_mode"]).unwrap().unwrap(), [("a", "1")]);
+    assert_eq!(debezium::from_value(&after["map_mode"]).unwrap().unwrap(), [("a", "1")]);
+    assert_eq!(debezium::from_value(&after["literal"]).unwrap().unwrap(), [("a", "1")]);
+    assert_eq!(debezium::from_value(&after["missing"]).unwrap(), None);
+    assert_eq!(debezium::from_value(&after["number"]), Err(DebeziumError::NotAnHstore));
+}
+
+#[test]
+fn errors() {
+    match debezium::parse(r#"{"a":1}"#) {
+        Err(DebeziumError::NonStringValue { ref key }) => assert_eq!(key, "a"),
+        other => panic!("unexpected {:?}", other),
+    }
+    match debezium::parse(r#"{"a":"1""#) {
+        Err(DebeziumError::Json(_)) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+    match debezium::parse(r#""a"=>"1"#) {
+        Err(DebeziumError::Text(_)) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+}
@@ -0,0 +1,67 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::dedup::{EncodeCache, EncodedHstore};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "hstore_table"]
+struct NewRow {
+    store: EncodedHstore,
+}
+
+fn run(n: usize) -> Hstore {
+    let mut store = Hstore::new();
+    store.insert("run".into(), n.to_string());
+    store.insert("source".into(), "import".into());
+    store
+}
+
+#[test]
+fn encodes_each_distinct_hstore_once() {
+    let mut cache = EncodeCache::new();
+    let first = cache.encode(&run(1)).unwrap();
+    let again = cache.encode(&run(1)).unwrap();
+    let other = cache.encode(&run(2)).unwrap();
+
+    assert!(EncodedHstore::ptr_eq(&first, &again));
+    assert!(!EncodedHstore::ptr_eq(&first, &other));
+    assert_eq!(cache.distinct(), 2);
+    assert_eq!(cache.hits(), 1);
+}
+
+#[test]
+fn bulk_insert() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let runs: Vec<Hstore> = (0..300).map(|i| run(i % 3)).collect();
+    let mut cache = EncodeCache::new();
+    let rows: Vec<NewRow> = runs.iter()
+        .map(|store| NewRow { store: cache.encode(store).unwrap() })
+        .collect();
+    assert_eq!(cache.distinct(), 3);
+
+    diesel::insert_into(hstore_table::table).values(&rows).execute(&db).unwrap();
+
+    let loaded: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .order(hstore_table::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(loaded, runs);
+}
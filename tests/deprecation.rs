@@ -0,0 +1,58 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::deprecation;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+// Deprecated keys are global, so everything is checked in one test.
+#[test]
+fn report() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("old".into(), "1".into());
+    store.insert("new".into(), "2".into());
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    deprecation::deprecate_key("old", Some("new"));
+    deprecation::deprecate_key("unused", None);
+
+    // Loading and looking up keys is not counted
+    let loaded: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded["old"], "1");
+    assert_eq!(loaded.get("old").unwrap(), "1");
+    assert!(deprecation::report().iter().all(|used| used.found() == 0));
+
+    assert_eq!(deprecation::check(&loaded), 1);
+    assert_eq!(deprecation::check(&Hstore::new()), 0);
+
+    let report = deprecation::report();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0].key(), "old");
+    assert_eq!(report[0].replacement(), Some("new"));
+    assert_eq!(report[0].found(), 1);
+    assert_eq!(report[1].key(), "unused");
+    assert_eq!(report[1].found(), 0);
+
+    deprecation::clear_deprecated_keys();
+    assert_eq!(deprecation::check(&loaded), 0);
+    assert!(deprecation::report().is_empty());
+}
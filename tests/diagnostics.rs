@@ -0,0 +1,29 @@
+//! Checks the hints given when a type which isn't an hstore is used as one.
+//!
+//! Each file in `tests/ui` must fail to compile. The crate in `tests/ui` is checked with the
+//! cargo running the tests, and the errors are matched on the lines the `diagnostics` feature
+//! adds, rather than compared in full, as the rest of the output changes between compilers.
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn hints() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .arg("check")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(root.join("tests/ui/Cargo.toml"))
+        .env("CARGO_TARGET_DIR", root.join("target/ui"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "{}", stderr);
+    assert_eq!(stderr.matches("error[E0277]").count(), 2, "{}", stderr);
+    assert_eq!(stderr.matches("::Text` is not an hstore SQL type").count(), 1, "{}", stderr);
+    assert_eq!(stderr.matches("::Integer` is not an hstore SQL type").count(), 1, "{}", stderr);
+    assert_eq!(stderr.matches("note: columns must be declared `-> Hstore` in `table!`").count(), 2, "{}", stderr);
+    assert_eq!(stderr.matches("`HstoreTextExtensions::cast_to_hstore`").count(), 2, "{}", stderr);
+}
@@ -0,0 +1,249 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::dsl::{HstoreOpExtensions, HstoreTextExtensions};
+use diesel_pg_hstore::testing;
+use diesel_pg_hstore::window;
+
+table! {
+    legacy_table {
+        id -> Integer,
+        meta -> Text,
+    }
+}
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    snapshots {
+        id -> Integer,
+        entity_id -> Integer,
+        meta -> Hstore,
+    }
+}
+
+table! {
+    use diesel_pg_hstore::table_import::*;
+
+    fields {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(snapshots, fields);
+
+#[test]
+fn cast_to_hstore() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE legacy_table (id SERIAL PRIMARY KEY, meta TEXT NOT NULL);
+        INSERT INTO legacy_table (meta) VALUES ('"views"=>"1", "color"=>"red"');
+    "#).unwrap();
+
+    let store: Hstore = legacy_table::table
+        .select(legacy_table::meta.cast_to_hstore())
+        .first(&db)
+        .unwrap();
+    assert_eq!(store["color"], "red");
+
+    let incremented: Hstore = legacy_table::table
+        .select(legacy_table::meta.cast_to_hstore().incr("views", 1))
+        .first(&db)
+        .unwrap();
+    assert_eq!(incremented["views"], "2");
+}
+
+#[test]
+fn changes_since_previous() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE snapshots (id INTEGER PRIMARY KEY, entity_id INTEGER NOT NULL, meta hstore NOT NULL);
+        INSERT INTO snapshots VALUES
+          (1, 1, 'a=>1, b=>2'),
+          (2, 2, 'x=>1'),
+          (3, 1, 'a=>1, b=>3, c=>4'),
+          (4, 1, 'c=>4');
+    "#).unwrap();
+
+    let in_order = window::order_by(snapshots::id).partition_by(snapshots::entity_id);
+    let changes: Vec<(i32, (Hstore, Hstore))> = snapshots::table
+        .select((snapshots::id, snapshots::meta.changes_since_previous(in_order)))
+        .order(snapshots::id)
+        .load(&db)
+        .unwrap();
+
+    let text = |store: &Hstore| diesel_pg_hstore::text::format(store);
+    let changes: Vec<(i32, String, String)> = changes.iter()
+        .map(|&(id, (ref added, ref removed))| (id, text(added), text(removed)))
+        .collect();
+    assert_eq!(changes, vec![
+        (1, r#""a"=>"1", "b"=>"2""#.to_string(), "".to_string()),
+        (2, r#""x"=>"1""#.to_string(), "".to_string()),
+        (3, r#""b"=>"3", "c"=>"4""#.to_string(), r#""b"=>"2""#.to_string()),
+        (4, "".to_string(), r#""a"=>"1", "b"=>"3""#.to_string()),
+    ]);
+
+    let unpartitioned: Vec<Hstore> = snapshots::table
+        .select(snapshots::meta.removed_since_previous(window::order_by(snapshots::id.desc())))
+        .order(snapshots::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(text(&unpartitioned[3]), "");
+    assert_eq!(text(&unpartitioned[1]), r#""a"=>"1", "b"=>"3", "c"=>"4""#);
+}
+
+#[test]
+fn get_localized() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE snapshots (id SERIAL PRIMARY KEY, entity_id INTEGER NOT NULL, meta hstore NOT NULL);
+        INSERT INTO snapshots (entity_id, meta) VALUES
+          (1, 'title.fr=>Bonjour, title.en=>Hello'),
+          (2, 'title.en=>Hello'),
+          (3, 'title.de=>Hallo');
+    "#).unwrap();
+
+    let titles: Vec<Option<String>> = snapshots::table
+        .select(snapshots::meta.get_localized("title", &["fr-CA", "fr", "en"]))
+        .order(snapshots::entity_id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(titles, vec![Some("Bonjour".to_string()), Some("Hello".to_string()), None]);
+
+    let stores: Vec<Hstore> = snapshots::table.select(snapshots::meta).order(snapshots::entity_id).load(&db).unwrap();
+    let local: Vec<Option<&String>> = stores.iter().map(|s| s.get_localized("title", &["fr-CA", "fr", "en"])).collect();
+    assert_eq!(local, titles.iter().map(Option::as_ref).collect::<Vec<_>>());
+
+    let none: Option<String> = snapshots::table
+        .select(snapshots::meta.get_localized("title", Vec::<String>::new()))
+        .first(&db)
+        .unwrap();
+    assert_eq!(none, None);
+}
+
+#[test]
+fn stored_size() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE snapshots (id SERIAL PRIMARY KEY, entity_id INTEGER NOT NULL, meta hstore NOT NULL);
+        INSERT INTO snapshots (entity_id, meta) VALUES
+          (1, ''),
+          (2, 'a=>1'),
+          (3, hstore('big', repeat('x', 1000)));
+    "#).unwrap();
+
+    let sizes: Vec<(i32, i32)> = snapshots::table
+        .select((snapshots::meta.stored_size(), snapshots::meta.text_length()))
+        .order(snapshots::entity_id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(sizes[1].1, r#""a"=>"1""#.len() as i32);
+    assert_eq!(sizes[2].1, r#""big"=>"""#.len() as i32 + 1000);
+    assert!(sizes[0].0 < sizes[1].0 && sizes[1].0 < sizes[2].0, "sizes {:?}", sizes);
+
+    let large: Vec<i32> = snapshots::table
+        .select(snapshots::entity_id)
+        .filter(snapshots::meta.stored_size().gt(100))
+        .load(&db)
+        .unwrap();
+    assert_eq!(large, vec![3]);
+}
+
+#[test]
+fn cast_to_text() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE snapshots (id SERIAL PRIMARY KEY, entity_id INTEGER NOT NULL, meta hstore NOT NULL);
+        INSERT INTO snapshots (entity_id, meta) VALUES
+          (1, 'b=>1, a=>2'),
+          (2, 'a=>1'),
+          (3, 'a=>2, b=>1');
+    "#).unwrap();
+
+    let distinct: Vec<String> = snapshots::table
+        .select(snapshots::meta.cast_to_text())
+        .distinct()
+        .order(snapshots::meta.cast_to_text())
+        .load(&db)
+        .unwrap();
+    assert_eq!(distinct, vec![r#""a"=>"1""#, r#""a"=>"2", "b"=>"1""#]);
+
+    let round_trip: Vec<Hstore> = snapshots::table
+        .select(snapshots::meta.cast_to_text().cast_to_hstore())
+        .order(snapshots::entity_id)
+        .load(&db)
+        .unwrap();
+    let stores: Vec<Hstore> = snapshots::table.select(snapshots::meta).order(snapshots::entity_id).load(&db).unwrap();
+    assert_eq!(round_trip, stores);
+}
+
+#[test]
+fn keys_from_expressions() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE snapshots (id SERIAL PRIMARY KEY, entity_id INTEGER NOT NULL, meta hstore NOT NULL);
+        CREATE TEMPORARY TABLE fields (id SERIAL PRIMARY KEY, name TEXT NOT NULL);
+        INSERT INTO snapshots (entity_id, meta) VALUES (1, 'color=>red, size=>L');
+        INSERT INTO fields (name) VALUES ('size'), ('weight');
+    "#).unwrap();
+
+    let joined = snapshots::table.inner_join(fields::table.on(fields::id.gt(0)));
+    let values: Vec<(String, Option<String>, bool, Hstore)> = joined
+        .select((
+            fields::name,
+            snapshots::meta.get_value(fields::name),
+            snapshots::meta.has_key(fields::name),
+            snapshots::meta.remove_key(fields::name),
+        ))
+        .order(fields::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(values[0].1, Some("L".to_string()));
+    assert!(values[0].2);
+    assert_eq!(values[0].3, [("color", "red")]);
+    assert_eq!(values[1].1, None);
+    assert!(!values[1].2);
+    assert_eq!(values[1].3, [("color", "red"), ("size", "L")]);
+
+    let with_key: Vec<i32> = joined
+        .select(fields::id)
+        .filter(snapshots::meta.has_key(fields::name))
+        .load(&db)
+        .unwrap();
+    assert_eq!(with_key, vec![1]);
+
+    let bound: (Option<String>, bool, Hstore) = snapshots::table
+        .select((
+            snapshots::meta.get_value("color"),
+            snapshots::meta.has_key(String::from("missing")),
+            snapshots::meta.remove_key("color"),
+        ))
+        .first(&db)
+        .unwrap();
+    assert_eq!(bound.0, Some("red".to_string()));
+    assert!(!bound.1);
+    assert_eq!(bound.2, [("size", "L")]);
+
+    let defaults: Vec<(String, String)> = joined
+        .select((
+            snapshots::meta.get_value_or(fields::name, "none"),
+            snapshots::meta.get_value_or("color", fields::name),
+        ))
+        .order(fields::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(defaults, vec![("L".to_string(), "red".to_string()), ("none".to_string(), "red".to_string())]);
+
+    let values: Vec<Option<String>> = snapshots::table
+        .select(snapshots::meta.get_array(vec!["size", "missing", "color"]))
+        .first(&db)
+        .unwrap();
+    assert_eq!(values, vec![Some("L".to_string()), None, Some("red".to_string())]);
+}
@@ -0,0 +1,91 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+#[macro_use]
+extern crate lazy_static;
+
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::types::Text;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::encryption::AesGcmCodec;
+use diesel_pg_hstore::value_codec::{ColumnCodec, HstoreWith, ValueCodec};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn codec() {
+    let codec = AesGcmCodec::new(&[1; 32], &["secret", "other_secret"]);
+
+    assert_eq!(codec.encode("plain", "value").unwrap(), "value");
+
+    let sealed = codec.encode("secret", "hunter2").unwrap().into_owned();
+    assert!(sealed.starts_with("aes256gcm:"));
+    assert_ne!(sealed, codec.encode("secret", "hunter2").unwrap());
+    assert_eq!(codec.decode("secret", &sealed).unwrap(), "hunter2");
+
+    // Written before encryption was enabled
+    assert_eq!(codec.decode("secret", "hunter2").unwrap(), "hunter2");
+
+    // Bound to the key, and to the secret
+    assert!(codec.decode("other_secret", &sealed).is_err());
+    let tampered = sealed.replace("aes256gcm:", "aes256gcm:A");
+    assert!(codec.decode("secret", &tampered).is_err());
+    assert!(AesGcmCodec::new(&[2; 32], &["secret"]).decode("secret", &sealed).is_err());
+}
+
+lazy_static! {
+    static ref CODEC: AesGcmCodec = AesGcmCodec::new(&[1; 32], &["token"]);
+}
+
+struct Tokens;
+
+impl ColumnCodec for Tokens {
+    fn value_codec() -> &'static ValueCodec {
+        &*CODEC
+    }
+}
+
+#[test]
+fn round_trip() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("token".into(), "hunter2".into());
+    store.insert("name".into(), "visible".into());
+    let store = HstoreWith::<Tokens>::new(store);
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    let stored: String = hstore_table::table
+        .select(sql::<Text>("store -> 'token'"))
+        .first(&db)
+        .unwrap();
+    assert!(stored.starts_with("aes256gcm:"));
+
+    let name: String = hstore_table::table
+        .select(sql::<Text>("store -> 'name'"))
+        .first(&db)
+        .unwrap();
+    assert_eq!(name, "visible");
+
+    let loaded: HstoreWith<Tokens> = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded, store);
+
+    // Other columns, and plain Hstores, are never decrypted
+    let raw: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(raw["token"], stored);
+}
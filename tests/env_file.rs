@@ -0,0 +1,72 @@
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::env_file::EnvFileError;
+
+#[test]
+fn round_trip() {
+    let store = common::hstore(&[
+        ("PLAIN", "a-b_c.d/e:f@g"),
+        ("EMPTY", ""),
+        ("SPACES", "  padded  "),
+        ("SHELL", "$HOME `id` \\ \"q\" 'single'"),
+        ("LINES", "one\ntwo\r\n\tthree"),
+        ("UNICODE", "cödé ✓"),
+        ("_dotted.key-name", "x"),
+    ]);
+
+    let file = store.to_env_file().unwrap();
+    assert_eq!(file.lines().count(), 7);
+    assert_eq!(Hstore::from_env_file(&file).unwrap(), store);
+}
+
+#[test]
+fn invalid_keys() {
+    for key in &["", "1ST", "has space", "a=b"] {
+        assert_eq!(
+            common::hstore(&[(key, "v")]).to_env_file(),
+            Err(EnvFileError::InvalidKey { key: key.to_string() })
+        );
+    }
+}
+
+#[test]
+fn parse() {
+    let file = r#"
+# a comment
+export A=1
+B = unquoted value # trailing comment
+C='single "quoted" \n'
+D="double" # comment
+E=
+A=2
+F="unknown \q escape"
+"#;
+    assert_eq!(Hstore::from_env_file(file).unwrap(), common::hstore(&[
+        ("A", "2"),
+        ("B", "unquoted value"),
+        ("C", "single \"quoted\" \\n"),
+        ("D", "double"),
+        ("E", ""),
+        ("F", "unknown \\q escape"),
+    ]));
+}
+
+#[test]
+fn syntax_errors() {
+    let errors = [
+        ("A=1\nB", 2, "expected `=`"),
+        ("1A=1", 1, "invalid key"),
+        ("A='open", 1, "unterminated single quoted value"),
+        ("A=\"open\\\"", 1, "unterminated double quoted value"),
+        ("A=\"x\" y", 1, "unexpected text after quoted value"),
+    ];
+    for &(file, line, message) in &errors {
+        assert_eq!(
+            Hstore::from_env_file(file),
+            Err(EnvFileError::Syntax { line: line, message: message })
+        );
+    }
+}
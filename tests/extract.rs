@@ -0,0 +1,81 @@
+extern crate axum_core;
+extern crate diesel_pg_hstore;
+extern crate http;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use axum_core::body::Body;
+use axum_core::extract::{FromRequest, FromRequestParts, Request};
+use axum_core::response::IntoResponse;
+
+use diesel_pg_hstore::extract::{HstoreForm, HstoreQuery, Rejection};
+use diesel_pg_hstore::urlencoded::{FormError, FormLimits};
+
+/// Poll a future which is ready without waiting, as every request body here is in memory
+fn now<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    match Pin::as_mut(&mut future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future isn't ready"),
+    }
+}
+
+fn form(body: &str, limits: Option<FormLimits>) -> Request {
+    let mut req = http::Request::post("/")
+        .header("content-type", "application/x-www-form-urlencoded; charset=utf-8")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    if let Some(limits) = limits {
+        req.extensions_mut().insert(limits);
+    }
+    req
+}
+
+#[test]
+fn limits() {
+    let limits = FormLimits::new().allow_keys(vec!["a", "b"]).max_keys(2).max_value_len(3);
+    assert_eq!(limits.parse("a=1&b=%2F").unwrap(), [("a", "1"), ("b", "/")]);
+    assert_eq!(limits.parse("a=1&c=2"), Err(FormError::KeyNotAllowed { key: "c".into() }));
+    assert_eq!(limits.parse("a=1&a=2&b=3"), Err(FormError::TooManyKeys));
+    assert_eq!(limits.parse("a=1234"), Err(FormError::ValueTooLong { key: "a".into() }));
+
+    let limits = FormLimits::new().max_bytes(8).max_key_len(2);
+    assert_eq!(limits.parse("abc=1"), Err(FormError::KeyTooLong { key: "abc".into() }));
+    assert_eq!(limits.parse("a=1&b=2&c=3"), Err(FormError::TooLarge));
+    assert!(limits.parse("").unwrap().is_empty());
+}
+
+#[test]
+fn axum_form() {
+    let HstoreForm(store) = now(HstoreForm::from_request(form("color=red&size=L", None), &())).unwrap();
+    assert_eq!(store, [("color", "red"), ("size", "L")]);
+
+    let limits = FormLimits::new().allow_key("color");
+    let rejected = now(HstoreForm::from_request(form("color=red&size=L", Some(limits)), &())).unwrap_err();
+    assert_eq!(rejected, Rejection::Form(FormError::KeyNotAllowed { key: "size".into() }));
+    assert_eq!(rejected.into_response().status(), 400);
+
+    let limits = FormLimits::new().max_bytes(4);
+    let rejected = now(HstoreForm::from_request(form("color=red", Some(limits)), &())).unwrap_err();
+    assert_eq!(rejected.into_response().status(), 413);
+
+    let json = http::Request::post("/")
+        .header("content-type", "application/json")
+        .body(Body::from("{}"))
+        .unwrap();
+    let rejected = now(HstoreForm::from_request(json, &())).unwrap_err();
+    assert_eq!(rejected, Rejection::UnsupportedContentType);
+    assert_eq!(rejected.into_response().status(), 415);
+}
+
+#[test]
+fn axum_query() {
+    let (mut parts, _) = http::Request::get("/search?tag=a%20b&page=2").body(()).unwrap().into_parts();
+    let HstoreQuery(store) = now(HstoreQuery::from_request_parts(&mut parts, &())).unwrap();
+    assert_eq!(store, [("page", "2"), ("tag", "a b")]);
+
+    let (mut parts, _) = http::Request::get("/").body(()).unwrap().into_parts();
+    assert!(now(HstoreQuery::from_request_parts(&mut parts, &())).unwrap().is_empty());
+}
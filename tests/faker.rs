@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+extern crate fake;
+
+use diesel::prelude::*;
+use fake::{Fake, Faker};
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::faker::HstoreFaker;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn shape() {
+    let config = HstoreFaker {
+        count: 20..21,
+        key_len: 8..9,
+        value_len: 4..5,
+        unicode: false,
+    };
+    let store: Hstore = config.fake();
+
+    assert_eq!(store.len(), 20);
+    for (k, v) in &store {
+        assert_eq!(k.len(), 8);
+        assert_eq!(v.len(), 4);
+        assert!(k.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+
+    let _: Hstore = Faker.fake();
+}
+
+#[test]
+fn round_trip() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let config = HstoreFaker {
+        count: 50..100,
+        value_len: 0..200,
+        unicode: true,
+        ..HstoreFaker::default()
+    };
+    let stores: Vec<Hstore> = (0..10).map(|_| config.fake()).collect();
+
+    for store in &stores {
+        diesel::insert_into(hstore_table::table)
+            .values(hstore_table::store.eq(store))
+            .execute(&db)
+            .expect("To insert data");
+    }
+
+    let data: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .order(hstore_table::id)
+        .load(&db)
+        .expect("To get data");
+
+    assert_eq!(data, stores);
+}
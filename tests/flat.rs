@@ -0,0 +1,59 @@
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::flat::{FlatError, Nesting};
+
+#[test]
+fn toml() {
+    let store = Hstore::from_toml(r#"
+        name = "api"
+        replicas = 3
+        debug = false
+
+        [database]
+        host = "db.internal"
+    "#, Nesting::OneLevel).unwrap();
+    assert_eq!(store, [("name", "api"), ("replicas", "3"), ("debug", "false"), ("database.host", "db.internal")]);
+
+    assert_eq!(Hstore::from_toml(&store.to_toml(Nesting::OneLevel).unwrap(), Nesting::OneLevel).unwrap(), store);
+    assert_eq!(Hstore::from_toml(&store.to_toml(Nesting::Flat).unwrap(), Nesting::Flat).unwrap(), store);
+
+    assert_eq!(
+        Hstore::from_toml("[database]\nhost = \"x\"", Nesting::Flat),
+        Err(FlatError::Nested { key: "database".into() })
+    );
+    assert_eq!(
+        Hstore::from_toml("[a.b]\nc = 1", Nesting::OneLevel),
+        Err(FlatError::Nested { key: "a.b".into() })
+    );
+    assert_eq!(
+        Hstore::from_toml("tags = [\"a\"]", Nesting::Flat),
+        Err(FlatError::List { key: "tags".into() })
+    );
+}
+
+#[test]
+fn yaml() {
+    let store = Hstore::from_yaml("
+name: api
+replicas: 3
+unset: ~
+database:
+  host: db.internal
+", Nesting::OneLevel).unwrap();
+    assert_eq!(store, [("name", "api"), ("replicas", "3"), ("database.host", "db.internal")]);
+
+    assert_eq!(Hstore::from_yaml(&store.to_yaml(Nesting::OneLevel).unwrap(), Nesting::OneLevel).unwrap(), store);
+    assert_eq!(Hstore::from_yaml("", Nesting::Flat).unwrap(), Hstore::new());
+    assert_eq!(Hstore::from_yaml("- a", Nesting::Flat), Err(FlatError::NotATable));
+}
+
+#[test]
+fn conflicts() {
+    let store = common::hstore(&[("database", "x"), ("database.host", "y")]);
+    assert_eq!(store.to_toml(Nesting::OneLevel), Err(FlatError::Conflict { key: "database".into() }));
+    assert_eq!(store.to_yaml(Nesting::OneLevel), Err(FlatError::Conflict { key: "database".into() }));
+    assert!(store.to_toml(Nesting::Flat).is_ok());
+}
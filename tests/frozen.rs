@@ -0,0 +1,86 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use std::thread;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::{CowHstore, FrozenHstore, Hstore};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[derive(Queryable, Debug)]
+struct Settings {
+    id: i32,
+    store: FrozenHstore,
+}
+
+#[test]
+fn copy_on_write() {
+    let mut store = Hstore::new();
+    store.insert("a".into(), "1".into());
+
+    let mut frozen = store.freeze();
+    let shared = frozen.clone();
+    assert!(FrozenHstore::ptr_eq(&frozen, &shared));
+
+    let reader = {
+        let shared = shared.clone();
+        thread::spawn(move || shared["a"].clone())
+    };
+    assert_eq!(reader.join().unwrap(), "1");
+
+    frozen.make_mut().insert("b".into(), "2".into());
+    assert!(!FrozenHstore::ptr_eq(&frozen, &shared));
+    assert!(!shared.contains_key("b"));
+
+    // No longer shared, so thawing doesn't copy
+    let ptr = &frozen["b"] as *const String;
+    let thawed = frozen.thaw();
+    assert_eq!(&thawed["b"] as *const String, ptr);
+}
+
+#[test]
+fn round_trip() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("theme".into(), "dark".into());
+    let frozen = store.clone().freeze();
+
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&frozen))
+        .execute(&db)
+        .unwrap();
+
+    let loaded: Settings = hstore_table::table.first(&db).unwrap();
+    assert_eq!(loaded.id, 1);
+    assert_eq!(*loaded.store, store);
+}
+
+#[test]
+fn cow_copies_only_while_shared() {
+    let mut cow = CowHstore::new(Hstore::new());
+    cow.make_mut().insert("a".into(), "1".into());
+
+    let before = cow.make_mut() as *const Hstore;
+    cow.make_mut().insert("b".into(), "2".into());
+    assert_eq!(cow.make_mut() as *const Hstore, before);
+
+    let shared = cow.clone();
+    cow.make_mut().remove("a");
+    assert_ne!(cow.make_mut() as *const Hstore, before);
+    assert_eq!(shared["a"], "1");
+    assert!(!cow.contains_key("a"));
+}
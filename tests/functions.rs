@@ -0,0 +1,161 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+
+use diesel_pg_hstore::{Hstore, HstoreNullable};
+use diesel_pg_hstore::functions::{each, hstore_from_record, hstore_to_matrix, populate_record, skeys, svals, update_record};
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    products {
+        id -> Integer,
+        attributes -> Hstore,
+    }
+}
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    parts {
+        id -> Integer,
+        name -> Text,
+        qty -> Integer,
+        overrides -> Hstore,
+    }
+}
+
+/// The row type of `parts`
+struct PartRow;
+
+fn setup(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (id SERIAL PRIMARY KEY, attributes hstore NOT NULL);
+        INSERT INTO products (attributes) VALUES
+          ('color=>red, size=>10'),
+          (''),
+          ('color=>blue, discontinued=>NULL');
+    "#).unwrap();
+}
+
+#[test]
+fn each_entry() {
+    let db = testing::connection();
+    setup(&db);
+
+    let mut entries: Vec<(i32, (String, Option<String>))> = products::table
+        .select((products::id, each(products::attributes)))
+        .load(&db)
+        .unwrap();
+    entries.sort();
+    assert_eq!(entries, vec![
+        (1, ("color".to_string(), Some("red".to_string()))),
+        (1, ("size".to_string(), Some("10".to_string()))),
+        (3, ("color".to_string(), Some("blue".to_string()))),
+        (3, ("discontinued".to_string(), None)),
+    ]);
+
+    let mut columns: Vec<(String, Option<String>)> = products::table
+        .select((each(products::attributes).key(), each(products::attributes).value()))
+        .filter(products::id.eq(1))
+        .load(&db)
+        .unwrap();
+    columns.sort();
+    assert_eq!(columns, vec![("color".to_string(), Some("red".to_string())), ("size".to_string(), Some("10".to_string()))]);
+}
+
+#[test]
+fn keys_and_values() {
+    let db = testing::connection();
+    setup(&db);
+
+    let keys: Vec<String> = products::table
+        .select(skeys(products::attributes))
+        .distinct()
+        .order(skeys(products::attributes))
+        .load(&db)
+        .unwrap();
+    assert_eq!(keys, vec!["color", "discontinued", "size"]);
+
+    let mut values: Vec<Option<String>> = products::table
+        .select(svals(products::attributes))
+        .filter(products::id.eq(3))
+        .load(&db)
+        .unwrap();
+    values.sort();
+    assert_eq!(values, vec![None, Some("blue".to_string())]);
+
+    let mut store = Hstore::new();
+    store.insert("b".into(), "2".into());
+    store.insert("a".into(), "1".into());
+    let pairs: Vec<(String, Option<String>)> = diesel::select(each(&store)).load(&db).unwrap();
+    assert_eq!(pairs, vec![("a".to_string(), Some("1".to_string())), ("b".to_string(), Some("2".to_string()))]);
+}
+
+#[test]
+fn pairs() {
+    let db = testing::connection();
+    setup(&db);
+
+    let pairs: Vec<Vec<(String, Option<String>)>> = products::table
+        .select(products::attributes.to_pairs())
+        .order(products::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(pairs, vec![
+        vec![("size".to_string(), Some("10".to_string())), ("color".to_string(), Some("red".to_string()))],
+        vec![],
+        vec![("color".to_string(), Some("blue".to_string())), ("discontinued".to_string(), None)],
+    ]);
+
+    let store: Hstore = "\"quote\\\"d\"=>\"{a, b}\", \"\"=>\"\"".parse().unwrap();
+    let matrix: Vec<(String, Option<String>)> = diesel::select(hstore_to_matrix(&store)).get_result(&db).unwrap();
+    assert_eq!(matrix, vec![("".to_string(), Some("".to_string())), ("quote\"d".to_string(), Some("{a, b}".to_string()))]);
+
+    let nullable: Option<Vec<(String, Option<String>)>> = diesel::select(sql::<diesel::types::Nullable<Hstore>>("NULL::hstore").to_pairs())
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(nullable, None);
+}
+
+#[test]
+fn records() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE parts (id SERIAL PRIMARY KEY, name TEXT, qty INTEGER, overrides hstore NOT NULL);
+        INSERT INTO parts (name, qty, overrides) VALUES
+          ('bolt', 3, 'qty=>5'),
+          ('nut', 10, 'name=>NULL');
+    "#).unwrap();
+
+    let populated: Vec<HstoreNullable> = parts::table
+        .select(hstore_from_record(populate_record(sql::<PartRow>("parts"), parts::overrides)).remove_key("overrides"))
+        .order(parts::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(populated[0].to_string(), r#""id"=>"1", "qty"=>"5", "name"=>"bolt""#);
+    assert_eq!(populated[1].to_string(), r#""id"=>"2", "qty"=>"10", "name"=>NULL"#);
+
+    let mut overrides = Hstore::new();
+    overrides.insert("qty".into(), "0".into());
+    let updated: Vec<Hstore> = parts::table
+        .select(hstore_from_record(update_record(sql::<PartRow>("parts"), &overrides)))
+        .order(parts::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(updated.iter().map(|row| row["qty"].as_str()).collect::<Vec<_>>(), vec!["0", "0"]);
+    assert_eq!(updated[1]["name"], "nut");
+
+    let invalid = parts::table
+        .select(hstore_from_record(populate_record(sql::<PartRow>("parts"), "qty=>many".parse::<Hstore>().unwrap())))
+        .load::<Hstore>(&db);
+    assert!(invalid.is_err());
+}
@@ -0,0 +1,60 @@
+//! Replays inputs found by the fuzz targets in `fuzz/`.
+//!
+//! Each file in `fuzz/regressions/<target>` is fed to the same code the fuzz target exercises.
+//! A panic fails the test and names the offending file.
+
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use std::fs;
+use std::panic;
+use std::path::Path;
+
+use diesel::pg::Pg;
+use diesel::types::FromSql;
+use diesel_pg_hstore::{text, Hstore};
+
+fn replay<F>(target: &str, check: F)
+    where F: Fn(&[u8]) + panic::RefUnwindSafe
+{
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/regressions").join(target);
+
+    let mut failures = Vec::new();
+    for entry in fs::read_dir(&dir).expect("To read the regressions directory") {
+        let path = entry.unwrap().path();
+        let data = fs::read(&path).unwrap();
+
+        if panic::catch_unwind(|| check(&data)).is_err() {
+            failures.push(path.display().to_string());
+        }
+    }
+
+    assert!(failures.is_empty(), "{} panicked on: {:?}", target, failures);
+}
+
+#[test]
+fn decode_binary() {
+    replay("decode_binary", |data| {
+        let _ = <Hstore as FromSql<Hstore, Pg>>::from_sql(Some(data));
+    });
+}
+
+#[test]
+fn decode_compact() {
+    replay("decode_compact", |data| {
+        if let Ok(store) = Hstore::from_bytes(data) {
+            assert_eq!(store.to_bytes(), data);
+        }
+    });
+}
+
+#[test]
+fn parse_text() {
+    replay("parse_text", |data| {
+        if let Ok(s) = std::str::from_utf8(data) {
+            if let Ok(store) = text::parse(s) {
+                assert_eq!(text::parse(&text::format(&store)).unwrap(), store);
+            }
+        }
+    });
+}
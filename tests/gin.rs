@@ -0,0 +1,165 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::debug_query;
+use diesel::dsl::{not, sql};
+use diesel::pg::Pg;
+use diesel::types::Text;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::gin::GinIndexes;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel_pg_hstore::table_import::*;
+
+    services {
+        id -> Integer,
+        labels -> Hstore,
+        annotations -> Hstore,
+        notes -> Nullable<Hstore>,
+    }
+}
+
+fn pairs(entries: &[(&str, &str)]) -> Hstore {
+    entries.iter().map(|&(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+fn make_table(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE services (id SERIAL PRIMARY KEY, labels hstore NOT NULL, annotations hstore NOT NULL, notes hstore);
+        CREATE INDEX services_labels ON services USING gin (labels);
+        INSERT INTO services (labels, annotations, notes) VALUES
+          ('env=>prod, active=>t', 'env=>dev', 'env=>prod'),
+          ('env=>prod, active=>f', '', NULL),
+          ('env=>dev, active=>t', 'env=>prod', '');
+    "#).unwrap();
+}
+
+#[test]
+fn rewrites_indexed_columns() {
+    let indexes = GinIndexes::new().index(services::labels);
+    assert!(indexes.is_indexed(&services::labels));
+    assert!(!indexes.is_indexed(&services::annotations));
+
+    let plain = services::labels.key_eq("env", "prod");
+    assert!(!plain.is_containment());
+    let rewritten = indexes.rewrite(plain);
+    assert!(rewritten.is_containment());
+    let query = services::table.select(services::id).filter(rewritten);
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    assert!(sql.contains(r#"WHERE ("services"."labels" @> hstore($1, $2))"#), "{}", sql);
+
+    let query = services::table.select(services::id).filter(indexes.rewrite(services::annotations.key_eq("env", "prod")));
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    assert!(sql.contains(r#"WHERE ("services"."annotations" ? $1 AND "services"."annotations" -> $2 = $3)"#), "{}", sql);
+
+    let by_pairs = services::labels.filter_by_pairs(pairs(&[("env", "prod"), ("active", "t")]));
+    let query = services::table.select(services::id).filter(by_pairs.clone());
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    assert!(sql.contains(r#"WHERE ("services"."labels" ? $1 AND "services"."labels" -> $2 = $3 AND "services"."labels" ? $4 AND "services"."labels" -> $5 = $6)"#), "{}", sql);
+    let query = services::table.select(services::id).filter(indexes.rewrite(by_pairs));
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    assert!(sql.contains(r#"WHERE ("services"."labels" @> $1)"#), "{}", sql);
+
+    let query = services::table.select(services::id).filter(services::labels.filter_by_pairs(Hstore::new()));
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    assert!(sql.contains(r#"WHERE ("services"."labels" @> $1)"#), "{}", sql);
+}
+
+#[test]
+fn both_forms_select_the_same_rows() {
+    let db = testing::connection();
+    make_table(&db);
+    let indexes = GinIndexes::new().index(services::labels).index(services::annotations);
+
+    let ids = |filter_pairs: Hstore, rewrite: bool| -> Vec<i32> {
+        let predicate = services::labels.filter_by_pairs(filter_pairs);
+        let predicate = if rewrite { indexes.rewrite(predicate) } else { predicate };
+        services::table.select(services::id).filter(predicate).order(services::id).load(&db).unwrap()
+    };
+    for entries in &[&[("env", "prod")][..], &[("env", "prod"), ("active", "t")], &[("env", "staging")], &[]] {
+        let plain = ids(pairs(entries), false);
+        assert_eq!(ids(pairs(entries), true), plain, "{:?}", entries);
+    }
+    assert_eq!(ids(pairs(&[("env", "prod")]), true), vec![1, 2]);
+
+    let dev: Vec<i32> = services::table.select(services::id)
+        .filter(indexes.rewrite(services::annotations.key_eq("env", "dev")))
+        .load(&db)
+        .unwrap();
+    assert_eq!(dev, vec![1]);
+}
+
+#[test]
+fn negated_on_missing_keys() {
+    let db = testing::connection();
+    make_table(&db);
+    let indexes = GinIndexes::new().index(services::annotations);
+
+    // The second row has no "env" annotation, so it is not "dev" either
+    let plain: Vec<i32> = services::table.select(services::id)
+        .filter(not(services::annotations.key_eq("env", "dev")))
+        .order(services::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(plain, vec![2, 3]);
+    let rewritten: Vec<i32> = services::table.select(services::id)
+        .filter(not(indexes.rewrite(services::annotations.key_eq("env", "dev"))))
+        .order(services::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(rewritten, plain);
+
+    let plain: Vec<i32> = services::table.select(services::id)
+        .filter(not(services::annotations.filter_by_pairs(pairs(&[("env", "dev")]))))
+        .order(services::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(plain, vec![2, 3]);
+}
+
+#[test]
+fn null_on_the_same_rows() {
+    let db = testing::connection();
+    make_table(&db);
+    let indexes = GinIndexes::new().index(services::notes);
+
+    let values = |filter_pairs: Hstore, rewrite: bool| -> Vec<Option<bool>> {
+        let predicate = services::notes.filter_by_pairs(filter_pairs);
+        let predicate = if rewrite { indexes.rewrite(predicate) } else { predicate };
+        services::table.select(predicate).order(services::id).load(&db).unwrap()
+    };
+    assert_eq!(values(pairs(&[("env", "prod")]), false), vec![Some(true), None, Some(false)]);
+    assert_eq!(values(pairs(&[("env", "prod")]), true), vec![Some(true), None, Some(false)]);
+    assert_eq!(values(Hstore::new(), false), vec![Some(true), None, Some(true)]);
+    assert_eq!(values(Hstore::new(), true), vec![Some(true), None, Some(true)]);
+
+    let key_eq: Vec<Option<bool>> = services::table.select(services::notes.key_eq("env", "prod"))
+        .order(services::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(key_eq, vec![Some(true), None, Some(false)]);
+}
+
+#[test]
+fn uses_the_index() {
+    let db = testing::connection();
+    make_table(&db);
+    db.batch_execute("SET LOCAL enable_seqscan = off").unwrap();
+
+    let indexes = GinIndexes::new().index(services::labels);
+    let query = services::table.select(services::id).filter(indexes.rewrite(services::labels.key_eq("env", "prod")));
+    let plan = format!("EXPLAIN {}", debug_query::<Pg, _>(&query))
+        .split(" -- binds")
+        .next()
+        .unwrap()
+        .replace("$1", "'env'")
+        .replace("$2", "'prod'");
+    let plan: Vec<String> = sql::<Text>(&plan).load(&db).unwrap();
+    assert!(plan.iter().any(|line| line.contains("services_labels")), "{:?}", plan);
+}
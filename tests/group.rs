@@ -0,0 +1,53 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+use diesel::types::{Integer, Nullable, Text};
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::group::group_rows;
+use diesel_pg_hstore::testing;
+
+#[test]
+fn groups_consecutive_rows() {
+    let rows = vec![
+        ("a", "k".to_string(), Some("1".to_string())),
+        ("a", "k".to_string(), Some("2".to_string())),
+        ("b", "k".to_string(), None),
+        ("a", "j".to_string(), Some("3".to_string())),
+    ];
+
+    let groups: Vec<(&str, Hstore)> = group_rows(rows).collect();
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups[0].0, "a");
+    assert_eq!(groups[0].1, [("k", "2")]);
+    assert_eq!(groups[1].0, "b");
+    assert!(groups[1].1.is_empty());
+    assert_eq!(groups[2].1, [("j", "3")]);
+
+    assert_eq!(group_rows(Vec::<(i32, String, Option<String>)>::new()).count(), 0);
+}
+
+#[test]
+fn folds_each_back_into_hstores() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (1, 'a=>1, b=>2'),
+          (2, 'a=>NULL'),
+          (3, 'c=>3');
+    "#).unwrap();
+
+    let rows: Vec<(i32, String, Option<String>)> = sql::<(Integer, Text, Nullable<Text>)>(
+        "SELECT id, e.key, e.value FROM hstore_table, each(store) AS e ORDER BY id"
+    ).load(&db).unwrap();
+    let folded: Vec<(i32, Hstore)> = group_rows(rows).collect();
+
+    let stores: Vec<(i32, Hstore)> = sql::<(Integer, diesel_pg_hstore::Hstore)>(
+        "SELECT id, store FROM hstore_table ORDER BY id"
+    ).load(&db).unwrap();
+    assert_eq!(folded, stores);
+}
@@ -0,0 +1,48 @@
+extern crate diesel_pg_hstore;
+
+use std::mem;
+
+use diesel_pg_hstore::{FrozenHstore, Hstore};
+
+#[test]
+fn counts_capacity() {
+    let empty = Hstore::new();
+    assert_eq!(empty.heap_size(), 0);
+
+    let mut store = Hstore::with_capacity(100);
+    let table = store.heap_size();
+    assert!(table >= 100 * mem::size_of::<(String, String)>());
+
+    let mut value = String::with_capacity(1000);
+    value.push('x');
+    store.insert("key".into(), value);
+    assert_eq!(store.heap_size(), table + 3 + 1000);
+
+    store.shrink_to_fit();
+    assert!(store.heap_size() < table + 3 + 1000);
+    assert!(store.heap_size() >= 3 + 1000 + mem::size_of::<(String, String)>());
+}
+
+#[test]
+fn grows_with_entries() {
+    let mut store = Hstore::new();
+    let mut previous = 0;
+    for i in 0..1000 {
+        store.insert(format!("key{}", i), "value".into());
+        let size = store.heap_size();
+        assert!(size > previous);
+        assert!(size >= store.capacity() * mem::size_of::<(String, String)>());
+        previous = size;
+    }
+}
+
+#[test]
+fn frozen_counts_the_shared_map() {
+    let mut store = Hstore::new();
+    store.insert("a".into(), "1".into());
+    let size = store.heap_size();
+
+    let frozen = FrozenHstore::new(store);
+    assert_eq!(frozen.heap_size(), 2 * mem::size_of::<usize>() + mem::size_of::<Hstore>() + size);
+    assert_eq!(frozen.clone().heap_size(), frozen.heap_size());
+}
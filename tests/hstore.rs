@@ -3,22 +3,13 @@
 #[macro_use]
 extern crate diesel;
 extern crate diesel_pg_hstore;
-extern crate dotenv;
-
-use std::env;
 
 use diesel::prelude::*;
-use diesel::Connection;
 use diesel::pg::PgConnection;
 use diesel::connection::SimpleConnection;
 
 use diesel_pg_hstore::Hstore;
-
-fn connection() -> PgConnection {
-    dotenv::dotenv().ok();
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL to be defined (may use .env)");
-    PgConnection::establish(&database_url).unwrap()
-}
+use diesel_pg_hstore::testing::{self, connection};
 
 table! {
     use diesel::types::*;
@@ -30,6 +21,17 @@ table! {
     }
 }
 
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    optional_hstore_table {
+        id -> Integer,
+        with_default -> Hstore,
+        nullable -> Nullable<Hstore>,
+    }
+}
+
 #[derive(Insertable, Queryable, Identifiable, Debug, PartialEq)]
 #[table_name = "hstore_table"]
 struct HasHstore {
@@ -37,14 +39,33 @@ struct HasHstore {
     store: Hstore,
 }
 
+#[derive(QueryableByName, Debug, PartialEq)]
+struct RawHstoreRow {
+    #[sql_type = "diesel::types::Integer"]
+    id: i32,
+    #[sql_type = "Hstore"]
+    store: Hstore,
+}
+
+#[derive(QueryableByName)]
+struct RawHstores {
+    #[sql_type = "diesel::types::Nullable<Hstore>"]
+    maybe: Option<Hstore>,
+    #[sql_type = "diesel::types::Array<Hstore>"]
+    all: Vec<Hstore>,
+}
+
+#[derive(Insertable)]
+#[table_name = "optional_hstore_table"]
+struct NewOptionalHstore {
+    id: i32,
+    with_default: Option<Hstore>,
+    nullable: Option<Hstore>,
+}
+
 fn make_table(db: &PgConnection) {
+    testing::create_table(db, "hstore_table").unwrap();
     db.batch_execute(r#"
-        CREATE EXTENSION IF NOT EXISTS hstore;
-        DROP TABLE IF EXISTS hstore_table;
-        CREATE TABLE hstore_table (
-            id SERIAL PRIMARY KEY,
-            store hstore NOT NULL
-        );
         INSERT INTO hstore_table (id, store)
           VALUES (1, 'a=>1,b=>2'::hstore);
     "#).unwrap();
@@ -79,3 +100,201 @@ fn metadata() {
     assert_eq!(data[1].store["Hello"], "There".to_string());
     assert_eq!(data[1].store["Again"], "Stuff".to_string());
 }
+
+#[test]
+fn bind_by_value() {
+    let db = connection();
+    make_table(&db);
+
+    let mut m = Hstore::new();
+    m.insert("owned".into(), "value".into());
+
+    diesel::insert_into(hstore_table::table)
+        .values((hstore_table::id.eq(2), hstore_table::store.eq(m.clone())))
+        .execute(&db)
+        .expect("To insert data");
+
+    let data: HasHstore = hstore_table::table.find(2).first(&db).expect("To get data");
+    assert_eq!(data.store, m);
+}
+
+#[test]
+fn composite_member() {
+    use diesel::dsl::sql;
+    use diesel::types::{Bool, Integer};
+
+    let db = connection();
+    db.batch_execute("CREATE TYPE pg_temp.setting_row AS (id integer, store hstore);").unwrap();
+
+    let mut m = Hstore::new();
+    m.insert("in".into(), "row".into());
+
+    let same: bool = sql::<Bool>("SELECT ROW(")
+        .bind::<Integer, _>(1)
+        .sql(", ")
+        .bind::<Hstore, _>(m.clone())
+        .sql(")::pg_temp.setting_row = ROW(1, 'in=>row')::pg_temp.setting_row")
+        .get_result(&db)
+        .expect("To compare rows");
+    assert!(same);
+
+    let store: Hstore = sql::<Hstore>("SELECT (ROW(")
+        .bind::<Integer, _>(1)
+        .sql(", ")
+        .bind::<Hstore, _>(m.clone())
+        .sql(")::pg_temp.setting_row).store")
+        .get_result(&db)
+        .expect("To get the member");
+    assert_eq!(store, m);
+}
+
+#[test]
+fn sql_query_binds() {
+    use diesel::types::{Array, Integer, Nullable, Text};
+
+    let db = connection();
+    make_table(&db);
+
+    let mut m = Hstore::new();
+    m.insert("raw".into(), "sql".into());
+
+    diesel::sql_query("INSERT INTO hstore_table (id, store) VALUES ($1, $2)")
+        .bind::<Integer, _>(2)
+        .bind::<Hstore, _>(&m)
+        .execute(&db)
+        .expect("To insert data");
+
+    diesel::sql_query("INSERT INTO hstore_table (id, store) VALUES ($1, hstore($2, $3))")
+        .bind::<Integer, _>(3)
+        .bind::<Array<Text>, _>(vec!["k1", "k2"])
+        .bind::<Array<Text>, _>(vec!["v1", "v2"])
+        .execute(&db)
+        .expect("To insert data");
+
+    let rows: Vec<RawHstoreRow> = diesel::sql_query("SELECT id, store FROM hstore_table WHERE store ?| $1 ORDER BY id")
+        .bind::<Array<Text>, _>(vec!["raw", "k2"])
+        .load(&db)
+        .expect("To get data");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0], RawHstoreRow { id: 2, store: m.clone() });
+    assert_eq!(rows[1].store["k2"], "v2");
+
+    let raw: Vec<RawHstores> = diesel::sql_query("SELECT $1 AS maybe, $2 AS all")
+        .bind::<Nullable<Hstore>, _>(None::<Hstore>)
+        .bind::<Array<Hstore>, _>(vec![m.clone(), Hstore::new()])
+        .load(&db)
+        .expect("To get data");
+    assert_eq!(raw[0].maybe, None);
+    assert_eq!(raw[0].all, vec![m.clone(), Hstore::new()]);
+
+    let raw: Vec<RawHstores> = diesel::sql_query("SELECT $1 AS maybe, ARRAY[]::hstore[] AS all")
+        .bind::<Nullable<Hstore>, _>(Some(&m))
+        .load(&db)
+        .expect("To get data");
+    assert_eq!(raw[0].maybe, Some(m));
+    assert!(raw[0].all.is_empty());
+}
+
+#[test]
+fn optional_fields_use_default() {
+    let db = connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE optional_hstore_table (
+          id INTEGER PRIMARY KEY,
+          with_default hstore NOT NULL DEFAULT 'from=>default',
+          nullable hstore
+        );
+    "#).unwrap();
+
+    let mut m = Hstore::new();
+    m.insert("given".into(), "value".into());
+
+    let rows = vec![
+        NewOptionalHstore { id: 1, with_default: None, nullable: None },
+        NewOptionalHstore { id: 2, with_default: Some(m.clone()), nullable: Some(m.clone()) },
+    ];
+    diesel::insert_into(optional_hstore_table::table)
+        .values(&rows)
+        .execute(&db)
+        .expect("To insert data");
+
+    let data: Vec<(i32, Hstore, Option<Hstore>)> = optional_hstore_table::table
+        .order(optional_hstore_table::id)
+        .load(&db)
+        .expect("To get data");
+
+    assert_eq!(data[0].1["from"], "default");
+    assert_eq!(data[0].2, None);
+    assert_eq!(data[1].1, m);
+    assert_eq!(data[1].2, Some(m));
+}
+
+#[test]
+fn decode_errors_have_context() {
+    use diesel::pg::Pg;
+    use diesel::types::FromSql;
+    use diesel_pg_hstore::error::{DecodeError, DecodeErrorKind};
+
+    fn decode(bytes: &[u8]) -> DecodeError {
+        let err = <Hstore as FromSql<Hstore, Pg>>::from_sql(Some(bytes)).unwrap_err();
+        *err.downcast::<DecodeError>().unwrap()
+    }
+
+    // Two entries, a=>1 then a key "b" whose value length runs past the end
+    let bytes = b"\0\0\0\x02\0\0\0\x01a\0\0\0\x011\0\0\0\x01b\0\0\0\x09x";
+    let err = decode(bytes);
+    match *err.kind() {
+        DecodeErrorKind::InvalidValueLength => {}
+        ref kind => panic!("unexpected {:?}", kind),
+    }
+    assert_eq!(err.offset(), 19);
+    assert_eq!(err.entry(), Some(1));
+    assert_eq!(err.key(), Some("b"));
+    assert_eq!(err.previous_key(), Some("a"));
+    assert_eq!(
+        err.to_string(),
+        r#"error deserializing hstore: invalid value length at byte 19, in entry 1 (key "b")"#,
+    );
+
+    let err = decode(b"\0\0\0\x01\0\0\0\x01\xff\0\0\0\x011");
+    assert_eq!(err.to_string(), "error deserializing hstore: invalid UTF-8 at byte 8, in entry 0");
+
+    let err = decode(b"\0\0\0\x01\0\0\0\x01a\0\0\0\x011\0\0\0\x01b\0\0\0\x011");
+    assert_eq!(err.to_string(), "error deserializing hstore: invalid buffer size at byte 14");
+
+    let err = decode(b"\xff\xff\xff\xff");
+    assert_eq!(err.entry(), None);
+    assert_eq!(err.to_string(), "error deserializing hstore: invalid entry count at byte 0");
+}
+
+#[test]
+fn arrays_with_null_elements() {
+    use diesel::dsl::sql;
+    use diesel::types::{Array, Nullable};
+
+    let db = connection();
+    make_table(&db);
+
+    let aggregated: Vec<Option<Hstore>> = diesel::select(sql::<Array<Nullable<Hstore>>>(
+        "(SELECT array_agg(t.store ORDER BY s.n) FROM generate_series(0, 2) AS s(n) \
+         LEFT JOIN hstore_table AS t ON t.id = s.n)"
+    )).get_result(&db).unwrap();
+    assert_eq!(aggregated.len(), 3);
+    assert_eq!(aggregated[0], None);
+    assert_eq!(aggregated[1].as_ref().unwrap()["b"], "2");
+    assert_eq!(aggregated[2], None);
+
+    let mut m = Hstore::new();
+    m.insert("k".into(), "v".into());
+    let bound = vec![None, Some(m), Some(Hstore::new())];
+    #[derive(QueryableByName)]
+    struct Row {
+        #[sql_type = "Array<Nullable<Hstore>>"]
+        stores: Vec<Option<Hstore>>,
+    }
+    let rows: Vec<Row> = diesel::sql_query("SELECT $1 AS stores")
+        .bind::<Array<Nullable<Hstore>>, _>(&bound)
+        .load(&db)
+        .unwrap();
+    assert_eq!(rows[0].stores, bound);
+}
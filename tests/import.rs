@@ -0,0 +1,122 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+use diesel::types::{Integer, Nullable, Text};
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::import::{BulkImport, FailureKind};
+use diesel_pg_hstore::testing;
+
+fn make_table(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (
+          id SERIAL PRIMARY KEY,
+          sku TEXT NOT NULL UNIQUE,
+          stock INTEGER,
+          attributes hstore CHECK (attributes ? 'color')
+        );
+    "#).unwrap();
+}
+
+fn row(sku: Option<&str>, stock: Option<&str>, attributes: Option<&str>) -> Vec<Option<String>> {
+    vec![sku.map(String::from), stock.map(String::from), attributes.map(String::from)]
+}
+
+fn import<'a>() -> BulkImport<'a> {
+    BulkImport::new("products")
+        .column("sku")
+        .column("stock")
+        .hstore_column("attributes")
+}
+
+#[test]
+fn reports_failing_rows() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let rows = vec![
+        row(Some("A"), Some("3"), Some("color=>red")),
+        row(Some("B"), Some("many"), Some("color=>blue")),
+        row(Some("C"), None, Some("color=>")),
+        row(Some("D"), Some("1"), Some("size=>M")),
+        row(Some("A"), Some("1"), Some("color=>green")),
+        row(None, Some("1"), Some("color=>green")),
+        row(Some("E"), Some("2"), Some("color=>black")),
+        row(Some("F"), Some("2"), None),
+        vec![Some("G".to_string())],
+        row(Some("H"), Some("5"), Some("color=>white, size=>XL")),
+    ];
+    let report = import().chunk_size(4).run(&db, &rows).unwrap();
+
+    assert_eq!(report.imported(), 4);
+    assert!(!report.is_complete());
+    let failed: Vec<usize> = report.failures().iter().map(|failure| failure.row).collect();
+    assert_eq!(failed, vec![1, 2, 3, 4, 5, 8]);
+
+    let failures = report.failures();
+    match failures[1].kind {
+        FailureKind::Decode(_) => assert_eq!(failures[1].column.as_ref().unwrap(), "attributes"),
+        ref kind => panic!("expected a decode failure, got {:?}", kind),
+    }
+    assert_eq!(failures[5].kind, FailureKind::Columns { expected: 3, found: 1 });
+    for &i in &[0, 2, 3, 4] {
+        match failures[i].kind {
+            FailureKind::Database(_) => {}
+            ref kind => panic!("expected row {} to be rejected, got {:?}", failures[i].row, kind),
+        }
+    }
+    assert_eq!(failures[4].column.as_ref().unwrap(), "sku");
+    assert!(failures[1].to_string().starts_with("row 2, column attributes: invalid hstore"));
+
+    let imported: Vec<(String, Option<i32>)> = sql::<(Text, Nullable<Integer>)>("SELECT sku, stock FROM products ORDER BY sku")
+        .load(&db)
+        .unwrap();
+    assert_eq!(imported, vec![
+        ("A".to_string(), Some(3)),
+        ("E".to_string(), Some(2)),
+        ("F".to_string(), Some(2)),
+        ("H".to_string(), Some(5)),
+    ]);
+    let stored: Hstore = sql("SELECT attributes FROM products WHERE sku = 'H'").get_result(&db).unwrap();
+    assert_eq!(stored, [("color", "white"), ("size", "XL")]);
+}
+
+#[test]
+fn validation_hook() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let rows = vec![
+        row(Some("A"), None, Some("color=>red")),
+        row(Some("B"), None, Some("color=>red, internal=>1")),
+    ];
+    let report = import()
+        .validate(|column, store| {
+            assert_eq!(column, "attributes");
+            match store.get("internal") {
+                Some(_) => Err("internal keys can't be imported".into()),
+                None => Ok(()),
+            }
+        })
+        .run(&db, &rows)
+        .unwrap();
+
+    assert_eq!(report.imported(), 1);
+    assert_eq!(report.failures()[0].row, 1);
+    assert_eq!(report.failures()[0].kind, FailureKind::Invalid("internal keys can't be imported".into()));
+}
+
+#[test]
+fn complete_import() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let rows: Vec<_> = (0..25).map(|i| vec![Some(format!("SKU-{}", i)), Some(i.to_string()), Some("color=>red".into())]).collect();
+    let report = import().chunk_size(10).run(&db, &rows).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.imported(), 25);
+    assert!(import().run(&db, &[]).unwrap().is_complete());
+}
@@ -0,0 +1,89 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+extern crate serde_json;
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::{BTreeHstore, Hstore, HstoreNullable};
+use diesel_pg_hstore::functions::{hstore_to_json, hstore_to_json_loose, hstore_to_jsonb};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    products {
+        id -> Integer,
+        attributes -> Hstore,
+        published -> Jsonb,
+    }
+}
+
+#[test]
+fn serializes_as_an_object() {
+    let store = common::hstore(&[("size", "10"), ("color", "red"), ("", "\"quoted\"")]);
+    let json = serde_json::to_string(&store).unwrap();
+    assert_eq!(json, r#"{"":"\"quoted\"","color":"red","size":"10"}"#);
+    assert_eq!(serde_json::from_str::<Hstore>(&json).unwrap(), store);
+}
+
+#[test]
+fn deserializes_loose_values() {
+    let json = r#"{"color": "red", "size": 10, "ratio": 0.5, "active": true, "discontinued": null}"#;
+
+    let store: Hstore = serde_json::from_str(json).unwrap();
+    assert_eq!(store, common::hstore(&[("color", "red"), ("size", "10"), ("ratio", "0.5"), ("active", "true")]));
+
+    let nullable: HstoreNullable = serde_json::from_str(json).unwrap();
+    assert_eq!(nullable.null_keys(), vec!["discontinued"]);
+    assert_eq!(serde_json::to_string(&nullable).unwrap(), r#"{"active":"true","color":"red","discontinued":null,"ratio":"0.5","size":"10"}"#);
+
+    assert!(serde_json::from_str::<Hstore>(r#"{"tags": ["a"]}"#).is_err());
+    assert!(serde_json::from_str::<Hstore>(r#"["a"]"#).is_err());
+}
+
+#[test]
+fn ordered() {
+    let json = r#"{"size": 10, "color": "red", "discontinued": null}"#;
+
+    let sorted: BTreeHstore = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&sorted).unwrap(), r#"{"color":"red","size":"10"}"#);
+}
+
+#[test]
+fn converts_in_sql() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (id SERIAL PRIMARY KEY, attributes hstore NOT NULL, published jsonb NOT NULL);
+        INSERT INTO products (attributes, published) VALUES
+          ('color=>red, size=>10', '{"color": "red", "size": "10"}'),
+          ('color=>blue, size=>12', '{"color": "blue", "size": "10"}');
+    "#).unwrap();
+
+    let stores: Vec<(Hstore, serde_json::Value)> = products::table
+        .select((products::attributes, hstore_to_json(products::attributes)))
+        .order(products::id)
+        .load(&db)
+        .unwrap();
+    for (store, json) in stores {
+        assert_eq!(json, serde_json::to_value(&store).unwrap());
+        assert_eq!(serde_json::from_value::<Hstore>(json).unwrap(), store);
+    }
+
+    let loose: serde_json::Value = diesel::select(hstore_to_json_loose(common::hstore(&[("size", "10"), ("active", "t"), ("color", "red")])))
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(loose, serde_json::json!({"size": 10, "active": true, "color": "red"}));
+    assert_eq!(serde_json::from_value::<Hstore>(loose).unwrap(), common::hstore(&[("size", "10"), ("active", "true"), ("color", "red")]));
+
+    let changed: Vec<i32> = products::table
+        .select(products::id)
+        .filter(hstore_to_jsonb(products::attributes).ne(products::published))
+        .load(&db)
+        .unwrap();
+    assert_eq!(changed, vec![2]);
+}
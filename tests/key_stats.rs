@@ -0,0 +1,67 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::key_stats::{KeyStatistics, StatsError};
+use diesel_pg_hstore::testing;
+
+fn make_table(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (id SERIAL PRIMARY KEY, attributes hstore);
+        INSERT INTO products (attributes)
+          SELECT CASE
+            WHEN i % 10 = 0 THEN NULL
+            WHEN i % 4 = 0 THEN 'color=>red, size=>M'::hstore
+            ELSE 'color=>blue'::hstore
+          END
+          FROM generate_series(1, 1000) i;
+    "#).unwrap();
+}
+
+#[test]
+fn guides_until_gathered() {
+    let db = testing::connection();
+    make_table(&db);
+    let stats = KeyStatistics::new("products").store("attributes");
+    assert_eq!(stats.name(), "products_attributes_keys");
+    assert_eq!(stats.create_sql(), r#"CREATE STATISTICS IF NOT EXISTS "products_attributes_keys" ON (akeys("attributes")) FROM "products""#);
+
+    match stats.read(&db) {
+        Err(StatsError::NoStatistics { create }) => {
+            assert_eq!(create, stats.create_sql());
+            db.execute(&create).unwrap();
+        }
+        other => panic!("expected missing statistics, got {:?}", other),
+    }
+    match stats.read(&db) {
+        Err(StatsError::NotAnalyzed { analyze }) => assert_eq!(analyze, r#"ANALYZE "products""#),
+        other => panic!("expected statistics not gathered, got {:?}", other),
+    }
+
+    db.execute(&stats.analyze_sql()).unwrap();
+    assert!(stats.read(&db).is_ok());
+}
+
+#[test]
+fn reads_frequencies() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let stats = KeyStatistics::new("products")
+        .store("attributes")
+        .statistics_name("products_keys")
+        .create_statistics()
+        .analyze()
+        .read(&db)
+        .unwrap();
+
+    assert!((stats.null_frac() - 0.1).abs() < 0.01, "{}", stats.null_frac());
+    let keys: Vec<&str> = stats.keys().iter().map(|entry| entry.0.as_str()).collect();
+    assert_eq!(keys, vec!["color", "size"]);
+    assert!((stats.frequency("color").unwrap() - 0.9).abs() < 0.01);
+    assert!((stats.selectivity("size") - 0.2).abs() < 0.01);
+    assert_eq!(stats.frequency("weight"), None);
+    assert!(stats.selectivity("weight") <= 0.005);
+}
@@ -0,0 +1,43 @@
+extern crate diesel_pg_hstore;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::labels::{self, LabelRules};
+
+fn labels(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn sanitize_names() {
+    assert_eq!(labels::sanitize_name("region").unwrap(), "region");
+    assert_eq!(labels::sanitize_name("app.version-2").unwrap(), "app_version_2");
+    assert_eq!(labels::sanitize_name("2fa").unwrap(), "_2fa");
+    assert_eq!(labels::sanitize_name("größe").unwrap(), "gr__e");
+    assert_eq!(labels::sanitize_name("_private").unwrap(), "_private");
+    assert_eq!(labels::sanitize_name("__name__"), None);
+    assert_eq!(labels::sanitize_name(".hidden"), Some("_hidden".to_string()));
+    assert_eq!(labels::sanitize_name(""), None);
+}
+
+#[test]
+fn rules() {
+    let mut store = Hstore::new();
+    store.insert("a.b".into(), "dotted".into());
+    store.insert("a-b".into(), "dashed".into());
+    store.insert("c".into(), "".into());
+    store.insert("__meta".into(), "x".into());
+    store.insert("d".into(), "déjà vu".into());
+    store.insert("e".into(), "5".into());
+    store.insert("secret".into(), "hunter2".into());
+
+    let all = store.to_labels(&LabelRules::new());
+    assert_eq!(all.labels, labels(&[("a_b", "dashed"), ("d", "déjà vu"), ("e", "5"), ("secret", "hunter2")]));
+    assert_eq!(all.dropped, ["__meta", "a.b", "c"]);
+
+    let rules = LabelRules::new().drop_keys(vec!["secret", "missing"]).max_labels(2).max_value_len(2);
+    let capped = store.to_labels(&rules);
+    assert_eq!(capped.labels, labels(&[("a_b", "da"), ("d", "d")]));
+    assert_eq!(capped.dropped, ["__meta", "a.b", "c", "e", "secret"]);
+
+    assert_eq!(Hstore::new().to_labels(&rules).labels, labels(&[]));
+}
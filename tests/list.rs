@@ -0,0 +1,95 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::list::ListCodec;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn codec() {
+    let codec = ListCodec::new('|', '\\');
+    let items = vec!["plain", "pipe|d", "back\\slash", "both\\|", "\\1", ""];
+
+    let encoded = codec.encode(&items);
+    assert_eq!(encoded.matches('|').count(), items.len() - 1);
+    assert_eq!(codec.decode(&encoded), items);
+
+    assert_eq!(codec.decode(""), Vec::<String>::new());
+    assert_eq!(codec.decode("a||b"), vec!["a", "", "b"]);
+}
+
+#[test]
+#[should_panic]
+fn codec_requires_distinct_characters() {
+    ListCodec::new(',', ',');
+}
+
+#[test]
+fn server_side_edits() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let codec = ListCodec::default();
+    let mut store = Hstore::new();
+    store.set_list("tags", &["a", "b,c", "a"], &codec);
+    store.insert("other".into(), "x".into());
+
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    let load = || -> Hstore {
+        hstore_table::table.select(hstore_table::store).first(&db).unwrap()
+    };
+
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.list_append("tags", "d^e", &codec)))
+        .execute(&db)
+        .unwrap();
+    assert_eq!(load().get_list("tags", &codec).unwrap(), vec!["a", "b,c", "a", "d^e"]);
+
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.list_remove("tags", "a", &codec)))
+        .execute(&db)
+        .unwrap();
+    assert_eq!(load().get_list("tags", &codec).unwrap(), vec!["b,c", "d^e"]);
+
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.list_remove("tags", "b,c", &codec)))
+        .execute(&db)
+        .unwrap();
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.list_remove("tags", "d^e", &codec)))
+        .execute(&db)
+        .unwrap();
+    assert_eq!(load()["tags"], "");
+
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.list_append("new", "first", &codec)))
+        .execute(&db)
+        .unwrap();
+    diesel::update(hstore_table::table)
+        .set(hstore_table::store.eq(hstore_table::store.list_remove("missing", "x", &codec)))
+        .execute(&db)
+        .unwrap();
+
+    let store = load();
+    assert_eq!(store.get_list("new", &codec).unwrap(), vec!["first"]);
+    assert!(!store.contains_key("missing"));
+    assert_eq!(store["other"], "x");
+}
@@ -0,0 +1,74 @@
+extern crate diesel_pg_hstore;
+extern crate valuable;
+
+use valuable::{Valuable, Value, Visit};
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::audit::AuditFormat;
+
+/// Collects the entries of a visited map
+#[derive(Default)]
+struct Entries(Vec<(String, String)>);
+
+impl Visit for Entries {
+    fn visit_value(&mut self, value: Value) {
+        match value {
+            Value::Mappable(map) => map.visit(self),
+            other => panic!("expected a map, got {:?}", other),
+        }
+    }
+
+    fn visit_entry(&mut self, key: Value, value: Value) {
+        match (key, value) {
+            (Value::String(key), Value::String(value)) => self.0.push((key.into(), value.into())),
+            other => panic!("expected strings, got {:?}", other),
+        }
+    }
+}
+
+fn visit<V: Valuable>(value: &V) -> Vec<(String, String)> {
+    let mut entries = Entries::default();
+    valuable::visit(value, &mut entries);
+    entries.0
+}
+
+fn store() -> Hstore {
+    let mut store = Hstore::new();
+    store.insert("region".into(), "eu".into());
+    store.insert("db_password".into(), "hunter2".into());
+    store.insert("name".into(), "say \"hi\"".into());
+    store
+}
+
+#[test]
+fn debug_fields() {
+    let store = store();
+    assert_eq!(
+        format!("{:?}", store.log_value()),
+        r#"{"db_password": "hunter2", "name": "say \"hi\"", "region": "eu"}"#
+    );
+
+    let format = AuditFormat::new().redact_if(|key| key.contains("password")).placeholder("***");
+    assert_eq!(
+        format!("{:?}", store.log_value_redacted(&format)),
+        r#"{"db_password": "***", "name": "say \"hi\"", "region": "eu"}"#
+    );
+}
+
+#[test]
+fn valuable_maps() {
+    let store = store();
+    let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    assert_eq!(visit(&store), pairs(&[("db_password", "hunter2"), ("name", "say \"hi\""), ("region", "eu")]));
+
+    let format = AuditFormat::new().redact("db_password");
+    let redacted = store.log_value_redacted(&format);
+    assert_eq!(visit(&redacted), pairs(&[("db_password", "[redacted]"), ("name", "say \"hi\""), ("region", "eu")]));
+    match redacted.as_value() {
+        Value::Mappable(map) => assert_eq!(map.size_hint(), (3, Some(3))),
+        other => panic!("expected a map, got {:?}", other),
+    }
+}
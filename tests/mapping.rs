@@ -0,0 +1,95 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::mapping::{FromHstore, FromHstoreError, Mapped, ToHstore};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    accounts {
+        id -> Integer,
+        settings -> Hstore,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, ToHstore, FromHstore)]
+struct Settings {
+    retries: u32,
+    #[hstore(rename = "ui.theme")]
+    theme: Option<String>,
+    ratio: f64,
+}
+
+#[derive(Queryable)]
+struct Account {
+    id: i32,
+    settings: Mapped<Settings>,
+}
+
+fn setup(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE accounts (id INTEGER PRIMARY KEY, settings hstore NOT NULL);
+        INSERT INTO accounts VALUES (1, 'retries=>3, ratio=>0.5, ui.theme=>dark, legacy=>1');
+    "#).unwrap();
+}
+
+#[test]
+fn derived() {
+    let settings = Settings {
+        retries: 2,
+        theme: None,
+        ratio: 1.5,
+    };
+    let hstore = settings.to_hstore();
+    assert_eq!(hstore, [("retries", "2"), ("ratio", "1.5")]);
+    assert_eq!(Settings::from_hstore(&hstore), Ok(settings.clone()));
+
+    let themed = Settings {
+        theme: Some("dark".into()),
+        ..settings
+    };
+    assert_eq!(themed.to_hstore()["ui.theme"], "dark");
+}
+
+#[test]
+fn errors() {
+    let missing = Settings::from_hstore(&"ratio=>1".parse().unwrap()).unwrap_err();
+    assert_eq!(missing, FromHstoreError::MissingKey("retries".into()));
+
+    let invalid = Settings::from_hstore(&"retries=>-1, ratio=>1".parse().unwrap()).unwrap_err();
+    assert_eq!(invalid.key(), "retries");
+    assert_eq!(invalid.to_string(), r#"invalid value "-1" for hstore key "retries": invalid digit found in string"#);
+}
+
+#[test]
+fn round_trip() {
+    let db = testing::connection();
+    setup(&db);
+
+    let account: Account = accounts::table.find(1).first(&db).unwrap();
+    assert_eq!(account.id, 1);
+    assert_eq!(*account.settings, Settings {
+        retries: 3,
+        theme: Some("dark".into()),
+        ratio: 0.5,
+    });
+
+    let mut settings = account.settings;
+    settings.retries = 5;
+    settings.theme = None;
+    diesel::update(accounts::table.find(1))
+        .set(accounts::settings.eq(&settings))
+        .execute(&db)
+        .unwrap();
+
+    let saved: Hstore = accounts::table.find(1).select(accounts::settings).first(&db).unwrap();
+    assert_eq!(saved, [("retries", "5"), ("ratio", "0.5")]);
+}
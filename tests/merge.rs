@@ -0,0 +1,100 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, QueryFragment};
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::merge::{ConcatMerge, HstoreMergeStrategy, MergeDeleteMissing, Replace};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel_pg_hstore::table_import::*;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+/// Keeps the stored values, only adding keys which are missing
+struct KeepExisting;
+
+impl HstoreMergeStrategy for KeepExisting {
+    fn walk_merge(&self, store: &QueryFragment<Pg>, new: &Hstore, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("(");
+        out.push_bind_param::<Hstore, _>(new)?;
+        out.push_sql(" || ");
+        store.walk_ast(out.reborrow())?;
+        out.push_sql(")");
+        Ok(())
+    }
+
+    fn merge(&self, old: &Hstore, new: &Hstore) -> Hstore {
+        let mut merged = new.clone();
+        merged.extend(old.iter().map(|(key, value)| (key.clone(), value.clone())));
+        merged
+    }
+}
+
+fn old() -> Hstore {
+    vec![("color", "red"), ("size", "M"), ("sku", "A1")].into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn new() -> Hstore {
+    let mut new = Hstore::new();
+    new.insert("color".into(), "blue".into());
+    new.insert("fit".into(), "slim".into());
+    new
+}
+
+/// Save `new` over `old` in the database with `strategy`, checking it agrees with the merge in
+/// memory
+fn save<M: HstoreMergeStrategy>(db: &PgConnection, strategy: M) -> Hstore {
+    db.batch_execute("DELETE FROM hstore_table").unwrap();
+    diesel::insert_into(hstore_table::table)
+        .values((hstore_table::id.eq(1), hstore_table::store.eq(old())))
+        .execute(db)
+        .unwrap();
+
+    let expected = strategy.merge(&old(), &new());
+    let saved = diesel::update(hstore_table::table.find(1))
+        .set(hstore_table::store.eq(hstore_table::store.merged(new(), strategy)))
+        .returning(hstore_table::store)
+        .get_result::<Hstore>(db)
+        .unwrap();
+    assert_eq!(saved, expected);
+    saved
+}
+
+#[test]
+fn strategies() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    assert_eq!(save(&db, Replace), new());
+    assert_eq!(save(&db, ConcatMerge), [("color", "blue"), ("fit", "slim"), ("size", "M"), ("sku", "A1")]);
+
+    let form = MergeDeleteMissing::new(vec!["color", "size", "fit"]);
+    assert_eq!(save(&db, &form), [("color", "blue"), ("fit", "slim"), ("sku", "A1")]);
+    assert_eq!(save(&db, MergeDeleteMissing::default()), save(&db, ConcatMerge));
+
+    assert_eq!(save(&db, KeepExisting), [("color", "red"), ("fit", "slim"), ("size", "M"), ("sku", "A1")]);
+}
+
+#[test]
+fn chosen_at_runtime() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let strategies: Vec<Box<HstoreMergeStrategy>> = vec![Box::new(Replace), Box::new(KeepExisting)];
+    let saved: Vec<Hstore> = strategies.into_iter().map(|strategy| save(&db, strategy)).collect();
+    assert_eq!(saved[0], new());
+    assert_eq!(saved[1]["color"], "red");
+}
@@ -0,0 +1,72 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use std::sync::Arc;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::pg::Pg;
+use diesel::types::FromSql;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::metrics::{self, CodecObserver, CodecStats};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+// The observer is global, so everything is checked from a single test.
+#[test]
+fn codec_stats() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (store)
+          VALUES ('a=>1, b=>NULL, c=>NULL'::hstore);
+    "#).unwrap();
+
+    let stats = Arc::new(CodecStats::with_size_limit(10));
+    metrics::set_observer(stats.clone());
+
+    let mut store = Hstore::new();
+    store.insert("small".into(), "x".into());
+    store.insert("large".into(), "x".repeat(11));
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+    assert_eq!(stats.encoded(), 1);
+    assert_eq!(stats.oversized_values(), 1);
+
+    let _: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .load(&db)
+        .unwrap();
+    assert_eq!(stats.decoded(), 2);
+    assert_eq!(stats.nulls_dropped(), 2);
+    assert_eq!(stats.oversized_values(), 2);
+
+    assert!(<Hstore as FromSql<Hstore, Pg>>::from_sql(Some(&[0, 0, 0, 1])).is_err());
+    assert_eq!(stats.decode_failures(), 1);
+
+    metrics::clear_observer();
+    let _: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .load(&db)
+        .unwrap();
+    assert_eq!(stats.decoded(), 2);
+}
+
+#[test]
+fn default_stats_have_no_size_limit() {
+    assert_eq!(CodecStats::default().value_size_limit(), usize::max_value());
+    assert_eq!(CodecStats::new().value_size_limit(), usize::max_value());
+}
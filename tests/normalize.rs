@@ -0,0 +1,69 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::normalize::KeyNormalizer;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn normalize() {
+    let normalizer = KeyNormalizer::new().trim(true).lowercase(true).nfc(true);
+
+    assert_eq!(normalizer.normalize("  Größe\t"), "größe");
+    assert_eq!(normalizer.normalize("Cafe\u{301}"), "caf\u{e9}");
+    assert!(normalizer.is_normalized("plain"));
+    assert_eq!(KeyNormalizer::new().normalize(" As Is "), " As Is ");
+}
+
+#[test]
+fn collisions() {
+    let normalizer = KeyNormalizer::new().trim(true).lowercase(true);
+
+    let mut store: Hstore = vec![
+        ("Name ", "second"),
+        (" name", "first"),
+        ("other", "x"),
+    ].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    store.normalize_keys(&normalizer);
+    assert_eq!(store.len(), 2);
+    assert_eq!(store["name"], "first");
+
+    store.insert_normalized("NAME", "canonical", &normalizer);
+    store.insert(" Other".into(), "y".into());
+    store.normalize_keys(&normalizer);
+    assert_eq!(store.len(), 2);
+    assert_eq!(store["name"], "canonical");
+    assert_eq!(store["other"], "x");
+}
+
+#[test]
+fn before_serialization() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let normalizer = KeyNormalizer::new().trim(true);
+
+    let mut store = Hstore::new();
+    store.insert(" padded ".into(), "value".into());
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(normalizer.normalized(&store)))
+        .execute(&db)
+        .unwrap();
+
+    assert!(store.contains_key(" padded "));
+    let loaded: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded["padded"], "value");
+}
@@ -0,0 +1,76 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::types::Bool;
+
+use diesel_pg_hstore::{Hstore, HstoreNullable};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[derive(Queryable, Debug)]
+struct Product {
+    id: i32,
+    store: HstoreNullable,
+}
+
+fn entries(pairs: &[(&str, Option<&str>)]) -> HstoreNullable {
+    pairs.iter().map(|&(key, value)| (key.to_string(), value.map(String::from))).collect()
+}
+
+#[test]
+fn reads_null_values() {
+    let db = testing::connection();
+
+    let store: HstoreNullable = sql("SELECT 'color=>red, discontinued=>NULL, note=>\"NULL\"'::hstore").get_result(&db).unwrap();
+    assert_eq!(store, entries(&[("color", Some("red")), ("discontinued", None), ("note", Some("NULL"))]));
+    assert_eq!(store.null_keys(), vec!["discontinued"]);
+
+    let lossy: Hstore = sql("SELECT 'color=>red, discontinued=>NULL, note=>\"NULL\"'::hstore").get_result(&db).unwrap();
+    assert_eq!(lossy, store.into_hstore());
+}
+
+#[test]
+fn round_trips() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let store = entries(&[("color", Some("red")), ("discontinued", None), ("", Some(""))]);
+    diesel::insert_into(hstore_table::table)
+        .values((hstore_table::id.eq(1), hstore_table::store.eq(&store)))
+        .execute(&db)
+        .unwrap();
+
+    let is_null: bool = sql::<Bool>("SELECT (store -> 'discontinued') IS NULL AND store ? 'discontinued' FROM hstore_table").get_result(&db).unwrap();
+    assert!(is_null);
+    let has_null: bool = sql::<Bool>("SELECT exist(store, 'discontinued') FROM hstore_table").get_result(&db).unwrap();
+    assert!(has_null);
+
+    let product = hstore_table::table.first::<Product>(&db).unwrap();
+    assert_eq!(product.id, 1);
+    assert_eq!(product.store, store);
+
+    let as_hstore: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(as_hstore, store.into_hstore());
+}
+
+#[test]
+fn from_hstore() {
+    let mut hstore = Hstore::new();
+    hstore.insert("a".into(), "1".into());
+    let store = HstoreNullable::from(hstore);
+    assert_eq!(store, entries(&[("a", Some("1"))]));
+    assert!(store.null_keys().is_empty());
+    assert_eq!(format!("{:?}", entries(&[("b", None), ("a", Some("1"))])), r#"HstoreNullable({"a": Some("1"), "b": None})"#);
+}
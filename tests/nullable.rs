@@ -0,0 +1,129 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    accounts {
+        id -> Integer,
+        settings -> Nullable<Hstore>,
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "accounts"]
+struct NewAccount {
+    id: i32,
+    settings: Option<Hstore>,
+}
+
+fn setup(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE accounts (id INTEGER PRIMARY KEY, settings hstore);
+        INSERT INTO accounts VALUES (1, 'theme=>dark, lang=>en'), (2, NULL), (3, '');
+    "#).unwrap();
+}
+
+#[test]
+fn option_round_trip() {
+    let db = testing::connection();
+    setup(&db);
+
+    let mut settings = Hstore::new();
+    settings.insert("theme".into(), "light".into());
+    diesel::insert_into(accounts::table)
+        .values(&vec![
+            NewAccount { id: 4, settings: Some(settings.clone()) },
+            NewAccount { id: 5, settings: None },
+        ])
+        .execute(&db)
+        .unwrap();
+    diesel::insert_into(accounts::table)
+        .values((accounts::id.eq(6), accounts::settings.eq(None::<Hstore>)))
+        .execute(&db)
+        .unwrap();
+
+    let loaded: Vec<(i32, Option<Hstore>)> = accounts::table
+        .filter(accounts::id.gt(3))
+        .order(accounts::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(loaded, vec![(4, Some(settings)), (5, None), (6, None)]);
+}
+
+/// A selection of the operators on a nullable hstore
+type Row = (Option<String>, Option<bool>, Option<i32>, Option<String>);
+
+#[test]
+fn operators() {
+    let db = testing::connection();
+    setup(&db);
+
+    let rows: Vec<Row> = accounts::table
+        .select((
+            accounts::settings.get_value("theme"),
+            accounts::settings.has_key("lang"),
+            accounts::settings.remove_key("lang").text_length(),
+            accounts::settings.cast_to_text(),
+        ))
+        .order(accounts::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(rows, vec![
+        (Some("dark".to_string()), Some(true), Some(r#""theme"=>"dark""#.len() as i32), Some(r#""lang"=>"en", "theme"=>"dark""#.to_string())),
+        (None, None, None, None),
+        (None, Some(false), Some(0), Some(String::new())),
+    ]);
+}
+
+#[test]
+fn defaults_and_arrays() {
+    let db = testing::connection();
+    setup(&db);
+
+    let rows: Vec<(String, Option<Vec<Option<String>>>)> = accounts::table
+        .select((
+            accounts::settings.get_value_or("theme", "light"),
+            accounts::settings.get_array(vec!["lang", "theme"]),
+        ))
+        .order(accounts::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(rows, vec![
+        ("dark".to_string(), Some(vec![Some("en".to_string()), Some("dark".to_string())])),
+        ("light".to_string(), None),
+        ("light".to_string(), Some(vec![None, None])),
+    ]);
+}
+
+#[test]
+fn concat() {
+    let db = testing::connection();
+    setup(&db);
+
+    let mut patch = Hstore::new();
+    patch.insert("theme".into(), "light".into());
+    diesel::update(accounts::table)
+        .set(accounts::settings.eq(accounts::settings.concat(&patch)))
+        .execute(&db)
+        .unwrap();
+
+    let stores: Vec<Option<Hstore>> = accounts::table
+        .select(accounts::settings)
+        .order(accounts::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(stores[0].as_ref().map(|store| store["theme"].as_str()), Some("light"));
+    assert_eq!(stores[0].as_ref().map(|store| store["lang"].as_str()), Some("en"));
+    assert_eq!(stores[1], None);
+    assert_eq!(stores[2], Some(patch));
+}
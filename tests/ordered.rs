@@ -0,0 +1,84 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::{BTreeHstore, Hstore, IndexHstore};
+use diesel_pg_hstore::dsl::HstoreOpExtensions;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    products {
+        id -> Integer,
+        attributes -> Hstore,
+        extra -> Nullable<Hstore>,
+    }
+}
+
+fn setup(db: &PgConnection) {
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (id INTEGER PRIMARY KEY, attributes hstore NOT NULL, extra hstore);
+        INSERT INTO products VALUES (1, 'weight=>2kg, color=>red, size=>10, a=>1', NULL);
+    "#).unwrap();
+}
+
+fn keys<'a, I: IntoIterator<Item = (&'a String, &'a String)>>(entries: I) -> Vec<&'a str> {
+    entries.into_iter().map(|(key, _)| key.as_str()).collect()
+}
+
+#[test]
+fn load() {
+    let db = testing::connection();
+    setup(&db);
+
+    let sorted: BTreeHstore = products::table.select(products::attributes).first(&db).unwrap();
+    assert_eq!(keys(&sorted), vec!["a", "color", "size", "weight"]);
+
+    let stored: IndexHstore = products::table.select(products::attributes).first(&db).unwrap();
+    assert_eq!(keys(&stored), vec!["a", "size", "color", "weight"]);
+
+    let hstore: Hstore = products::table.select(products::attributes).first(&db).unwrap();
+    assert_eq!(Hstore::from(sorted.clone()), hstore);
+    assert_eq!(Hstore::from(stored), hstore);
+    assert_eq!(BTreeHstore::from(hstore), sorted);
+
+    let missing: Option<BTreeHstore> = products::table.select(products::extra).first(&db).unwrap();
+    assert_eq!(missing, None);
+}
+
+#[test]
+fn bind() {
+    let db = testing::connection();
+    setup(&db);
+
+    let mut extra = IndexHstore::new();
+    extra.insert("zone".into(), "eu".into());
+    extra.insert("b".into(), "2".into());
+    let sorted: BTreeHstore = extra.clone().into_iter().collect();
+
+    diesel::insert_into(products::table)
+        .values((products::id.eq(2), products::attributes.eq(&sorted), products::extra.eq(&extra)))
+        .execute(&db)
+        .unwrap();
+
+    let loaded: Vec<(i32, BTreeHstore, Option<IndexHstore>)> = products::table
+        .filter(products::attributes.eq(&sorted))
+        .load(&db)
+        .unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].0, 2);
+    assert_eq!(loaded[0].1, sorted);
+    assert_eq!(keys(loaded[0].2.as_ref().unwrap()), vec!["b", "zone"]);
+
+    let merged: BTreeHstore = products::table
+        .find(1)
+        .select(products::attributes.concat(extra))
+        .first(&db)
+        .unwrap();
+    assert_eq!(keys(&merged), vec!["a", "b", "color", "size", "weight", "zone"]);
+}
@@ -0,0 +1,85 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::patch::{update_returning_diff, DiffingUpdate, HstorePatch};
+use diesel_pg_hstore::testing;
+
+fn make_table(db: &PgConnection) {
+    testing::create_table(db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (1, 'status=>draft, draft=>1'),
+          (2, 'status=>archived'),
+          (3, 'status=>draft');
+    "#).unwrap();
+}
+
+#[test]
+fn diff() {
+    let old = Hstore::new();
+    assert!(HstorePatch::diff(&old, &old).is_empty());
+
+    let mut new = Hstore::new();
+    new.insert("a".into(), "1".into());
+    let patch = HstorePatch::diff(&old, &new);
+    assert_eq!(patch, HstorePatch::new().set("a", "1"));
+
+    let mut applied = old.clone();
+    patch.apply_to(&mut applied);
+    assert_eq!(applied, new);
+    assert_eq!(HstorePatch::diff(&new, &old), HstorePatch::new().delete("a"));
+}
+
+#[test]
+fn returns_what_changed() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let patch = HstorePatch::new().set("status", "archived").delete("draft");
+    let changed = DiffingUpdate::new("hstore_table", patch)
+        .filter_sql("id <> 3")
+        .run(&db)
+        .unwrap();
+    assert_eq!(changed, vec![
+        (1, HstorePatch::new().set("status", "archived").delete("draft")),
+        (2, HstorePatch::new()),
+    ]);
+
+    let stores: Vec<Hstore> = sql("SELECT store FROM hstore_table ORDER BY id").load(&db).unwrap();
+    assert_eq!(stores[0], [("status", "archived")]);
+    assert_eq!(stores[2], [("status", "draft")]);
+}
+
+#[test]
+fn new_value_from_sql() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let changed = DiffingUpdate::new_sql("hstore_table", "store || hstore('id', id::text)")
+        .store("store")
+        .run(&db)
+        .unwrap();
+    assert_eq!(changed.len(), 3);
+    assert_eq!(changed[2], (3, HstorePatch::new().set("id", "3")));
+}
+
+#[test]
+fn single_row() {
+    let db = testing::connection();
+    make_table(&db);
+
+    let patch = HstorePatch::new().set("status", "draft");
+    let diff = update_returning_diff(&db, "hstore_table", "store", 2, &patch).unwrap();
+    assert_eq!(diff, Some(HstorePatch::new().set("status", "draft")));
+
+    let diff = update_returning_diff(&db, "hstore_table", "store", 3, &patch).unwrap();
+    assert_eq!(diff, Some(HstorePatch::new()));
+
+    let missing = update_returning_diff(&db, "hstore_table", "store", 99, &patch).unwrap();
+    assert_eq!(missing, None);
+}
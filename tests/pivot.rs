@@ -0,0 +1,53 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::types::{Integer, Nullable, Text};
+
+use diesel_pg_hstore::pivot::Pivot;
+use diesel_pg_hstore::testing;
+
+#[derive(Queryable, Debug, PartialEq)]
+struct Attributes {
+    category: i32,
+    color: Option<String>,
+    size: Option<String>,
+}
+
+#[test]
+fn pivot() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE products (id SERIAL PRIMARY KEY, category INTEGER NOT NULL, attributes hstore NOT NULL);
+        INSERT INTO products (category, attributes) VALUES
+          (1, 'color=>red, size=>L, other=>x'),
+          (1, 'color=>blue'),
+          (2, 'size=>S'),
+          (3, '');
+    "#).unwrap();
+
+    let pivot = Pivot::new("products", "attributes")
+        .group_by("category")
+        .key("color")
+        .key_as("size", "size");
+
+    let rows: Vec<Attributes> = pivot
+        .query::<(Integer, Nullable<Text>, Nullable<Text>)>()
+        .load(&db)
+        .unwrap();
+    assert_eq!(rows, vec![
+        Attributes { category: 1, color: Some("red".into()), size: Some("L".into()) },
+        Attributes { category: 2, color: None, size: Some("S".into()) },
+        Attributes { category: 3, color: None, size: None },
+    ]);
+
+    let totals: (Option<String>, Option<String>) = Pivot::new("products", "attributes")
+        .key("other")
+        .key("missing")
+        .query::<(Nullable<Text>, Nullable<Text>)>()
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(totals, (Some("x".into()), None));
+}
@@ -0,0 +1,47 @@
+extern crate diesel_pg_hstore;
+extern crate polars_core;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::polars_frame;
+
+fn rows() -> Vec<(i64, Hstore)> {
+    let mut first = Hstore::new();
+    first.insert("size".into(), "L".into());
+    first.insert("color".into(), "red".into());
+    let mut second = Hstore::new();
+    second.insert("weight".into(), "3kg".into());
+    vec![(1, first), (2, Hstore::new()), (3, second)]
+}
+
+#[test]
+fn exploded() {
+    let frame = polars_frame::exploded(&rows()).unwrap();
+    assert_eq!(frame.get_column_names(), ["id", "key", "value"]);
+    assert_eq!(frame.height(), 3);
+
+    let ids: Vec<_> = frame.column("id").unwrap().i64().unwrap().into_no_null_iter().collect();
+    assert_eq!(ids, [1, 1, 3]);
+    let keys: Vec<_> = frame.column("key").unwrap().str().unwrap().into_no_null_iter().collect();
+    assert_eq!(keys, ["color", "size", "weight"]);
+    let values: Vec<_> = frame.column("value").unwrap().str().unwrap().into_no_null_iter().collect();
+    assert_eq!(values, ["red", "L", "3kg"]);
+
+    assert_eq!(polars_frame::exploded(&[]).unwrap().height(), 0);
+}
+
+#[test]
+fn struct_column() {
+    let frame = polars_frame::struct_column(&rows(), "attributes").unwrap();
+    assert_eq!(frame.get_column_names(), ["id", "attributes"]);
+    assert_eq!(frame.height(), 3);
+
+    let attributes = frame.column("attributes").unwrap().struct_().unwrap();
+    let names: Vec<_> = attributes.fields_as_series().iter().map(|field| field.name().to_string()).collect();
+    assert_eq!(names, ["color", "size", "weight"]);
+
+    let unnested = attributes.clone().unnest();
+    let weight: Vec<_> = unnested.column("weight").unwrap().str().unwrap().into_iter().collect();
+    assert_eq!(weight, [None, None, Some("3kg")]);
+    let color: Vec<_> = unnested.column("color").unwrap().str().unwrap().into_iter().collect();
+    assert_eq!(color, [Some("red"), None, None]);
+}
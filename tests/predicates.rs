@@ -0,0 +1,88 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::debug_query;
+use diesel::pg::Pg;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::predicates::{FilterRegistry, FilterTemplate};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel_pg_hstore::table_import::*;
+
+    services {
+        id -> Integer,
+        labels -> Hstore,
+        annotations -> Hstore,
+    }
+}
+
+fn registry() -> FilterRegistry {
+    FilterRegistry::new()
+        .define("active_prod", FilterTemplate::new().contains("env", "prod").contains("active", "t"))
+        .define("tagged", FilterTemplate::new().has_any_keys(vec!["tag", "label"]))
+        .define("owned", FilterTemplate::new().has_key("owner").has_all_keys(vec!["team", "pager"]))
+        .define("everything", FilterTemplate::new())
+}
+
+#[test]
+fn renders_conditions() {
+    let registry = registry();
+
+    let query = services::table.select(services::id).filter(registry.filter("owned", services::labels).unwrap());
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    assert!(sql.contains(r#"WHERE ("services"."labels" ? $1 AND "services"."labels" ?& $2)"#), "{}", sql);
+
+    let query = services::table.select(services::id).filter(registry.filter("everything", services::labels).unwrap());
+    assert!(debug_query::<Pg, _>(&query).to_string().contains("WHERE TRUE"));
+
+    assert!(registry.filter("missing", services::labels).is_none());
+}
+
+#[test]
+fn boxed_on_any_column() {
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TEMPORARY TABLE services (id SERIAL PRIMARY KEY, labels hstore NOT NULL, annotations hstore NOT NULL);
+        INSERT INTO services (labels, annotations) VALUES
+          ('env=>prod, active=>t', 'tag=>x'),
+          ('env=>prod, active=>f', ''),
+          ('env=>dev, active=>t, label=>y', 'env=>prod, active=>t');
+    "#).unwrap();
+
+    let registry = registry();
+    let find = |name: &str, on_annotations: bool| -> Vec<i32> {
+        let filter = if on_annotations {
+            registry.boxed::<services::table, _>(name, services::annotations)
+        }
+        else {
+            registry.boxed::<services::table, _>(name, services::labels)
+        };
+        services::table
+            .select(services::id)
+            .filter(filter.unwrap())
+            .order(services::id)
+            .load(&db)
+            .unwrap()
+    };
+
+    assert_eq!(find("active_prod", false), [1]);
+    assert_eq!(find("active_prod", true), [3]);
+    assert_eq!(find("tagged", false), [3]);
+    assert_eq!(find("tagged", true), [1]);
+    assert_eq!(find("everything", true), [1, 2, 3]);
+
+    let mut required = Hstore::new();
+    required.insert("env".into(), "dev".into());
+    let registry = FilterRegistry::new().define("dev", FilterTemplate::new().contains_all(required).has_key("label"));
+    let dev: Vec<i32> = services::table
+        .select(services::id)
+        .filter(registry.boxed::<services::table, _>("dev", services::labels).unwrap())
+        .load(&db)
+        .unwrap();
+    assert_eq!(dev, [3]);
+}
@@ -0,0 +1,44 @@
+extern crate diesel_pg_hstore;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::pretty::PrettyOptions;
+
+fn store() -> Hstore {
+    let mut store = Hstore::new();
+    store.insert("b".into(), "2".into());
+    store.insert("a".into(), "1".into());
+    store.insert("long key".into(), "".into());
+    store.insert("c".into(), "x=>y".into());
+    store
+}
+
+#[test]
+fn quotes_ambiguous_text() {
+    assert_eq!(store().pretty(&PrettyOptions::new()), r#"a => 1, b => 2, c => "x=>y", "long key" => """#);
+    assert_eq!(Hstore::new().pretty(&PrettyOptions::new().multi_line(true)), "");
+}
+
+#[test]
+fn single_line_width() {
+    let options = PrettyOptions::new().max_width(22);
+    assert_eq!(store().pretty(&options), "a => 1, … (3 more)");
+
+    let options = PrettyOptions::new().max_width(5);
+    assert_eq!(store().pretty(&options), "… (4 more)");
+
+    let options = PrettyOptions::new().max_width(100);
+    assert_eq!(store().pretty(&options), store().pretty(&PrettyOptions::new()));
+}
+
+#[test]
+fn multi_line_width() {
+    let options = PrettyOptions::new().multi_line(true).align(true).max_width(15);
+    assert_eq!(store().pretty(&options), "\
+a          => 1
+b          => 2
+c          => …
+\"long key\" => …");
+
+    let options = PrettyOptions::new().multi_line(true).max_value_len(1);
+    assert_eq!(store().pretty(&options), "a => 1\nb => 2\nc => …\n\"long key\" => \"\"");
+}
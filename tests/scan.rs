@@ -0,0 +1,46 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::scan::Scan;
+use diesel_pg_hstore::testing;
+
+#[test]
+fn rows_in_pages() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store)
+          SELECT i, hstore('n', i::text) FROM generate_series(1, 25) AS i;
+    "#).unwrap();
+
+    let scan = Scan::new("hstore_table").fetch_size(10);
+    let rows: Vec<_> = scan.rows(&db).map(|row| row.unwrap()).collect();
+    assert_eq!(rows.len(), 25);
+    for (i, &(id, ref store)) in rows.iter().enumerate() {
+        assert_eq!(id, i as i64 + 1);
+        assert_eq!(store["n"], id.to_string());
+    }
+
+    let odd = Scan::new("hstore_table").filter_sql("id % 2 = 1").fetch_size(5);
+    let ids: Vec<i64> = odd.rows(&db).map(|row| row.unwrap().0).collect();
+    assert_eq!(ids.len(), 13);
+    assert_eq!(ids[12], 25);
+
+    let exact = Scan::new("hstore_table").fetch_size(25);
+    assert_eq!(exact.rows(&db).count(), 25);
+}
+
+#[test]
+fn errors_end_the_rows() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    assert_eq!(Scan::new("hstore_table").rows(&db).count(), 0);
+
+    let scan = Scan::new("hstore_table").store("missing");
+    let mut rows = scan.rows(&db);
+    assert!(rows.next().unwrap().is_err());
+    assert!(rows.next().is_none());
+}
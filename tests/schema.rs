@@ -0,0 +1,122 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+#[macro_use]
+extern crate lazy_static;
+extern crate regex;
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use regex::Regex;
+
+use diesel_pg_hstore::schema::{HstoreSchema, Schema, Validated, Violation, ViolationFinder};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+struct Contact;
+
+impl Schema for Contact {
+    fn schema() -> &'static HstoreSchema {
+        lazy_static! {
+            static ref SCHEMA: HstoreSchema = HstoreSchema::new()
+                .required("email")
+                .allowed("phone")
+                .matches("email", Regex::new("^[^@]+@[^@]+$").unwrap())
+                .parses::<u8>("age")
+                .deny_unknown_keys();
+        }
+        &SCHEMA
+    }
+}
+
+#[test]
+fn validate() {
+    let schema = Contact::schema();
+
+    assert_eq!(schema.validate(&common::hstore(&[("email", "a@b"), ("phone", "555"), ("age", "30")])), Ok(()));
+
+    let violations = schema.validate(&common::hstore(&[("age", "300"), ("fax", "1"), ("telex", "2")])).unwrap_err();
+    assert_eq!(violations, vec![
+        Violation::Missing { key: "email".into() },
+        Violation::Unknown { key: "fax".into() },
+        Violation::Unknown { key: "telex".into() },
+        Violation::InvalidValue {
+            key: "age".into(),
+            value: "300".into(),
+            message: "number too large to fit in target type".into(),
+        },
+    ]);
+
+    let violations = schema.validate(&common::hstore(&[("email", "nobody")])).unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].to_string(), r#"invalid value "nobody" for key "email": does not match ^[^@]+@[^@]+$"#);
+
+    assert_eq!(HstoreSchema::new().validate(&common::hstore(&[("anything", "")])), Ok(()));
+}
+
+#[test]
+fn enforced_on_write() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let invalid = Validated::<Contact>::from_hstore(common::hstore(&[("phone", "555")]));
+    let err = diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&invalid))
+        .execute(&db)
+        .unwrap_err();
+    assert!(err.to_string().contains("missing required key \"email\""), "{}", err);
+
+    let mut valid = invalid.clone();
+    valid.insert("email".into(), "a@b".into());
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&valid))
+        .execute(&db)
+        .unwrap();
+
+    let loaded: Validated<Contact> = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded, valid);
+}
+
+#[test]
+fn find_violations() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (1, 'email=>a@b, phone=>555'),
+          (2, 'phone=>555, fax=>1'),
+          (3, 'email=>nobody, age=>300');
+    "#).unwrap();
+
+    let violations = ViolationFinder::new(Contact::schema(), "hstore_table", "store", "id")
+        .find(&db)
+        .unwrap();
+    let found: Vec<(&str, &Violation)> = violations.iter()
+        .map(|v| (v.row(), v.violation()))
+        .collect();
+
+    // The age check is a Rust parser, so it's skipped
+    assert_eq!(found, vec![
+        ("2", &Violation::Missing { key: "email".into() }),
+        ("2", &Violation::Unknown { key: "fax".into() }),
+        ("3", &Violation::InvalidValue {
+            key: "email".into(),
+            value: "nobody".into(),
+            message: "does not match ^[^@]+@[^@]+$".into(),
+        }),
+    ]);
+
+    let everything = HstoreSchema::new();
+    assert!(ViolationFinder::new(&everything, "hstore_table", "store", "id").find(&db).unwrap().is_empty());
+}
@@ -0,0 +1,39 @@
+extern crate config;
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use config::Config;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::settings::HstoreSource;
+use diesel_pg_hstore::testing;
+
+#[test]
+fn layered_over_defaults() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store)
+          VALUES (1, 'database.pool_size=>20, theme=>dark'::hstore);
+    "#).unwrap();
+
+    let mut defaults = Hstore::new();
+    defaults.insert("database.pool_size".into(), "5".into());
+    defaults.insert("database.host".into(), "localhost".into());
+    defaults.insert("theme".into(), "light".into());
+
+    let tenant = HstoreSource::load(&db, "hstore_table", "store", 1).unwrap();
+    assert_eq!(tenant.origin(), "hstore_table.store (id 1)");
+
+    let settings = Config::builder()
+        .add_source(defaults.clone())
+        .add_source(tenant)
+        .add_source(HstoreSource::load(&db, "hstore_table", "store", 2).unwrap())
+        .build()
+        .unwrap();
+
+    assert_eq!(settings.get::<u32>("database.pool_size").unwrap(), 20);
+    assert_eq!(settings.get::<String>("database.host").unwrap(), "localhost");
+    assert_eq!(settings.get::<String>("theme").unwrap(), "dark");
+}
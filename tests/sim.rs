@@ -0,0 +1,85 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::types::{Array, Bool, Text};
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::sim;
+use diesel_pg_hstore::testing;
+
+#[test]
+fn operators() {
+    let a = common::hstore(&[("a", "1"), ("b", "2"), ("c", "3")]);
+    let b = common::hstore(&[("b", "2"), ("c", "x"), ("d", "4")]);
+
+    assert_eq!(sim::fetch(&a, "a"), Some("1"));
+    assert_eq!(sim::fetch(&a, "z"), None);
+    assert_eq!(sim::fetch_many(&a, &["c", "z"]), vec![Some("3"), None]);
+
+    assert_eq!(sim::concat(&a, &b), common::hstore(&[("a", "1"), ("b", "2"), ("c", "x"), ("d", "4")]));
+
+    assert!(sim::exist(&a, "a"));
+    assert!(!sim::defined(&a, "d"));
+    assert!(sim::exists_any(&a, &["z", "a"]));
+    assert!(!sim::exists_any(&a, &[]));
+    assert!(!sim::exists_all(&a, &["a", "z"]));
+    assert!(sim::exists_all(&a, &[]));
+
+    assert!(sim::contains(&a, &common::hstore(&[("a", "1")])));
+    assert!(!sim::contains(&a, &common::hstore(&[("a", "2")])));
+    assert!(sim::contained(&common::hstore(&[("b", "2")]), &b));
+
+    assert_eq!(sim::delete(&a, "a"), common::hstore(&[("b", "2"), ("c", "3")]));
+    assert_eq!(sim::delete_keys(&a, &["a", "b", "z"]), common::hstore(&[("c", "3")]));
+    assert_eq!(sim::delete_pairs(&a, &b), common::hstore(&[("a", "1"), ("c", "3")]));
+    assert_eq!(sim::slice(&a, &["a", "z"]), common::hstore(&[("a", "1")]));
+
+    assert_eq!(sim::from_arrays(&["a", "a"], &["1", "2"]), Some(common::hstore(&[("a", "1")])));
+    assert_eq!(sim::from_arrays(&["a"], &[]), None);
+}
+
+#[test]
+fn matches_postgres_ordering() {
+    let db = testing::connection();
+    let literal = "'bb=>1,a=>2,ab=>3,B=>4,ccc=>5'::hstore";
+    let store = common::hstore(&[("bb", "1"), ("a", "2"), ("ab", "3"), ("B", "4"), ("ccc", "5")]);
+
+    let keys: Vec<String> = diesel::select(sql::<Array<Text>>(&format!("akeys({})", literal)))
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(sim::akeys(&store), keys);
+
+    let values: Vec<String> = diesel::select(sql::<Array<Text>>(&format!("avals({})", literal)))
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(sim::avals(&store), values);
+
+    let flat: Vec<String> = diesel::select(sql::<Array<Text>>(&format!("%% {}", literal)))
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(sim::to_flat_array(&store), flat);
+
+    let contains: bool = diesel::select(sql::<Bool>(&format!("{} @> 'a=>2,B=>4'", literal)))
+        .get_result(&db)
+        .unwrap();
+    assert_eq!(sim::contains(&store, &common::hstore(&[("a", "2"), ("B", "4")])), contains);
+}
+
+#[test]
+fn sum_matches_postgres_concat() {
+    let db = testing::connection();
+    let literals = ["a=>1, b=>1", "b=>2, c=>2", "", "c=>3"];
+
+    let concatenated: Hstore = diesel::select(sql(&format!("'{}'::hstore", literals.join("'::hstore || '"))))
+        .get_result(&db)
+        .unwrap();
+    let stores: Vec<Hstore> = literals.iter().map(|l| diesel_pg_hstore::text::parse(l).unwrap()).collect();
+
+    assert_eq!(stores.iter().cloned().sum::<Hstore>(), concatenated);
+    assert_eq!(stores.into_iter().collect::<Hstore>(), concatenated);
+    assert_eq!(Vec::<Hstore>::new().into_iter().sum::<Hstore>(), Hstore::new());
+}
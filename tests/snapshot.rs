@@ -0,0 +1,41 @@
+extern crate diesel_pg_hstore;
+
+use diesel_pg_hstore::{snapshot, FrozenHstore, Hstore};
+
+// Only read through its Debug output
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Row {
+    id: i32,
+    store: Hstore,
+    frozen: FrozenHstore,
+}
+
+fn store() -> Hstore {
+    (0..20).rev().map(|i| (format!("key{:02}", i), i.to_string())).collect()
+}
+
+#[test]
+fn debug_is_sorted() {
+    let keys: Vec<String> = (0..20).map(|i| format!("\"key{:02}\": \"{}\"", i, i)).collect();
+    let sorted = format!("Hstore({{{}}})", keys.join(", "));
+
+    let row = Row { id: 1, store: store(), frozen: store().freeze() };
+    assert_eq!(
+        format!("{:?}", row),
+        format!("Row {{ id: 1, store: {}, frozen: FrozenHstore({}) }}", sorted, sorted),
+    );
+
+    let pretty = format!("{:#?}", store());
+    assert!(pretty.starts_with("Hstore(\n    {\n        \"key00\": \"0\",\n        \"key01\": \"1\",\n"), "{}", pretty);
+}
+
+#[test]
+fn text_is_sorted_and_quoted() {
+    let mut store = Hstore::new();
+    store.insert("bb".into(), "NULL".into());
+    store.insert("a\\b".into(), "".into());
+    store.insert("c".into(), "x".into());
+    assert_eq!(snapshot::text(&store), r#""a\\b"=>"", "bb"=>"NULL", "c"=>"x""#);
+    assert_eq!(snapshot::text(&Hstore::new()), "");
+}
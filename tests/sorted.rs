@@ -0,0 +1,61 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::types::Binary;
+
+use diesel_pg_hstore::{codec, text, Hstore};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+fn store() -> Hstore {
+    text::parse(r#"bb=>1, a=>2, ab=>3, B=>4, ccc=>5, "ü"=>6, ""=>7"#).unwrap()
+}
+
+#[test]
+fn equal_maps_encode_identically() {
+    let mut first = Vec::new();
+    codec::encode_sorted(&store(), &mut first).unwrap();
+
+    // A map built in another order, with a different capacity, iterates differently
+    let mut other = Hstore::new();
+    other.reserve(1000);
+    let mut entries: Vec<_> = store().into_iter().collect();
+    entries.reverse();
+    other.extend(entries);
+
+    let mut second = Vec::new();
+    codec::encode_sorted(&other, &mut second).unwrap();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn matches_postgres_output() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let store = store();
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(store.sorted()))
+        .execute(&db)
+        .unwrap();
+
+    let sent: Vec<u8> = sql::<Binary>("SELECT hstore_send(store) FROM hstore_table").get_result(&db).unwrap();
+    let mut encoded = Vec::new();
+    codec::encode_sorted(&store, &mut encoded).unwrap();
+    assert_eq!(encoded, sent);
+
+    let loaded: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded, store);
+}
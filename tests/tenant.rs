@@ -0,0 +1,83 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::patch::HstorePatch;
+use diesel_pg_hstore::tenant::Tenant;
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel_pg_hstore::table_import::*;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn scoping() {
+    let acme = Tenant::new("acme");
+    assert_eq!(acme.name(), "acme");
+    assert_eq!(acme.key("plan"), "acme:plan");
+    assert_eq!(acme.strip("acme:a:b"), Some("a:b"));
+    assert_eq!(acme.strip("acmecorp:plan"), None);
+
+    let mut own = Hstore::new();
+    own.insert("plan".into(), "gold".into());
+    let mut stored = acme.scope(&own);
+    assert_eq!(stored, [("acme:plan", "gold")]);
+
+    stored.insert("globex:plan".into(), "free".into());
+    stored.insert("plan".into(), "shared".into());
+    assert_eq!(acme.unscope(&stored), own);
+    assert!(Tenant::new("initech").unscope(&stored).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "must not contain")]
+fn names_cannot_nest() {
+    Tenant::new("acme:eu");
+}
+
+#[test]
+fn queries_and_patches() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES (1, 'acme:plan=>gold, globex:plan=>free, globex:trial=>t');
+    "#).unwrap();
+
+    let acme = Tenant::new("acme");
+    let globex = Tenant::new("globex");
+
+    let plans: (Option<String>, Option<String>) = hstore_table::table
+        .select((acme.get_value(hstore_table::store, "plan"), globex.get_value(hstore_table::store, "plan")))
+        .first(&db)
+        .unwrap();
+    assert_eq!(plans, (Some("gold".into()), Some("free".into())));
+
+    let trials: Vec<i32> = hstore_table::table
+        .select(hstore_table::id)
+        .filter(acme.has_key(hstore_table::store, "trial"))
+        .load(&db)
+        .unwrap();
+    assert!(trials.is_empty());
+
+    let without: Hstore = hstore_table::table
+        .select(globex.remove_key(hstore_table::store, "trial"))
+        .first(&db)
+        .unwrap();
+    assert_eq!(without, [("acme:plan", "gold"), ("globex:plan", "free")]);
+
+    let patch = acme.patch(&HstorePatch::new().set("plan", "platinum").delete("trial"));
+    db.execute(&format!("UPDATE hstore_table SET store = {}", patch.to_sql("store"))).unwrap();
+    let stored: Hstore = sql("SELECT store FROM hstore_table").get_result(&db).unwrap();
+    assert_eq!(stored, [("acme:plan", "platinum"), ("globex:plan", "free"), ("globex:trial", "t")]);
+    assert_eq!(acme.unscope(&stored), [("plan", "platinum")]);
+}
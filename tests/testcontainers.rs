@@ -0,0 +1,35 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::testing::{self, EphemeralPostgres};
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let postgres = EphemeralPostgres::start().unwrap();
+    let db = postgres.connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut store = Hstore::new();
+    store.insert("a".into(), "1".into());
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&store))
+        .execute(&db)
+        .unwrap();
+
+    let loaded: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(loaded, store);
+}
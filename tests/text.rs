@@ -0,0 +1,162 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use std::io;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+use diesel::types::Text;
+
+use diesel_pg_hstore::{text, Hstore, HstoreNullable};
+use diesel_pg_hstore::testing;
+
+const LITERALS: &[&str] = &[
+    "",
+    "  ",
+    "a=>1",
+    "a=>1,",
+    "a=>1 , b => 2",
+    "a=>NuLl, b=>\"NULL\"",
+    "a=>b=c",
+    "a\\ b=>1",
+    "a=>1,,b=>2",
+    "a=>1, a=>2",
+    "\"quote\\\"d\"=>\"back\\\\slash\"",
+    "\"\"=>\"\"",
+    "\"ünï\"=>\"cödé ✓\"",
+    "\"a=>b\"=>\" x, y \"",
+    "a=>NULL, a=>1, b=>1, b=>NULL",
+];
+
+#[test]
+fn parse_matches_postgres() {
+    let db = testing::connection();
+
+    for literal in LITERALS {
+        let expected: Hstore = diesel::select(sql(&format!("$${}$$::hstore", literal)))
+            .get_result(&db)
+            .unwrap();
+        assert_eq!(text::parse(literal).unwrap(), expected, "parsing {:?}", literal);
+    }
+}
+
+#[test]
+fn format_matches_postgres() {
+    let db = testing::connection();
+
+    for literal in LITERALS {
+        let store = text::parse(literal).unwrap();
+        let expected: String = diesel::select(sql::<Text>(&format!("$${}$$::hstore::text", text::format(&store))))
+            .get_result(&db)
+            .unwrap();
+        assert_eq!(text::format(&store), expected, "formatting {:?}", literal);
+    }
+}
+
+#[test]
+fn nullable_matches_postgres() {
+    let db = testing::connection();
+
+    for literal in LITERALS {
+        let expected: HstoreNullable = diesel::select(sql(&format!("$${}$$::hstore", literal)))
+            .get_result(&db)
+            .unwrap();
+        let store = text::parse_nullable(literal).unwrap();
+        assert_eq!(store, expected, "parsing {:?}", literal);
+
+        let formatted: String = diesel::select(sql::<Text>(&format!("$${}$$::hstore::text", literal)))
+            .get_result(&db)
+            .unwrap();
+        assert_eq!(text::format_nullable(&store), formatted, "formatting {:?}", literal);
+    }
+}
+
+#[test]
+fn from_str_and_display() {
+    for literal in LITERALS {
+        let store: Hstore = literal.parse().unwrap();
+        assert_eq!(store, text::parse(literal).unwrap());
+        assert_eq!(store.to_string(), text::format(&store));
+        assert_eq!(store.to_string().parse::<Hstore>().unwrap(), store, "round trip of {:?}", literal);
+
+        let nullable: HstoreNullable = literal.parse().unwrap();
+        assert_eq!(nullable.to_string().parse::<HstoreNullable>().unwrap(), nullable, "round trip of {:?}", literal);
+    }
+
+    let nullable: HstoreNullable = "b=>NULL, a=>\"NULL\"".parse().unwrap();
+    assert_eq!(nullable.to_string(), r#""a"=>"NULL", "b"=>NULL"#);
+    assert_eq!(format!("{}", Hstore::new()), "");
+    assert_eq!("a=>".parse::<Hstore>().unwrap_err().position(), 3);
+}
+
+#[test]
+fn errors() {
+    for &(literal, position) in &[
+        ("a = > 1", 3),
+        ("\"a\"=>\"1\"x", 8),
+        ("a=>1 b=>2", 5),
+        ("=>1", 0),
+        ("a=>", 3),
+        ("\"a=>1", 5),
+    ] {
+        let err = text::parse(literal).unwrap_err();
+        assert_eq!(err.position(), position, "parsing {:?}: {}", literal, err);
+    }
+}
+
+#[test]
+fn sql_literal_round_trips() {
+    let db = testing::connection();
+
+    for literal in LITERALS.iter().chain(&["\"it's\"=>\"'; DROP TABLE x; --\""]) {
+        let store = text::parse(literal).unwrap();
+        let loaded: Hstore = diesel::select(sql(&store.to_sql_literal()))
+            .get_result(&db)
+            .unwrap();
+        assert_eq!(loaded, store, "quoting {:?}", literal);
+    }
+}
+
+#[test]
+fn sql_literal_without_standard_conforming_strings() {
+    let db = testing::connection();
+    db.batch_execute("SET LOCAL standard_conforming_strings = off").unwrap();
+
+    for literal in LITERALS.iter().chain(&["\"it's\"=>\"\\\\'; DROP TABLE x; --\""]) {
+        let store = text::parse(literal).unwrap();
+        let loaded: Hstore = diesel::select(sql(&store.to_sql_literal()))
+            .get_result(&db)
+            .unwrap();
+        assert_eq!(loaded, store, "quoting {:?}", literal);
+    }
+}
+
+#[test]
+fn reader_and_writer_match_parse_and_format() {
+    for literal in LITERALS {
+        // A one byte buffer splits every multibyte character across reads
+        let reader = io::BufReader::with_capacity(1, literal.as_bytes());
+        let store = Hstore::from_reader(reader).unwrap();
+        assert_eq!(store, text::parse(literal).unwrap(), "reading {:?}", literal);
+
+        let mut written = Vec::new();
+        store.to_writer(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), text::format(&store), "writing {:?}", literal);
+    }
+}
+
+#[test]
+fn reader_errors() {
+    for &(literal, position) in &[("a = > 1", 3), ("\"ü\"=>", 6), ("\"a=>1", 5)] {
+        match Hstore::from_reader(io::BufReader::with_capacity(1, literal.as_bytes())) {
+            Err(text::ReadError::Parse(err)) => assert_eq!(err.position(), position, "reading {:?}: {}", literal, err),
+            other => panic!("reading {:?} gave {:?}", literal, other),
+        }
+    }
+
+    match Hstore::from_reader(&b"a=>\"\xff\""[..]) {
+        Err(text::ReadError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::InvalidData),
+        other => panic!("reading invalid UTF-8 gave {:?}", other),
+    }
+}
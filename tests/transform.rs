@@ -0,0 +1,47 @@
+extern crate diesel_pg_hstore;
+
+mod common;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::transform::{Collision, KeyCollision};
+
+#[test]
+fn partition() {
+    let store = common::hstore(&[("a", "1"), ("b", "22"), ("c", "333"), ("d", "")]);
+
+    let (long, short) = store.clone().partition(|_, value| value.len() > 1);
+    assert_eq!(long, [("b", "22"), ("c", "333")]);
+    assert_eq!(short, [("a", "1"), ("d", "")]);
+
+    let (all, none) = store.clone().partition(|_, _| true);
+    assert_eq!(all, store);
+    assert!(none.is_empty());
+
+    let (left, right) = Hstore::new().partition(|_, _| true);
+    assert!(left.is_empty() && right.is_empty());
+}
+
+#[test]
+fn map_keys() {
+    let store = common::hstore(&[("a", "1"), ("B", "2"), ("b", "3")]);
+
+    let prefixed = store.clone().map_keys(|key| format!("app.{}", key), Collision::Error).unwrap();
+    assert_eq!(prefixed, [("app.a", "1"), ("app.B", "2"), ("app.b", "3")]);
+
+    let first = store.clone().map_keys(|key| key.to_lowercase(), Collision::KeepFirst).unwrap();
+    assert_eq!(first, [("a", "1"), ("b", "2")]);
+
+    let last = store.clone().map_keys(|key| key.to_lowercase(), Collision::KeepLast).unwrap();
+    assert_eq!(last, [("a", "1"), ("b", "3")]);
+
+    let err = store.map_keys(|key| key.to_lowercase(), Collision::Error).unwrap_err();
+    assert_eq!(err, KeyCollision { key: "b".into() });
+}
+
+#[test]
+fn map_values() {
+    let store = common::hstore(&[("a", "1"), ("b", "2")]);
+
+    let mapped = store.map_values(|key, value| format!("{}={}", key, value));
+    assert_eq!(mapped, [("a", "a=1"), ("b", "b=2")]);
+}
@@ -0,0 +1,82 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+
+use diesel_pg_hstore::Hstore;
+use diesel_pg_hstore::testing;
+use diesel_pg_hstore::ttl::ExpiredKeys;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[test]
+fn accessors() {
+    let now = UNIX_EPOCH + Duration::from_secs(1000);
+    let mut store = Hstore::new();
+    store.insert("plain".into(), "1".into());
+    store.insert_expiring("old".into(), "2".into(), now - Duration::from_secs(1));
+    store.insert_expiring("new".into(), "3".into(), now + Duration::from_secs(1));
+    store.insert("broken".into(), "4".into());
+    store.insert("broken.__ttl".into(), "soon".into());
+
+    assert_eq!(store.expires_at("new"), Some(now + Duration::from_secs(1)));
+    assert_eq!(store.expires_at("plain"), None);
+    assert_eq!(store.get_unexpired("plain", now).unwrap(), "1");
+    assert_eq!(store.get_unexpired("old", now), None);
+    assert_eq!(store.get_unexpired("new", now).unwrap(), "3");
+    assert_eq!(store.get_unexpired("broken", now).unwrap(), "4");
+
+    assert_eq!(store.remove_expired(now), 1);
+    assert!(!store.contains_key("old") && !store.contains_key("old.__ttl"));
+    assert_eq!(store.len(), 5);
+
+    let before_epoch = UNIX_EPOCH - Duration::from_millis(1500);
+    store.insert_expiring("ancient".into(), "5".into(), before_epoch);
+    assert_eq!(store["ancient.__ttl"], "-2");
+    assert_eq!(store.expires_at("ancient"), Some(UNIX_EPOCH - Duration::from_secs(2)));
+}
+
+#[test]
+fn find_and_purge() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (id, store) VALUES
+          (1, 'a=>1, a.__ttl=>900, b=>2, b.__ttl=>1100'),
+          (2, 'c=>3, c.__ttl=>1000, d=>4, d.__ttl=>never'),
+          (3, 'e=>5');
+    "#).unwrap();
+
+    let expired = ExpiredKeys::new("hstore_table", "store", "id")
+        .as_of(UNIX_EPOCH + Duration::from_secs(1000));
+    assert_eq!(expired.find(&db).unwrap(), vec![
+        ("1".to_string(), "a".to_string()),
+        ("2".to_string(), "c".to_string()),
+    ]);
+
+    assert_eq!(expired.purge(&db).unwrap(), 2);
+    assert!(expired.find(&db).unwrap().is_empty());
+
+    let stores: Vec<Hstore> = hstore_table::table
+        .select(hstore_table::store)
+        .order(hstore_table::id)
+        .load(&db)
+        .unwrap();
+    assert_eq!(stores[0], [("b", "2"), ("b.__ttl", "1100")]);
+    assert_eq!(stores[1], [("d", "4"), ("d.__ttl", "never")]);
+
+    let now = ExpiredKeys::new("hstore_table", "store", "id");
+    assert_eq!(now.find(&db).unwrap(), vec![("1".to_string(), "b".to_string())]);
+}
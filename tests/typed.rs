@@ -0,0 +1,61 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+
+use diesel_pg_hstore::{Hstore, HstoreOf};
+use diesel_pg_hstore::testing;
+
+table! {
+    use diesel::types::*;
+    use diesel_pg_hstore::Hstore;
+
+    hstore_table {
+        id -> Integer,
+        store -> Hstore,
+    }
+}
+
+#[derive(Queryable, Debug, PartialEq)]
+struct Counters {
+    id: i32,
+    store: HstoreOf<i64>,
+}
+
+#[test]
+fn round_trip() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+
+    let mut counters = HstoreOf::new();
+    counters.insert("views".to_string(), 10i64);
+    counters.insert("clicks".to_string(), -3);
+
+    diesel::insert_into(hstore_table::table)
+        .values(hstore_table::store.eq(&counters))
+        .execute(&db)
+        .unwrap();
+
+    let loaded: Counters = hstore_table::table.first(&db).unwrap();
+    assert_eq!(loaded.store, counters);
+
+    let raw: Hstore = hstore_table::table.select(hstore_table::store).first(&db).unwrap();
+    assert_eq!(raw["views"], "10");
+}
+
+#[test]
+fn bad_value() {
+    let db = testing::connection();
+    testing::create_table(&db, "hstore_table").unwrap();
+    db.batch_execute(r#"
+        INSERT INTO hstore_table (store)
+          VALUES ('views=>10, clicks=>lots'::hstore);
+    "#).unwrap();
+
+    let err = hstore_table::table.first::<Counters>(&db).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("\"clicks\""), "{}", message);
+    assert!(message.contains("\"lots\""), "{}", message);
+}
@@ -0,0 +1,28 @@
+#[macro_use]
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::expression::Expression;
+use diesel_pg_hstore::changeset::HstoreChangeset;
+use diesel_pg_hstore::dsl::{HstoreOpExtensions, HstoreOrNullableHstore};
+
+table! {
+    use diesel_pg_hstore::table_import::*;
+
+    posts {
+        id -> Integer,
+        meta -> Text,
+    }
+}
+
+fn select_tags<T>(store: T)
+    where T: Expression,
+          T::SqlType: HstoreOrNullableHstore
+{
+    let _ = store.get_value("tags");
+}
+
+fn main() {
+    let _ = HstoreChangeset::new().set("color", "red").on(posts::meta);
+    select_tags(posts::id);
+}
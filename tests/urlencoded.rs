@@ -0,0 +1,22 @@
+extern crate diesel_pg_hstore;
+
+use diesel_pg_hstore::Hstore;
+
+#[test]
+fn round_trip() {
+    let mut store = Hstore::new();
+    store.insert("q".into(), "a&b=c d".into());
+    store.insert("ünï".into(), "cödé ✓".into());
+    store.insert("empty".into(), "".into());
+
+    let encoded = store.to_urlencoded();
+    assert!(encoded.starts_with("empty=&q=a%26b%3Dc+d&"));
+    assert_eq!(Hstore::from_urlencoded(&encoded).unwrap(), store);
+}
+
+#[test]
+fn parse() {
+    let store = Hstore::from_urlencoded("a=1&b=%2F&a=2&flag").unwrap();
+    assert_eq!(store, [("a", "2"), ("b", "/"), ("flag", "")]);
+    assert!(Hstore::from_urlencoded("").unwrap().is_empty());
+}
@@ -0,0 +1,41 @@
+#[macro_use]
+extern crate lazy_static;
+extern crate diesel_pg_hstore;
+extern crate validator;
+
+mod common;
+
+use diesel_pg_hstore::schema::{HstoreSchema, Schema};
+use diesel_pg_hstore::validation::validate_schema;
+use validator::ValidateLength;
+
+struct Labels;
+
+impl Schema for Labels {
+    fn schema() -> &'static HstoreSchema {
+        lazy_static! {
+            static ref SCHEMA: HstoreSchema = HstoreSchema::new()
+                .required("team")
+                .parses::<u32>("priority");
+        }
+        &SCHEMA
+    }
+}
+
+#[test]
+fn length() {
+    let labels = common::hstore(&[("team", "core"), ("priority", "1")]);
+    assert_eq!(labels.length(), Some(2));
+    assert!(labels.validate_length(Some(1), Some(2), None));
+    assert!(!labels.validate_length(Some(3), None, None));
+}
+
+#[test]
+fn schema() {
+    assert!(validate_schema::<Labels>(&common::hstore(&[("team", "core"), ("priority", "1")])).is_ok());
+
+    let error = validate_schema::<Labels>(&common::hstore(&[("priority", "high")])).unwrap_err();
+    assert_eq!(error.code, "hstore_schema");
+    assert_eq!(error.params["violations"].as_array().unwrap().len(), 2);
+    assert!(error.message.unwrap().starts_with("missing required key \"team\"; "));
+}
@@ -0,0 +1,56 @@
+extern crate diesel;
+extern crate diesel_pg_hstore;
+
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::types::{Integer, Nullable, Text};
+
+use diesel_pg_hstore::testing;
+use diesel_pg_hstore::view::KeyColumnView;
+
+#[test]
+fn quoting() {
+    let view = KeyColumnView::new("reporting.odd \"view\"", "t", "store")
+        .key("it's", "text");
+
+    assert_eq!(
+        view.create_sql(),
+        r#"CREATE VIEW "reporting"."odd ""view""" AS SELECT ("store" -> 'it''s')::text AS "it's" FROM "t""#
+    );
+    assert_eq!(view.refresh_sql(), None);
+    assert_eq!(view.drop_sql(), r#"DROP VIEW IF EXISTS "reporting"."odd ""view""""#);
+}
+
+#[test]
+fn materialized() {
+    // Materialized views can't use temporary tables. The test transaction is never committed.
+    let db = testing::connection();
+    db.batch_execute(r#"
+        CREATE TABLE products (id INTEGER PRIMARY KEY, attributes hstore NOT NULL);
+        INSERT INTO products VALUES (1, 'color=>red, weight_kg=>1.5'), (2, 'color=>blue');
+    "#).unwrap();
+
+    let view = KeyColumnView::new("product_attributes", "products", "attributes")
+        .materialized(true)
+        .column("id")
+        .key("color", "text")
+        .key_as("weight_kg", "weight", "numeric");
+
+    db.batch_execute(&view.create_sql()).unwrap();
+    db.batch_execute("INSERT INTO products VALUES (3, 'color=>green')").unwrap();
+    db.batch_execute(&view.refresh_sql().unwrap()).unwrap();
+
+    let rows: Vec<(i32, Option<String>, Option<String>)> = diesel::select(
+        sql::<(Integer, Nullable<Text>, Nullable<Text>)>(
+            "id, color, weight::text FROM product_attributes ORDER BY id"
+        )
+    ).load(&db).unwrap();
+    assert_eq!(rows, vec![
+        (1, Some("red".to_string()), Some("1.5".to_string())),
+        (2, Some("blue".to_string()), None),
+        (3, Some("green".to_string()), None),
+    ]);
+
+    db.batch_execute(&view.drop_sql()).unwrap();
+}
@@ -0,0 +1,27 @@
+extern crate diesel_pg_hstore;
+extern crate zeroize;
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use diesel_pg_hstore::Hstore;
+
+fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+
+#[test]
+fn zeroize() {
+    assert_zeroize_on_drop::<Hstore>();
+
+    let mut store = Hstore::new();
+    store.insert("password".into(), "hunter2".into());
+    store.zeroize();
+    assert!(store.is_empty());
+}
+
+#[test]
+fn into_iter() {
+    let mut store = Hstore::new();
+    store.insert("password".into(), "hunter2".into());
+
+    let entries: Vec<_> = store.into_iter().collect();
+    assert_eq!(entries, vec![("password".to_string(), "hunter2".to_string())]);
+}